@@ -0,0 +1,271 @@
+//! Derive macros for the `ai` crate. Use them through the re-exports in `ai`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprLit, Fields, GenericParam, Lit, LitStr, Meta, Type,
+    parse_macro_input, parse_quote,
+};
+
+/// Derives `ai::ToolSchema`, producing the JSON Schema for a tool's
+/// parameters from the struct that deserializes them.
+///
+/// Structs with named fields become `object` schemas. Fields are required
+/// unless they are `Option<_>` or marked `#[serde(default)]`, and doc comments
+/// become descriptions. Enums with only unit variants become string enums.
+/// `#[serde(rename = "...")]`, `#[serde(rename_all = "...")]`,
+/// `#[serde(default)]`, and `#[serde(skip)]` are honored so the schema matches
+/// what serde accepts.
+#[proc_macro_derive(ToolSchema, attributes(serde))]
+pub fn derive_tool_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let container = SerdeAttrs::parse(&input.attrs)?;
+    let description = option_tokens(doc_comment(&input.attrs));
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let Fields::Named(fields) = &data.fields else {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "ToolSchema can only be derived for structs with named fields",
+                ));
+            };
+            let mut properties = Vec::new();
+            for field in &fields.named {
+                let attrs = SerdeAttrs::parse(&field.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let ident = field.ident.as_ref().expect("named field");
+                let ident = ident.to_string();
+                let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+                let name = attrs.rename.unwrap_or_else(|| {
+                    container
+                        .rename_all
+                        .as_deref()
+                        .map_or_else(|| ident.to_string(), |rule| rename_field(ident, rule))
+                });
+                let ty = &field.ty;
+                let required = !(container.default || attrs.default || is_option(ty));
+                let description = option_tokens(doc_comment(&field.attrs));
+                properties.push(quote! {
+                    ::ai::tool_schema::__private::property(
+                        #name,
+                        <#ty as ::ai::ToolSchema>::tool_schema(),
+                        #description,
+                        #required,
+                    )
+                });
+            }
+            quote! {
+                ::ai::tool_schema::__private::object_schema(
+                    #description,
+                    ::std::vec![#(#properties),*],
+                )
+            }
+        }
+        Data::Enum(data) => {
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        "ToolSchema can only be derived for enums with unit variants",
+                    ));
+                }
+                let attrs = SerdeAttrs::parse(&variant.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let ident = variant.ident.to_string();
+                variants.push(attrs.rename.unwrap_or_else(|| {
+                    container
+                        .rename_all
+                        .as_deref()
+                        .map_or_else(|| ident.clone(), |rule| rename_variant(&ident, rule))
+                }));
+            }
+            quote! {
+                ::ai::tool_schema::__private::string_enum_schema(
+                    #description,
+                    &[#(#variants),*],
+                )
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "ToolSchema cannot be derived for unions",
+            ));
+        }
+    };
+
+    for param in &mut input.generics.params {
+        if let GenericParam::Type(param) = param {
+            param.bounds.push(parse_quote!(::ai::ToolSchema));
+        }
+    }
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ai::ToolSchema for #ident #type_generics #where_clause {
+            fn tool_schema() -> ::ai::tool_schema::Value {
+                #body
+            }
+        }
+    })
+}
+
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    default: bool,
+    skip: bool,
+}
+
+impl SerdeAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    parsed.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("rename_all") {
+                    let rule = meta.value()?.parse::<LitStr>()?;
+                    if !RENAME_RULES.contains(&rule.value().as_str()) {
+                        return Err(syn::Error::new_spanned(rule, "unknown rename_all rule"));
+                    }
+                    parsed.rename_all = Some(rule.value());
+                } else if meta.path.is_ident("default") {
+                    parsed.default = true;
+                    if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<LitStr>()?;
+                    }
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    parsed.skip = true;
+                } else if meta.input.peek(syn::Token![=]) {
+                    // Attributes that do not change the accepted shape, such
+                    // as `skip_serializing_if` or `with`.
+                    meta.value()?.parse::<Expr>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|nested| {
+                        if nested.input.peek(syn::Token![=]) {
+                            nested.value()?.parse::<Expr>()?;
+                        }
+                        Ok(())
+                    })?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(parsed)
+    }
+}
+
+const RENAME_RULES: &[&str] = &[
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+/// Applies a serde `rename_all` rule to a snake_case field name.
+fn rename_field(name: &str, rule: &str) -> String {
+    match rule {
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_ascii_uppercase(),
+        "PascalCase" | "camelCase" => {
+            let mut renamed = String::new();
+            let mut capitalize = rule == "PascalCase";
+            for ch in name.chars() {
+                if ch == '_' {
+                    capitalize = true;
+                } else if capitalize {
+                    renamed.extend(ch.to_uppercase());
+                    capitalize = false;
+                } else {
+                    renamed.push(ch);
+                }
+            }
+            renamed
+        }
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.replace('_', "-").to_ascii_uppercase(),
+        _ => name.to_string(),
+    }
+}
+
+/// Applies a serde `rename_all` rule to a PascalCase variant name.
+fn rename_variant(name: &str, rule: &str) -> String {
+    match rule {
+        "lowercase" => name.to_ascii_lowercase(),
+        "UPPERCASE" => name.to_ascii_uppercase(),
+        "camelCase" => {
+            let mut chars = name.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_lowercase().chain(chars).collect()
+            })
+        }
+        "snake_case" | "SCREAMING_SNAKE_CASE" | "kebab-case" | "SCREAMING-KEBAB-CASE" => {
+            let separator = if rule.contains('_') { '_' } else { '-' };
+            let mut renamed = String::new();
+            for (index, ch) in name.char_indices() {
+                if index > 0 && ch.is_uppercase() {
+                    renamed.push(separator);
+                }
+                renamed.push(ch);
+            }
+            if rule.starts_with("SCREAMING") {
+                renamed.to_ascii_uppercase()
+            } else {
+                renamed.to_ascii_lowercase()
+            }
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(text),
+                    ..
+                }) => Some(text.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn option_tokens(value: Option<String>) -> TokenStream2 {
+    match value {
+        Some(value) => quote!(::std::option::Option::Some(#value)),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    matches!(
+        ty,
+        Type::Path(path)
+            if path.qself.is_none()
+                && path.path.segments.last().is_some_and(|segment| segment.ident == "Option")
+    )
+}
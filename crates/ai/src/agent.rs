@@ -702,7 +702,10 @@ impl Agent {
             } else {
                 StopReason::Error
             },
+            stop_sequence: None,
             error_message: Some(error.to_string()),
+            error_status: None,
+            circuit_open: None,
             timestamp: crate::utils::time::now_millis(),
         });
         drop(state);
@@ -378,10 +378,14 @@ async fn stream_assistant_response(
             }
             AssistantMessageEvent::TextStart { partial, .. }
             | AssistantMessageEvent::TextDelta { partial, .. }
+            | AssistantMessageEvent::TextLogprobs { partial, .. }
             | AssistantMessageEvent::TextEnd { partial, .. }
             | AssistantMessageEvent::ThinkingStart { partial, .. }
             | AssistantMessageEvent::ThinkingDelta { partial, .. }
             | AssistantMessageEvent::ThinkingEnd { partial, .. }
+            | AssistantMessageEvent::AudioStart { partial, .. }
+            | AssistantMessageEvent::AudioDelta { partial, .. }
+            | AssistantMessageEvent::AudioEnd { partial, .. }
             | AssistantMessageEvent::ToolCallStart { partial, .. }
             | AssistantMessageEvent::ToolCallDelta { partial, .. }
             | AssistantMessageEvent::ToolCallEnd { partial, .. } => {
@@ -5,9 +5,10 @@ use std::sync::{Arc, Mutex};
 use crate::{
     AssistantContent, AssistantEventStream, AssistantMessage, AssistantMessageEvent, Context,
     ImageContent, Message, Model, SimpleStreamOptions, TextContent, Tool, ToolResultContent,
-    ToolResultMessage,
+    ToolResultMessage, ToolSchema,
 };
 use async_trait::async_trait;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 use tokio_util::sync::CancellationToken;
 
@@ -174,6 +175,52 @@ impl AgentToolBuilder {
         self
     }
 
+    /// Like [`execute`](Self::execute), but deserializes the arguments into
+    /// `T` first. Parameters default to `T`'s derived schema when not set.
+    pub fn execute_typed<T, F, Fut>(self, execute: F) -> Self
+    where
+        T: DeserializeOwned + ToolSchema + Send + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = AgentResult<AgentToolResult>> + Send + 'static,
+    {
+        self.execute_typed_with_context(
+            move |_tool_call_id, args, _cancellation_token, _on_update| execute(args),
+        )
+    }
+
+    /// Like [`execute_with_context`](Self::execute_with_context), but
+    /// deserializes the arguments into `T` first. Parameters default to `T`'s
+    /// derived schema when not set.
+    pub fn execute_typed_with_context<T, F, Fut>(mut self, execute: F) -> Self
+    where
+        T: DeserializeOwned + ToolSchema + Send + 'static,
+        F: Fn(String, T, Option<CancellationToken>, Option<AgentToolUpdateCallback>) -> Fut
+            + Send
+            + Sync
+            + 'static,
+        Fut: Future<Output = AgentResult<AgentToolResult>> + Send + 'static,
+    {
+        if self.parameters.is_none() {
+            self.parameters = Some(T::tool_schema());
+        }
+        let name = self.name.clone();
+        self.execute = Some(Arc::new(
+            move |tool_call_id, args, cancellation_token, on_update| {
+                let args = match serde_json::from_value::<T>(args) {
+                    Ok(args) => args,
+                    Err(error) => {
+                        let error = crate::Error::Validation(format!(
+                            "invalid arguments for tool \"{name}\": {error}"
+                        ));
+                        return Box::pin(async move { Err(error.into()) });
+                    }
+                };
+                Box::pin(execute(tool_call_id, args, cancellation_token, on_update))
+            },
+        ));
+        self
+    }
+
     pub fn build(self) -> crate::Result<DynAgentTool> {
         let mut tool_builder = Tool::builder(self.name);
         if let Some(description) = self.description {
@@ -687,6 +734,67 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn agent_tool_builder_executes_typed_arguments() {
+        #[derive(serde::Deserialize, ToolSchema)]
+        struct WeatherArgs {
+            /// City to look up.
+            city: String,
+            days: Option<u8>,
+        }
+
+        let tool = AgentToolBuilder::new("weather")
+            .description("Get weather.")
+            .execute_typed(|args: WeatherArgs| async move {
+                Ok(AgentToolResult::text(format!(
+                    "clear in {} for {} days",
+                    args.city,
+                    args.days.unwrap_or(1)
+                )))
+            })
+            .build()
+            .expect("tool");
+
+        assert_eq!(
+            tool.definition().parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "city": { "type": "string", "description": "City to look up." },
+                    "days": { "type": "integer", "minimum": 0 }
+                },
+                "required": ["city"]
+            })
+        );
+
+        let result = tool
+            .execute(
+                "tool-1",
+                json!({ "city": "Seattle", "days": 3 }),
+                None,
+                None,
+            )
+            .await
+            .expect("tool result");
+        assert_eq!(
+            result.content,
+            vec![ToolResultContent::text("clear in Seattle for 3 days")]
+        );
+
+        let error = match tool
+            .execute("tool-2", json!({ "days": 3 }), None, None)
+            .await
+        {
+            Ok(_) => panic!("expected invalid arguments error"),
+            Err(error) => error,
+        };
+        assert!(
+            error
+                .to_string()
+                .starts_with("invalid arguments for tool \"weather\": missing field `city`")
+        );
+    }
+
     #[test]
     fn agent_tool_builder_requires_execute_callback() {
         let result = AgentToolBuilder::new("missing")
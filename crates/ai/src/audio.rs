@@ -0,0 +1,72 @@
+use crate::types::{
+    AudioContent, GeneratedSpeech, Model, SpeechOptions, Transcription, TranscriptionOptions,
+};
+use crate::{Error, Result};
+
+/// Turns spoken `audio` into text.
+pub async fn transcribe(
+    model: Model,
+    audio: AudioContent,
+    options: Option<TranscriptionOptions>,
+) -> Result<Transcription> {
+    if audio.data.is_empty() {
+        return Err(Error::Validation(
+            "transcription audio must not be empty".to_string(),
+        ));
+    }
+    let api = model.transcription_api().ok_or_else(|| {
+        Error::unsupported_capability(model.provider.clone(), "transcription models")
+    })?;
+    api.transcribe(model, audio, options.unwrap_or_default())
+        .await
+}
+
+/// Reads `text` aloud in `voice`. The audio streams back as it is produced;
+/// [`GeneratedSpeech::bytes`] waits for all of it.
+pub async fn generate_speech(
+    model: Model,
+    text: impl Into<String>,
+    voice: impl Into<String>,
+    options: Option<SpeechOptions>,
+) -> Result<GeneratedSpeech> {
+    let text = text.into();
+    if text.trim().is_empty() {
+        return Err(Error::Validation(
+            "speech input must not be empty".to_string(),
+        ));
+    }
+    let api = model
+        .speech_api()
+        .ok_or_else(|| Error::unsupported_capability(model.provider.clone(), "speech models"))?;
+    api.generate_speech(model, text, voice.into(), options.unwrap_or_default())
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn transcribe_requires_a_transcription_model() {
+        let audio = AudioContent {
+            data: "UklGRg==".to_string(),
+            mime_type: "audio/wav".to_string(),
+        };
+
+        let error = transcribe(Model::default(), audio, None)
+            .await
+            .expect_err("default model should fail");
+
+        assert!(matches!(error, Error::UnsupportedCapability { .. }));
+    }
+
+    #[tokio::test]
+    async fn generate_speech_rejects_blank_text_before_provider_dispatch() {
+        let error = generate_speech(Model::default(), "  ", "alloy", None)
+            .await
+            .err()
+            .expect("blank text should fail");
+
+        assert!(matches!(error, Error::Validation(_)));
+    }
+}
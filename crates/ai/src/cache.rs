@@ -0,0 +1,753 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serde_json::{Value, json};
+
+use crate::cassette::normalized_context;
+use crate::event_stream::AssistantEventStream;
+use crate::middleware::{LanguageModelMiddleware, LanguageModelRequest, Next, wrap_model};
+use crate::provider::EmbeddingModelApi;
+use crate::types::{
+    AssistantContent, AssistantMessage, AssistantMessageEvent, EmbeddingBatch, EmbeddingOptions,
+    EmbeddingUsage, EmbeddingVector, Model, StopReason, ToolChoice, UsageCost,
+};
+use crate::utils::hash::short_hash;
+use crate::{Error, Result};
+
+/// Storage for cached responses. Keys are short hashes that are safe to use
+/// as file names.
+pub trait CacheStore: Send + Sync + 'static {
+    fn get(&self, key: &str) -> Result<Option<Value>>;
+
+    fn put(&self, key: &str, value: Value) -> Result<()>;
+}
+
+/// An in-memory store that evicts the least recently used entry once it
+/// holds `capacity` entries.
+pub struct MemoryCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Default)]
+struct LruState {
+    entries: HashMap<String, (u64, Value)>,
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl LruState {
+    fn touch(&mut self, key: &str) -> u64 {
+        self.tick += 1;
+        if let Some((last_used, _)) = self.entries.get(key) {
+            self.recency.remove(last_used);
+        }
+        self.recency.insert(self.tick, key.to_string());
+        self.tick
+    }
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &str) -> Result<Option<Value>> {
+        let mut state = self.state.lock();
+        if !state.entries.contains_key(key) {
+            return Ok(None);
+        }
+        let tick = state.touch(key);
+        let entry = state.entries.get_mut(key).expect("cache entry");
+        entry.0 = tick;
+        Ok(Some(entry.1.clone()))
+    }
+
+    fn put(&self, key: &str, value: Value) -> Result<()> {
+        let mut state = self.state.lock();
+        let tick = state.touch(key);
+        state.entries.insert(key.to_string(), (tick, value));
+        while state.entries.len() > self.capacity {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+        Ok(())
+    }
+}
+
+/// A store that keeps one `<key>.json` file per entry in a directory, so
+/// cached responses survive restarts. Entries never expire.
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+impl CacheStore for DiskCache {
+    fn get(&self, key: &str) -> Result<Option<Value>> {
+        match std::fs::read_to_string(self.path(key)) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn put(&self, key: &str, value: Value) -> Result<()> {
+        // Write then rename so readers never see a partial entry.
+        let path = self.path(key);
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_vec(&value)?)?;
+        std::fs::rename(partial, path)?;
+        Ok(())
+    }
+}
+
+/// Caches the responses of `model` in `store`. Language calls go through a
+/// [`ResponseCache`] layer; embedding calls are cached per input, so a batch
+/// only sends the inputs that are not cached yet.
+pub fn with_response_cache(mut model: Model, store: Arc<dyn CacheStore>) -> Result<Model> {
+    if model.language_api.is_none() && model.embedding_api.is_none() {
+        return Err(Error::unsupported_capability(
+            model.provider,
+            "language or embedding models",
+        ));
+    }
+    if let Some(inner) = model.embedding_api.take() {
+        model.embedding_api = Some(Arc::new(CachedEmbeddingModelApi {
+            inner,
+            store: Arc::clone(&store),
+        }));
+    }
+    if model.language_api.is_some() {
+        model = wrap_model(
+            model,
+            [Arc::new(ResponseCache::new(store)) as Arc<dyn LanguageModelMiddleware>],
+        )?;
+    }
+    Ok(model)
+}
+
+/// Middleware that answers repeated language model calls from a
+/// [`CacheStore`].
+///
+/// Calls are keyed by provider, model id, context (including tools, without
+/// timestamps or usage), sampling options, spoken-answer voice, max tokens,
+/// and reasoning level.
+/// Only calls that finish without an error are stored. A hit replays the stored
+/// message as a synthetic event stream with `usage.cost` zeroed; token counts
+/// are kept. Calls that request log-probabilities bypass the cache.
+#[derive(Clone)]
+pub struct ResponseCache {
+    store: Arc<dyn CacheStore>,
+}
+
+impl ResponseCache {
+    pub fn new(store: Arc<dyn CacheStore>) -> Self {
+        Self { store }
+    }
+}
+
+impl LanguageModelMiddleware for ResponseCache {
+    fn wrap_stream(
+        &self,
+        request: LanguageModelRequest,
+        next: Next,
+    ) -> Result<AssistantEventStream> {
+        let options = &request.options.stream;
+        // Replays carry no log-probabilities, so those calls always go
+        // through.
+        if options.logprobs || options.top_logprobs.is_some() {
+            return next.run(request);
+        }
+        let mut input = json!({
+            "provider": request.model.provider,
+            "model": request.model.id,
+            "context": normalized_context(&request.context)?,
+            "temperature": options.temperature,
+            "maxTokens": options.max_tokens,
+            "reasoning": request.options.reasoning,
+        });
+        // Added only when set so keys from before these options existed
+        // still match.
+        if options.top_p.is_some()
+            || options.top_k.is_some()
+            || !options.stop.is_empty()
+            || options.seed.is_some()
+            || options.frequency_penalty.is_some()
+            || options.presence_penalty.is_some()
+        {
+            input["sampling"] = json!({
+                "topP": options.top_p,
+                "topK": options.top_k,
+                "stop": options.stop,
+                "seed": options.seed,
+                "frequencyPenalty": options.frequency_penalty,
+                "presencePenalty": options.presence_penalty,
+            });
+        }
+        if let Some(audio_output) = &options.audio_output {
+            input["audioVoice"] = json!(audio_output.voice);
+        }
+        if let Some(tool_choice) = &options.tool_choice {
+            input["toolChoice"] = match tool_choice {
+                ToolChoice::Auto => json!("auto"),
+                ToolChoice::None => json!("none"),
+                ToolChoice::Required => json!("required"),
+                ToolChoice::Tool(name) => json!({ "tool": name }),
+            };
+        }
+        if let Some(parallel_tool_calls) = options.parallel_tool_calls {
+            input["parallelToolCalls"] = json!(parallel_tool_calls);
+        }
+        if let Some(format) = &options.response_format {
+            input["responseFormat"] = json!({
+                "name": format.name,
+                "description": format.description,
+                "schema": format.schema,
+                "strict": format.strict,
+            });
+        }
+        // Provider-specific settings such as `toolChoice`, reasoning effort,
+        // or Ollama's `numCtx` live here. Maps serialize with sorted keys.
+        if !options.provider_options.is_empty() {
+            input["providerOptions"] = json!(options.provider_options);
+        }
+        let key = short_hash(&serde_json::to_string(&input)?);
+        if let Some(cached) = self.store.get(&key)? {
+            let mut message: AssistantMessage = serde_json::from_value(cached)?;
+            message.usage.cost = UsageCost::default();
+            message.timestamp = crate::utils::time::now_millis();
+            return Ok(futures::stream::iter(message_events(message).into_iter().map(Ok)).boxed());
+        }
+
+        let store = Arc::clone(&self.store);
+        Ok(next
+            .run(request)?
+            .inspect(move |event| {
+                if let Ok(AssistantMessageEvent::Done { message, .. }) = event
+                    && !matches!(message.stop_reason, StopReason::Error | StopReason::Aborted)
+                    && let Ok(value) = serde_json::to_value(message)
+                {
+                    // A failed cache write must not fail a completed call.
+                    let _ = store.put(&key, value);
+                }
+            })
+            .boxed())
+    }
+}
+
+/// Rebuilds the event sequence a provider would stream for `message`, with
+/// each content block delivered in a single delta.
+fn message_events(message: AssistantMessage) -> Vec<AssistantMessageEvent> {
+    let mut partial = AssistantMessage {
+        content: Vec::new(),
+        ..message.clone()
+    };
+    let mut events = vec![AssistantMessageEvent::Start {
+        partial: partial.clone(),
+    }];
+    for (content_index, block) in message.content.iter().enumerate() {
+        partial.content.push(block.clone());
+        let partial = partial.clone();
+        match block {
+            AssistantContent::Text(text) => events.extend([
+                AssistantMessageEvent::TextStart {
+                    content_index,
+                    partial: partial.clone(),
+                },
+                AssistantMessageEvent::TextDelta {
+                    content_index,
+                    delta: text.text.clone(),
+                    partial: partial.clone(),
+                },
+                AssistantMessageEvent::TextEnd {
+                    content_index,
+                    content: text.text.clone(),
+                    partial,
+                },
+            ]),
+            AssistantContent::Thinking(thinking) => events.extend([
+                AssistantMessageEvent::ThinkingStart {
+                    content_index,
+                    partial: partial.clone(),
+                },
+                AssistantMessageEvent::ThinkingDelta {
+                    content_index,
+                    delta: thinking.thinking.clone(),
+                    partial: partial.clone(),
+                },
+                AssistantMessageEvent::ThinkingEnd {
+                    content_index,
+                    content: thinking.thinking.clone(),
+                    partial,
+                },
+            ]),
+            AssistantContent::Audio(audio) => events.extend([
+                AssistantMessageEvent::AudioStart {
+                    content_index,
+                    partial: partial.clone(),
+                },
+                AssistantMessageEvent::AudioDelta {
+                    content_index,
+                    data: audio.data.clone(),
+                    transcript: audio.transcript.clone(),
+                    partial: partial.clone(),
+                },
+                AssistantMessageEvent::AudioEnd {
+                    content_index,
+                    content: audio.clone(),
+                    partial,
+                },
+            ]),
+            AssistantContent::ToolCall(tool_call) => events.extend([
+                AssistantMessageEvent::ToolCallStart {
+                    content_index,
+                    partial: partial.clone(),
+                },
+                AssistantMessageEvent::ToolCallDelta {
+                    content_index,
+                    delta: tool_call.arguments.to_string(),
+                    partial: partial.clone(),
+                },
+                AssistantMessageEvent::ToolCallEnd {
+                    content_index,
+                    tool_call: tool_call.clone(),
+                    partial,
+                },
+            ]),
+        }
+    }
+    events.push(AssistantMessageEvent::Done {
+        reason: message.stop_reason,
+        message,
+    });
+    events
+}
+
+#[derive(Clone)]
+struct CachedEmbeddingModelApi {
+    inner: Arc<dyn EmbeddingModelApi>,
+    store: Arc<dyn CacheStore>,
+}
+
+#[async_trait]
+impl EmbeddingModelApi for CachedEmbeddingModelApi {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    async fn embed_many(
+        &self,
+        model: Model,
+        inputs: Vec<String>,
+        options: EmbeddingOptions,
+    ) -> Result<EmbeddingBatch> {
+        let keys = inputs
+            .iter()
+            .map(|input| {
+                Ok(short_hash(&serde_json::to_string(&json!({
+                    "provider": model.provider,
+                    "model": model.id,
+                    "dimensions": options.dimensions,
+                    "encodingFormat": options.encoding_format,
+                    "inputType": options.input_type,
+                    "input": input,
+                }))?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut embeddings = Vec::with_capacity(inputs.len());
+        let mut misses = Vec::new();
+        for (index, key) in keys.iter().enumerate() {
+            let cached = self
+                .store
+                .get(key)?
+                .map(serde_json::from_value::<EmbeddingVector>)
+                .transpose()?;
+            if cached.is_none() {
+                misses.push(index);
+            }
+            embeddings.push(cached);
+        }
+        if misses.is_empty() {
+            return Ok(EmbeddingBatch {
+                embeddings: embeddings.into_iter().flatten().collect(),
+                model: model.id,
+                usage: EmbeddingUsage {
+                    prompt_tokens: 0,
+                    total_tokens: 0,
+                },
+            });
+        }
+
+        let batch = self
+            .inner
+            .embed_many(
+                model,
+                misses.iter().map(|index| inputs[*index].clone()).collect(),
+                options,
+            )
+            .await?;
+        if batch.embeddings.len() != misses.len() {
+            return Err(Error::InvalidProviderResponse(format!(
+                "expected {} embeddings, got {}",
+                misses.len(),
+                batch.embeddings.len()
+            )));
+        }
+        for (index, embedding) in misses.into_iter().zip(batch.embeddings) {
+            if let Ok(value) = serde_json::to_value(&embedding) {
+                // A failed cache write must not fail a completed call.
+                let _ = self.store.put(&keys[index], value);
+            }
+            embeddings[index] = Some(embedding);
+        }
+        Ok(EmbeddingBatch {
+            embeddings: embeddings.into_iter().flatten().collect(),
+            model: batch.model,
+            usage: batch.usage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::provider::ModelBuilder;
+    use crate::providers::faux::{faux_assistant_message, register_faux_provider};
+    use crate::types::{
+        AssistantAudioContent, AudioOutputOptions, Context, JsonSchemaFormat, Message,
+        StreamOptions, UserMessage,
+    };
+
+    fn context(text: &str) -> Context {
+        Context::builder()
+            .message(Message::User(UserMessage::text(text)))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn replays_completed_calls_with_zero_cost() {
+        let faux = register_faux_provider(None);
+        faux.set_responses([
+            faux_assistant_message("cached answer", None),
+            faux_assistant_message("fresh answer", None),
+        ]);
+        let store = Arc::new(MemoryCache::new(8));
+        let model = with_response_cache(faux.get_model(), store.clone()).unwrap();
+
+        let first = crate::complete(model.clone(), context("hi"), None)
+            .await
+            .unwrap();
+        let events = crate::stream(model.clone(), context("hi"), None)
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        let other = crate::complete(model, context("bye"), None).await.unwrap();
+
+        assert_eq!(faux.state.call_count(), 2);
+        assert_eq!(store.len(), 2);
+        assert!(matches!(events[0], AssistantMessageEvent::Start { .. }));
+        assert!(matches!(
+            &events[2],
+            AssistantMessageEvent::TextDelta { delta, .. } if delta == "cached answer"
+        ));
+        let Some(AssistantMessageEvent::Done { message, .. }) = events.last() else {
+            panic!("expected done event");
+        };
+        assert_eq!(message.content, first.content);
+        assert_eq!(message.usage.input, first.usage.input);
+        assert_eq!(message.usage.cost, UsageCost::default());
+        assert!(matches!(
+            &other.content[..],
+            [AssistantContent::Text(text)] if text.text == "fresh answer"
+        ));
+    }
+
+    #[tokio::test]
+    async fn logprob_requests_bypass_the_cache() {
+        let faux = register_faux_provider(None);
+        faux.set_responses([
+            faux_assistant_message("first", None),
+            faux_assistant_message("second", None),
+        ]);
+        let store = Arc::new(MemoryCache::new(8));
+        let model = with_response_cache(faux.get_model(), store.clone()).unwrap();
+        let options = StreamOptions {
+            logprobs: true,
+            ..Default::default()
+        };
+
+        crate::complete(model.clone(), context("hi"), Some(options.clone()))
+            .await
+            .unwrap();
+        let second = crate::complete(model, context("hi"), Some(options))
+            .await
+            .unwrap();
+
+        assert_eq!(faux.state.call_count(), 2);
+        assert!(store.is_empty());
+        assert!(matches!(
+            &second.content[..],
+            [AssistantContent::Text(text)] if text.text == "second"
+        ));
+    }
+
+    #[tokio::test]
+    async fn keys_spoken_answers_by_voice_and_replays_audio_events() {
+        let faux = register_faux_provider(None);
+        let audio = AssistantAudioContent {
+            id: "audio_1".to_string(),
+            data: "AAAA".to_string(),
+            mime_type: "audio/pcm".to_string(),
+            transcript: "Hello.".to_string(),
+            expires_at: None,
+        };
+        faux.set_responses([
+            faux_assistant_message(AssistantContent::Audio(audio.clone()), None),
+            faux_assistant_message("text answer", None),
+        ]);
+        let store = Arc::new(MemoryCache::new(8));
+        let model = with_response_cache(faux.get_model(), store.clone()).unwrap();
+        let options = StreamOptions {
+            audio_output: Some(AudioOutputOptions::new("alloy")),
+            ..Default::default()
+        };
+
+        crate::complete(model.clone(), context("hi"), Some(options.clone()))
+            .await
+            .unwrap();
+        let events = crate::stream(model.clone(), context("hi"), Some(options))
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        let text = crate::complete(model, context("hi"), None).await.unwrap();
+
+        assert_eq!(faux.state.call_count(), 2);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            AssistantMessageEvent::AudioEnd { content, .. } if *content == audio
+        )));
+        assert!(matches!(
+            &text.content[..],
+            [AssistantContent::Text(text)] if text.text == "text answer"
+        ));
+    }
+
+    #[tokio::test]
+    async fn keys_structured_output_by_response_format() {
+        let faux = register_faux_provider(None);
+        faux.set_responses([
+            faux_assistant_message("Paris is the capital.", None),
+            faux_assistant_message(r#"{"city":"Paris"}"#, None),
+        ]);
+        let store = Arc::new(MemoryCache::new(8));
+        let model = with_response_cache(faux.get_model(), store).unwrap();
+        let options = StreamOptions {
+            response_format: Some(JsonSchemaFormat::new(json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } }
+            }))),
+            ..Default::default()
+        };
+
+        crate::complete(model.clone(), context("capital?"), None)
+            .await
+            .unwrap();
+        let structured = crate::complete(model, context("capital?"), Some(options))
+            .await
+            .unwrap();
+
+        assert_eq!(faux.state.call_count(), 2);
+        assert!(matches!(
+            &structured.content[..],
+            [AssistantContent::Text(text)] if text.text == r#"{"city":"Paris"}"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn keys_answers_by_tool_choice_and_parallel_tool_calls() {
+        let faux = register_faux_provider(None);
+        faux.set_responses([
+            faux_assistant_message("auto answer", None),
+            faux_assistant_message("no-tools answer", None),
+            faux_assistant_message("serial answer", None),
+        ]);
+        let store = Arc::new(MemoryCache::new(8));
+        let model = with_response_cache(faux.get_model(), store).unwrap();
+        let no_tools = StreamOptions {
+            tool_choice: Some(ToolChoice::None),
+            ..Default::default()
+        };
+        let serial = StreamOptions {
+            parallel_tool_calls: Some(false),
+            ..Default::default()
+        };
+
+        crate::complete(model.clone(), context("hi"), None)
+            .await
+            .unwrap();
+        let answer = crate::complete(model.clone(), context("hi"), Some(no_tools))
+            .await
+            .unwrap();
+        crate::complete(model, context("hi"), Some(serial))
+            .await
+            .unwrap();
+
+        assert_eq!(faux.state.call_count(), 3);
+        assert!(matches!(
+            &answer.content[..],
+            [AssistantContent::Text(text)] if text.text == "no-tools answer"
+        ));
+    }
+
+    #[tokio::test]
+    async fn keys_answers_by_provider_options() {
+        let faux = register_faux_provider(None);
+        faux.set_responses([
+            faux_assistant_message("default answer", None),
+            faux_assistant_message("large context answer", None),
+        ]);
+        let store = Arc::new(MemoryCache::new(8));
+        let model = with_response_cache(faux.get_model(), store).unwrap();
+        let provider_options = |num_ctx: u32| StreamOptions {
+            provider_options: [
+                ("numCtx".to_string(), json!(num_ctx)),
+                ("think".to_string(), json!(false)),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        crate::complete(model.clone(), context("hi"), None)
+            .await
+            .unwrap();
+        let first = crate::complete(model.clone(), context("hi"), Some(provider_options(32_768)))
+            .await
+            .unwrap();
+        let replayed = crate::complete(model, context("hi"), Some(provider_options(32_768)))
+            .await
+            .unwrap();
+
+        assert_eq!(faux.state.call_count(), 2);
+        assert_eq!(first.content, replayed.content);
+        assert!(matches!(
+            &replayed.content[..],
+            [AssistantContent::Text(text)] if text.text == "large context answer"
+        ));
+    }
+
+    #[derive(Clone)]
+    struct CountingEmbeddings {
+        embedded: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl EmbeddingModelApi for CountingEmbeddings {
+        fn id(&self) -> &str {
+            "counting-embeddings"
+        }
+
+        async fn embed_many(
+            &self,
+            model: Model,
+            inputs: Vec<String>,
+            _options: EmbeddingOptions,
+        ) -> Result<EmbeddingBatch> {
+            self.embedded.fetch_add(inputs.len(), Ordering::SeqCst);
+            Ok(EmbeddingBatch {
+                embeddings: inputs
+                    .iter()
+                    .map(|input| EmbeddingVector::Float(vec![input.len() as f32]))
+                    .collect(),
+                model: model.id,
+                usage: EmbeddingUsage {
+                    prompt_tokens: inputs.len() as u32,
+                    total_tokens: inputs.len() as u32,
+                },
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn embeds_only_uncached_inputs_and_persists_to_disk() {
+        let dir = std::env::temp_dir().join(format!("ai-cache-{}", std::process::id()));
+        let embedded = Arc::new(AtomicUsize::new(0));
+        let model = ModelBuilder::new_embedding(
+            "test",
+            "embed",
+            Arc::new(CountingEmbeddings {
+                embedded: Arc::clone(&embedded),
+            }),
+        )
+        .build()
+        .unwrap();
+        let cached =
+            with_response_cache(model.clone(), Arc::new(DiskCache::new(&dir).unwrap())).unwrap();
+
+        crate::embed_many(cached, ["a", "bb"], None).await.unwrap();
+        let reopened = with_response_cache(model, Arc::new(DiskCache::new(&dir).unwrap())).unwrap();
+        let batch = crate::embed_many(reopened, ["bb", "ccc", "a"], None)
+            .await
+            .unwrap();
+
+        assert_eq!(embedded.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            batch.embeddings,
+            vec![
+                EmbeddingVector::Float(vec![2.0]),
+                EmbeddingVector::Float(vec![3.0]),
+                EmbeddingVector::Float(vec![1.0]),
+            ]
+        );
+        assert_eq!(batch.usage.prompt_tokens, 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn memory_cache_evicts_the_least_recently_used_entry() {
+        let cache = MemoryCache::new(2);
+        cache.put("a", json!(1)).unwrap();
+        cache.put("b", json!(2)).unwrap();
+        assert_eq!(cache.get("a").unwrap(), Some(json!(1)));
+        cache.put("c", json!(3)).unwrap();
+
+        assert_eq!(cache.get("b").unwrap(), None);
+        assert_eq!(cache.get("a").unwrap(), Some(json!(1)));
+        assert_eq!(cache.get("c").unwrap(), Some(json!(3)));
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::event_stream::AssistantEventStream;
+use crate::middleware::{LanguageModelMiddleware, LanguageModelRequest, Next, wrap_model};
+use crate::provider::LanguageModelApi;
+use crate::types::{AssistantMessageEvent, Context, Model, SimpleStreamOptions, StreamOptions};
+use crate::utils::hash::short_hash;
+use crate::{Error, Result};
+
+const CASSETTE_VERSION: u32 = 1;
+
+/// Message fields that change between otherwise identical runs and are left
+/// out of [`cassette_key`].
+const VOLATILE_FIELDS: &[&str] = &["timestamp", "usage", "diagnostics", "responseId"];
+
+/// Recorded language model calls, stored as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cassette {
+    pub version: u32,
+    pub interactions: Vec<CassetteInteraction>,
+}
+
+impl Default for Cassette {
+    fn default() -> Self {
+        Self {
+            version: CASSETTE_VERSION,
+            interactions: Vec::new(),
+        }
+    }
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let cassette: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if cassette.version != CASSETTE_VERSION {
+            return Err(Error::Validation(format!(
+                "unsupported cassette version {}",
+                cassette.version
+            )));
+        }
+        Ok(cassette)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// One recorded call: the request as sent and every event the model
+/// streamed back. `options` keeps the serializable request options for
+/// reference; only `key` is used for matching.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CassetteInteraction {
+    pub key: String,
+    pub provider: String,
+    pub model: String,
+    pub context: Context,
+    pub options: Value,
+    pub events: Vec<AssistantMessageEvent>,
+}
+
+/// Hashes the model id and `context`, ignoring timestamps, usage, response
+/// ids, and diagnostics so a replayed session matches its recording.
+pub fn cassette_key(model: &Model, context: &Context) -> Result<String> {
+    let input = json!({
+        "provider": model.provider,
+        "model": model.id,
+        "context": normalized_context(context)?,
+    });
+    Ok(short_hash(&serde_json::to_string(&input)?))
+}
+
+/// `context` as JSON without the fields that differ between otherwise
+/// identical runs.
+pub(crate) fn normalized_context(context: &Context) -> Result<Value> {
+    let mut context = serde_json::to_value(context)?;
+    strip_volatile_fields(&mut context);
+    Ok(context)
+}
+
+/// Removes [`VOLATILE_FIELDS`] from each message. Nested values such as tool
+/// call arguments and tool schemas are part of the request and stay.
+fn strip_volatile_fields(context: &mut Value) {
+    let Some(messages) = context.get_mut("messages").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for message in messages.iter_mut().filter_map(Value::as_object_mut) {
+        for field in VOLATILE_FIELDS {
+            message.remove(*field);
+        }
+    }
+}
+
+/// Wraps `model` so every call is recorded to a cassette at `path`. The file
+/// is rewritten after each call completes, starting from an empty cassette.
+pub fn record_model(model: Model, path: impl Into<PathBuf>) -> Result<Model> {
+    let recorder = CassetteRecorder {
+        path: path.into(),
+        cassette: Arc::new(Mutex::new(Cassette::default())),
+    };
+    wrap_model(
+        model,
+        [Arc::new(recorder) as Arc<dyn LanguageModelMiddleware>],
+    )
+}
+
+/// Replaces `model`'s language API with the cassette at `path`, keeping the
+/// rest of the model as is. Nothing is sent over the network.
+pub fn replay_model(mut model: Model, path: impl AsRef<Path>) -> Result<Model> {
+    let replay = ReplayModelApi::new(&model.api, Cassette::load(path)?);
+    model.language_api = Some(Arc::new(replay));
+    Ok(model)
+}
+
+#[derive(Clone)]
+struct CassetteRecorder {
+    path: PathBuf,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl LanguageModelMiddleware for CassetteRecorder {
+    fn wrap_stream(
+        &self,
+        request: LanguageModelRequest,
+        next: Next,
+    ) -> Result<AssistantEventStream> {
+        let mut interaction = CassetteInteraction {
+            key: cassette_key(&request.model, &request.context)?,
+            provider: request.model.provider.clone(),
+            model: request.model.id.clone(),
+            context: request.context.clone(),
+            options: recorded_options(&request),
+            events: Vec::new(),
+        };
+        let mut stream = next.run(request)?;
+        let recorder = self.clone();
+        // Save before yielding the terminal event: callers such as
+        // `complete` stop polling as soon as they see it.
+        Ok(async_stream::stream! {
+            while let Some(event) = stream.next().await {
+                if let Ok(event) = &event {
+                    interaction.events.push(event.clone());
+                    if matches!(
+                        event,
+                        AssistantMessageEvent::Done { .. } | AssistantMessageEvent::Error { .. }
+                    ) && let Err(error) = recorder.append(interaction.clone())
+                    {
+                        yield Err(error);
+                        return;
+                    }
+                }
+                yield event;
+            }
+        }
+        .boxed())
+    }
+}
+
+impl CassetteRecorder {
+    fn append(&self, interaction: CassetteInteraction) -> Result<()> {
+        let mut cassette = self.cassette.lock();
+        cassette.interactions.push(interaction);
+        cassette.save(&self.path)
+    }
+}
+
+fn recorded_options(request: &LanguageModelRequest) -> Value {
+    let options = &request.options.stream;
+    json!({
+        "simple": request.simple,
+        "reasoning": request.options.reasoning,
+        "temperature": options.temperature,
+        "maxTokens": options.max_tokens,
+        "topP": options.top_p,
+        "topK": options.top_k,
+        "stop": options.stop,
+        "seed": options.seed,
+        "frequencyPenalty": options.frequency_penalty,
+        "presencePenalty": options.presence_penalty,
+        "sessionId": options.session_id,
+        "metadata": options.metadata,
+        "providerOptions": options.provider_options,
+    })
+}
+
+/// Serves recorded events for calls whose [`cassette_key`] matches an
+/// interaction. Interactions sharing a key are served in recorded order, and
+/// the last one repeats once the others are used up.
+#[derive(Clone)]
+pub struct ReplayModelApi {
+    api: String,
+    interactions: Arc<Mutex<HashMap<String, Vec<Vec<AssistantMessageEvent>>>>>,
+}
+
+impl ReplayModelApi {
+    /// `api` is the id reported through `LanguageModelApi::id`; use the
+    /// recorded model's `api` so the replay model matches the original.
+    pub fn new(api: &str, cassette: Cassette) -> Self {
+        let mut interactions = HashMap::<String, Vec<_>>::new();
+        for interaction in cassette.interactions.into_iter().rev() {
+            interactions
+                .entry(interaction.key)
+                .or_default()
+                .push(interaction.events);
+        }
+        Self {
+            api: api.to_string(),
+            interactions: Arc::new(Mutex::new(interactions)),
+        }
+    }
+
+    fn replay(&self, model: &Model, context: &Context) -> Result<AssistantEventStream> {
+        let key = cassette_key(model, context)?;
+        let mut interactions = self.interactions.lock();
+        let recorded = interactions.get_mut(&key).ok_or_else(|| {
+            Error::Validation(format!(
+                "no recorded interaction for {}/{} with cassette key {key}",
+                model.provider, model.id
+            ))
+        })?;
+        let events = if recorded.len() > 1 {
+            recorded.pop().expect("recorded interaction")
+        } else {
+            recorded[0].clone()
+        };
+        Ok(futures::stream::iter(events.into_iter().map(Ok)).boxed())
+    }
+}
+
+impl LanguageModelApi for ReplayModelApi {
+    fn id(&self) -> &str {
+        &self.api
+    }
+
+    fn stream(
+        &self,
+        model: Model,
+        context: Context,
+        _options: StreamOptions,
+    ) -> Result<AssistantEventStream> {
+        self.replay(&model, &context)
+    }
+
+    fn stream_simple(
+        &self,
+        model: Model,
+        context: Context,
+        _options: SimpleStreamOptions,
+    ) -> Result<AssistantEventStream> {
+        self.replay(&model, &context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::faux::{faux_assistant_message, register_faux_provider};
+    use crate::types::{AssistantContent, AssistantMessage, Message, Tool, ToolCall, UserMessage};
+
+    fn context(messages: Vec<Message>) -> Context {
+        Context::builder()
+            .system_prompt("Be brief.")
+            .messages(messages)
+            .build()
+    }
+
+    fn user(text: &str, timestamp: u64) -> Message {
+        Message::User(UserMessage {
+            timestamp,
+            ..UserMessage::text(text)
+        })
+    }
+
+    #[tokio::test]
+    async fn replays_a_recorded_session_offline() {
+        let path = std::env::temp_dir()
+            .join(format!("ai-cassette-{}", std::process::id()))
+            .join("session.json");
+        let faux = register_faux_provider(None);
+        faux.set_responses([
+            faux_assistant_message("first answer", None),
+            faux_assistant_message("second answer", None),
+        ]);
+        let model = faux.get_model();
+        let recording = record_model(model.clone(), &path).unwrap();
+
+        let first = crate::complete(recording.clone(), context(vec![user("one", 1)]), None)
+            .await
+            .unwrap();
+        let second = crate::complete_simple(
+            recording,
+            context(vec![
+                user("one", 1),
+                Message::Assistant(first.clone()),
+                user("two", 2),
+            ]),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let cassette = Cassette::load(&path).unwrap();
+        assert_eq!(cassette.interactions.len(), 2);
+        assert_eq!(cassette.interactions[1].options["simple"], true);
+
+        faux.unregister();
+        let replay = replay_model(model, &path).unwrap();
+        let replayed_first = crate::complete(replay.clone(), context(vec![user("one", 100)]), None)
+            .await
+            .unwrap();
+        let replayed_second = crate::complete(
+            replay.clone(),
+            context(vec![
+                user("one", 100),
+                Message::Assistant(AssistantMessage {
+                    timestamp: 101,
+                    ..replayed_first.clone()
+                }),
+                user("two", 102),
+            ]),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(replayed_first, first);
+        assert_eq!(replayed_second, second);
+        assert!(matches!(
+            crate::stream(replay, context(vec![user("three", 1)]), None),
+            Err(Error::Validation(_))
+        ));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn cassette_key_ignores_volatile_fields_but_not_content() {
+        let model = Model {
+            provider: "openai".to_string(),
+            id: "gpt-5.5".to_string(),
+            ..Model::default()
+        };
+
+        assert_eq!(
+            cassette_key(&model, &context(vec![user("hi", 1)])).unwrap(),
+            cassette_key(&model, &context(vec![user("hi", 2)])).unwrap()
+        );
+        assert_ne!(
+            cassette_key(&model, &context(vec![user("hi", 1)])).unwrap(),
+            cassette_key(&model, &context(vec![user("bye", 1)])).unwrap()
+        );
+    }
+
+    #[test]
+    fn cassette_key_keeps_volatile_names_inside_tool_calls_and_schemas() {
+        let model = Model {
+            provider: "openai".to_string(),
+            id: "gpt-5.5".to_string(),
+            ..Model::default()
+        };
+        let tool_call = |timestamp: &str| {
+            context(vec![Message::Assistant(AssistantMessage {
+                content: vec![AssistantContent::ToolCall(ToolCall {
+                    id: "call_1".to_string(),
+                    name: "log_event".to_string(),
+                    arguments: json!({ "timestamp": timestamp }),
+                    thought_signature: None,
+                })],
+                ..faux_assistant_message("", None)
+            })])
+        };
+        let tool = |property: &str| Context {
+            tools: vec![
+                Tool::builder("log_event")
+                    .description("Logs an event.")
+                    .parameters(json!({ "properties": { property: { "type": "string" } } }))
+                    .build()
+                    .unwrap(),
+            ],
+            ..context(vec![user("hi", 1)])
+        };
+
+        assert_ne!(
+            cassette_key(&model, &tool_call("2026-01-01")).unwrap(),
+            cassette_key(&model, &tool_call("2026-01-02")).unwrap()
+        );
+        assert_ne!(
+            cassette_key(&model, &tool("timestamp")).unwrap(),
+            cassette_key(&model, &tool("usage")).unwrap()
+        );
+    }
+}
@@ -4,6 +4,12 @@ pub const ANTHROPIC_OAUTH_TOKEN_ENV_VAR: &str = "ANTHROPIC_OAUTH_TOKEN";
 pub const ANTHROPIC_API_KEY_ENV_VAR: &str = "ANTHROPIC_API_KEY";
 pub const OPENAI_API_KEY_ENV_VAR: &str = "OPENAI_API_KEY";
 pub const OPENROUTER_API_KEY_ENV_VAR: &str = "OPENROUTER_API_KEY";
+pub const GEMINI_API_KEY_ENV_VAR: &str = "GEMINI_API_KEY";
+pub const AWS_BEARER_TOKEN_BEDROCK_ENV_VAR: &str = "AWS_BEARER_TOKEN_BEDROCK";
+pub const AZURE_OPENAI_API_KEY_ENV_VAR: &str = "AZURE_OPENAI_API_KEY";
+pub const OLLAMA_API_KEY_ENV_VAR: &str = "OLLAMA_API_KEY";
+pub const MISTRAL_API_KEY_ENV_VAR: &str = "MISTRAL_API_KEY";
+pub const COHERE_API_KEY_ENV_VAR: &str = "COHERE_API_KEY";
 
 #[cfg(test)]
 pub(crate) static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
@@ -14,6 +20,12 @@ pub enum KnownProvider {
     Anthropic,
     OpenAi,
     OpenRouter,
+    Google,
+    AmazonBedrock,
+    AzureOpenAi,
+    Ollama,
+    Mistral,
+    Cohere,
 }
 
 impl KnownProvider {
@@ -23,6 +35,12 @@ impl KnownProvider {
             Self::Anthropic => "anthropic",
             Self::OpenAi => "openai",
             Self::OpenRouter => "openrouter",
+            Self::Google => "google",
+            Self::AmazonBedrock => "amazon-bedrock",
+            Self::AzureOpenAi => "azure-openai",
+            Self::Ollama => "ollama",
+            Self::Mistral => "mistral",
+            Self::Cohere => "cohere",
         }
     }
 }
@@ -74,6 +92,27 @@ pub(crate) fn get_env_api_key_with_env(
         provider if provider == KnownProvider::OpenRouter.as_str() => {
             crate::utils::provider_env::get_provider_env_value(OPENROUTER_API_KEY_ENV_VAR, env)
         }
+        provider if provider == KnownProvider::Google.as_str() => {
+            crate::utils::provider_env::get_provider_env_value(GEMINI_API_KEY_ENV_VAR, env)
+        }
+        provider if provider == KnownProvider::AmazonBedrock.as_str() => {
+            crate::utils::provider_env::get_provider_env_value(
+                AWS_BEARER_TOKEN_BEDROCK_ENV_VAR,
+                env,
+            )
+        }
+        provider if provider == KnownProvider::AzureOpenAi.as_str() => {
+            crate::utils::provider_env::get_provider_env_value(AZURE_OPENAI_API_KEY_ENV_VAR, env)
+        }
+        provider if provider == KnownProvider::Ollama.as_str() => {
+            crate::utils::provider_env::get_provider_env_value(OLLAMA_API_KEY_ENV_VAR, env)
+        }
+        provider if provider == KnownProvider::Mistral.as_str() => {
+            crate::utils::provider_env::get_provider_env_value(MISTRAL_API_KEY_ENV_VAR, env)
+        }
+        provider if provider == KnownProvider::Cohere.as_str() => {
+            crate::utils::provider_env::get_provider_env_value(COHERE_API_KEY_ENV_VAR, env)
+        }
         _ => None,
     }
 }
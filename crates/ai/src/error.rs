@@ -38,6 +38,12 @@ pub enum Error {
     #[error("{0}")]
     Validation(String),
 
+    #[error("circuit open for {endpoint} after repeated failures; retry in {retry_after_ms}ms")]
+    CircuitOpen {
+        endpoint: String,
+        retry_after_ms: u64,
+    },
+
     #[error("request was cancelled")]
     Cancelled,
 
@@ -54,4 +60,19 @@ impl Error {
             capability,
         }
     }
+
+    /// The open circuit behind an `Error::CircuitOpen`, for recording on a
+    /// failed `AssistantMessage`.
+    pub fn open_circuit(&self) -> Option<crate::types::OpenCircuit> {
+        match self {
+            Self::CircuitOpen {
+                endpoint,
+                retry_after_ms,
+            } => Some(crate::types::OpenCircuit {
+                endpoint: endpoint.clone(),
+                retry_after_ms: *retry_after_ms,
+            }),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,498 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+use crate::event_stream::AssistantEventStream;
+use crate::provider::{LanguageModelApi, ModelBuilder};
+use crate::stream::{track_pooled_api_key, with_env_api_key, with_env_api_key_simple};
+use crate::types::{
+    AssistantMessage, AssistantMessageEvent, Context, Model, SimpleStreamOptions, StopReason,
+    StreamOptions,
+};
+use crate::utils::diagnostics::{
+    AssistantMessageDiagnostic, DiagnosticErrorInfo, append_assistant_message_diagnostic,
+    create_assistant_message_diagnostic,
+};
+use crate::{Error, Result};
+
+pub(crate) const FALLBACK_API: &str = "fallback";
+const FALLBACK_PROVIDER: &str = "fallback";
+const FALLBACK_DIAGNOSTIC: &str = "model-fallback";
+
+/// Serves each request from the first model in an ordered list that answers.
+///
+/// A model that fails before emitting any content (an HTTP 429 or 5xx, a
+/// context overflow, a transport error, or any other error) is skipped and the
+/// request is retried on the next model. Once a model has streamed content,
+/// its stream is passed through unchanged, including any later error.
+/// Cancellation never falls through to the next model.
+///
+/// The final `AssistantMessage` carries the `api`, `provider`, and `model` of
+/// the model that answered, plus a `model-fallback` diagnostic for every model
+/// that was skipped. A model skipped because its circuit breaker was open has
+/// the `circuit_open` details under `details.circuitOpen`.
+///
+/// API keys resolve per model: provider handles keep their own keys and
+/// environment keys are looked up for each model's provider. An explicit
+/// `StreamOptions::api_key` is sent to every model, so leave it unset when the
+/// models belong to different providers.
+#[derive(Clone)]
+pub struct FallbackModelApi {
+    models: Vec<Model>,
+}
+
+impl FallbackModelApi {
+    pub fn new(models: Vec<Model>) -> Result<Self> {
+        if models.is_empty() {
+            return Err(Error::Validation(
+                "fallback requires at least one model".to_string(),
+            ));
+        }
+        if let Some(model) = models.iter().find(|model| model.language_api().is_none()) {
+            return Err(Error::unsupported_capability(
+                model.provider.clone(),
+                "language models",
+            ));
+        }
+        Ok(Self { models })
+    }
+
+    pub fn models(&self) -> &[Model] {
+        &self.models
+    }
+
+    /// Builds a model served by this fallback chain. Metadata such as the
+    /// context window, input types, and cost starts from the first model.
+    pub fn model(&self, id: &str) -> ModelBuilder {
+        let primary = &self.models[0];
+        ModelBuilder::new(FALLBACK_PROVIDER, id, Arc::new(self.clone()))
+            .name(primary.name.clone())
+            .base_url(primary.base_url.clone())
+            .reasoning(primary.reasoning)
+            .input(primary.input.clone())
+            .cost(primary.cost.clone())
+            .context_window(primary.context_window)
+            .max_tokens(primary.max_tokens)
+    }
+}
+
+impl LanguageModelApi for FallbackModelApi {
+    fn id(&self) -> &str {
+        FALLBACK_API
+    }
+
+    fn stream(
+        &self,
+        _model: Model,
+        context: Context,
+        options: StreamOptions,
+    ) -> Result<AssistantEventStream> {
+        let cancellation_token = options.cancellation_token.clone();
+        Ok(fallback_stream(
+            self.models.clone(),
+            cancellation_token,
+            move |model| {
+                let api = model.language_api().expect("validated language model");
+                let options = with_env_api_key(&model, options.clone());
+                let track = track_pooled_api_key(&options);
+                api.stream(model, context.clone(), options).map(track)
+            },
+        ))
+    }
+
+    fn stream_simple(
+        &self,
+        _model: Model,
+        context: Context,
+        options: SimpleStreamOptions,
+    ) -> Result<AssistantEventStream> {
+        let cancellation_token = options.stream.cancellation_token.clone();
+        Ok(fallback_stream(
+            self.models.clone(),
+            cancellation_token,
+            move |model| {
+                let api = model.language_api().expect("validated language model");
+                let options = with_env_api_key_simple(&model, options.clone());
+                let track = track_pooled_api_key(&options.stream);
+                api.stream_simple(model, context.clone(), options)
+                    .map(track)
+            },
+        ))
+    }
+}
+
+fn fallback_stream<F>(
+    models: Vec<Model>,
+    cancellation_token: Option<CancellationToken>,
+    open: F,
+) -> AssistantEventStream
+where
+    F: Fn(Model) -> Result<AssistantEventStream> + Send + 'static,
+{
+    async_stream::stream! {
+        let last_index = models.len() - 1;
+        let mut skipped = Vec::new();
+
+        for (index, model) in models.into_iter().enumerate() {
+            let can_fall_back = index < last_index
+                && !cancellation_token
+                    .as_ref()
+                    .is_some_and(CancellationToken::is_cancelled);
+            let mut stream = match open(model.clone()) {
+                Ok(stream) => stream,
+                Err(error) if can_fall_back => {
+                    skipped.push(fallback_diagnostic(&model, error.to_string()));
+                    continue;
+                }
+                Err(error) => {
+                    yield Err(error);
+                    return;
+                }
+            };
+
+            // Hold back `start` until the model commits to an answer, so a
+            // skipped model leaves no trace in the caller's event stream.
+            let mut pending = Vec::new();
+            let mut committed = false;
+            while let Some(event) = stream.next().await {
+                match event {
+                    Ok(AssistantMessageEvent::Error { reason, mut error })
+                        if !committed && can_fall_back && reason != StopReason::Aborted =>
+                    {
+                        let message = error
+                            .error_message
+                            .take()
+                            .unwrap_or_else(|| "model returned an error".to_string());
+                        let mut diagnostic = fallback_diagnostic(&model, message);
+                        if let (Some(circuit_open), Some(details)) =
+                            (error.circuit_open.take(), diagnostic.details.as_mut())
+                        {
+                            details["circuitOpen"] = json!(circuit_open);
+                        }
+                        skipped.push(diagnostic);
+                        break;
+                    }
+                    Err(error)
+                        if !committed && can_fall_back && !matches!(error, Error::Cancelled) =>
+                    {
+                        skipped.push(fallback_diagnostic(&model, error.to_string()));
+                        break;
+                    }
+                    Ok(event @ AssistantMessageEvent::Start { .. }) if !committed => {
+                        pending.push(event);
+                    }
+                    Ok(event) => {
+                        committed = true;
+                        for event in pending.drain(..) {
+                            yield Ok(event);
+                        }
+                        yield Ok(with_skipped_diagnostics(event, &skipped));
+                    }
+                    Err(error) => {
+                        for event in pending.drain(..) {
+                            yield Ok(event);
+                        }
+                        yield Err(error);
+                        return;
+                    }
+                }
+            }
+            if committed || !can_fall_back {
+                for event in pending {
+                    yield Ok(event);
+                }
+                return;
+            }
+            if skipped.len() <= index {
+                skipped.push(fallback_diagnostic(&model, Error::StreamClosed.to_string()));
+            }
+        }
+    }
+    .boxed()
+}
+
+fn fallback_diagnostic(model: &Model, message: String) -> AssistantMessageDiagnostic {
+    create_assistant_message_diagnostic(
+        FALLBACK_DIAGNOSTIC,
+        DiagnosticErrorInfo {
+            name: None,
+            message,
+            stack: None,
+            code: None,
+        },
+        Some(json!({
+            "api": model.api,
+            "provider": model.provider,
+            "model": model.id,
+        })),
+    )
+}
+
+fn with_skipped_diagnostics(
+    event: AssistantMessageEvent,
+    skipped: &[AssistantMessageDiagnostic],
+) -> AssistantMessageEvent {
+    let append = |message: &mut AssistantMessage| {
+        for diagnostic in skipped {
+            append_assistant_message_diagnostic(message, diagnostic.clone());
+        }
+    };
+    match event {
+        AssistantMessageEvent::Done {
+            reason,
+            mut message,
+        } => {
+            append(&mut message);
+            AssistantMessageEvent::Done { reason, message }
+        }
+        AssistantMessageEvent::Error { reason, mut error } => {
+            append(&mut error);
+            AssistantMessageEvent::Error { reason, error }
+        }
+        event => event,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::key_pool::ApiKeyPool;
+    use crate::providers::faux::{
+        FauxAssistantMessageOptions, FauxProviderRegistration, FauxResponseStep,
+        RegisterFauxProviderOptions, faux_assistant_message, register_faux_provider,
+    };
+    use crate::types::{AssistantContent, Message, UserMessage};
+
+    fn faux(provider: &str) -> FauxProviderRegistration {
+        register_faux_provider(Some(RegisterFauxProviderOptions {
+            provider: Some(provider.to_string()),
+            ..Default::default()
+        }))
+    }
+
+    fn rate_limited() -> FauxResponseStep {
+        FauxResponseStep::factory(|_, _, _, _| async {
+            Err(Error::ApiStatus {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                body: "rate limited".to_string(),
+            })
+        })
+    }
+
+    async fn collect(model: Model) -> Vec<AssistantMessageEvent> {
+        crate::stream(
+            model,
+            Context::builder()
+                .message(Message::User(UserMessage::text("hi")))
+                .build(),
+            None,
+        )
+        .unwrap()
+        .map(|event| event.unwrap())
+        .collect()
+        .await
+    }
+
+    #[tokio::test]
+    async fn falls_back_when_a_model_fails_before_content() {
+        let primary = faux("primary");
+        let backup = faux("backup");
+        primary.set_responses([rate_limited()]);
+        backup.set_responses([faux_assistant_message("from backup", None)]);
+        let model = FallbackModelApi::new(vec![primary.get_model(), backup.get_model()])
+            .unwrap()
+            .model("agent")
+            .build()
+            .unwrap();
+
+        let events = collect(model).await;
+
+        let starts = events
+            .iter()
+            .filter(|event| matches!(event, AssistantMessageEvent::Start { .. }))
+            .count();
+        assert_eq!(starts, 1);
+        let Some(AssistantMessageEvent::Done { message, .. }) = events.last() else {
+            panic!("expected done event");
+        };
+        assert_eq!(message.provider, "backup");
+        assert_eq!(message.model, backup.get_model().id);
+        assert!(matches!(
+            &message.content[..],
+            [AssistantContent::Text(text)] if text.text == "from backup"
+        ));
+        assert_eq!(message.diagnostics.len(), 1);
+        assert_eq!(message.diagnostics[0]["type"], "model-fallback");
+        assert_eq!(message.diagnostics[0]["details"]["provider"], "primary");
+        assert!(
+            message.diagnostics[0]["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("429")
+        );
+    }
+
+    #[tokio::test]
+    async fn draws_a_pooled_key_for_each_model_tried() {
+        let primary = faux("primary");
+        let backup = faux("backup");
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = |seen: Arc<std::sync::Mutex<Vec<String>>>, fail: bool| {
+            FauxResponseStep::factory(move |_, options, _, _| {
+                seen.lock().unwrap().extend(options.api_key.clone());
+                async move {
+                    if fail {
+                        return Err(Error::ApiStatus {
+                            status: StatusCode::TOO_MANY_REQUESTS,
+                            body: "rate limited".to_string(),
+                        });
+                    }
+                    Ok(faux_assistant_message("from backup", None))
+                }
+            })
+        };
+        primary.set_responses([record(Arc::clone(&seen), true)]);
+        backup.set_responses([record(Arc::clone(&seen), false)]);
+        let model = FallbackModelApi::new(vec![primary.get_model(), backup.get_model()])
+            .unwrap()
+            .model("agent")
+            .build()
+            .unwrap();
+        let pool = ApiKeyPool::new(["key-a", "key-b"]);
+
+        let message = crate::complete(
+            model,
+            Context::builder()
+                .message(Message::User(UserMessage::text("hi")))
+                .build(),
+            Some(StreamOptions {
+                api_key_pool: Some(pool.clone()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(message.provider, "backup");
+        assert_eq!(*seen.lock().unwrap(), ["key-a", "key-b"]);
+        let usage = pool.usage();
+        assert_eq!((usage[0].requests, usage[0].rate_limited), (1, 1));
+        assert_eq!((usage[1].requests, usage[1].successes), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn passes_through_errors_after_content_and_when_every_model_fails() {
+        let primary = faux("primary");
+        let backup = faux("backup");
+        primary.set_responses([faux_assistant_message(
+            "partial",
+            Some(FauxAssistantMessageOptions {
+                stop_reason: Some(StopReason::Error),
+                error_message: Some("connection reset".to_string()),
+                ..Default::default()
+            }),
+        )]);
+        let fallback =
+            FallbackModelApi::new(vec![primary.get_model(), backup.get_model()]).unwrap();
+
+        let events = collect(fallback.model("agent").build().unwrap()).await;
+
+        let Some(AssistantMessageEvent::Error { error, .. }) = events.last() else {
+            panic!("expected error event");
+        };
+        assert_eq!(error.provider, "primary");
+        assert_eq!(error.error_message.as_deref(), Some("connection reset"));
+        assert_eq!(backup.state.call_count(), 0);
+
+        primary.set_responses([rate_limited()]);
+        backup.set_responses([rate_limited()]);
+        let events = collect(fallback.model("agent").build().unwrap()).await;
+
+        let Some(AssistantMessageEvent::Error { error, .. }) = events.last() else {
+            panic!("expected error event");
+        };
+        assert_eq!(error.provider, "backup");
+        assert_eq!(error.diagnostics.len(), 1);
+        assert_eq!(error.diagnostics[0]["details"]["provider"], "primary");
+    }
+
+    #[tokio::test]
+    async fn reports_an_open_circuit_and_falls_back_past_it() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buffer = vec![0u8; 16 * 1024];
+                let _ = socket.read(&mut buffer).await;
+                let body = r#"{"error":{"message":"overloaded"}}"#;
+                let response = format!(
+                    "HTTP/1.1 503 Service Unavailable\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        let primary = crate::providers::openai::builder()
+            .api_key(Some("test-key"))
+            .base_url(base_url)
+            .build()
+            .unwrap()
+            .model("gpt-5.5")
+            .build()
+            .unwrap();
+        let context = || {
+            Context::builder()
+                .message(Message::User(UserMessage::text("hi")))
+                .build()
+        };
+
+        for _ in 0..5 {
+            let message = crate::complete(primary.clone(), context(), None)
+                .await
+                .unwrap();
+            assert_eq!(message.error_status, Some(503));
+            assert_eq!(message.circuit_open, None);
+        }
+        let message = crate::complete(primary.clone(), context(), None)
+            .await
+            .unwrap();
+        assert_eq!(message.stop_reason, StopReason::Error);
+        assert_eq!(message.error_status, None);
+        let circuit_open = message.circuit_open.expect("circuit open");
+        assert!(circuit_open.endpoint.ends_with("/v1 (gpt-5.5)"));
+        assert!(circuit_open.retry_after_ms > 0);
+
+        let backup = faux("backup");
+        backup.set_responses([faux_assistant_message("from backup", None)]);
+        let model = FallbackModelApi::new(vec![primary, backup.get_model()])
+            .unwrap()
+            .model("agent")
+            .build()
+            .unwrap();
+        let message = crate::complete(model, context(), None).await.unwrap();
+
+        assert_eq!(message.provider, "backup");
+        assert_eq!(
+            message.diagnostics[0]["details"]["circuitOpen"]["endpoint"],
+            circuit_open.endpoint
+        );
+    }
+
+    #[test]
+    fn requires_language_models() {
+        assert!(matches!(
+            FallbackModelApi::new(Vec::new()),
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            FallbackModelApi::new(vec![Model::default()]),
+            Err(Error::UnsupportedCapability { .. })
+        ));
+    }
+}
@@ -0,0 +1,380 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use parking_lot::Mutex;
+
+use crate::Error;
+use crate::event_stream::AssistantEventStream;
+use crate::types::{AssistantMessageEvent, StopReason, Usage};
+
+const DEFAULT_RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(60);
+const DEFAULT_AUTH_FAILURE_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+const KEY_SUFFIX_CHARS: usize = 4;
+
+/// A set of API keys for one provider, used in rotation.
+///
+/// Set it as `StreamOptions::api_key_pool`; an explicit
+/// `StreamOptions::api_key` still wins. Each call takes the next healthy key
+/// in round-robin order. A key whose call fails with HTTP 429 is quarantined
+/// for the rate-limit cooldown, and one that fails with HTTP 401 or 403 for
+/// the auth-failure cooldown. When every key is quarantined, the key whose
+/// quarantine ends first is used rather than failing the call.
+///
+/// Clones share rotation state and usage counters.
+#[derive(Clone)]
+pub struct ApiKeyPool {
+    keys: Arc<Mutex<PoolState>>,
+    rate_limit_cooldown: Duration,
+    auth_failure_cooldown: Duration,
+}
+
+/// Per-key counters reported by [`ApiKeyPool::usage`]. Keys are identified
+/// by their last few characters only.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiKeyUsage {
+    pub key_suffix: String,
+    pub requests: u64,
+    pub successes: u64,
+    pub rate_limited: u64,
+    pub unauthorized: u64,
+    /// Failed calls other than rate limits and auth failures.
+    pub failures: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub quarantined: bool,
+}
+
+struct PoolState {
+    keys: Vec<PooledKey>,
+    next: usize,
+}
+
+struct PooledKey {
+    key: String,
+    usage: ApiKeyUsage,
+    quarantined_until: Option<Instant>,
+}
+
+enum KeyOutcome {
+    Success(Usage),
+    Status(u16),
+    Failure,
+}
+
+impl ApiKeyPool {
+    /// Builds a pool from `keys`, skipping blank and duplicate entries.
+    pub fn new<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut pooled: Vec<PooledKey> = Vec::new();
+        for key in keys {
+            let key = key.into().trim().to_string();
+            if key.is_empty() || pooled.iter().any(|pooled| pooled.key == key) {
+                continue;
+            }
+            let start = key
+                .char_indices()
+                .rev()
+                .nth(KEY_SUFFIX_CHARS - 1)
+                .map_or(0, |(index, _)| index);
+            pooled.push(PooledKey {
+                usage: ApiKeyUsage {
+                    key_suffix: key[start..].to_string(),
+                    ..Default::default()
+                },
+                key,
+                quarantined_until: None,
+            });
+        }
+        Self {
+            keys: Arc::new(Mutex::new(PoolState {
+                keys: pooled,
+                next: 0,
+            })),
+            rate_limit_cooldown: DEFAULT_RATE_LIMIT_COOLDOWN,
+            auth_failure_cooldown: DEFAULT_AUTH_FAILURE_COOLDOWN,
+        }
+    }
+
+    /// How long a key stays out of rotation after an HTTP 429. Defaults to one
+    /// minute.
+    pub fn rate_limit_cooldown(mut self, cooldown: Duration) -> Self {
+        self.rate_limit_cooldown = cooldown;
+        self
+    }
+
+    /// How long a key stays out of rotation after an HTTP 401 or 403.
+    /// Defaults to one hour.
+    pub fn auth_failure_cooldown(mut self, cooldown: Duration) -> Self {
+        self.auth_failure_cooldown = cooldown;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.lock().keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the next key in rotation, skipping quarantined keys.
+    pub fn next_key(&self) -> Option<String> {
+        let now = Instant::now();
+        let mut state = self.keys.lock();
+        let count = state.keys.len();
+        let healthy = (0..count)
+            .map(|offset| (state.next + offset) % count)
+            .find(|index| {
+                state.keys[*index]
+                    .quarantined_until
+                    .is_none_or(|until| until <= now)
+            });
+        let index = healthy.or_else(|| {
+            state
+                .keys
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, key)| key.quarantined_until)
+                .map(|(index, _)| index)
+        })?;
+        state.next = (index + 1) % count;
+        Some(state.keys[index].key.clone())
+    }
+
+    /// Current counters for every key, in the order the keys were given.
+    pub fn usage(&self) -> Vec<ApiKeyUsage> {
+        let now = Instant::now();
+        self.keys
+            .lock()
+            .keys
+            .iter()
+            .map(|key| ApiKeyUsage {
+                quarantined: key.quarantined_until.is_some_and(|until| until > now),
+                ..key.usage.clone()
+            })
+            .collect()
+    }
+
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.keys.lock().keys.iter().any(|pooled| pooled.key == key)
+    }
+
+    /// Counts a call made with `key` and records how it ends.
+    pub(crate) fn track(&self, key: String, stream: AssistantEventStream) -> AssistantEventStream {
+        self.with_key(&key, |pooled| pooled.usage.requests += 1);
+        let pool = self.clone();
+        stream
+            .inspect(move |event| {
+                let outcome = match event {
+                    Ok(AssistantMessageEvent::Done { message, .. }) => {
+                        KeyOutcome::Success(message.usage.clone())
+                    }
+                    Ok(AssistantMessageEvent::Error { reason, .. })
+                        if *reason == StopReason::Aborted =>
+                    {
+                        return;
+                    }
+                    Ok(AssistantMessageEvent::Error { error, .. }) => error
+                        .error_status
+                        .map_or(KeyOutcome::Failure, KeyOutcome::Status),
+                    Err(Error::Cancelled) => return,
+                    Err(Error::ApiStatus { status, .. }) => KeyOutcome::Status(status.as_u16()),
+                    Err(_) => KeyOutcome::Failure,
+                    Ok(_) => return,
+                };
+                pool.record(&key, outcome);
+            })
+            .boxed()
+    }
+
+    fn record(&self, key: &str, outcome: KeyOutcome) {
+        let now = Instant::now();
+        let (rate_limit_cooldown, auth_failure_cooldown) =
+            (self.rate_limit_cooldown, self.auth_failure_cooldown);
+        self.with_key(key, |pooled| match outcome {
+            KeyOutcome::Success(usage) => {
+                pooled.usage.successes += 1;
+                pooled.usage.input_tokens += u64::from(usage.input);
+                pooled.usage.output_tokens += u64::from(usage.output);
+            }
+            KeyOutcome::Status(429) => {
+                pooled.usage.rate_limited += 1;
+                pooled.quarantined_until = Some(now + rate_limit_cooldown);
+            }
+            KeyOutcome::Status(401 | 403) => {
+                pooled.usage.unauthorized += 1;
+                pooled.quarantined_until = Some(now + auth_failure_cooldown);
+            }
+            KeyOutcome::Status(_) | KeyOutcome::Failure => pooled.usage.failures += 1,
+        });
+    }
+
+    fn with_key(&self, key: &str, update: impl FnOnce(&mut PooledKey)) {
+        if let Some(pooled) = self
+            .keys
+            .lock()
+            .keys
+            .iter_mut()
+            .find(|pooled| pooled.key == key)
+        {
+            update(pooled);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::providers::faux::{
+        FauxResponseStep, faux_assistant_message, register_faux_provider,
+    };
+    use crate::types::{Context, Message, StreamOptions, UserMessage};
+
+    fn context() -> Context {
+        Context::builder()
+            .message(Message::User(UserMessage::text("hi")))
+            .build()
+    }
+
+    fn answer_unless_key(bad_key: &'static str, status: StatusCode) -> FauxResponseStep {
+        FauxResponseStep::factory(move |_, options, _, _| async move {
+            if options.api_key.as_deref() == Some(bad_key) {
+                return Err(Error::ApiStatus {
+                    status,
+                    body: "nope".to_string(),
+                });
+            }
+            Ok(faux_assistant_message("ok", None))
+        })
+    }
+
+    #[tokio::test]
+    async fn rotates_keys_and_quarantines_rate_limited_ones() {
+        let faux = register_faux_provider(None);
+        let step = answer_unless_key("key-b", StatusCode::TOO_MANY_REQUESTS);
+        faux.set_responses(vec![step; 4]);
+        let pool = ApiKeyPool::new(["key-a", "key-b", "key-c", "key-a", " "]);
+        let options = StreamOptions {
+            api_key_pool: Some(pool.clone()),
+            ..Default::default()
+        };
+
+        let mut stop_reasons = Vec::new();
+        for _ in 0..4 {
+            let message = crate::complete(faux.get_model(), context(), Some(options.clone()))
+                .await
+                .unwrap();
+            stop_reasons.push(message.stop_reason);
+        }
+
+        assert_eq!(
+            stop_reasons,
+            vec![
+                StopReason::Stop,
+                StopReason::Error,
+                StopReason::Stop,
+                StopReason::Stop
+            ]
+        );
+        let usage = pool.usage();
+        assert_eq!(usage.len(), 3);
+        assert_eq!(
+            (usage[0].requests, usage[1].requests, usage[2].requests),
+            (2, 1, 1)
+        );
+        assert_eq!(usage[0].successes, 2);
+        assert!(usage[0].output_tokens > 0);
+        assert_eq!(usage[1].key_suffix, "ey-b");
+        assert_eq!(usage[1].rate_limited, 1);
+        assert!(usage[1].quarantined);
+        assert!(!usage[0].quarantined);
+    }
+
+    #[tokio::test]
+    async fn explicit_api_keys_bypass_the_pool() {
+        let faux = register_faux_provider(None);
+        faux.set_responses([answer_unless_key("revoked", StatusCode::UNAUTHORIZED)]);
+        let pool = ApiKeyPool::new(["pooled"]);
+
+        crate::complete(
+            faux.get_model(),
+            context(),
+            Some(StreamOptions {
+                api_key: Some("explicit".to_string()),
+                api_key_pool: Some(pool.clone()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(pool.usage()[0].requests, 0);
+    }
+
+    #[test]
+    fn uses_the_key_that_recovers_first_when_all_are_quarantined() {
+        let pool = ApiKeyPool::new(["key-a", "key-b"])
+            .rate_limit_cooldown(Duration::from_secs(60))
+            .auth_failure_cooldown(Duration::from_secs(3600));
+        pool.record("key-a", KeyOutcome::Status(401));
+        pool.record("key-b", KeyOutcome::Status(429));
+
+        assert_eq!(pool.next_key().as_deref(), Some("key-b"));
+        assert_eq!(pool.usage()[0].unauthorized, 1);
+        assert_eq!(ApiKeyPool::new(Vec::<String>::new()).next_key(), None);
+    }
+
+    #[tokio::test]
+    async fn quarantines_keys_rejected_by_a_provider_error_response() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 16 * 1024];
+            let _ = socket.read(&mut buffer).await.unwrap();
+            let body = r#"{"error":{"message":"Rate limit reached","type":"requests"}}"#;
+            let response = format!(
+                "HTTP/1.1 429 Too Many Requests\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        let model = crate::providers::openai::builder()
+            .base_url(base_url)
+            .responses()
+            .build()
+            .unwrap()
+            .model("gpt-5.5")
+            .build()
+            .unwrap();
+        let pool = ApiKeyPool::new(["key-a", "key-b"]);
+
+        let message = crate::complete(
+            model,
+            context(),
+            Some(StreamOptions {
+                api_key_pool: Some(pool.clone()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            message.error_message.as_deref(),
+            Some("OpenAI API error (429): Rate limit reached")
+        );
+        assert_eq!(message.error_status, Some(429));
+        let usage = pool.usage();
+        assert_eq!(usage[0].rate_limited, 1);
+        assert!(usage[0].quarantined);
+        assert_eq!(pool.next_key().as_deref(), Some("key-b"));
+    }
+}
@@ -1,18 +1,30 @@
+extern crate self as ai;
+
 pub mod agent;
 pub mod agent_error;
 pub mod agent_loop;
 pub mod agent_types;
+pub mod audio;
+pub mod cache;
+pub mod cassette;
 pub mod embeddings;
 pub mod env_api_keys;
 pub mod error;
 pub mod event_stream;
+pub mod fallback;
 pub mod images;
+pub mod key_pool;
+pub mod middleware;
+pub mod model_registry;
 mod models;
 pub mod oauth;
+pub mod object;
 pub mod provider;
 pub mod providers;
+pub mod rerank;
 pub mod session_resources;
 pub mod stream;
+pub mod tool_schema;
 pub mod types;
 pub mod utils;
 
@@ -25,17 +37,29 @@ pub use agent_loop::{
     AgentEventStream, agent_loop, agent_loop_continue, run_agent_loop, run_agent_loop_continue,
 };
 pub use agent_types::*;
+/// Derive macro for [`ToolSchema`].
+pub use ai_macros::ToolSchema;
+pub use audio::{generate_speech, transcribe};
+pub use cache::{CacheStore, DiskCache, MemoryCache, ResponseCache, with_response_cache};
+pub use cassette::{
+    Cassette, CassetteInteraction, ReplayModelApi, cassette_key, record_model, replay_model,
+};
 pub use embeddings::{embed, embed_many};
 pub use env_api_keys::{
     ANTHROPIC_API_KEY_ENV_VAR, ANTHROPIC_AUTH_TOKEN_ENV_VAR, ANTHROPIC_OAUTH_TOKEN_ENV_VAR,
-    GITHUB_COPILOT_TOKEN_ENV_VAR, KnownProvider, OPENAI_API_KEY_ENV_VAR,
-    OPENROUTER_API_KEY_ENV_VAR, get_env_api_key,
+    AWS_BEARER_TOKEN_BEDROCK_ENV_VAR, AZURE_OPENAI_API_KEY_ENV_VAR, COHERE_API_KEY_ENV_VAR,
+    GEMINI_API_KEY_ENV_VAR, GITHUB_COPILOT_TOKEN_ENV_VAR, KnownProvider, MISTRAL_API_KEY_ENV_VAR,
+    OLLAMA_API_KEY_ENV_VAR, OPENAI_API_KEY_ENV_VAR, OPENROUTER_API_KEY_ENV_VAR, get_env_api_key,
 };
 pub use error::{Error, Result};
 pub use event_stream::{
     AssistantEventStream, AssistantMessageEventStreamSender, create_assistant_message_event_stream,
 };
+pub use fallback::FallbackModelApi;
 pub use images::generate_images;
+pub use key_pool::{ApiKeyPool, ApiKeyUsage};
+pub use middleware::{LanguageModelMiddleware, LanguageModelRequest, wrap_model};
+pub use model_registry::ModelRegistry;
 pub use models::{
     calculate_cost, clamp_thinking_level, get_supported_thinking_levels, models_are_equal,
 };
@@ -51,14 +75,22 @@ pub use oauth::{
     refresh_anthropic_token, refresh_github_copilot_token, refresh_oauth_token,
     register_oauth_provider, reset_oauth_providers, unregister_oauth_provider,
 };
+pub use object::{
+    GeneratedObject, ObjectStream, ObjectStreamEvent, generate_object, stream_object,
+};
 pub use provider::{
-    EmbeddingModelApi, ImageModelApi, LanguageModelApi, ModelBuilder, Provider,
-    ProviderCapabilities,
+    EmbeddingModelApi, FimModelApi, ImageModelApi, LanguageModelApi, ModelBuilder, Provider,
+    ProviderCapabilities, RerankModelApi, SpeechModelApi, TranscriptionModelApi,
 };
 pub use providers::anthropic::{
     Anthropic, AnthropicEffort, AnthropicOptions, AnthropicThinkingDisplay, stream_anthropic,
     stream_simple_anthropic,
 };
+pub use providers::azure_openai::AzureOpenAi;
+pub use providers::bedrock::{
+    AwsCredentials, Bedrock, BedrockOptions, stream_bedrock, stream_simple_bedrock,
+};
+pub use providers::cohere::{Cohere, CohereOptions, stream_cohere, stream_simple_cohere};
 pub use providers::faux::{
     FauxAssistantContent, FauxAssistantMessageOptions, FauxModelDefinition,
     FauxProviderRegistration, FauxProviderState, FauxResponseStep, FauxTokenSize,
@@ -66,6 +98,13 @@ pub use providers::faux::{
     register_faux_provider,
 };
 pub use providers::github_copilot::{GitHubCopilot, GitHubCopilotApi};
+pub use providers::google::{
+    Google, GoogleOptions, GoogleThinkingLevel, stream_google, stream_simple_google,
+};
+pub use providers::mistral::{Mistral, stream_mistral_fim};
+pub use providers::ollama::{
+    Ollama, OllamaOptions, OllamaThink, stream_ollama, stream_simple_ollama,
+};
 pub use providers::openai::{OpenAi, OpenAiApi};
 pub use providers::openai_completions::{
     OpenAICompletionsOptions, stream_openai_completions, stream_simple_openai_completions,
@@ -74,16 +113,22 @@ pub use providers::openai_responses::{
     OpenAIResponsesOptions, stream_openai_responses, stream_simple_openai_responses,
 };
 pub use providers::openrouter::OpenRouter;
+pub use rerank::rerank;
 pub use session_resources::{
     SessionResourceCleanup, SessionResourceCleanupRegistration, cleanup_session_resources,
     register_session_resource_cleanup,
 };
-pub use stream::{complete, complete_simple, stream, stream_simple};
+pub use stream::{complete, complete_fim, complete_simple, stream, stream_fim, stream_simple};
+pub use tool_schema::ToolSchema;
 pub use types::*;
+pub use utils::circuit_breaker::{
+    CircuitBreakerConfig, reset_circuit_breakers, set_circuit_breaker_config,
+};
 pub use utils::diagnostics::{
     AssistantMessageDiagnostic, DiagnosticErrorInfo, append_assistant_message_diagnostic,
     create_assistant_message_diagnostic, extract_diagnostic_error, format_thrown_value,
 };
 pub use utils::json::{parse_json_with_repair, parse_streaming_json, repair_json};
 pub use utils::overflow::{get_overflow_patterns, is_context_overflow};
-pub use utils::validation::{validate_tool_arguments, validate_tool_call};
+pub use utils::rate_limit::{RateLimits, set_rate_limits};
+pub use utils::validation::{validate_json_value, validate_tool_arguments, validate_tool_call};
@@ -0,0 +1,272 @@
+use std::sync::Arc;
+
+use crate::event_stream::AssistantEventStream;
+use crate::provider::LanguageModelApi;
+use crate::types::{Context, Model, SimpleStreamOptions, StreamOptions};
+use crate::{Error, Result};
+
+/// A language model call as middleware sees it.
+///
+/// `stream` and `stream_simple` calls share this shape: `options.stream`
+/// holds the `StreamOptions`, and `simple` records which entry point was
+/// used so the call reaches the provider the same way.
+#[derive(Clone)]
+pub struct LanguageModelRequest {
+    pub model: Model,
+    pub context: Context,
+    pub options: SimpleStreamOptions,
+    pub simple: bool,
+}
+
+/// Intercepts language model calls with typed values, the same way for every
+/// provider.
+///
+/// `transform_request` rewrites the request on its way in. `wrap_stream`
+/// runs around the rest of the stack: it can inspect or replace the request,
+/// wrap the returned `AssistantEventStream`, or answer without calling
+/// `next` at all.
+pub trait LanguageModelMiddleware: dyn_clone::DynClone + Send + Sync + 'static {
+    fn transform_request(&self, request: LanguageModelRequest) -> Result<LanguageModelRequest> {
+        Ok(request)
+    }
+
+    fn wrap_stream(
+        &self,
+        request: LanguageModelRequest,
+        next: Next,
+    ) -> Result<AssistantEventStream> {
+        next.run(request)
+    }
+}
+
+dyn_clone::clone_trait_object!(LanguageModelMiddleware);
+
+/// The rest of the middleware stack, ending at the wrapped model's API.
+#[derive(Clone)]
+pub struct Next {
+    api: Arc<dyn LanguageModelApi>,
+}
+
+impl Next {
+    pub fn run(self, request: LanguageModelRequest) -> Result<AssistantEventStream> {
+        if request.simple {
+            self.api
+                .stream_simple(request.model, request.context, request.options)
+        } else {
+            self.api
+                .stream(request.model, request.context, request.options.stream)
+        }
+    }
+}
+
+/// Wraps `model` so its language API runs through `layers`. The first layer
+/// is outermost: it sees each request first and the returned stream last.
+/// The wrapped model keeps its id, api, and provider.
+pub fn wrap_model(
+    mut model: Model,
+    layers: impl IntoIterator<Item = Arc<dyn LanguageModelMiddleware>>,
+) -> Result<Model> {
+    let mut api = model
+        .language_api
+        .take()
+        .ok_or_else(|| Error::unsupported_capability(model.provider.clone(), "language models"))?;
+    let layers = layers.into_iter().collect::<Vec<_>>();
+    for layer in layers.into_iter().rev() {
+        api = Arc::new(MiddlewareModelApi { layer, inner: api });
+    }
+    model.language_api = Some(api);
+    Ok(model)
+}
+
+#[derive(Clone)]
+struct MiddlewareModelApi {
+    layer: Arc<dyn LanguageModelMiddleware>,
+    inner: Arc<dyn LanguageModelApi>,
+}
+
+impl MiddlewareModelApi {
+    fn call(&self, request: LanguageModelRequest) -> Result<AssistantEventStream> {
+        let request = self.layer.transform_request(request)?;
+        self.layer.wrap_stream(
+            request,
+            Next {
+                api: Arc::clone(&self.inner),
+            },
+        )
+    }
+}
+
+impl LanguageModelApi for MiddlewareModelApi {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    fn stream(
+        &self,
+        model: Model,
+        context: Context,
+        options: StreamOptions,
+    ) -> Result<AssistantEventStream> {
+        self.call(LanguageModelRequest {
+            model,
+            context,
+            options: SimpleStreamOptions {
+                stream: options,
+                ..Default::default()
+            },
+            simple: false,
+        })
+    }
+
+    fn stream_simple(
+        &self,
+        model: Model,
+        context: Context,
+        options: SimpleStreamOptions,
+    ) -> Result<AssistantEventStream> {
+        self.call(LanguageModelRequest {
+            model,
+            context,
+            options,
+            simple: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::providers::faux::{
+        FauxResponseStep, faux_assistant_message, register_faux_provider,
+    };
+    use crate::types::{AssistantMessage, AssistantMessageEvent, Message, StopReason, UserMessage};
+
+    #[derive(Clone)]
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl LanguageModelMiddleware for Recorder {
+        fn transform_request(
+            &self,
+            mut request: LanguageModelRequest,
+        ) -> Result<LanguageModelRequest> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} request", self.name));
+            let prompt = request.context.system_prompt.take().unwrap_or_default();
+            request.context.system_prompt = Some(format!("{prompt}[{}]", self.name));
+            Ok(request)
+        }
+
+        fn wrap_stream(
+            &self,
+            request: LanguageModelRequest,
+            next: Next,
+        ) -> Result<AssistantEventStream> {
+            let name = self.name;
+            let log = Arc::clone(&self.log);
+            let stream = next.run(request)?;
+            Ok(stream
+                .inspect(move |event| {
+                    if matches!(event, Ok(AssistantMessageEvent::Done { .. })) {
+                        log.lock().unwrap().push(format!("{name} done"));
+                    }
+                })
+                .boxed())
+        }
+    }
+
+    #[derive(Clone)]
+    struct Guardrail;
+
+    impl LanguageModelMiddleware for Guardrail {
+        fn wrap_stream(
+            &self,
+            request: LanguageModelRequest,
+            next: Next,
+        ) -> Result<AssistantEventStream> {
+            let blocked = request.context.messages.iter().any(|message| {
+                matches!(message, Message::User(user) if format!("{:?}", user.content).contains("secret"))
+            });
+            if !blocked {
+                return next.run(request);
+            }
+            let mut error = AssistantMessage::empty_for(&request.model);
+            error.stop_reason = StopReason::Error;
+            error.error_message = Some("blocked by guardrail".to_string());
+            Ok(futures::stream::iter([Ok(AssistantMessageEvent::Error {
+                reason: StopReason::Error,
+                error,
+            })])
+            .boxed())
+        }
+    }
+
+    fn context(text: &str) -> Context {
+        Context::builder()
+            .system_prompt("base")
+            .message(Message::User(UserMessage::text(text)))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn layers_run_outermost_first_and_keep_the_model_identity() {
+        let faux = register_faux_provider(None);
+        let seen = Arc::new(Mutex::new(None));
+        let seen_by_provider = Arc::clone(&seen);
+        faux.set_responses([FauxResponseStep::factory(move |context, _, _, _| {
+            *seen_by_provider.lock().unwrap() = context.system_prompt.clone();
+            async { Ok(faux_assistant_message("ok", None)) }
+        })]);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let layer = |name| -> Arc<dyn LanguageModelMiddleware> {
+            Arc::new(Recorder {
+                name,
+                log: Arc::clone(&log),
+            })
+        };
+        let original = faux.get_model();
+        let model = wrap_model(original.clone(), [layer("outer"), layer("inner")]).unwrap();
+
+        let message = crate::complete_simple(model.clone(), context("hi"), None)
+            .await
+            .unwrap();
+
+        assert_eq!(model.api, original.api);
+        assert_eq!(model.provider, original.provider);
+        assert_eq!(message.stop_reason, StopReason::Stop);
+        assert_eq!(seen.lock().unwrap().as_deref(), Some("base[outer][inner]"));
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["outer request", "inner request", "inner done", "outer done"]
+        );
+    }
+
+    #[tokio::test]
+    async fn middleware_can_answer_without_calling_the_model() {
+        let faux = register_faux_provider(None);
+        let model = wrap_model(faux.get_model(), [Arc::new(Guardrail) as Arc<_>]).unwrap();
+
+        let message = crate::complete(model, context("the secret is 42"), None)
+            .await
+            .unwrap();
+
+        assert_eq!(message.stop_reason, StopReason::Error);
+        assert_eq!(
+            message.error_message.as_deref(),
+            Some("blocked by guardrail")
+        );
+        assert_eq!(faux.state.call_count(), 0);
+        assert!(matches!(
+            wrap_model(Model::default(), []),
+            Err(Error::UnsupportedCapability { .. })
+        ));
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::provider::Provider;
+use crate::types::Model;
+use crate::{Error, Result};
+
+/// Resolves `provider/model` names into ready-to-use models.
+///
+/// Providers are registered under their `Provider::id`. Catalog entries are
+/// partial `Model` objects (camelCase keys, as `Model` serializes) that need
+/// at least `provider` and `id`; every other field overrides what the
+/// provider handle would build for that id. Names without a catalog entry
+/// fall back to the provider's defaults.
+///
+/// ```toml
+/// [[models]]
+/// provider = "anthropic"
+/// id = "claude-sonnet-4"
+/// contextWindow = 200000
+/// cost = { input = 3.0, output = 15.0, cacheRead = 0.3, cacheWrite = 3.75 }
+/// ```
+#[derive(Clone, Default)]
+pub struct ModelRegistry {
+    providers: HashMap<String, Box<dyn Provider>>,
+    entries: BTreeMap<(String, String), Map<String, Value>>,
+}
+
+impl ModelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_provider(mut self, provider: impl Provider) -> Self {
+        self.register_provider(provider);
+        self
+    }
+
+    /// Registers `provider` under its id, replacing any provider already
+    /// registered with that id.
+    pub fn register_provider(&mut self, provider: impl Provider) {
+        self.providers
+            .insert(provider.id().to_string(), Box::new(provider));
+    }
+
+    /// Loads a JSON catalog: either an array of entries or an object with a
+    /// `models` array. Entries replace earlier entries with the same name.
+    pub fn load_json(&mut self, json: &str) -> Result<()> {
+        self.load_catalog(serde_json::from_str(json)?)
+    }
+
+    /// Loads a TOML catalog made of `[[models]]` tables.
+    pub fn load_toml(&mut self, toml: &str) -> Result<()> {
+        let catalog = toml::from_str::<Value>(toml)
+            .map_err(|error| Error::Validation(format!("invalid TOML model catalog: {error}")))?;
+        self.load_catalog(catalog)
+    }
+
+    /// Loads a catalog file, choosing the format from a `.json` or `.toml`
+    /// extension.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => self.load_json(&contents),
+            Some("toml") => self.load_toml(&contents),
+            _ => Err(Error::Validation(format!(
+                "model catalog {} must have a .json or .toml extension",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Adds or replaces one catalog entry from a fully specified model.
+    pub fn add_model(&mut self, model: &Model) -> Result<()> {
+        let Value::Object(entry) = serde_json::to_value(model)? else {
+            unreachable!("models serialize as objects");
+        };
+        self.insert_entry(entry)
+    }
+
+    /// Resolves `provider/model` into a model bound to the registered
+    /// provider. Only the first `/` separates the provider, so
+    /// `openrouter/anthropic/claude-sonnet-4` names the
+    /// `anthropic/claude-sonnet-4` model on `openrouter`.
+    pub fn resolve(&self, name: &str) -> Result<Model> {
+        let Some((provider_id, model_id)) = name
+            .split_once('/')
+            .filter(|(provider_id, model_id)| !provider_id.is_empty() && !model_id.is_empty())
+        else {
+            return Err(Error::Validation(format!(
+                "model name {name:?} must look like \"provider/model\""
+            )));
+        };
+        let key = (provider_id.to_string(), model_id.to_string());
+        self.bind(provider_id, model_id, self.entries.get(&key))
+    }
+
+    /// Resolves every catalog entry, in `provider/model` order.
+    pub fn models(&self) -> Result<Vec<Model>> {
+        self.entries
+            .iter()
+            .map(|((provider_id, model_id), entry)| self.bind(provider_id, model_id, Some(entry)))
+            .collect()
+    }
+
+    /// `provider/model` names of all catalog entries.
+    pub fn names(&self) -> Vec<String> {
+        self.entries
+            .keys()
+            .map(|(provider_id, model_id)| format!("{provider_id}/{model_id}"))
+            .collect()
+    }
+
+    fn load_catalog(&mut self, catalog: Value) -> Result<()> {
+        let entries = match catalog {
+            Value::Array(entries) => entries,
+            Value::Object(mut catalog) => match catalog.remove("models") {
+                Some(Value::Array(entries)) => entries,
+                _ => {
+                    return Err(Error::Validation(
+                        "model catalog must contain a \"models\" array".to_string(),
+                    ));
+                }
+            },
+            _ => {
+                return Err(Error::Validation(
+                    "model catalog must be an array or an object".to_string(),
+                ));
+            }
+        };
+        for entry in entries {
+            let Value::Object(entry) = entry else {
+                return Err(Error::Validation(
+                    "model catalog entries must be objects".to_string(),
+                ));
+            };
+            self.insert_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    fn insert_entry(&mut self, entry: Map<String, Value>) -> Result<()> {
+        let field = |name: &str| {
+            entry
+                .get(name)
+                .and_then(Value::as_str)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    Error::Validation(format!("model catalog entry is missing \"{name}\""))
+                })
+        };
+        let key = (field("provider")?, field("id")?);
+        self.entries.insert(key, entry);
+        Ok(())
+    }
+
+    fn bind(
+        &self,
+        provider_id: &str,
+        model_id: &str,
+        entry: Option<&Map<String, Value>>,
+    ) -> Result<Model> {
+        let provider = self.providers.get(provider_id).ok_or_else(|| {
+            Error::Validation(format!("no provider registered for {provider_id:?}"))
+        })?;
+        let base = provider.model(model_id).build_language()?;
+        let Some(entry) = entry else {
+            return Ok(base);
+        };
+
+        let mut merged = serde_json::to_value(&base)?;
+        merge(&mut merged, Value::Object(entry.clone()));
+        let mut model: Model = serde_json::from_value(merged).map_err(|error| {
+            Error::Validation(format!(
+                "invalid catalog entry for {provider_id}/{model_id}: {error}"
+            ))
+        })?;
+        if model.api != base.api {
+            return Err(Error::Validation(format!(
+                "catalog entry {provider_id}/{model_id} uses api {:?}, but provider {provider_id:?} serves {:?}",
+                model.api, base.api
+            )));
+        }
+        model.language_api = base.language_api;
+        model.image_api = base.image_api;
+        model.embedding_api = base.embedding_api;
+        model.fim_api = base.fim_api;
+        model.rerank_api = base.rerank_api;
+        model.transcription_api = base.transcription_api;
+        model.speech_api = base.speech_api;
+        Ok(model)
+    }
+}
+
+/// Overlays `patch` onto `target`, merging nested objects key by key.
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{anthropic, openai};
+    use crate::types::ModelInput;
+
+    fn registry() -> ModelRegistry {
+        ModelRegistry::new()
+            .with_provider(anthropic::builder().api_key("key").build().unwrap())
+            .with_provider(
+                openai::builder()
+                    .api_key(Some("key"))
+                    .provider_id("openrouter")
+                    .base_url("https://openrouter.ai/api/v1")
+                    .chat_completions()
+                    .build()
+                    .unwrap(),
+            )
+    }
+
+    #[test]
+    fn resolves_json_catalog_entries_onto_provider_models() {
+        let mut registry = registry();
+        registry
+            .load_json(
+                r#"{
+                    "models": [{
+                        "provider": "anthropic",
+                        "id": "claude-sonnet-4",
+                        "name": "Claude Sonnet 4",
+                        "reasoning": true,
+                        "input": ["text", "image"],
+                        "contextWindow": 200000,
+                        "maxTokens": 64000,
+                        "cost": { "input": 3.0, "output": 15.0, "cacheRead": 0.3, "cacheWrite": 3.75 },
+                        "thinkingLevelMap": { "xhigh": null },
+                        "compat": { "supportsEagerToolInputStreaming": false }
+                    }]
+                }"#,
+            )
+            .unwrap();
+
+        let model = registry.resolve("anthropic/claude-sonnet-4").unwrap();
+
+        assert_eq!(model.name, "Claude Sonnet 4");
+        assert_eq!(model.api, "anthropic-messages");
+        assert_eq!(model.base_url, "https://api.anthropic.com/v1");
+        assert_eq!(model.context_window, 200_000);
+        assert_eq!(model.cost.cache_write, 3.75);
+        assert_eq!(model.input, vec![ModelInput::Text, ModelInput::Image]);
+        assert_eq!(model.thinking_level_map.get("xhigh"), Some(&None));
+        assert_eq!(
+            model
+                .compat
+                .anthropic_messages
+                .supports_eager_tool_input_streaming,
+            Some(false)
+        );
+        assert!(model.language_api().is_some());
+        assert_eq!(registry.names(), vec!["anthropic/claude-sonnet-4"]);
+    }
+
+    #[test]
+    fn resolves_toml_catalog_and_model_ids_containing_slashes() {
+        let mut registry = registry();
+        registry
+            .load_toml(
+                r#"
+                [[models]]
+                provider = "openrouter"
+                id = "anthropic/claude-sonnet-4"
+                contextWindow = 1000000
+                compat = { supportsStore = false }
+                "#,
+            )
+            .unwrap();
+
+        let model = registry
+            .resolve("openrouter/anthropic/claude-sonnet-4")
+            .unwrap();
+
+        assert_eq!(model.provider, "openrouter");
+        assert_eq!(model.id, "anthropic/claude-sonnet-4");
+        assert_eq!(model.context_window, 1_000_000);
+        assert_eq!(model.compat.openai_completions.supports_store, Some(false));
+        assert_eq!(registry.models().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn falls_back_to_provider_defaults_without_a_catalog_entry() {
+        let model = registry().resolve("anthropic/claude-opus-4-1").unwrap();
+
+        assert_eq!(model.id, "claude-opus-4-1");
+        assert!(model.language_api().is_some());
+    }
+
+    #[test]
+    fn rejects_unknown_providers_malformed_names_and_api_mismatches() {
+        let mut registry = registry();
+        registry
+            .load_json(
+                r#"[{ "provider": "anthropic", "id": "claude", "api": "openai-responses" }]"#,
+            )
+            .unwrap();
+
+        assert!(matches!(
+            registry.resolve("mistral/mistral-large"),
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            registry.resolve("claude-sonnet-4"),
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            registry.resolve("anthropic/claude"),
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            registry.load_json(r#"[{ "id": "missing-provider" }]"#),
+            Err(Error::Validation(_))
+        ));
+    }
+}
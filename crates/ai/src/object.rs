@@ -0,0 +1,352 @@
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::types::{
+    AssistantContent, AssistantMessage, AssistantMessageEvent, Context, JsonSchemaFormat, Model,
+    StopReason, StreamOptions,
+};
+use crate::utils::json::{parse_json_with_repair, parse_streaming_json};
+use crate::utils::validation::validate_json_value;
+use crate::{Error, Result};
+
+/// A validated structured answer and the message it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedObject<T> {
+    pub object: T,
+    pub message: AssistantMessage,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum ObjectStreamEvent {
+    /// The object parsed so far. Later partials replace earlier ones.
+    Partial(Value),
+    /// The final object, validated against the schema.
+    Done(GeneratedObject<Value>),
+}
+
+pub type ObjectStream = BoxStream<'static, Result<ObjectStreamEvent>>;
+
+/// How a model is asked for JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ObjectMode {
+    /// OpenAI `json_schema` response format; the answer is the text.
+    ResponseFormat,
+    /// Anthropic forced tool use; the answer is the tool call's arguments.
+    ForcedTool,
+    /// Schema instructions in the system prompt; the answer is the text.
+    Prompt,
+}
+
+impl ObjectMode {
+    fn for_model(model: &Model, format: &JsonSchemaFormat) -> Self {
+        match model.api.as_str() {
+            "openai-completions" | "openai-responses" => Self::ResponseFormat,
+            // Tool inputs must be objects, so other roots fall back to the
+            // prompt.
+            "anthropic-messages" if format.schema["type"] == "object" => Self::ForcedTool,
+            _ => Self::Prompt,
+        }
+    }
+}
+
+/// Asks `model` for a JSON value matching `schema` and deserializes it into
+/// `T`. The value is validated against the schema first; a mismatch is an
+/// `Error::Validation`.
+pub async fn generate_object<T: DeserializeOwned>(
+    model: Model,
+    context: Context,
+    schema: impl Into<JsonSchemaFormat>,
+    options: Option<StreamOptions>,
+) -> Result<GeneratedObject<T>> {
+    let mut stream = stream_object(model, context, schema, options)?;
+    while let Some(event) = stream.next().await {
+        if let ObjectStreamEvent::Done(done) = event? {
+            return Ok(GeneratedObject {
+                object: serde_json::from_value(done.object)?,
+                message: done.message,
+            });
+        }
+    }
+    Err(Error::StreamClosed)
+}
+
+/// Streams partial objects as `model` writes a JSON value matching `schema`,
+/// ending with the validated object.
+///
+/// OpenAI Chat Completions and Responses models receive the schema as a
+/// `json_schema` response format, and Anthropic models as a forced tool call.
+/// Other models get the schema in the system prompt, and the first JSON value
+/// in their answer is used.
+pub fn stream_object(
+    model: Model,
+    mut context: Context,
+    schema: impl Into<JsonSchemaFormat>,
+    options: Option<StreamOptions>,
+) -> Result<ObjectStream> {
+    let format = schema.into();
+    let mode = ObjectMode::for_model(&model, &format);
+    let mut options = options.unwrap_or_default();
+    if mode == ObjectMode::Prompt {
+        let instructions = prompt_instructions(&format)?;
+        context.system_prompt = Some(match context.system_prompt.take() {
+            Some(prompt) if !prompt.trim().is_empty() => format!("{prompt}\n\n{instructions}"),
+            _ => instructions,
+        });
+    } else {
+        options.response_format = Some(format.clone());
+    }
+    let mut events = crate::stream(model, context, Some(options))?;
+
+    Ok(async_stream::try_stream! {
+        let mut buffer = String::new();
+        let mut last_partial = None;
+        while let Some(event) = events.next().await {
+            match event? {
+                AssistantMessageEvent::TextDelta { delta, .. }
+                    if mode != ObjectMode::ForcedTool =>
+                {
+                    buffer.push_str(&delta);
+                }
+                AssistantMessageEvent::ToolCallDelta { content_index, delta, partial }
+                    if mode == ObjectMode::ForcedTool
+                        && matches!(
+                            partial.content.get(content_index),
+                            Some(AssistantContent::ToolCall(call)) if call.name == format.name
+                        ) =>
+                {
+                    buffer.push_str(&delta);
+                }
+                AssistantMessageEvent::Done { message, .. } => {
+                    let value = final_value(mode, &format, &message)?;
+                    let object = validate_json_value(&value, &format.schema)?;
+                    yield ObjectStreamEvent::Done(GeneratedObject { object, message });
+                    return;
+                }
+                AssistantMessageEvent::Error { error, .. } => {
+                    Err(match error.stop_reason {
+                        StopReason::Aborted => Error::Cancelled,
+                        _ => Error::Provider(
+                            error
+                                .error_message
+                                .unwrap_or_else(|| "model returned an error".to_string()),
+                        ),
+                    })?;
+                }
+                _ => continue,
+            }
+            let Some(json) = json_region(&buffer) else {
+                continue;
+            };
+            let partial = parse_streaming_json(Some(json));
+            if last_partial.as_ref() != Some(&partial) {
+                last_partial = Some(partial.clone());
+                yield ObjectStreamEvent::Partial(partial);
+            }
+        }
+        Err(Error::StreamClosed)?;
+    }
+    .boxed())
+}
+
+fn prompt_instructions(format: &JsonSchemaFormat) -> Result<String> {
+    let mut instructions = format!(
+        "Respond with only a JSON value named \"{}\" that matches this JSON Schema, with no other text:\n{}",
+        format.name,
+        serde_json::to_string_pretty(&format.schema)?
+    );
+    if let Some(description) = &format.description {
+        instructions.push_str(&format!("\n\nThe value describes: {description}"));
+    }
+    Ok(instructions)
+}
+
+fn final_value(
+    mode: ObjectMode,
+    format: &JsonSchemaFormat,
+    message: &AssistantMessage,
+) -> Result<Value> {
+    if mode == ObjectMode::ForcedTool {
+        return message
+            .content
+            .iter()
+            .find_map(|content| match content {
+                AssistantContent::ToolCall(call) if call.name == format.name => {
+                    Some(call.arguments.clone())
+                }
+                _ => None,
+            })
+            .ok_or_else(|| {
+                Error::InvalidProviderResponse(format!(
+                    "model did not call the \"{}\" tool",
+                    format.name
+                ))
+            });
+    }
+    let text = message
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            AssistantContent::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect::<String>();
+    let json = json_region(&text).ok_or_else(|| {
+        Error::InvalidProviderResponse("model did not answer with a JSON value".to_string())
+    })?;
+    parse_json_with_repair(json)
+}
+
+/// The JSON part of a text answer: from the first `{` or `[` up to a closing
+/// code fence, if the model added one.
+fn json_region(text: &str) -> Option<&str> {
+    let start = text.find(['{', '['])?;
+    let json = &text[start..];
+    Some(json.find("```").map_or(json, |end| &json[..end]).trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::providers::faux::{
+        FauxResponseStep, faux_assistant_message, register_faux_provider,
+    };
+    use crate::types::{Message, UserMessage};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    fn person_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "age": { "type": "integer" }
+            },
+            "required": ["name", "age"]
+        })
+    }
+
+    fn context() -> Context {
+        Context::builder()
+            .system_prompt("Extract the person.")
+            .message(Message::User(UserMessage::text("Ada Lovelace, 36")))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn prompt_fallback_parses_and_validates_the_answer() {
+        let faux = register_faux_provider(None);
+        faux.set_responses([FauxResponseStep::factory(
+            |context, options, _, _| async move {
+                assert!(options.response_format.is_none());
+                let prompt = context.system_prompt.unwrap_or_default();
+                assert!(
+                    prompt.starts_with("Extract the person.\n\nRespond with only a JSON value")
+                );
+                assert!(prompt.contains("\"required\""));
+                Ok(faux_assistant_message(
+                    "Here you go:\n```json\n{\"name\": \"Ada\", \"age\": \"36\"}\n```",
+                    None,
+                ))
+            },
+        )]);
+
+        let generated =
+            generate_object::<Person>(faux.get_model(), context(), person_schema(), None)
+                .await
+                .unwrap();
+
+        assert_eq!(
+            generated.object,
+            Person {
+                name: "Ada".to_string(),
+                age: 36
+            }
+        );
+        assert_eq!(generated.message.stop_reason, StopReason::Stop);
+    }
+
+    #[tokio::test]
+    async fn streams_growing_partials_then_the_final_object() {
+        let faux = register_faux_provider(None);
+        faux.set_responses([faux_assistant_message(
+            "{\"name\": \"Ada Lovelace\", \"age\": 36}",
+            None,
+        )]);
+
+        let events = stream_object(faux.get_model(), context(), person_schema(), None)
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        let partials = events
+            .iter()
+            .filter_map(|event| match event {
+                ObjectStreamEvent::Partial(value) => Some(value),
+                ObjectStreamEvent::Done(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!partials.is_empty());
+        assert!(partials.iter().all(|partial| partial.is_object()));
+        let Some(ObjectStreamEvent::Done(done)) = events.last() else {
+            panic!("expected done event");
+        };
+        assert_eq!(done.object, json!({ "name": "Ada Lovelace", "age": 36 }));
+    }
+
+    #[tokio::test]
+    async fn rejects_answers_that_do_not_match_the_schema() {
+        let faux = register_faux_provider(None);
+        faux.set_responses([
+            faux_assistant_message("{\"name\": \"Ada\"}", None),
+            faux_assistant_message("I cannot answer that.", None),
+        ]);
+
+        let missing_field =
+            generate_object::<Person>(faux.get_model(), context(), person_schema(), None).await;
+        let no_json =
+            generate_object::<Person>(faux.get_model(), context(), person_schema(), None).await;
+
+        assert!(
+            matches!(missing_field, Err(Error::Validation(message)) if message.contains("age"))
+        );
+        assert!(matches!(no_json, Err(Error::InvalidProviderResponse(_))));
+    }
+
+    #[test]
+    fn picks_the_native_mode_per_api() {
+        let model = |api: &str| Model {
+            api: api.to_string(),
+            ..Model::default()
+        };
+        let object = JsonSchemaFormat::new(person_schema());
+        let array = JsonSchemaFormat::new(json!({ "type": "array" }));
+
+        assert_eq!(
+            ObjectMode::for_model(&model("openai-responses"), &array),
+            ObjectMode::ResponseFormat
+        );
+        assert_eq!(
+            ObjectMode::for_model(&model("anthropic-messages"), &object),
+            ObjectMode::ForcedTool
+        );
+        assert_eq!(
+            ObjectMode::for_model(&model("anthropic-messages"), &array),
+            ObjectMode::Prompt
+        );
+        assert_eq!(
+            ObjectMode::for_model(&model("google-generative-ai"), &object),
+            ObjectMode::Prompt
+        );
+    }
+}
@@ -5,9 +5,10 @@ use reqwest::header::{HeaderName, HeaderValue};
 
 use crate::event_stream::AssistantEventStream;
 use crate::types::{
-    AssistantImages, Context, EmbeddingBatch, EmbeddingOptions, ImageGenerationOptions,
-    ImagesContext, Model, ModelCompat, ModelCost, ModelInput, ModelOutput, SimpleStreamOptions,
-    StreamOptions,
+    AssistantImages, AudioContent, Context, EmbeddingBatch, EmbeddingOptions, FimContext,
+    GeneratedSpeech, ImageGenerationOptions, ImagesContext, Model, ModelCompat, ModelCost,
+    ModelInput, ModelOutput, RerankOptions, Reranking, SimpleStreamOptions, SpeechOptions,
+    StreamOptions, Transcription, TranscriptionOptions,
 };
 use crate::{Error, Result};
 
@@ -16,8 +17,13 @@ pub struct ProviderCapabilities {
     pub language_models: bool,
     pub image_models: bool,
     pub embedding_models: bool,
+    pub fim_models: bool,
+    pub rerank_models: bool,
+    pub transcription_models: bool,
+    pub speech_models: bool,
 }
 
+#[async_trait]
 pub trait Provider: dyn_clone::DynClone + Send + Sync + 'static {
     fn id(&self) -> &str;
 
@@ -26,6 +32,12 @@ pub trait Provider: dyn_clone::DynClone + Send + Sync + 'static {
     fn model(&self, id: &str) -> ModelBuilder {
         ModelBuilder::unsupported(self.id(), id)
     }
+
+    /// Fetches the models the provider currently serves, with pricing and
+    /// context limits filled in where the provider reports them.
+    async fn list_models(&self) -> Result<Vec<Model>> {
+        Err(Error::unsupported_capability(self.id(), "model listing"))
+    }
 }
 
 dyn_clone::clone_trait_object!(Provider);
@@ -78,6 +90,70 @@ pub trait EmbeddingModelApi: dyn_clone::DynClone + Send + Sync + 'static {
 
 dyn_clone::clone_trait_object!(EmbeddingModelApi);
 
+/// Speech to text.
+#[async_trait]
+pub trait TranscriptionModelApi: dyn_clone::DynClone + Send + Sync + 'static {
+    fn id(&self) -> &str;
+
+    async fn transcribe(
+        &self,
+        model: Model,
+        audio: AudioContent,
+        options: TranscriptionOptions,
+    ) -> Result<Transcription>;
+}
+
+dyn_clone::clone_trait_object!(TranscriptionModelApi);
+
+/// Text to speech. The audio streams back as it is produced.
+#[async_trait]
+pub trait SpeechModelApi: dyn_clone::DynClone + Send + Sync + 'static {
+    fn id(&self) -> &str;
+
+    async fn generate_speech(
+        &self,
+        model: Model,
+        text: String,
+        voice: String,
+        options: SpeechOptions,
+    ) -> Result<GeneratedSpeech>;
+}
+
+dyn_clone::clone_trait_object!(SpeechModelApi);
+
+/// Scores `documents` by relevance to `query`. Results are ordered from most
+/// to least relevant and truncated to `top_n` when set.
+#[async_trait]
+pub trait RerankModelApi: dyn_clone::DynClone + Send + Sync + 'static {
+    fn id(&self) -> &str;
+
+    async fn rerank(
+        &self,
+        model: Model,
+        query: String,
+        documents: Vec<String>,
+        top_n: Option<u32>,
+        options: RerankOptions,
+    ) -> Result<Reranking>;
+}
+
+dyn_clone::clone_trait_object!(RerankModelApi);
+
+/// Fill-in-the-middle completion: the model writes the text between
+/// `FimContext::prompt` and `FimContext::suffix`.
+pub trait FimModelApi: dyn_clone::DynClone + Send + Sync + 'static {
+    fn id(&self) -> &str;
+
+    fn stream_fim(
+        &self,
+        model: Model,
+        context: FimContext,
+        options: StreamOptions,
+    ) -> Result<AssistantEventStream>;
+}
+
+dyn_clone::clone_trait_object!(FimModelApi);
+
 #[derive(Clone)]
 pub struct ModelBuilder {
     model: Model,
@@ -133,6 +209,62 @@ impl ModelBuilder {
         }
     }
 
+    pub fn new_fim(provider_id: &str, id: &str, api: Arc<dyn FimModelApi>) -> Self {
+        Self {
+            model: Model {
+                id: id.to_string(),
+                name: id.to_string(),
+                api: api.id().to_string(),
+                provider: provider_id.to_string(),
+                fim_api: Some(api),
+                ..Model::default()
+            },
+        }
+    }
+
+    pub fn new_rerank(provider_id: &str, id: &str, api: Arc<dyn RerankModelApi>) -> Self {
+        Self {
+            model: Model {
+                id: id.to_string(),
+                name: id.to_string(),
+                api: api.id().to_string(),
+                provider: provider_id.to_string(),
+                rerank_api: Some(api),
+                ..Model::default()
+            },
+        }
+    }
+
+    pub fn new_transcription(
+        provider_id: &str,
+        id: &str,
+        api: Arc<dyn TranscriptionModelApi>,
+    ) -> Self {
+        Self {
+            model: Model {
+                id: id.to_string(),
+                name: id.to_string(),
+                api: api.id().to_string(),
+                provider: provider_id.to_string(),
+                transcription_api: Some(api),
+                ..Model::default()
+            },
+        }
+    }
+
+    pub fn new_speech(provider_id: &str, id: &str, api: Arc<dyn SpeechModelApi>) -> Self {
+        Self {
+            model: Model {
+                id: id.to_string(),
+                name: id.to_string(),
+                api: api.id().to_string(),
+                provider: provider_id.to_string(),
+                speech_api: Some(api),
+                ..Model::default()
+            },
+        }
+    }
+
     pub fn name(mut self, name: impl Into<String>) -> Self {
         self.model.name = name.into();
         self
@@ -199,10 +331,14 @@ impl ModelBuilder {
         if self.model.language_api.is_none()
             && self.model.image_api.is_none()
             && self.model.embedding_api.is_none()
+            && self.model.fim_api.is_none()
+            && self.model.rerank_api.is_none()
+            && self.model.transcription_api.is_none()
+            && self.model.speech_api.is_none()
         {
             return Err(Error::unsupported_capability(
                 self.model.provider,
-                "language, image, or embedding models",
+                "language, image, embedding, FIM, rerank, transcription, or speech models",
             ));
         }
         Ok(self.model)
@@ -237,6 +373,46 @@ impl ModelBuilder {
         }
         Ok(self.model)
     }
+
+    pub fn build_fim(self) -> Result<Model> {
+        if self.model.fim_api.is_none() {
+            return Err(Error::unsupported_capability(
+                self.model.provider,
+                "FIM models",
+            ));
+        }
+        Ok(self.model)
+    }
+
+    pub fn build_rerank(self) -> Result<Model> {
+        if self.model.rerank_api.is_none() {
+            return Err(Error::unsupported_capability(
+                self.model.provider,
+                "rerank models",
+            ));
+        }
+        Ok(self.model)
+    }
+
+    pub fn build_transcription(self) -> Result<Model> {
+        if self.model.transcription_api.is_none() {
+            return Err(Error::unsupported_capability(
+                self.model.provider,
+                "transcription models",
+            ));
+        }
+        Ok(self.model)
+    }
+
+    pub fn build_speech(self) -> Result<Model> {
+        if self.model.speech_api.is_none() {
+            return Err(Error::unsupported_capability(
+                self.model.provider,
+                "speech models",
+            ));
+        }
+        Ok(self.model)
+    }
 }
 
 #[cfg(test)]
@@ -1,5 +1,6 @@
+use async_trait::async_trait;
 use futures::{StreamExt, pin_mut};
-use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
 use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -13,6 +14,7 @@ use crate::providers::deferred_tools::split_deferred_tools;
 use crate::providers::github_copilot_headers::{
     build_copilot_dynamic_headers, has_copilot_vision_input,
 };
+use crate::providers::sampling::{SamplingSupport, sampling};
 use crate::providers::simple_options;
 use crate::providers::simple_options::{
     adjust_max_tokens_for_thinking, build_base_options, clamp_max_tokens_to_context,
@@ -21,12 +23,14 @@ use crate::providers::simple_options::{
 use crate::providers::transform_messages::transform_messages;
 use crate::types::{
     AnthropicMessagesCompat, AssistantContent, AssistantMessage, AssistantMessageEvent,
-    CacheRetention, Context, Model, ModelCompat, ModelThinkingLevel, SimpleStreamOptions,
-    StopReason, StreamOptions, TextContent, ThinkingContent, Tool, ToolCall, ToolResultContent,
-    UserContent, UserMessageContent,
+    CacheRetention, Context, DocumentContent, DocumentSource, Model, ModelCompat,
+    ModelThinkingLevel, SimpleStreamOptions, StopReason, StreamOptions, TextContent,
+    ThinkingContent, Tool, ToolCall, ToolChoice, ToolResultContent, UserContent,
+    UserMessageContent,
 };
+use crate::utils::circuit_breaker::model_circuit;
 use crate::utils::headers::has_non_empty_header;
-use crate::utils::http::{request_timeout, send_with_retries};
+use crate::utils::http::{get_json, request_timeout, send_with_retries};
 use crate::utils::json::{parse_json_with_repair, parse_streaming_json};
 use crate::utils::provider_env::get_provider_env_value;
 use crate::utils::sse;
@@ -66,6 +70,7 @@ impl Anthropic {
     }
 }
 
+#[async_trait]
 impl Provider for Anthropic {
     fn id(&self) -> &str {
         &self.provider_id
@@ -76,6 +81,10 @@ impl Provider for Anthropic {
             language_models: true,
             image_models: false,
             embedding_models: false,
+            fim_models: false,
+            rerank_models: false,
+            transcription_models: false,
+            speech_models: false,
         }
     }
 
@@ -87,7 +96,11 @@ impl Provider for Anthropic {
         });
         ModelBuilder::new(&self.provider_id, id, runtime)
             .base_url(self.base_url.clone())
-            .input(vec![crate::ModelInput::Text, crate::ModelInput::Image])
+            .input(vec![
+                crate::ModelInput::Text,
+                crate::ModelInput::Image,
+                crate::ModelInput::Pdf,
+            ])
             .context_window(1_000_000)
             .max_tokens(16_384)
             .compat(ModelCompat {
@@ -98,6 +111,81 @@ impl Provider for Anthropic {
                 ..Default::default()
             })
     }
+
+    /// Lists `/models`, following `after_id` pagination. Anthropic reports
+    /// display names but no pricing or limits, so those keep their defaults.
+    async fn list_models(&self) -> Result<Vec<Model>> {
+        let mut headers = HeaderMap::new();
+        headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
+        let authorization = match (self.api_key.as_deref(), self.auth_token.as_deref()) {
+            (Some(api_key), _) if is_oauth_token(api_key) => {
+                headers.insert(
+                    "anthropic-beta",
+                    HeaderValue::from_static("oauth-2025-04-20"),
+                );
+                Some(format!("Bearer {api_key}"))
+            }
+            (Some(api_key), _) => {
+                headers.insert(
+                    "x-api-key",
+                    HeaderValue::from_str(api_key).map_err(|error| {
+                        Error::InvalidHeaderValue("x-api-key".to_string(), error)
+                    })?,
+                );
+                None
+            }
+            (None, Some(auth_token)) => Some(format!("Bearer {auth_token}")),
+            (None, None) => return Err(Error::MissingApiKey(self.provider_id.clone())),
+        };
+        if let Some(authorization) = authorization {
+            headers.insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&authorization).map_err(|error| {
+                    Error::InvalidHeaderValue("authorization".to_string(), error)
+                })?,
+            );
+        }
+
+        let client = self.http_client.clone().unwrap_or_default();
+        let base_url = trim_end_slash(&self.base_url);
+        let mut models = Vec::new();
+        let mut after_id: Option<String> = None;
+        loop {
+            let mut url = format!("{base_url}/models?limit=1000");
+            if let Some(after_id) = &after_id {
+                url.push_str(&format!("&after_id={after_id}"));
+            }
+            let page = get_json(&client, &url, headers.clone()).await?;
+            for entry in page
+                .get("data")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let Some(id) = entry.get("id").and_then(Value::as_str) else {
+                    continue;
+                };
+                let mut builder = self.model(id);
+                if let Some(name) = entry.get("display_name").and_then(Value::as_str) {
+                    builder = builder.name(name);
+                }
+                models.push(builder.build()?);
+            }
+            after_id = page
+                .get("last_id")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if !page
+                .get("has_more")
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+                || after_id.is_none()
+            {
+                break;
+            }
+        }
+        Ok(models)
+    }
 }
 
 #[derive(Default)]
@@ -237,7 +325,7 @@ pub enum AnthropicEffort {
 }
 
 impl AnthropicEffort {
-    fn as_str(self) -> &'static str {
+    pub(crate) fn as_str(self) -> &'static str {
         match self {
             Self::Low => "low",
             Self::Medium => "medium",
@@ -486,7 +574,7 @@ async fn run_stream(
         Err(error) => return Err(StreamFailure::new(output, error)),
     };
     let client = options.base.http_client.clone().unwrap_or_default();
-    let response_result = send_with_retries(&options.base, || {
+    let response_result = send_with_retries(&options.base, &model_circuit(&model), || {
         client
             .post(request_url.as_str())
             .headers(request_headers.clone())
@@ -497,11 +585,15 @@ async fn run_stream(
     let response = match response_result {
         Ok(response) => response,
         Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
-        Err(error) => return Err(StreamFailure::new(output, error)),
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
     };
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
+        output.error_status = Some(status.as_u16());
         return Err(StreamFailure::new(
             output,
             Error::ApiStatus { status, body },
@@ -771,7 +863,7 @@ async fn run_stream(
                             partial: output.clone(),
                         });
                     }
-                    None => {}
+                    Some(AssistantContent::Audio(_)) | None => {}
                 }
             }
             Some("message_delta") => {
@@ -780,6 +872,10 @@ async fn run_stream(
                     match map_stop_reason(reason, stop_details) {
                         Ok((stop_reason, error_message)) => {
                             output.stop_reason = stop_reason;
+                            output.stop_sequence = parsed
+                                .pointer("/delta/stop_sequence")
+                                .and_then(Value::as_str)
+                                .map(str::to_string);
                             output.error_message = error_message;
                         }
                         Err(message) => return Err(StreamFailure::new(output, message)),
@@ -911,6 +1007,22 @@ fn try_build_anthropic_payload(
     {
         object.insert("temperature".to_string(), json!(temperature));
     }
+    let mut sampling = sampling(
+        model,
+        &options.base,
+        SamplingSupport {
+            seed: false,
+            penalties: false,
+            ..SamplingSupport::ALL
+        },
+    );
+    // Like temperature, `top_p` and `top_k` are rejected with extended
+    // thinking.
+    if options.thinking_enabled == Some(true) {
+        sampling.top_p = None;
+        sampling.top_k = None;
+    }
+    sampling.insert_into(object, "stop_sequences");
     if !placement.immediate.is_empty() || !placement.deferred.is_empty() {
         let mut tools = convert_tools(
             &placement.immediate,
@@ -976,12 +1088,55 @@ fn try_build_anthropic_payload(
     {
         object.insert("metadata".to_string(), json!({ "user_id": user_id }));
     }
-    if let Some(tool_choice) = &options.tool_choice {
-        let value = tool_choice
-            .as_str()
-            .map(|choice| json!({ "type": choice }))
-            .unwrap_or_else(|| tool_choice.clone());
-        object.insert("tool_choice".to_string(), value);
+    let mut tool_choice = match (&options.tool_choice, &options.base.tool_choice) {
+        (Some(tool_choice), _) => Some(
+            tool_choice
+                .as_str()
+                .map(|choice| json!({ "type": choice }))
+                .unwrap_or_else(|| tool_choice.clone()),
+        ),
+        (None, Some(tool_choice)) => Some(convert_tool_choice(tool_choice)),
+        (None, None) => None,
+    };
+    if options.base.parallel_tool_calls == Some(false) && object.contains_key("tools") {
+        let tool_choice = tool_choice.get_or_insert_with(|| json!({ "type": "auto" }));
+        if tool_choice["type"] != "none" {
+            tool_choice["disable_parallel_tool_use"] = json!(true);
+        }
+    }
+    if let Some(tool_choice) = tool_choice {
+        object.insert("tool_choice".to_string(), tool_choice);
+    }
+    if let Some(format) = &options.base.response_format {
+        // Structured output is a forced call to a tool whose input schema is
+        // the requested schema.
+        let mut tool = json!({
+            "name": format.name,
+            "input_schema": format.schema,
+        });
+        if let Some(description) = &format.description {
+            tool["description"] = json!(description);
+        }
+        match object.get_mut("tools").and_then(Value::as_array_mut) {
+            Some(tools) => tools.push(tool),
+            None => {
+                object.insert("tools".to_string(), json!([tool]));
+            }
+        }
+        object.insert(
+            "tool_choice".to_string(),
+            json!({ "type": "tool", "name": format.name }),
+        );
+    }
+    // Anthropic rejects forced tool use while extended thinking is on.
+    if object
+        .get("tool_choice")
+        .is_some_and(|tool_choice| matches!(tool_choice["type"].as_str(), Some("any" | "tool")))
+        && object
+            .get("thinking")
+            .is_some_and(|thinking| thinking["type"] != "disabled")
+    {
+        object.remove("thinking");
     }
     Ok(payload)
 }
@@ -1057,6 +1212,8 @@ fn convert_transformed_messages(
                                     "data": image.data
                                 }
                             })),
+                            UserContent::Document(document) => Some(convert_document(document)),
+                            UserContent::Audio(_) => None,
                         })
                         .collect();
                     if !blocks.is_empty() {
@@ -1212,10 +1369,10 @@ fn convert_transformed_messages(
 }
 
 fn convert_content_blocks(content: &[ToolResultContent]) -> Value {
-    let has_images = content
+    let has_attachments = content
         .iter()
-        .any(|content| matches!(content, ToolResultContent::Image(_)));
-    if !has_images {
+        .any(|content| !matches!(content, ToolResultContent::Text(_)));
+    if !has_attachments {
         let text = content
             .iter()
             .filter_map(|content| match content {
@@ -1241,17 +1398,51 @@ fn convert_content_blocks(content: &[ToolResultContent]) -> Value {
                     "data": image.data
                 }
             }),
+            ToolResultContent::Document(document) => convert_document(document),
         })
         .collect();
     let has_text = blocks
         .iter()
         .any(|block| block.get("type").and_then(Value::as_str) == Some("text"));
     if !has_text {
-        blocks.insert(0, json!({ "type": "text", "text": "(see attached image)" }));
+        let placeholder = if content
+            .iter()
+            .any(|content| matches!(content, ToolResultContent::Image(_)))
+        {
+            "(see attached image)"
+        } else {
+            "(see attached document)"
+        };
+        blocks.insert(0, json!({ "type": "text", "text": placeholder }));
     }
     Value::Array(blocks)
 }
 
+fn convert_document(document: &DocumentContent) -> Value {
+    let source = match &document.source {
+        DocumentSource::Base64 { data } => json!({
+            "type": "base64",
+            "media_type": document.mime_type,
+            "data": data
+        }),
+        DocumentSource::Url { url } => json!({ "type": "url", "url": url }),
+    };
+    let mut block = json!({ "type": "document", "source": source });
+    if let Some(title) = &document.title {
+        block["title"] = json!(title);
+    }
+    block
+}
+
+fn convert_tool_choice(tool_choice: &ToolChoice) -> Value {
+    match tool_choice {
+        ToolChoice::Auto => json!({ "type": "auto" }),
+        ToolChoice::None => json!({ "type": "none" }),
+        ToolChoice::Required => json!({ "type": "any" }),
+        ToolChoice::Tool(name) => json!({ "type": "tool", "name": name }),
+    }
+}
+
 fn convert_tools(
     tools: &[Tool],
     is_oauth_token: bool,
@@ -1555,7 +1746,10 @@ fn has_authorization_override(headers: &crate::types::ProviderHeaders) -> bool {
         .any(|(name, _)| name.eq_ignore_ascii_case("authorization"))
 }
 
-fn map_thinking_level_to_effort(model: &Model, level: ModelThinkingLevel) -> AnthropicEffort {
+pub(crate) fn map_thinking_level_to_effort(
+    model: &Model,
+    level: ModelThinkingLevel,
+) -> AnthropicEffort {
     if let Some(Some(mapped)) = model.thinking_level_map.get(level.as_str()) {
         return match mapped.as_str() {
             "low" => AnthropicEffort::Low,
@@ -1676,7 +1870,8 @@ mod tests {
 
     use super::*;
     use crate::types::{
-        AssistantContent, CacheRetention, ModelCost, ModelInput, PayloadHook, ResponseHook, Usage,
+        AssistantContent, CacheRetention, JsonSchemaFormat, ModelCost, ModelInput, PayloadHook,
+        ResponseHook, ToolChoice, Usage,
     };
 
     fn anthropic_model(id: &str) -> Model {
@@ -2285,7 +2480,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::Stop,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: crate::utils::time::now_millis(),
         })
     }
@@ -2403,6 +2601,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn documents_convert_to_document_blocks() {
+        let mut model = anthropic_model("claude-sonnet-4-5");
+        model.input.push(ModelInput::Pdf);
+        let messages = vec![
+            crate::types::Message::User(crate::types::UserMessage {
+                content: crate::types::UserMessageContent::Parts(vec![
+                    crate::types::UserContent::Document(
+                        DocumentContent::pdf("JVBERi0=").title("Q3 report"),
+                    ),
+                    crate::types::UserContent::Document(DocumentContent::url(
+                        "https://example.com/q4.pdf",
+                        "application/pdf",
+                    )),
+                ]),
+                timestamp: 1,
+            }),
+            crate::types::Message::ToolResult(crate::types::ToolResultMessage {
+                tool_call_id: "toolu_123".to_string(),
+                tool_name: "fetch".to_string(),
+                content: vec![ToolResultContent::Document(DocumentContent::pdf(
+                    "JVBERi0x",
+                ))],
+                details: None,
+                usage: None,
+                added_tool_names: Vec::new(),
+                is_error: false,
+                timestamp: 2,
+            }),
+        ];
+
+        let converted = convert_messages(&messages, &model, false, None, false);
+
+        assert_eq!(
+            converted[0]["content"],
+            json!([
+                {
+                    "type": "document",
+                    "source": {
+                        "type": "base64",
+                        "media_type": "application/pdf",
+                        "data": "JVBERi0="
+                    },
+                    "title": "Q3 report"
+                },
+                {
+                    "type": "document",
+                    "source": { "type": "url", "url": "https://example.com/q4.pdf" }
+                }
+            ])
+        );
+        assert_eq!(
+            converted[1]["content"][0]["content"],
+            json!([
+                { "type": "text", "text": "(see attached document)" },
+                {
+                    "type": "document",
+                    "source": {
+                        "type": "base64",
+                        "media_type": "application/pdf",
+                        "data": "JVBERi0x"
+                    }
+                }
+            ])
+        );
+    }
+
     #[test]
     fn text_only_tool_result_content_uses_concatenated_string() {
         let model = anthropic_model("claude-haiku-4-5");
@@ -2568,6 +2833,145 @@ mod tests {
         assert_eq!(payload["tool_choice"], json!({ "type": "any" }));
     }
 
+    #[test]
+    fn response_format_becomes_a_forced_tool_call() {
+        let model = anthropic_model("claude-sonnet-4-5");
+        let schema = json!({
+            "type": "object",
+            "properties": { "city": { "type": "string" } },
+            "required": ["city"]
+        });
+        let payload = build_anthropic_payload(
+            &model,
+            &Context {
+                messages: vec![crate::types::Message::user_text("Where is the Louvre?")],
+                ..Default::default()
+            },
+            &AnthropicOptions {
+                base: StreamOptions {
+                    response_format: Some(
+                        JsonSchemaFormat::new(schema.clone())
+                            .name("location")
+                            .description("Where it is"),
+                    ),
+                    ..Default::default()
+                },
+                thinking_enabled: Some(true),
+                ..Default::default()
+            },
+            false,
+            None,
+        );
+
+        assert_eq!(
+            payload["tools"],
+            json!([{ "name": "location", "description": "Where it is", "input_schema": schema }])
+        );
+        assert_eq!(
+            payload["tool_choice"],
+            json!({ "type": "tool", "name": "location" })
+        );
+        assert!(payload.get("thinking").is_none());
+    }
+
+    #[test]
+    fn sampling_options_map_to_anthropic_fields() {
+        let model = anthropic_model("claude-sonnet-4-5");
+        let context = Context {
+            messages: vec![crate::types::Message::user_text("Count.")],
+            ..Default::default()
+        };
+        let base = StreamOptions {
+            top_p: Some(0.9),
+            top_k: Some(40),
+            stop: vec!["END".to_string()],
+            seed: Some(7),
+            frequency_penalty: Some(0.5),
+            ..Default::default()
+        };
+        let payload = build_anthropic_payload(
+            &model,
+            &context,
+            &AnthropicOptions {
+                base: base.clone(),
+                ..Default::default()
+            },
+            false,
+            None,
+        );
+
+        assert_eq!(payload["top_p"], json!(0.9));
+        assert_eq!(payload["top_k"], json!(40));
+        assert_eq!(payload["stop_sequences"], json!(["END"]));
+        assert!(payload.get("seed").is_none());
+        assert!(payload.get("frequency_penalty").is_none());
+
+        let thinking = build_anthropic_payload(
+            &model,
+            &context,
+            &AnthropicOptions {
+                base,
+                thinking_enabled: Some(true),
+                ..Default::default()
+            },
+            false,
+            None,
+        );
+        assert!(thinking.get("top_p").is_none());
+        assert!(thinking.get("top_k").is_none());
+        assert_eq!(thinking["stop_sequences"], json!(["END"]));
+    }
+
+    #[test]
+    fn typed_tool_choice_forces_the_tool_and_disables_parallel_use() {
+        let model = anthropic_model("claude-sonnet-4-5");
+        let context = Context {
+            messages: vec![crate::types::Message::user_text("Use lookup")],
+            tools: vec![lookup_tool()],
+            ..Default::default()
+        };
+        let payload = build_anthropic_payload(
+            &model,
+            &context,
+            &AnthropicOptions {
+                base: StreamOptions {
+                    tool_choice: Some(ToolChoice::tool("lookup")),
+                    parallel_tool_calls: Some(false),
+                    ..Default::default()
+                },
+                thinking_enabled: Some(true),
+                ..Default::default()
+            },
+            false,
+            None,
+        );
+
+        assert_eq!(
+            payload["tool_choice"],
+            json!({ "type": "tool", "name": "lookup", "disable_parallel_tool_use": true })
+        );
+        assert!(payload.get("thinking").is_none());
+
+        let payload = build_anthropic_payload(
+            &model,
+            &context,
+            &AnthropicOptions {
+                base: StreamOptions {
+                    parallel_tool_calls: Some(false),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            false,
+            None,
+        );
+
+        assert_eq!(
+            payload["tool_choice"],
+            json!({ "type": "auto", "disable_parallel_tool_use": true })
+        );
+    }
+
     #[test]
     fn object_tool_choice_passes_through_for_anthropic_payload() {
         let model = anthropic_model("claude-sonnet-4-5");
@@ -3676,6 +4080,75 @@ mod tests {
         (format!("http://{addr}"), release)
     }
 
+    async fn spawn_json_pages_server(
+        pages: Vec<Value>,
+    ) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let captured = Arc::clone(&requests);
+        tokio::spawn(async move {
+            for page in pages {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0u8; 4096];
+                let read = socket.read(&mut buffer).await.unwrap();
+                captured
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&buffer[..read]).into_owned());
+                let body = page.to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (format!("http://{addr}"), requests)
+    }
+
+    #[tokio::test]
+    async fn list_models_follows_pagination_and_uses_display_names() {
+        let (base_url, requests) = spawn_json_pages_server(vec![
+            json!({
+                "data": [{ "id": "claude-sonnet-4-5", "display_name": "Claude Sonnet 4.5" }],
+                "has_more": true,
+                "last_id": "claude-sonnet-4-5"
+            }),
+            json!({
+                "data": [{ "id": "claude-haiku-4-5", "display_name": "Claude Haiku 4.5" }],
+                "has_more": false,
+                "last_id": "claude-haiku-4-5"
+            }),
+        ])
+        .await;
+        let provider = builder()
+            .api_key("test-key")
+            .base_url(base_url)
+            .build()
+            .unwrap();
+
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(
+            models
+                .iter()
+                .map(|model| (model.id.as_str(), model.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("claude-sonnet-4-5", "Claude Sonnet 4.5"),
+                ("claude-haiku-4-5", "Claude Haiku 4.5"),
+            ]
+        );
+        assert!(models[0].language_api().is_some());
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("GET /models?limit=1000 "));
+        assert!(requests[0].contains("x-api-key: test-key"));
+        assert!(requests[0].contains("anthropic-version: 2023-06-01"));
+        assert!(requests[1].starts_with("GET /models?limit=1000&after_id=claude-sonnet-4-5 "));
+    }
+
     #[tokio::test]
     async fn repairs_malformed_sse_json_and_malformed_streamed_tool_json() {
         let body = sse_body(&[
@@ -3879,6 +4352,60 @@ mod tests {
         assert_eq!(result.usage.total_tokens, 12);
     }
 
+    #[tokio::test]
+    async fn stop_sequence_is_reported_on_the_final_message() {
+        let body = sse_body(&[
+            (
+                "message_start",
+                json!({
+                    "type": "message_start",
+                    "message": {
+                        "id": "msg_stop_sequence",
+                        "usage": { "input_tokens": 5, "output_tokens": 0 }
+                    }
+                })
+                .to_string(),
+            ),
+            (
+                "message_delta",
+                json!({
+                    "type": "message_delta",
+                    "delta": { "stop_reason": "stop_sequence", "stop_sequence": "END" }
+                })
+                .to_string(),
+            ),
+            (
+                "message_stop",
+                json!({ "type": "message_stop" }).to_string(),
+            ),
+        ]);
+        let base_url = spawn_sse_server(body).await;
+        let mut model = anthropic_model("claude-haiku-4-5");
+        model.base_url = base_url;
+        model.reasoning = false;
+
+        let result = crate::stream::final_message_from_stream(stream_anthropic(
+            model,
+            Context {
+                messages: vec![crate::types::Message::user_text("Count.")],
+                ..Default::default()
+            },
+            AnthropicOptions {
+                base: StreamOptions {
+                    api_key: Some("test-key".to_string()),
+                    stop: vec!["END".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(result.stop_reason, StopReason::Stop);
+        assert_eq!(result.stop_sequence.as_deref(), Some("END"));
+    }
+
     #[tokio::test]
     async fn response_id_is_exposed_from_message_start() {
         let body = sse_body(&[
@@ -0,0 +1,654 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::env_api_keys::{KnownProvider, get_env_api_key};
+use crate::event_stream::AssistantEventStream;
+use crate::provider::{LanguageModelApi, ModelBuilder, Provider, ProviderCapabilities};
+use crate::providers::openai::OpenAiApi;
+use crate::providers::{openai_completions, openai_responses, simple_options};
+use crate::types::{
+    Context, Model, ModelCompat, ModelInput, OpenAIResponsesCompat, SimpleStreamOptions,
+    StreamOptions,
+};
+use crate::utils::headers::has_non_empty_header;
+use crate::utils::provider_env::get_provider_env_value;
+use crate::{Error, Result};
+
+const DEFAULT_PROVIDER_ID: KnownProvider = KnownProvider::AzureOpenAi;
+/// GA data-plane version used for deployment-scoped Chat Completions.
+const DEFAULT_API_VERSION: &str = "2024-10-21";
+const AZURE_OPENAI_ENDPOINT_ENV_VAR: &str = "AZURE_OPENAI_ENDPOINT";
+const AZURE_OPENAI_RESOURCE_NAME_ENV_VAR: &str = "AZURE_OPENAI_RESOURCE_NAME";
+const AZURE_OPENAI_API_VERSION_ENV_VAR: &str = "AZURE_OPENAI_API_VERSION";
+
+/// Azure OpenAI resource handle.
+///
+/// Chat Completions requests go to
+/// `{endpoint}/openai/deployments/{deployment}/chat/completions?api-version=...`,
+/// and Responses requests go to the v1 route `{endpoint}/openai/v1/responses`.
+/// Model ids map to deployment names through [`AzureOpenAiBuilder::deployment`]
+/// and default to the model id itself.
+#[derive(Clone)]
+pub struct AzureOpenAi {
+    provider_id: String,
+    endpoint: String,
+    api: OpenAiApi,
+    api_version: Option<String>,
+    deployments: HashMap<String, String>,
+    api_key: Option<String>,
+    entra_token: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl AzureOpenAi {
+    pub fn builder() -> AzureOpenAiBuilder {
+        AzureOpenAiBuilder::default()
+    }
+
+    /// Reads `AZURE_OPENAI_API_KEY`, `AZURE_OPENAI_ENDPOINT` (or
+    /// `AZURE_OPENAI_RESOURCE_NAME`) and the optional
+    /// `AZURE_OPENAI_API_VERSION`.
+    pub fn from_env() -> Result<Self> {
+        let env = Default::default();
+        let api_key = get_env_api_key(DEFAULT_PROVIDER_ID)
+            .ok_or_else(|| Error::MissingApiKey(DEFAULT_PROVIDER_ID.into()))?;
+        let mut builder = Self::builder().api_key(Some(api_key.as_str()));
+        if let Some(endpoint) = get_provider_env_value(AZURE_OPENAI_ENDPOINT_ENV_VAR, &env) {
+            builder = builder.endpoint(endpoint);
+        } else if let Some(resource_name) =
+            get_provider_env_value(AZURE_OPENAI_RESOURCE_NAME_ENV_VAR, &env)
+        {
+            builder = builder.resource_name(resource_name);
+        }
+        if let Some(api_version) = get_provider_env_value(AZURE_OPENAI_API_VERSION_ENV_VAR, &env) {
+            builder = builder.api_version(api_version);
+        }
+        builder.build()
+    }
+
+    pub fn model(&self, id: &str) -> ModelBuilder {
+        <Self as Provider>::model(self, id)
+    }
+
+    /// Returns the deployment that serves `model_id`.
+    pub fn deployment_name<'a>(&'a self, model_id: &'a str) -> &'a str {
+        self.deployments
+            .get(model_id)
+            .map(String::as_str)
+            .unwrap_or(model_id)
+    }
+
+    fn model_base_url(&self, model_id: &str) -> String {
+        match self.api {
+            OpenAiApi::Responses => {
+                let base_url = format!("{}/openai/v1", self.endpoint);
+                match &self.api_version {
+                    Some(api_version) => format!("{base_url}?api-version={api_version}"),
+                    None => base_url,
+                }
+            }
+            _ => format!(
+                "{}/openai/deployments/{}?api-version={}",
+                self.endpoint,
+                self.deployment_name(model_id),
+                self.api_version.as_deref().unwrap_or(DEFAULT_API_VERSION)
+            ),
+        }
+    }
+}
+
+impl Provider for AzureOpenAi {
+    fn id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            language_models: true,
+            image_models: false,
+            embedding_models: false,
+            fim_models: false,
+            rerank_models: false,
+            transcription_models: false,
+            speech_models: false,
+        }
+    }
+
+    fn model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(AzureOpenAiLanguageModelApi {
+            api: self.api,
+            api_key: self.api_key.clone(),
+            entra_token: self.entra_token.clone(),
+            http_client: self.http_client.clone(),
+        });
+        let mut compat = ModelCompat::default();
+        if self.api == OpenAiApi::Responses {
+            compat.openai_responses = OpenAIResponsesCompat {
+                supports_strict_mode: Some(true),
+                ..Default::default()
+            };
+        }
+        // The v1 Responses route selects the deployment from the `model`
+        // field, so Responses models carry the deployment name as their id.
+        let model_id = if self.api == OpenAiApi::Responses {
+            self.deployment_name(id)
+        } else {
+            id
+        };
+        ModelBuilder::new(&self.provider_id, model_id, runtime)
+            .name(id)
+            .base_url(self.model_base_url(id))
+            .input(vec![ModelInput::Text, ModelInput::Image])
+            .context_window(128_000)
+            .max_tokens(16_384)
+            .compat(compat)
+    }
+}
+
+#[derive(Default)]
+pub struct AzureOpenAiBuilder {
+    provider_id: Option<String>,
+    endpoint: Option<String>,
+    api: Option<OpenAiApi>,
+    api_version: Option<String>,
+    deployments: HashMap<String, String>,
+    api_key: Option<String>,
+    entra_token: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl AzureOpenAiBuilder {
+    pub fn provider_id(mut self, provider_id: impl Into<String>) -> Self {
+        self.provider_id = Some(provider_id.into());
+        self
+    }
+
+    /// Sets the resource endpoint, e.g. `https://my-resource.openai.azure.com`.
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Shorthand for `endpoint("https://{resource_name}.openai.azure.com")`.
+    pub fn resource_name(self, resource_name: impl AsRef<str>) -> Self {
+        let endpoint = format!("https://{}.openai.azure.com", resource_name.as_ref());
+        self.endpoint(endpoint)
+    }
+
+    /// Sets the `api-version` query parameter. Chat Completions defaults to
+    /// `2024-10-21`; the v1 Responses route sends no version unless one is
+    /// set here.
+    pub fn api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = Some(api_version.into());
+        self
+    }
+
+    /// Routes `model_id` to the named deployment.
+    pub fn deployment(
+        mut self,
+        model_id: impl Into<String>,
+        deployment: impl Into<String>,
+    ) -> Self {
+        self.deployments.insert(model_id.into(), deployment.into());
+        self
+    }
+
+    /// Sets the resource key, sent in the `api-key` header.
+    pub fn api_key(mut self, api_key: Option<&str>) -> Self {
+        self.api_key = api_key
+            .map(str::trim)
+            .filter(|api_key| !api_key.is_empty())
+            .map(str::to_string);
+        self
+    }
+
+    /// Sets a Microsoft Entra ID access token, sent as a bearer token when no
+    /// API key is configured.
+    pub fn entra_token(mut self, entra_token: Option<&str>) -> Self {
+        self.entra_token = entra_token
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .map(str::to_string);
+        self
+    }
+
+    pub fn api(mut self, api: OpenAiApi) -> Self {
+        self.api = Some(api);
+        self
+    }
+
+    pub fn chat_completions(mut self) -> Self {
+        self.api = Some(OpenAiApi::ChatCompletions);
+        self
+    }
+
+    pub fn responses(mut self) -> Self {
+        self.api = Some(OpenAiApi::Responses);
+        self
+    }
+
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn build(self) -> Result<AzureOpenAi> {
+        let api = self.api.unwrap_or(OpenAiApi::ChatCompletions);
+        if matches!(api, OpenAiApi::Embeddings | OpenAiApi::Images) {
+            return Err(Error::UnsupportedApi(api.id().to_string()));
+        }
+        let endpoint = self.endpoint.ok_or_else(|| {
+            Error::Validation("Azure OpenAI requires an endpoint or resource name".to_string())
+        })?;
+        Ok(AzureOpenAi {
+            provider_id: self
+                .provider_id
+                .unwrap_or_else(|| DEFAULT_PROVIDER_ID.into()),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api,
+            api_version: self.api_version,
+            deployments: self.deployments,
+            api_key: self.api_key,
+            entra_token: self.entra_token,
+            http_client: self.http_client,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct AzureOpenAiLanguageModelApi {
+    api: OpenAiApi,
+    api_key: Option<String>,
+    entra_token: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl AzureOpenAiLanguageModelApi {
+    /// Moves credentials into Azure's headers. The OpenAI wire code sends
+    /// `StreamOptions::api_key` as a bearer token, so it is emptied once the
+    /// `api-key` or Entra header is in place.
+    fn with_api_key(&self, mut options: StreamOptions) -> StreamOptions {
+        let api_key = options
+            .api_key
+            .take()
+            .filter(|api_key| !api_key.trim().is_empty())
+            .or_else(|| self.api_key.clone());
+        let has_auth_override = has_non_empty_header(&options.headers, "api-key")
+            || has_non_empty_header(&options.headers, "authorization");
+        if !has_auth_override {
+            if let Some(api_key) = api_key {
+                options.headers.insert("api-key", Some(api_key));
+                options.api_key = Some(String::new());
+            } else if let Some(token) = &self.entra_token {
+                options.api_key = Some(token.clone());
+            }
+        } else {
+            options.api_key = Some(String::new());
+        }
+        if options.http_client.is_none() {
+            options.http_client = self.http_client.clone();
+        }
+        options
+    }
+
+    fn with_api_key_simple(&self, mut options: SimpleStreamOptions) -> SimpleStreamOptions {
+        options.stream = self.with_api_key(options.stream);
+        options
+    }
+}
+
+impl LanguageModelApi for AzureOpenAiLanguageModelApi {
+    fn id(&self) -> &str {
+        self.api.id()
+    }
+
+    fn stream(
+        &self,
+        model: Model,
+        context: Context,
+        options: StreamOptions,
+    ) -> Result<AssistantEventStream> {
+        let options = self.with_api_key(options);
+        match self.api {
+            OpenAiApi::Responses => Ok(openai_responses::stream_openai_responses(
+                model,
+                context,
+                simple_options::openai_responses_options_from_stream_options(options),
+            )),
+            _ => Ok(openai_completions::stream_openai_completions(
+                model,
+                context,
+                simple_options::openai_completions_options_from_stream_options(options),
+            )),
+        }
+    }
+
+    fn stream_simple(
+        &self,
+        model: Model,
+        context: Context,
+        options: SimpleStreamOptions,
+    ) -> Result<AssistantEventStream> {
+        let options = self.with_api_key_simple(options);
+        match self.api {
+            OpenAiApi::Responses => {
+                openai_responses::stream_simple_openai_responses(model, context, options)
+            }
+            _ => openai_completions::stream_simple_openai_completions(model, context, options),
+        }
+    }
+}
+
+pub fn builder() -> AzureOpenAiBuilder {
+    AzureOpenAi::builder()
+}
+
+pub fn from_env() -> Result<AzureOpenAi> {
+    AzureOpenAi::from_env()
+}
+
+/// Whether `model` is served by Azure OpenAI, whose responses carry content
+/// filter results that plain OpenAI-compatible servers do not.
+pub(crate) fn is_azure_openai(model: &Model) -> bool {
+    model.provider == KnownProvider::AzureOpenAi.as_str() || model.base_url.contains(".azure.com")
+}
+
+/// Formats Azure's content-filter rejection body, which reports the policy
+/// hit under `error.innererror.content_filter_result` instead of a plain
+/// OpenAI error message.
+pub(crate) fn content_filter_error(status: reqwest::StatusCode, body: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(body).ok()?;
+    let error = value.get("error")?;
+    if error.get("code").and_then(Value::as_str) != Some("content_filter") {
+        return None;
+    }
+    let message = error
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or("The request was filtered");
+    let mut formatted = format!(
+        "Azure OpenAI content filter ({}): {message}",
+        status.as_u16()
+    );
+    if let Some(categories) = error
+        .pointer("/innererror/content_filter_result")
+        .and_then(filtered_categories)
+    {
+        formatted.push_str(&format!(" [{categories}]"));
+    }
+    Some(formatted)
+}
+
+/// Lists the filtered categories of an Azure `content_filter_results` object,
+/// e.g. `hate (medium), violence (high)`.
+pub(crate) fn filtered_categories(results: &Value) -> Option<String> {
+    let categories = results
+        .as_object()?
+        .iter()
+        .filter(|(_, result)| result.get("filtered").and_then(Value::as_bool) == Some(true))
+        .map(
+            |(category, result)| match result.get("severity").and_then(Value::as_str) {
+                Some(severity) => format!("{category} ({severity})"),
+                None => category.clone(),
+            },
+        )
+        .collect::<Vec<_>>();
+    (!categories.is_empty()).then(|| categories.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::types::{Message, StopReason};
+
+    fn azure() -> AzureOpenAiBuilder {
+        builder()
+            .resource_name("contoso")
+            .api_key(Some("azure-key"))
+            .deployment("gpt-4o", "prod-gpt-4o")
+    }
+
+    #[test]
+    fn chat_models_route_to_deployments_with_api_version() {
+        let provider = azure().build().unwrap();
+        let model = provider.model("gpt-4o").build().unwrap();
+
+        assert_eq!(model.api_id(), "openai-completions");
+        assert_eq!(model.provider_id(), "azure-openai");
+        assert_eq!(model.id, "gpt-4o");
+        assert_eq!(
+            model.base_url,
+            "https://contoso.openai.azure.com/openai/deployments/prod-gpt-4o?api-version=2024-10-21"
+        );
+        assert_eq!(
+            provider.model("o4-mini").build().unwrap().base_url,
+            "https://contoso.openai.azure.com/openai/deployments/o4-mini?api-version=2024-10-21"
+        );
+    }
+
+    #[test]
+    fn responses_models_use_the_v1_route_and_deployment_as_model() {
+        let model = azure()
+            .responses()
+            .build()
+            .unwrap()
+            .model("gpt-4o")
+            .build()
+            .unwrap();
+        assert_eq!(model.api_id(), "openai-responses");
+        assert_eq!(model.id, "prod-gpt-4o");
+        assert_eq!(model.base_url, "https://contoso.openai.azure.com/openai/v1");
+
+        let versioned = azure()
+            .responses()
+            .api_version("preview")
+            .build()
+            .unwrap()
+            .model("gpt-4o")
+            .build()
+            .unwrap();
+        assert_eq!(
+            versioned.base_url,
+            "https://contoso.openai.azure.com/openai/v1?api-version=preview"
+        );
+    }
+
+    #[test]
+    fn builder_requires_an_endpoint_and_a_language_api() {
+        assert!(matches!(
+            builder().build(),
+            Err(Error::Validation(message)) if message.contains("endpoint")
+        ));
+        assert!(matches!(
+            azure().api(OpenAiApi::Embeddings).build(),
+            Err(Error::UnsupportedApi(api)) if api == "openai-embeddings"
+        ));
+    }
+
+    #[tokio::test]
+    async fn chat_requests_send_api_key_header_and_keep_query_string() {
+        let body = concat!(
+            "data: {\"id\":\"c1\",\"choices\":[],\"prompt_filter_results\":[]}\n\n",
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hi\"}}]}\n\n",
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let (endpoint, request) = spawn_server("200 OK", "text/event-stream", body).await;
+        let model = azure()
+            .endpoint(endpoint)
+            .build()
+            .unwrap()
+            .model("gpt-4o")
+            .build()
+            .unwrap();
+
+        let message = crate::complete(
+            model,
+            Context {
+                messages: vec![Message::user_text("hi")],
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        let request = request.await.unwrap();
+
+        assert_eq!(message.stop_reason, StopReason::Stop);
+        assert!(request.starts_with(
+            "POST /openai/deployments/prod-gpt-4o/chat/completions?api-version=2024-10-21 HTTP/1.1"
+        ));
+        assert!(request.contains("api-key: azure-key"));
+        assert!(!request.to_ascii_lowercase().contains("authorization:"));
+    }
+
+    #[tokio::test]
+    async fn entra_tokens_are_sent_as_bearer_auth() {
+        let body = concat!(
+            "data: {\"type\":\"response.completed\",\"response\":{\"id\":\"r1\",\"status\":\"completed\",",
+            "\"output\":[],\"usage\":{\"input_tokens\":1,\"output_tokens\":1,\"total_tokens\":2}}}\n\n"
+        );
+        let (endpoint, request) = spawn_server("200 OK", "text/event-stream", body).await;
+        let model = builder()
+            .endpoint(endpoint)
+            .entra_token(Some("entra-token"))
+            .responses()
+            .build()
+            .unwrap()
+            .model("gpt-4o")
+            .build()
+            .unwrap();
+
+        let mut stream = model
+            .language_api()
+            .unwrap()
+            .stream(
+                model.clone(),
+                Context {
+                    messages: vec![Message::user_text("hi")],
+                    ..Default::default()
+                },
+                StreamOptions::default(),
+            )
+            .unwrap();
+        while stream.next().await.is_some() {}
+        let request = request.await.unwrap();
+
+        assert!(request.starts_with("POST /openai/v1/responses HTTP/1.1"));
+        assert!(request.contains("authorization: Bearer entra-token"));
+        assert!(!request.contains("api-key:"));
+    }
+
+    fn content_filter_body() -> String {
+        json!({
+            "error": {
+                "code": "content_filter",
+                "message": "The response was filtered due to the prompt triggering Azure OpenAI's content management policy.",
+                "status": 400,
+                "innererror": {
+                    "code": "ResponsibleAIPolicyViolation",
+                    "content_filter_result": {
+                        "hate": { "filtered": true, "severity": "medium" },
+                        "self_harm": { "filtered": false, "severity": "safe" },
+                        "jailbreak": { "filtered": true, "detected": true }
+                    }
+                }
+            }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn content_filter_rejections_report_filtered_categories() {
+        let body = content_filter_body();
+        let (endpoint, _request) = spawn_server("400 Bad Request", "application/json", &body).await;
+        let model = azure()
+            .endpoint(endpoint)
+            .build()
+            .unwrap()
+            .model("gpt-4o")
+            .build()
+            .unwrap();
+
+        let message = crate::complete(
+            model,
+            Context {
+                messages: vec![Message::user_text("hi")],
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(message.stop_reason, StopReason::Error);
+        assert_eq!(
+            message.error_message.as_deref(),
+            Some(
+                "Azure OpenAI content filter (400): The response was filtered due to the prompt \
+                 triggering Azure OpenAI's content management policy. [hate (medium), jailbreak]"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn other_openai_compatible_servers_keep_their_own_error_text() {
+        let body = content_filter_body();
+        let (endpoint, _request) = spawn_server("400 Bad Request", "application/json", &body).await;
+        let model = crate::providers::openai::builder()
+            .api_key(Some("test-key"))
+            .base_url(format!("{endpoint}/v1"))
+            .build()
+            .unwrap()
+            .model("gpt-4o")
+            .build()
+            .unwrap();
+
+        let message = crate::complete(
+            model,
+            Context {
+                messages: vec![Message::user_text("hi")],
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(message.stop_reason, StopReason::Error);
+        let error = message.error_message.unwrap();
+        assert!(!error.contains("Azure OpenAI content filter"), "{error}");
+        assert!(error.contains("content management policy"), "{error}");
+    }
+
+    async fn spawn_server(
+        status: &'static str,
+        content_type: &'static str,
+        body: &str,
+    ) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = tokio::sync::oneshot::channel();
+        let body = body.to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 16 * 1024];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let _ = request_tx.send(String::from_utf8_lossy(&buffer[..read]).into_owned());
+            let response = format!(
+                "HTTP/1.1 {status}\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        (format!("http://{addr}"), request_rx)
+    }
+}
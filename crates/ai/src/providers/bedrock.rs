@@ -0,0 +1,2120 @@
+use futures::{StreamExt, pin_mut};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::env_api_keys::{KnownProvider, get_env_api_key, get_env_api_key_with_env};
+use crate::event_stream::AssistantMessageEventStreamSender;
+use crate::models::calculate_cost;
+use crate::provider::{LanguageModelApi, ModelBuilder, Provider, ProviderCapabilities};
+use crate::providers::anthropic::{AnthropicEffort, map_thinking_level_to_effort};
+use crate::providers::sampling::{SamplingSupport, sampling};
+use crate::providers::simple_options;
+use crate::providers::simple_options::{
+    adjust_max_tokens_for_thinking, build_base_options, clamp_max_tokens_to_context,
+    clamped_reasoning,
+};
+use crate::providers::transform_messages::transform_messages;
+use crate::types::{
+    AssistantContent, AssistantMessage, AssistantMessageEvent, CacheRetention, Context,
+    DocumentContent, DocumentSource, ImageContent, KnownApi, Message, Model, ModelInput,
+    ProviderEnv, ProviderResponse, SimpleStreamOptions, StopReason, StreamOptions, TextContent,
+    ThinkingContent, Tool, ToolCall, ToolChoice, ToolResultContent, UserContent,
+    UserMessageContent,
+};
+use crate::utils::aws_eventstream;
+use crate::utils::circuit_breaker::model_circuit;
+use crate::utils::headers::headers_to_record;
+use crate::utils::http::{request_timeout, send_with_retries};
+use crate::utils::json::{parse_json_with_repair, parse_streaming_json};
+use crate::utils::provider_env::get_provider_env_value;
+use crate::utils::sigv4::{SigningParams, sign_request, uri_encode};
+use crate::{Error, Result};
+
+const DEFAULT_PROVIDER_ID: KnownProvider = KnownProvider::AmazonBedrock;
+const DEFAULT_REGION: &str = "us-east-1";
+const SIGNING_SERVICE: &str = "bedrock";
+const INTERLEAVED_THINKING_BETA: &str = "interleaved-thinking-2025-05-14";
+const AWS_ACCESS_KEY_ID_ENV_VAR: &str = "AWS_ACCESS_KEY_ID";
+const AWS_SECRET_ACCESS_KEY_ENV_VAR: &str = "AWS_SECRET_ACCESS_KEY";
+const AWS_SESSION_TOKEN_ENV_VAR: &str = "AWS_SESSION_TOKEN";
+const AWS_REGION_ENV_VARS: [&str; 2] = ["AWS_REGION", "AWS_DEFAULT_REGION"];
+
+/// Static AWS credentials used to SigV4-sign Bedrock requests.
+#[derive(Clone, PartialEq, Eq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    pub fn new(access_key_id: impl Into<String>, secret_access_key: impl Into<String>) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: None,
+        }
+    }
+
+    pub fn with_session_token(mut self, session_token: impl Into<String>) -> Self {
+        self.session_token = Some(session_token.into());
+        self
+    }
+
+    /// Reads `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and the optional
+    /// `AWS_SESSION_TOKEN` from the process environment.
+    pub fn from_env() -> Option<Self> {
+        Self::from_provider_env(&ProviderEnv::default())
+    }
+
+    fn from_provider_env(env: &ProviderEnv) -> Option<Self> {
+        let access_key_id = get_provider_env_value(AWS_ACCESS_KEY_ID_ENV_VAR, env)?;
+        let secret_access_key = get_provider_env_value(AWS_SECRET_ACCESS_KEY_ENV_VAR, env)?;
+        let credentials = Self::new(access_key_id, secret_access_key);
+        Some(
+            match get_provider_env_value(AWS_SESSION_TOKEN_ENV_VAR, env) {
+                Some(session_token) => credentials.with_session_token(session_token),
+                None => credentials,
+            },
+        )
+    }
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"<redacted>")
+            .field(
+                "session_token",
+                &self.session_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct Bedrock {
+    provider_id: String,
+    region: String,
+    credentials: Option<AwsCredentials>,
+    api_key: Option<String>,
+    base_url: String,
+    http_client: Option<reqwest::Client>,
+}
+
+impl Bedrock {
+    pub fn builder() -> BedrockBuilder {
+        BedrockBuilder::default()
+    }
+
+    /// Builds a provider from `AWS_REGION`/`AWS_DEFAULT_REGION` and either a
+    /// Bedrock API key (`AWS_BEARER_TOKEN_BEDROCK`) or static AWS credentials.
+    pub fn from_env() -> Result<Self> {
+        let api_key = get_env_api_key(DEFAULT_PROVIDER_ID);
+        let credentials = AwsCredentials::from_env();
+        if api_key.is_none() && credentials.is_none() {
+            return Err(Error::MissingApiKey(DEFAULT_PROVIDER_ID.into()));
+        }
+        let mut builder = Self::builder().api_key(api_key.as_deref());
+        if let Some(region) = env_region(&ProviderEnv::default()) {
+            builder = builder.region(region);
+        }
+        if let Some(credentials) = credentials {
+            builder = builder.credentials(credentials);
+        }
+        builder.build()
+    }
+
+    pub fn model(&self, id: &str) -> ModelBuilder {
+        <Self as Provider>::model(self, id)
+    }
+}
+
+impl Provider for Bedrock {
+    fn id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            language_models: true,
+            image_models: false,
+            embedding_models: false,
+            fim_models: false,
+            rerank_models: false,
+            transcription_models: false,
+            speech_models: false,
+        }
+    }
+
+    fn model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(BedrockLanguageModelApi {
+            region: self.region.clone(),
+            credentials: self.credentials.clone(),
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .reasoning(is_claude_reasoning_model(id))
+            .input(vec![ModelInput::Text, ModelInput::Image, ModelInput::Pdf])
+            .context_window(200_000)
+            .max_tokens(16_384)
+    }
+}
+
+#[derive(Default)]
+pub struct BedrockBuilder {
+    provider_id: Option<String>,
+    region: Option<String>,
+    credentials: Option<AwsCredentials>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl BedrockBuilder {
+    pub fn provider_id(mut self, provider_id: impl Into<String>) -> Self {
+        self.provider_id = Some(provider_id.into());
+        self
+    }
+
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    pub fn credentials(mut self, credentials: AwsCredentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Sets a Bedrock API key, sent as a bearer token instead of signing the
+    /// request with AWS credentials.
+    pub fn api_key(mut self, api_key: Option<&str>) -> Self {
+        self.api_key = api_key
+            .map(str::trim)
+            .filter(|api_key| !api_key.is_empty())
+            .map(str::to_string);
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn build(self) -> Result<Bedrock> {
+        let region = self.region.unwrap_or_else(|| DEFAULT_REGION.to_string());
+        Ok(Bedrock {
+            provider_id: self
+                .provider_id
+                .unwrap_or_else(|| DEFAULT_PROVIDER_ID.into()),
+            base_url: self
+                .base_url
+                .unwrap_or_else(|| format!("https://bedrock-runtime.{region}.amazonaws.com")),
+            region,
+            credentials: self.credentials,
+            api_key: self.api_key,
+            http_client: self.http_client,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct BedrockLanguageModelApi {
+    region: String,
+    credentials: Option<AwsCredentials>,
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl BedrockLanguageModelApi {
+    fn with_api_key(&self, mut options: StreamOptions) -> StreamOptions {
+        if options
+            .api_key
+            .as_deref()
+            .is_none_or(|api_key| api_key.trim().is_empty())
+        {
+            options.api_key = self.api_key.clone();
+        }
+        if options.http_client.is_none() {
+            options.http_client = self.http_client.clone();
+        }
+        options
+    }
+
+    fn with_api_key_simple(&self, mut options: SimpleStreamOptions) -> SimpleStreamOptions {
+        options.stream = self.with_api_key(options.stream);
+        options
+    }
+
+    fn with_signing(&self, mut options: BedrockOptions) -> BedrockOptions {
+        if options.region.is_none() {
+            options.region = Some(self.region.clone());
+        }
+        if options.credentials.is_none() {
+            options.credentials = self.credentials.clone();
+        }
+        options
+    }
+}
+
+impl LanguageModelApi for BedrockLanguageModelApi {
+    fn id(&self) -> &str {
+        KnownApi::BedrockConverseStream.as_str()
+    }
+
+    fn stream(
+        &self,
+        model: Model,
+        context: Context,
+        options: StreamOptions,
+    ) -> Result<crate::AssistantEventStream> {
+        Ok(stream_bedrock(
+            model,
+            context,
+            self.with_signing(simple_options::bedrock_options_from_stream_options(
+                self.with_api_key(options),
+            )),
+        ))
+    }
+
+    fn stream_simple(
+        &self,
+        model: Model,
+        context: Context,
+        options: SimpleStreamOptions,
+    ) -> Result<crate::AssistantEventStream> {
+        let options = simple_bedrock_options(&model, &context, &self.with_api_key_simple(options));
+        Ok(stream_bedrock(model, context, self.with_signing(options)))
+    }
+}
+
+pub fn builder() -> BedrockBuilder {
+    Bedrock::builder()
+}
+
+pub fn from_env() -> Result<Bedrock> {
+    Bedrock::from_env()
+}
+
+#[derive(Clone)]
+pub struct BedrockOptions {
+    pub base: StreamOptions,
+    /// Signing region. Defaults to the region in the model's base URL, then
+    /// `AWS_REGION`/`AWS_DEFAULT_REGION`.
+    pub region: Option<String>,
+    /// Signing credentials. Ignored when `base.api_key` holds a Bedrock API
+    /// key; defaults to the `AWS_*` environment variables.
+    pub credentials: Option<AwsCredentials>,
+    pub thinking_enabled: Option<bool>,
+    pub thinking_budget_tokens: Option<u32>,
+    pub effort: Option<AnthropicEffort>,
+    pub interleaved_thinking: bool,
+    pub tool_choice: Option<Value>,
+}
+
+impl Default for BedrockOptions {
+    fn default() -> Self {
+        Self {
+            base: StreamOptions::default(),
+            region: None,
+            credentials: None,
+            thinking_enabled: None,
+            thinking_budget_tokens: None,
+            effort: None,
+            interleaved_thinking: true,
+            tool_choice: None,
+        }
+    }
+}
+
+pub fn stream_simple_bedrock(
+    model: Model,
+    context: Context,
+    options: SimpleStreamOptions,
+) -> Result<crate::AssistantEventStream> {
+    let options = simple_bedrock_options(&model, &context, &options);
+    Ok(stream_bedrock(model, context, options))
+}
+
+fn simple_bedrock_options(
+    model: &Model,
+    context: &Context,
+    options: &SimpleStreamOptions,
+) -> BedrockOptions {
+    let api_key = options
+        .stream
+        .api_key
+        .clone()
+        .filter(|key| !key.trim().is_empty());
+    let base = build_base_options(model, context, options, api_key);
+
+    let Some(reasoning) = clamped_reasoning(model, options) else {
+        return BedrockOptions {
+            base,
+            thinking_enabled: Some(false),
+            ..Default::default()
+        };
+    };
+
+    if model.compat.anthropic_messages.force_adaptive_thinking == Some(true) {
+        return BedrockOptions {
+            base,
+            thinking_enabled: Some(true),
+            effort: Some(map_thinking_level_to_effort(model, reasoning)),
+            ..Default::default()
+        };
+    }
+
+    let adjusted = adjust_max_tokens_for_thinking(
+        base.max_tokens,
+        model.max_tokens,
+        Some(reasoning),
+        options.thinking_budgets.as_ref(),
+    );
+    let max_tokens = clamp_max_tokens_to_context(
+        model,
+        context,
+        adjusted.max_tokens.unwrap_or(model.max_tokens),
+    );
+    let mut adjusted_base = base;
+    adjusted_base.max_tokens = Some(max_tokens);
+    BedrockOptions {
+        base: adjusted_base,
+        thinking_enabled: Some(true),
+        thinking_budget_tokens: Some(
+            adjusted
+                .thinking_budget
+                .min(max_tokens.saturating_sub(1_024)),
+        ),
+        ..Default::default()
+    }
+}
+
+pub fn stream_bedrock(
+    model: Model,
+    context: Context,
+    options: BedrockOptions,
+) -> crate::AssistantEventStream {
+    crate::event_stream::stream_from_producer(
+        move |mut sender| async move {
+            let output = AssistantMessage::empty_for(&model);
+            run_stream(model, context, options, output, &mut sender).await?;
+            Ok(())
+        },
+        |error: StreamFailure| {
+            let mut message = error.output;
+            message.stop_reason = if error.cancelled {
+                StopReason::Aborted
+            } else {
+                StopReason::Error
+            };
+            message.error_message = Some(error.message);
+            AssistantMessageEvent::Error {
+                reason: message.stop_reason,
+                error: message,
+            }
+        },
+    )
+}
+
+struct StreamFailure {
+    output: AssistantMessage,
+    message: String,
+    cancelled: bool,
+}
+
+impl StreamFailure {
+    fn new(output: AssistantMessage, error: impl std::fmt::Display) -> Self {
+        Self {
+            output,
+            message: error.to_string(),
+            cancelled: false,
+        }
+    }
+
+    fn cancelled(output: AssistantMessage) -> Self {
+        Self {
+            output,
+            message: "Request was aborted".to_string(),
+            cancelled: true,
+        }
+    }
+}
+
+async fn run_stream(
+    model: Model,
+    context: Context,
+    options: BedrockOptions,
+    mut output: AssistantMessage,
+    sender: &mut AssistantMessageEventStreamSender,
+) -> std::result::Result<(), StreamFailure> {
+    let is_cancelled = || {
+        options
+            .base
+            .cancellation_token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    };
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+
+    let mut payload = match try_build_bedrock_payload(&model, &context, &options) {
+        Ok(payload) => payload,
+        Err(error) => return Err(StreamFailure::new(output, error)),
+    };
+    if let Some(on_payload) = &options.base.on_payload {
+        match on_payload(payload.clone(), &model).await {
+            Ok(Some(next)) => payload = next,
+            Ok(None) => {}
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        }
+    }
+    let body = match serde_json::to_vec(&payload) {
+        Ok(body) => body,
+        Err(error) => return Err(StreamFailure::new(output, error)),
+    };
+
+    let request_url = match reqwest::Url::parse(&format!(
+        "{}/model/{}/converse-stream",
+        model.base_url.trim_end_matches('/'),
+        uri_encode(&model.id)
+    )) {
+        Ok(url) => url,
+        Err(error) => {
+            return Err(StreamFailure::new(
+                output,
+                Error::Validation(format!("Invalid Bedrock base URL: {error}")),
+            ));
+        }
+    };
+    let request_headers = match headers(&model, &options, &request_url, &body) {
+        Ok(headers) => headers,
+        Err(error) => return Err(StreamFailure::new(output, error)),
+    };
+    let client = options.base.http_client.clone().unwrap_or_default();
+    let response = match send_with_retries(&options.base, &model_circuit(&model), || {
+        client
+            .post(request_url.clone())
+            .headers(request_headers.clone())
+            .body(body.clone())
+            .timeout(request_timeout(options.base.timeout_ms))
+    })
+    .await
+    {
+        Ok(response) => response,
+        Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
+    };
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        output.error_status = Some(status.as_u16());
+        return Err(StreamFailure::new(
+            output,
+            Error::ApiStatus { status, body },
+        ));
+    }
+    if let Some(on_response) = &options.base.on_response {
+        let provider_response = ProviderResponse {
+            status: response.status().as_u16(),
+            headers: headers_to_record(response.headers()),
+        };
+        if let Err(error) = on_response(provider_response, &model).await {
+            return Err(StreamFailure::new(output, error));
+        }
+    }
+
+    sender.push(AssistantMessageEvent::Start {
+        partial: output.clone(),
+    });
+
+    let mut blocks_by_bedrock_index: HashMap<i64, usize> = HashMap::new();
+    let mut partial_json: HashMap<usize, String> = HashMap::new();
+    let mut stop_reason: Option<String> = None;
+    let messages = aws_eventstream::messages(response, options.base.cancellation_token.clone());
+    pin_mut!(messages);
+    while let Some(message) = messages.next().await {
+        if is_cancelled() {
+            return Err(StreamFailure::cancelled(output));
+        }
+        let message = match message {
+            Ok(message) => message,
+            Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        };
+        let payload = String::from_utf8_lossy(&message.payload);
+        match message.header(":message-type") {
+            Some("exception") => {
+                let exception = message.header(":exception-type").unwrap_or("exception");
+                let detail = parse_json_with_repair::<Value>(&payload)
+                    .ok()
+                    .and_then(|value| {
+                        value
+                            .get("message")
+                            .and_then(Value::as_str)
+                            .map(str::to_string)
+                    })
+                    .unwrap_or_else(|| payload.to_string());
+                return Err(StreamFailure::new(output, format!("{exception}: {detail}")));
+            }
+            Some("error") => {
+                let code = message.header(":error-code").unwrap_or("error");
+                let detail = message.header(":error-message").unwrap_or_default();
+                return Err(StreamFailure::new(output, format!("{code}: {detail}")));
+            }
+            _ => {}
+        }
+        let event: Value = match parse_json_with_repair(&payload) {
+            Ok(value) => value,
+            Err(error) => {
+                return Err(StreamFailure::new(
+                    output,
+                    format!(
+                        "Could not parse Bedrock event {}: {error}; data={payload}",
+                        message.header(":event-type").unwrap_or_default()
+                    ),
+                ));
+            }
+        };
+        let bedrock_index = event
+            .get("contentBlockIndex")
+            .and_then(Value::as_i64)
+            .unwrap_or(0);
+        match message.header(":event-type") {
+            Some("contentBlockStart") => {
+                let Some(tool_use) = event.pointer("/start/toolUse") else {
+                    continue;
+                };
+                output.content.push(AssistantContent::ToolCall(ToolCall {
+                    id: tool_use
+                        .get("toolUseId")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    name: tool_use
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    arguments: Value::Object(Map::new()),
+                    thought_signature: None,
+                }));
+                let content_index = output.content.len() - 1;
+                blocks_by_bedrock_index.insert(bedrock_index, content_index);
+                partial_json.insert(content_index, String::new());
+                sender.push(AssistantMessageEvent::ToolCallStart {
+                    content_index,
+                    partial: output.clone(),
+                });
+            }
+            Some("contentBlockDelta") => {
+                let Some(delta) = event.get("delta") else {
+                    continue;
+                };
+                if let Some(text) = delta.get("text").and_then(Value::as_str) {
+                    let content_index = open_block(
+                        &mut output,
+                        &mut blocks_by_bedrock_index,
+                        bedrock_index,
+                        false,
+                        sender,
+                    );
+                    if let Some(AssistantContent::Text(block)) =
+                        output.content.get_mut(content_index)
+                    {
+                        block.text.push_str(text);
+                        sender.push(AssistantMessageEvent::TextDelta {
+                            content_index,
+                            delta: text.to_string(),
+                            partial: output.clone(),
+                        });
+                    }
+                } else if let Some(reasoning) = delta.get("reasoningContent") {
+                    let content_index = open_block(
+                        &mut output,
+                        &mut blocks_by_bedrock_index,
+                        bedrock_index,
+                        true,
+                        sender,
+                    );
+                    let Some(AssistantContent::Thinking(block)) =
+                        output.content.get_mut(content_index)
+                    else {
+                        continue;
+                    };
+                    if let Some(text) = reasoning.get("text").and_then(Value::as_str) {
+                        block.thinking.push_str(text);
+                        sender.push(AssistantMessageEvent::ThinkingDelta {
+                            content_index,
+                            delta: text.to_string(),
+                            partial: output.clone(),
+                        });
+                    } else if let Some(signature) =
+                        reasoning.get("signature").and_then(Value::as_str)
+                    {
+                        block
+                            .thinking_signature
+                            .get_or_insert_with(String::new)
+                            .push_str(signature);
+                    } else if let Some(data) =
+                        reasoning.get("redactedContent").and_then(Value::as_str)
+                    {
+                        block.thinking = "[Reasoning redacted]".to_string();
+                        block.thinking_signature = Some(data.to_string());
+                        block.redacted = Some(true);
+                    }
+                } else if let Some(input) = delta.pointer("/toolUse/input").and_then(Value::as_str)
+                {
+                    let Some(content_index) = blocks_by_bedrock_index.get(&bedrock_index).copied()
+                    else {
+                        continue;
+                    };
+                    let entry = partial_json.entry(content_index).or_default();
+                    entry.push_str(input);
+                    if let Some(AssistantContent::ToolCall(block)) =
+                        output.content.get_mut(content_index)
+                    {
+                        block.arguments = parse_streaming_json(Some(entry));
+                        sender.push(AssistantMessageEvent::ToolCallDelta {
+                            content_index,
+                            delta: input.to_string(),
+                            partial: output.clone(),
+                        });
+                    }
+                }
+            }
+            Some("contentBlockStop") => {
+                let Some(content_index) = blocks_by_bedrock_index.remove(&bedrock_index) else {
+                    continue;
+                };
+                match output.content.get_mut(content_index) {
+                    Some(AssistantContent::Text(block)) => {
+                        sender.push(AssistantMessageEvent::TextEnd {
+                            content_index,
+                            content: block.text.clone(),
+                            partial: output.clone(),
+                        });
+                    }
+                    Some(AssistantContent::Thinking(block)) => {
+                        sender.push(AssistantMessageEvent::ThinkingEnd {
+                            content_index,
+                            content: block.thinking.clone(),
+                            partial: output.clone(),
+                        });
+                    }
+                    Some(AssistantContent::ToolCall(block)) => {
+                        if let Some(arguments) = partial_json.get(&content_index) {
+                            block.arguments = parse_streaming_json(Some(arguments));
+                        }
+                        sender.push(AssistantMessageEvent::ToolCallEnd {
+                            content_index,
+                            tool_call: block.clone(),
+                            partial: output.clone(),
+                        });
+                    }
+                    Some(AssistantContent::Audio(_)) | None => {}
+                }
+            }
+            Some("messageStop") => {
+                stop_reason = Some(
+                    event
+                        .get("stopReason")
+                        .and_then(Value::as_str)
+                        .unwrap_or("end_turn")
+                        .to_string(),
+                );
+                // Claude models report the matched sequence alongside.
+                output.stop_sequence = event
+                    .pointer("/additionalModelResponseFields/stop_sequence")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+            }
+            Some("metadata") => {
+                if let Some(usage) = event.get("usage") {
+                    update_bedrock_usage(&mut output, usage, &model);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+    let Some(stop_reason) = stop_reason else {
+        return Err(StreamFailure::new(
+            output,
+            "Bedrock stream ended before messageStop",
+        ));
+    };
+    match map_stop_reason(&stop_reason) {
+        Ok(reason) => output.stop_reason = reason,
+        Err(message) => return Err(StreamFailure::new(output, message)),
+    }
+    sender.push(AssistantMessageEvent::Done {
+        reason: output.stop_reason,
+        message: output,
+    });
+    Ok(())
+}
+
+/// Returns the output index for a text or reasoning block, starting one when
+/// Bedrock sends the first delta without a `contentBlockStart`.
+fn open_block(
+    output: &mut AssistantMessage,
+    blocks_by_bedrock_index: &mut HashMap<i64, usize>,
+    bedrock_index: i64,
+    thinking: bool,
+    sender: &mut AssistantMessageEventStreamSender,
+) -> usize {
+    if let Some(content_index) = blocks_by_bedrock_index.get(&bedrock_index) {
+        return *content_index;
+    }
+    output.content.push(if thinking {
+        AssistantContent::Thinking(ThinkingContent {
+            thinking: String::new(),
+            thinking_signature: None,
+            redacted: None,
+        })
+    } else {
+        AssistantContent::Text(TextContent {
+            text: String::new(),
+            text_signature: None,
+        })
+    });
+    let content_index = output.content.len() - 1;
+    blocks_by_bedrock_index.insert(bedrock_index, content_index);
+    sender.push(if thinking {
+        AssistantMessageEvent::ThinkingStart {
+            content_index,
+            partial: output.clone(),
+        }
+    } else {
+        AssistantMessageEvent::TextStart {
+            content_index,
+            partial: output.clone(),
+        }
+    });
+    content_index
+}
+
+fn try_build_bedrock_payload(
+    model: &Model,
+    context: &Context,
+    options: &BedrockOptions,
+) -> Result<Value> {
+    let compat = &model.compat.anthropic_messages;
+    let cache_point = cache_point(model, options);
+    let transformed = transform_messages(&context.messages, model, |id, _model, _source| {
+        normalize_tool_call_id(id)
+    });
+    let mut payload = json!({
+        "messages": convert_messages(&transformed, cache_point.as_ref()),
+    });
+    let object = payload.as_object_mut().expect("payload object");
+
+    if let Some(system_prompt) = &context.system_prompt
+        && !system_prompt.is_empty()
+    {
+        let mut system = vec![json!({ "text": system_prompt })];
+        if let Some(cache_point) = &cache_point {
+            system.push(json!({ "cachePoint": cache_point }));
+        }
+        object.insert("system".to_string(), Value::Array(system));
+    }
+
+    let mut inference_config = json!({
+        "maxTokens": options.base.max_tokens.unwrap_or(model.max_tokens)
+    });
+    if let Some(temperature) = options.base.temperature
+        && options.thinking_enabled != Some(true)
+        && compat.supports_temperature.unwrap_or(true)
+    {
+        inference_config["temperature"] = json!(temperature);
+    }
+    // Converse has no `top_k`; Claude models take it as an additional field.
+    let thinking =
+        model.reasoning && is_claude_model(&model.id) && options.thinking_enabled == Some(true);
+    let sampling = sampling(
+        model,
+        &options.base,
+        SamplingSupport {
+            top_k: is_claude_model(&model.id),
+            seed: false,
+            penalties: false,
+            ..SamplingSupport::ALL
+        },
+    );
+    if let Some(top_p) = sampling.top_p.filter(|_| !thinking) {
+        inference_config["topP"] = json!(top_p);
+    }
+    if !sampling.stop.is_empty() {
+        inference_config["stopSequences"] = json!(sampling.stop);
+    }
+    object.insert("inferenceConfig".to_string(), inference_config);
+
+    let tool_choice = match (&options.tool_choice, &options.base.tool_choice) {
+        (Some(tool_choice), _) => convert_tool_choice(tool_choice)?,
+        (None, Some(tool_choice)) => convert_typed_tool_choice(tool_choice),
+        (None, None) => None,
+    };
+    // Converse cannot forbid tool calls, so `ToolChoice::None` leaves the
+    // tools out. They stay when the history holds tool calls, because Converse
+    // rejects tool blocks without a tool config. Neither knob exists for
+    // parallel tool calls.
+    let tools_disabled = options.tool_choice.is_none()
+        && options.base.tool_choice == Some(ToolChoice::None)
+        && !has_tool_history(context);
+    if !context.tools.is_empty() && !tools_disabled {
+        let mut tools = convert_tools(&context.tools);
+        if let Some(cache_point) = &cache_point
+            && compat.supports_cache_control_on_tools.unwrap_or(true)
+        {
+            tools.push(json!({ "cachePoint": cache_point }));
+        }
+        let mut tool_config = json!({ "tools": tools });
+        if let Some(tool_choice) = tool_choice {
+            tool_config["toolChoice"] = tool_choice;
+        }
+        object.insert("toolConfig".to_string(), tool_config);
+    }
+
+    let mut fields = Map::new();
+    if let Some(top_k) = sampling.top_k.filter(|_| !thinking) {
+        fields.insert("top_k".to_string(), json!(top_k));
+    }
+    if thinking {
+        if compat.force_adaptive_thinking == Some(true) {
+            fields.insert("thinking".to_string(), json!({ "type": "adaptive" }));
+            if let Some(effort) = options.effort {
+                fields.insert(
+                    "output_config".to_string(),
+                    json!({ "effort": effort.as_str() }),
+                );
+            }
+        } else {
+            fields.insert(
+                "thinking".to_string(),
+                json!({
+                    "type": "enabled",
+                    "budget_tokens": options.thinking_budget_tokens.unwrap_or(1024)
+                }),
+            );
+            if options.interleaved_thinking {
+                fields.insert(
+                    "anthropic_beta".to_string(),
+                    json!([INTERLEAVED_THINKING_BETA]),
+                );
+            }
+        }
+    }
+    if !fields.is_empty() {
+        object.insert(
+            "additionalModelRequestFields".to_string(),
+            Value::Object(fields),
+        );
+    }
+    // Converse only returns the matched sequence when asked for it.
+    if is_claude_model(&model.id) && !sampling.stop.is_empty() {
+        object.insert(
+            "additionalModelResponseFieldPaths".to_string(),
+            json!(["/stop_sequence"]),
+        );
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+fn build_bedrock_payload(model: &Model, context: &Context, options: &BedrockOptions) -> Value {
+    try_build_bedrock_payload(model, context, options).unwrap()
+}
+
+fn convert_messages(messages: &[Message], cache_point: Option<&Value>) -> Vec<Value> {
+    let mut converted = Vec::new();
+    for message in messages {
+        match message {
+            Message::User(user) => {
+                let blocks = match &user.content {
+                    UserMessageContent::Text(text) => {
+                        if text.trim().is_empty() {
+                            Vec::new()
+                        } else {
+                            vec![json!({ "text": text })]
+                        }
+                    }
+                    UserMessageContent::Parts(parts) => parts
+                        .iter()
+                        .filter_map(|part| match part {
+                            UserContent::Text(text) => (!text.text.trim().is_empty())
+                                .then(|| json!({ "text": &text.text })),
+                            UserContent::Image(image) => image_block(image),
+                            UserContent::Document(document) => document_block(document),
+                            UserContent::Audio(_) => None,
+                        })
+                        .collect(),
+                };
+                push_blocks(&mut converted, "user", blocks);
+            }
+            Message::Assistant(assistant) => {
+                let mut blocks = Vec::new();
+                for block in &assistant.content {
+                    match block {
+                        AssistantContent::Text(text) if !text.text.trim().is_empty() => {
+                            blocks.push(json!({ "text": &text.text }));
+                        }
+                        AssistantContent::Thinking(thinking) if thinking.redacted == Some(true) => {
+                            if let Some(signature) = &thinking.thinking_signature {
+                                blocks.push(json!({
+                                    "reasoningContent": { "redactedContent": signature }
+                                }));
+                            }
+                        }
+                        AssistantContent::Thinking(thinking) => {
+                            let signature = thinking
+                                .thinking_signature
+                                .as_deref()
+                                .filter(|signature| !signature.trim().is_empty());
+                            match signature {
+                                Some(signature) => blocks.push(json!({
+                                    "reasoningContent": {
+                                        "reasoningText": {
+                                            "text": &thinking.thinking,
+                                            "signature": signature
+                                        }
+                                    }
+                                })),
+                                None if !thinking.thinking.trim().is_empty() => {
+                                    blocks.push(json!({ "text": &thinking.thinking }));
+                                }
+                                None => {}
+                            }
+                        }
+                        AssistantContent::ToolCall(tool_call) => blocks.push(json!({
+                            "toolUse": {
+                                "toolUseId": tool_call.id,
+                                "name": tool_call.name,
+                                "input": tool_call.arguments
+                            }
+                        })),
+                        _ => {}
+                    }
+                }
+                push_blocks(&mut converted, "assistant", blocks);
+            }
+            Message::ToolResult(tool_result) => {
+                let content = tool_result
+                    .content
+                    .iter()
+                    .filter_map(|block| match block {
+                        ToolResultContent::Text(text) => Some(json!({ "text": &text.text })),
+                        ToolResultContent::Image(image) => image_block(image),
+                        ToolResultContent::Document(document) => document_block(document),
+                    })
+                    .collect::<Vec<_>>();
+                push_blocks(
+                    &mut converted,
+                    "user",
+                    vec![json!({
+                        "toolResult": {
+                            "toolUseId": tool_result.tool_call_id,
+                            "content": content,
+                            "status": if tool_result.is_error { "error" } else { "success" }
+                        }
+                    })],
+                );
+            }
+            Message::Custom(_) => {}
+        }
+    }
+
+    if let Some(cache_point) = cache_point
+        && let Some(last) = converted
+            .last_mut()
+            .filter(|message| message.get("role").and_then(Value::as_str) == Some("user"))
+        && let Some(Value::Array(blocks)) = last.get_mut("content")
+    {
+        blocks.push(json!({ "cachePoint": cache_point }));
+    }
+    converted
+}
+
+/// Converse requires strictly alternating roles, so consecutive tool results
+/// and user turns are merged into a single message.
+fn push_blocks(messages: &mut Vec<Value>, role: &str, blocks: Vec<Value>) {
+    if blocks.is_empty() {
+        return;
+    }
+    if let Some(last) = messages.last_mut()
+        && last.get("role").and_then(Value::as_str) == Some(role)
+        && let Some(Value::Array(content)) = last.get_mut("content")
+    {
+        content.extend(blocks);
+        return;
+    }
+    messages.push(json!({ "role": role, "content": blocks }));
+}
+
+fn image_block(image: &ImageContent) -> Option<Value> {
+    let format = match image.mime_type.as_str() {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpeg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        _ => return None,
+    };
+    Some(json!({
+        "image": {
+            "format": format,
+            "source": { "bytes": image.data }
+        }
+    }))
+}
+
+/// Converse takes documents as inline bytes only, so URL documents are
+/// dropped. Names allow only letters, digits, single spaces, hyphens,
+/// parentheses, and square brackets.
+fn document_block(document: &DocumentContent) -> Option<Value> {
+    let DocumentSource::Base64 { data } = &document.source else {
+        return None;
+    };
+    let format = match document.mime_type.as_str() {
+        "application/pdf" => "pdf",
+        "text/csv" => "csv",
+        "application/msword" => "doc",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "docx",
+        "application/vnd.ms-excel" => "xls",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "xlsx",
+        "text/html" => "html",
+        "text/plain" => "txt",
+        "text/markdown" => "md",
+        _ => return None,
+    };
+    let name = document
+        .title
+        .as_deref()
+        .unwrap_or("document")
+        .split(|ch: char| {
+            !(ch.is_ascii_alphanumeric() || matches!(ch, '-' | '(' | ')' | '[' | ']'))
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(json!({
+        "document": {
+            "format": format,
+            "name": if name.is_empty() { "document".to_string() } else { name },
+            "source": { "bytes": data }
+        }
+    }))
+}
+
+fn convert_tools(tools: &[Tool]) -> Vec<Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "toolSpec": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": { "json": tool.parameters }
+                }
+            })
+        })
+        .collect()
+}
+
+fn convert_tool_choice(tool_choice: &Value) -> Result<Option<Value>> {
+    match tool_choice {
+        Value::String(choice) => match choice.as_str() {
+            "auto" => Ok(Some(json!({ "auto": {} }))),
+            "any" | "required" => Ok(Some(json!({ "any": {} }))),
+            // Converse has no way to disable tools for a single request.
+            "none" => Ok(None),
+            other => Err(Error::Validation(format!(
+                "Unsupported Bedrock tool choice: {other}"
+            ))),
+        },
+        Value::Object(object) if object.get("type").and_then(Value::as_str) == Some("tool") => Ok(
+            Some(json!({ "tool": { "name": object.get("name").cloned() } })),
+        ),
+        other => Ok(Some(other.clone())),
+    }
+}
+
+fn convert_typed_tool_choice(tool_choice: &ToolChoice) -> Option<Value> {
+    match tool_choice {
+        ToolChoice::Auto => Some(json!({ "auto": {} })),
+        ToolChoice::None => None,
+        ToolChoice::Required => Some(json!({ "any": {} })),
+        ToolChoice::Tool(name) => Some(json!({ "tool": { "name": name } })),
+    }
+}
+
+fn has_tool_history(context: &Context) -> bool {
+    context.messages.iter().any(|message| match message {
+        Message::Assistant(message) => message
+            .content
+            .iter()
+            .any(|content| matches!(content, AssistantContent::ToolCall(_))),
+        Message::ToolResult(_) => true,
+        _ => false,
+    })
+}
+
+fn cache_point(model: &Model, options: &BedrockOptions) -> Option<Value> {
+    if !supports_prompt_caching(&model.id) {
+        return None;
+    }
+    let retention = options.base.cache_retention.unwrap_or_else(|| {
+        if get_provider_env_value("PI_CACHE_RETENTION", &options.base.env).as_deref()
+            == Some("long")
+        {
+            CacheRetention::Long
+        } else {
+            CacheRetention::Short
+        }
+    });
+    match retention {
+        CacheRetention::None => None,
+        CacheRetention::Long
+            if model
+                .compat
+                .anthropic_messages
+                .supports_long_cache_retention
+                .unwrap_or(false) =>
+        {
+            Some(json!({ "type": "default", "ttl": "1h" }))
+        }
+        _ => Some(json!({ "type": "default" })),
+    }
+}
+
+fn update_bedrock_usage(output: &mut AssistantMessage, usage: &Value, model: &Model) {
+    let tokens = |name: &str| usage.get(name).and_then(Value::as_u64).unwrap_or_default() as u32;
+    output.usage.input = tokens("inputTokens");
+    output.usage.output = tokens("outputTokens");
+    output.usage.cache_read = tokens("cacheReadInputTokens");
+    output.usage.cache_write = tokens("cacheWriteInputTokens");
+    output.usage.total_tokens = output.usage.input
+        + output.usage.output
+        + output.usage.cache_read
+        + output.usage.cache_write;
+    calculate_cost(model, &mut output.usage);
+}
+
+fn map_stop_reason(reason: &str) -> std::result::Result<StopReason, String> {
+    match reason {
+        "end_turn" | "stop_sequence" => Ok(StopReason::Stop),
+        "tool_use" => Ok(StopReason::ToolUse),
+        "max_tokens" | "model_context_window_exceeded" => Ok(StopReason::Length),
+        "guardrail_intervened" => Err("Bedrock guardrail intervened".to_string()),
+        "content_filtered" => Err("Bedrock filtered the response content".to_string()),
+        other => Err(format!("Unhandled stop reason: {other}")),
+    }
+}
+
+fn headers(
+    model: &Model,
+    options: &BedrockOptions,
+    url: &reqwest::Url,
+    body: &[u8],
+) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    for (name, value) in &model.headers {
+        let Ok(name) = HeaderName::from_bytes(name.as_bytes()) else {
+            continue;
+        };
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| Error::InvalidHeaderValue(name.to_string(), e))?;
+        headers.insert(name, value);
+    }
+    crate::utils::headers::apply_provider_headers(&mut headers, &options.base.headers)?;
+    if headers.contains_key(AUTHORIZATION) {
+        return Ok(headers);
+    }
+
+    if let Some(api_key) = options
+        .base
+        .api_key
+        .as_deref()
+        .filter(|key| !key.trim().is_empty())
+    {
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {api_key}"))
+                .map_err(|e| Error::InvalidHeaderValue("authorization".to_string(), e))?,
+        );
+        return Ok(headers);
+    }
+
+    // Configured credentials win over ambient environment auth, and an
+    // environment bearer token wins over environment credentials.
+    let credentials = match options.credentials.clone() {
+        Some(credentials) => credentials,
+        None => {
+            if let Some(api_key) = get_env_api_key_with_env(&model.provider, &options.base.env) {
+                headers.insert(
+                    AUTHORIZATION,
+                    HeaderValue::from_str(&format!("Bearer {api_key}"))
+                        .map_err(|e| Error::InvalidHeaderValue("authorization".to_string(), e))?,
+                );
+                return Ok(headers);
+            }
+            AwsCredentials::from_provider_env(&options.base.env)
+                .ok_or_else(|| Error::MissingApiKey(model.provider.clone()))?
+        }
+    };
+    let region = options
+        .region
+        .clone()
+        .or_else(|| region_from_base_url(&model.base_url))
+        .or_else(|| env_region(&options.base.env))
+        .unwrap_or_else(|| DEFAULT_REGION.to_string());
+    sign_request(
+        "POST",
+        url,
+        &mut headers,
+        body,
+        &SigningParams {
+            access_key_id: &credentials.access_key_id,
+            secret_access_key: &credentials.secret_access_key,
+            session_token: credentials.session_token.as_deref(),
+            region: &region,
+            service: SIGNING_SERVICE,
+            time: SystemTime::now(),
+        },
+    )?;
+    Ok(headers)
+}
+
+fn env_region(env: &ProviderEnv) -> Option<String> {
+    AWS_REGION_ENV_VARS
+        .into_iter()
+        .find_map(|name| get_provider_env_value(name, env))
+}
+
+/// Extracts `{region}` from `https://bedrock-runtime[-fips].{region}.amazonaws.com`.
+fn region_from_base_url(base_url: &str) -> Option<String> {
+    let host = reqwest::Url::parse(base_url).ok()?.host_str()?.to_string();
+    let rest = host
+        .strip_prefix("bedrock-runtime-fips.")
+        .or_else(|| host.strip_prefix("bedrock-runtime."))?;
+    let (region, _) = rest.split_once('.')?;
+    Some(region.to_string())
+}
+
+fn normalize_tool_call_id(id: &str) -> String {
+    id.chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect()
+}
+
+fn is_claude_model(id: &str) -> bool {
+    id.contains("anthropic.claude")
+}
+
+fn is_claude_reasoning_model(id: &str) -> bool {
+    is_claude_model(id)
+        && [
+            "claude-3-7",
+            "claude-sonnet-4",
+            "claude-opus-4",
+            "claude-haiku-4",
+        ]
+        .iter()
+        .any(|family| id.contains(family))
+}
+
+fn supports_prompt_caching(id: &str) -> bool {
+    is_claude_model(id) || id.contains("amazon.nova")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::types::{ToolResultMessage, Usage, UserMessage};
+    use crate::utils::aws_eventstream::encode_message;
+
+    const CLAUDE_SONNET: &str = "us.anthropic.claude-sonnet-4-5-20250929-v1:0";
+
+    fn bedrock_model(id: &str) -> Model {
+        builder()
+            .region("us-west-2")
+            .build()
+            .expect("provider")
+            .model(id)
+            .build()
+            .expect("model")
+    }
+
+    fn lookup_tool() -> Tool {
+        Tool::builder("lookup")
+            .description("Lookup a value.")
+            .parameters(json!({
+                "type": "object",
+                "properties": { "key": { "type": "string" } },
+                "required": ["key"]
+            }))
+            .build()
+            .unwrap()
+    }
+
+    fn tool_result(tool_call_id: &str, text: &str, is_error: bool) -> Message {
+        Message::ToolResult(ToolResultMessage {
+            tool_call_id: tool_call_id.to_string(),
+            tool_name: "lookup".to_string(),
+            content: vec![ToolResultContent::text(text)],
+            details: None,
+            usage: None,
+            added_tool_names: Vec::new(),
+            is_error,
+            timestamp: 2,
+        })
+    }
+
+    fn credentials() -> AwsCredentials {
+        AwsCredentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY")
+    }
+
+    #[test]
+    fn provider_models_target_the_regional_runtime_endpoint() {
+        let model = bedrock_model(CLAUDE_SONNET);
+
+        assert_eq!(model.api_id(), "bedrock-converse-stream");
+        assert_eq!(model.provider_id(), "amazon-bedrock");
+        assert_eq!(
+            model.base_url,
+            "https://bedrock-runtime.us-west-2.amazonaws.com"
+        );
+        assert!(model.reasoning);
+        assert!(!bedrock_model("anthropic.claude-3-haiku-20240307-v1:0").reasoning);
+        assert!(!bedrock_model("amazon.nova-pro-v1:0").reasoning);
+        assert_eq!(
+            region_from_base_url("https://bedrock-runtime-fips.us-gov-west-1.amazonaws.com")
+                .as_deref(),
+            Some("us-gov-west-1")
+        );
+        assert_eq!(region_from_base_url("http://127.0.0.1:9"), None);
+    }
+
+    #[test]
+    fn payload_maps_system_tools_thinking_and_cache_points() {
+        let model = bedrock_model(CLAUDE_SONNET);
+        let context = Context::builder()
+            .system_prompt("Be brief.")
+            .message(Message::User(UserMessage {
+                content: UserMessageContent::Parts(vec![
+                    UserContent::text("What is this?"),
+                    UserContent::Image(ImageContent {
+                        data: "aW1n".to_string(),
+                        mime_type: "image/png".to_string(),
+                    }),
+                ]),
+                timestamp: 1,
+            }))
+            .tool(lookup_tool())
+            .build();
+
+        let payload = build_bedrock_payload(
+            &model,
+            &context,
+            &BedrockOptions {
+                base: StreamOptions {
+                    max_tokens: Some(4096),
+                    temperature: Some(0.2),
+                    ..Default::default()
+                },
+                thinking_enabled: Some(true),
+                thinking_budget_tokens: Some(2048),
+                tool_choice: Some(json!({ "type": "tool", "name": "lookup" })),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            payload["system"],
+            json!([{ "text": "Be brief." }, { "cachePoint": { "type": "default" } }])
+        );
+        assert_eq!(
+            payload["messages"],
+            json!([{
+                "role": "user",
+                "content": [
+                    { "text": "What is this?" },
+                    { "image": { "format": "png", "source": { "bytes": "aW1n" } } },
+                    { "cachePoint": { "type": "default" } }
+                ]
+            }])
+        );
+        // Temperature is dropped while extended thinking is enabled.
+        assert_eq!(payload["inferenceConfig"], json!({ "maxTokens": 4096 }));
+        assert_eq!(
+            payload["toolConfig"],
+            json!({
+                "tools": [
+                    {
+                        "toolSpec": {
+                            "name": "lookup",
+                            "description": "Lookup a value.",
+                            "inputSchema": { "json": lookup_tool().parameters }
+                        }
+                    },
+                    { "cachePoint": { "type": "default" } }
+                ],
+                "toolChoice": { "tool": { "name": "lookup" } }
+            })
+        );
+        assert_eq!(
+            payload["additionalModelRequestFields"],
+            json!({
+                "thinking": { "type": "enabled", "budget_tokens": 2048 },
+                "anthropic_beta": [INTERLEAVED_THINKING_BETA]
+            })
+        );
+    }
+
+    #[test]
+    fn documents_map_to_named_document_blocks() {
+        let context = Context {
+            messages: vec![Message::User(UserMessage {
+                content: UserMessageContent::Parts(vec![
+                    UserContent::Document(DocumentContent::pdf("JVBERi0=").title("q3_report.pdf")),
+                    UserContent::Document(DocumentContent::url(
+                        "https://example.com/q4.pdf",
+                        "application/pdf",
+                    )),
+                ]),
+                timestamp: 1,
+            })],
+            ..Default::default()
+        };
+
+        let payload = build_bedrock_payload(
+            &bedrock_model(CLAUDE_SONNET),
+            &context,
+            &BedrockOptions::default(),
+        );
+
+        assert_eq!(
+            payload["messages"][0]["content"][0],
+            json!({
+                "document": {
+                    "format": "pdf",
+                    "name": "q3 report pdf",
+                    "source": { "bytes": "JVBERi0=" }
+                }
+            })
+        );
+        // The URL document is dropped; what follows is the prompt cache point.
+        assert!(
+            payload["messages"][0]["content"][1]
+                .get("cachePoint")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn sampling_options_map_to_inference_config_and_claude_top_k() {
+        let options = BedrockOptions {
+            base: StreamOptions {
+                top_p: Some(0.9),
+                top_k: Some(40),
+                stop: vec!["END".to_string()],
+                seed: Some(7),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let claude =
+            build_bedrock_payload(&bedrock_model(CLAUDE_SONNET), &Context::default(), &options);
+        assert_eq!(claude["inferenceConfig"]["topP"], json!(0.9));
+        assert_eq!(claude["inferenceConfig"]["stopSequences"], json!(["END"]));
+        assert_eq!(claude["inferenceConfig"].get("seed"), None);
+        assert_eq!(
+            claude["additionalModelRequestFields"],
+            json!({ "top_k": 40 })
+        );
+        assert_eq!(
+            claude["additionalModelResponseFieldPaths"],
+            json!(["/stop_sequence"])
+        );
+
+        let llama = build_bedrock_payload(
+            &bedrock_model("meta.llama3-70b-instruct-v1:0"),
+            &Context::default(),
+            &options,
+        );
+        assert_eq!(llama["inferenceConfig"]["topP"], json!(0.9));
+        assert_eq!(llama.get("additionalModelRequestFields"), None);
+        assert_eq!(llama.get("additionalModelResponseFieldPaths"), None);
+    }
+
+    #[test]
+    fn typed_tool_choice_maps_to_converse_tool_choice() {
+        let model = bedrock_model(CLAUDE_SONNET);
+        let context = Context {
+            messages: vec![Message::user_text("Use lookup")],
+            tools: vec![lookup_tool()],
+            ..Default::default()
+        };
+        let payload = |context: &Context, tool_choice: ToolChoice| {
+            build_bedrock_payload(
+                &model,
+                context,
+                &BedrockOptions {
+                    base: StreamOptions {
+                        tool_choice: Some(tool_choice),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert_eq!(
+            payload(&context, ToolChoice::Auto)["toolConfig"]["toolChoice"],
+            json!({ "auto": {} })
+        );
+        assert_eq!(
+            payload(&context, ToolChoice::Required)["toolConfig"]["toolChoice"],
+            json!({ "any": {} })
+        );
+        assert_eq!(
+            payload(&context, ToolChoice::tool("lookup"))["toolConfig"]["toolChoice"],
+            json!({ "tool": { "name": "lookup" } })
+        );
+        assert_eq!(payload(&context, ToolChoice::None).get("toolConfig"), None);
+
+        let mut with_history = context.clone();
+        with_history
+            .messages
+            .push(tool_result("call-1", "42", false));
+        let payload = payload(&with_history, ToolChoice::None);
+        assert_eq!(payload["toolConfig"]["tools"].as_array().unwrap().len(), 2);
+        assert_eq!(payload["toolConfig"].get("toolChoice"), None);
+    }
+
+    #[test]
+    fn compat_knobs_control_adaptive_thinking_temperature_and_cache_ttl() {
+        let mut model = bedrock_model(CLAUDE_SONNET);
+        model.compat.anthropic_messages.force_adaptive_thinking = Some(true);
+        model
+            .compat
+            .anthropic_messages
+            .supports_long_cache_retention = Some(true);
+        model
+            .compat
+            .anthropic_messages
+            .supports_cache_control_on_tools = Some(false);
+        let context = Context {
+            system_prompt: Some("Be brief.".to_string()),
+            tools: vec![lookup_tool()],
+            ..Default::default()
+        };
+
+        let payload = build_bedrock_payload(
+            &model,
+            &context,
+            &BedrockOptions {
+                base: StreamOptions {
+                    cache_retention: Some(CacheRetention::Long),
+                    ..Default::default()
+                },
+                thinking_enabled: Some(true),
+                effort: Some(AnthropicEffort::Medium),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            payload["system"][1],
+            json!({ "cachePoint": { "type": "default", "ttl": "1h" } })
+        );
+        assert_eq!(payload["toolConfig"]["tools"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            payload["additionalModelRequestFields"],
+            json!({
+                "thinking": { "type": "adaptive" },
+                "output_config": { "effort": "medium" }
+            })
+        );
+
+        model.compat.anthropic_messages.supports_temperature = Some(false);
+        let payload = build_bedrock_payload(
+            &model,
+            &context,
+            &BedrockOptions {
+                base: StreamOptions {
+                    temperature: Some(0.5),
+                    cache_retention: Some(CacheRetention::None),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        assert_eq!(payload["inferenceConfig"].get("temperature"), None);
+        assert_eq!(payload["system"], json!([{ "text": "Be brief." }]));
+
+        let llama = build_bedrock_payload(
+            &bedrock_model("meta.llama3-70b-instruct-v1:0"),
+            &context,
+            &BedrockOptions::default(),
+        );
+        assert_eq!(llama["system"], json!([{ "text": "Be brief." }]));
+        assert_eq!(llama.get("additionalModelRequestFields"), None);
+    }
+
+    #[test]
+    fn messages_replay_reasoning_and_merge_tool_results_into_one_user_turn() {
+        let model = bedrock_model(CLAUDE_SONNET);
+        let messages = vec![
+            Message::user_text("look up a and b"),
+            Message::Assistant(AssistantMessage {
+                content: vec![
+                    AssistantContent::Thinking(ThinkingContent {
+                        thinking: "Need both.".to_string(),
+                        thinking_signature: Some("sig-1".to_string()),
+                        redacted: None,
+                    }),
+                    AssistantContent::Thinking(ThinkingContent {
+                        thinking: "[Reasoning redacted]".to_string(),
+                        thinking_signature: Some("opaque".to_string()),
+                        redacted: Some(true),
+                    }),
+                    AssistantContent::ToolCall(ToolCall {
+                        id: "call_a".to_string(),
+                        name: "lookup".to_string(),
+                        arguments: json!({ "key": "a" }),
+                        thought_signature: None,
+                    }),
+                    AssistantContent::ToolCall(ToolCall {
+                        id: "call_b".to_string(),
+                        name: "lookup".to_string(),
+                        arguments: json!({ "key": "b" }),
+                        thought_signature: None,
+                    }),
+                ],
+                stop_reason: StopReason::ToolUse,
+                usage: Usage::default(),
+                ..AssistantMessage::empty_for(&model)
+            }),
+            tool_result("call_a", "A", false),
+            tool_result("call_b", "missing", true),
+            Message::user_text("thanks"),
+        ];
+
+        let converted = convert_messages(
+            &transform_messages(&messages, &model, |id, _model, _source| {
+                normalize_tool_call_id(id)
+            }),
+            None,
+        );
+
+        assert_eq!(converted.len(), 3);
+        assert_eq!(
+            converted[1]["content"],
+            json!([
+                {
+                    "reasoningContent": {
+                        "reasoningText": { "text": "Need both.", "signature": "sig-1" }
+                    }
+                },
+                { "reasoningContent": { "redactedContent": "opaque" } },
+                { "toolUse": { "toolUseId": "call_a", "name": "lookup", "input": { "key": "a" } } },
+                { "toolUse": { "toolUseId": "call_b", "name": "lookup", "input": { "key": "b" } } }
+            ])
+        );
+        assert_eq!(
+            converted[2],
+            json!({
+                "role": "user",
+                "content": [
+                    {
+                        "toolResult": {
+                            "toolUseId": "call_a",
+                            "content": [{ "text": "A" }],
+                            "status": "success"
+                        }
+                    },
+                    {
+                        "toolResult": {
+                            "toolUseId": "call_b",
+                            "content": [{ "text": "missing" }],
+                            "status": "error"
+                        }
+                    },
+                    { "text": "thanks" }
+                ]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn streams_signed_converse_events() {
+        let body = [
+            event("messageStart", json!({ "role": "assistant" })),
+            event(
+                "contentBlockDelta",
+                json!({ "contentBlockIndex": 0, "delta": { "reasoningContent": { "text": "Thinking" } } }),
+            ),
+            event(
+                "contentBlockDelta",
+                json!({ "contentBlockIndex": 0, "delta": { "reasoningContent": { "signature": "sig-1" } } }),
+            ),
+            event("contentBlockStop", json!({ "contentBlockIndex": 0 })),
+            event(
+                "contentBlockDelta",
+                json!({ "contentBlockIndex": 1, "delta": { "text": "Let me check." } }),
+            ),
+            event("contentBlockStop", json!({ "contentBlockIndex": 1 })),
+            event(
+                "contentBlockStart",
+                json!({
+                    "contentBlockIndex": 2,
+                    "start": { "toolUse": { "toolUseId": "tooluse_1", "name": "lookup" } }
+                }),
+            ),
+            event(
+                "contentBlockDelta",
+                json!({ "contentBlockIndex": 2, "delta": { "toolUse": { "input": "{\"key\":" } } }),
+            ),
+            event(
+                "contentBlockDelta",
+                json!({ "contentBlockIndex": 2, "delta": { "toolUse": { "input": "\"a\"}" } } }),
+            ),
+            event("contentBlockStop", json!({ "contentBlockIndex": 2 })),
+            event("messageStop", json!({ "stopReason": "tool_use" })),
+            event(
+                "metadata",
+                json!({
+                    "usage": {
+                        "inputTokens": 20,
+                        "outputTokens": 12,
+                        "cacheReadInputTokens": 100,
+                        "cacheWriteInputTokens": 5,
+                        "totalTokens": 137
+                    },
+                    "metrics": { "latencyMs": 120 }
+                }),
+            ),
+        ]
+        .concat();
+        let (base_url, request) = spawn_event_stream_server(body).await;
+        let mut model = bedrock_model(CLAUDE_SONNET);
+        model.base_url = base_url;
+
+        let mut stream = stream_bedrock(
+            model,
+            Context {
+                messages: vec![Message::user_text("hi")],
+                tools: vec![lookup_tool()],
+                ..Default::default()
+            },
+            BedrockOptions {
+                region: Some("us-west-2".to_string()),
+                credentials: Some(credentials().with_session_token("session-token")),
+                ..Default::default()
+            },
+        );
+        let mut event_types = Vec::new();
+        let mut message = None;
+        while let Some(event) = stream.next().await {
+            let event = event.unwrap();
+            event_types.push(serde_json::to_value(&event).unwrap()["type"].clone());
+            if let AssistantMessageEvent::Done { message: done, .. } = event {
+                message = Some(done);
+            }
+        }
+        let message = message.expect("done");
+        let request = request.await.unwrap();
+
+        assert!(request.starts_with(
+            "POST /model/us.anthropic.claude-sonnet-4-5-20250929-v1%3A0/converse-stream HTTP/1.1"
+        ));
+        assert!(request.contains("authorization: AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+        assert!(request.contains("/us-west-2/bedrock/aws4_request, SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, Signature="));
+        assert!(request.contains("x-amz-security-token: session-token"));
+        assert_eq!(
+            event_types,
+            vec![
+                json!("start"),
+                json!("thinking_start"),
+                json!("thinking_delta"),
+                json!("thinking_end"),
+                json!("text_start"),
+                json!("text_delta"),
+                json!("text_end"),
+                json!("toolcall_start"),
+                json!("toolcall_delta"),
+                json!("toolcall_delta"),
+                json!("toolcall_end"),
+                json!("done"),
+            ]
+        );
+        assert_eq!(message.stop_reason, StopReason::ToolUse);
+        assert_eq!(
+            message.content[0],
+            AssistantContent::Thinking(ThinkingContent {
+                thinking: "Thinking".to_string(),
+                thinking_signature: Some("sig-1".to_string()),
+                redacted: None,
+            })
+        );
+        let AssistantContent::ToolCall(tool_call) = &message.content[2] else {
+            panic!("expected tool call");
+        };
+        assert_eq!(tool_call.id, "tooluse_1");
+        assert_eq!(tool_call.arguments, json!({ "key": "a" }));
+        assert_eq!(message.usage.input, 20);
+        assert_eq!(message.usage.output, 12);
+        assert_eq!(message.usage.cache_read, 100);
+        assert_eq!(message.usage.cache_write, 5);
+        assert_eq!(message.usage.total_tokens, 137);
+    }
+
+    #[tokio::test]
+    async fn api_key_is_sent_as_bearer_token() {
+        let body = [
+            event(
+                "contentBlockDelta",
+                json!({ "contentBlockIndex": 0, "delta": { "text": "Hi" } }),
+            ),
+            event("contentBlockStop", json!({ "contentBlockIndex": 0 })),
+            event("messageStop", json!({ "stopReason": "end_turn" })),
+        ]
+        .concat();
+        let (base_url, request) = spawn_event_stream_server(body).await;
+        let mut model = bedrock_model(CLAUDE_SONNET);
+        model.base_url = base_url;
+
+        let message = crate::stream::final_message_from_stream(stream_bedrock(
+            model,
+            Context {
+                messages: vec![Message::user_text("hi")],
+                ..Default::default()
+            },
+            BedrockOptions {
+                base: StreamOptions {
+                    api_key: Some("bedrock-api-key".to_string()),
+                    ..Default::default()
+                },
+                credentials: Some(credentials()),
+                ..Default::default()
+            },
+        ))
+        .await
+        .unwrap();
+        let request = request.await.unwrap();
+
+        assert_eq!(message.stop_reason, StopReason::Stop);
+        assert_eq!(message.stop_sequence, None);
+        assert!(request.contains("authorization: Bearer bedrock-api-key"));
+        assert!(!request.contains("x-amz-date"));
+    }
+
+    async fn request_with_env_bearer_token(credentials: Option<AwsCredentials>) -> String {
+        let body = [event("messageStop", json!({ "stopReason": "end_turn" }))].concat();
+        let (base_url, request) = spawn_event_stream_server(body).await;
+        let mut provider = builder().region("us-west-2");
+        if let Some(credentials) = credentials {
+            provider = provider.credentials(credentials);
+        }
+        let mut model = provider
+            .build()
+            .expect("provider")
+            .model(CLAUDE_SONNET)
+            .build()
+            .expect("model");
+        model.base_url = base_url;
+
+        let message = crate::complete(
+            model,
+            Context {
+                messages: vec![Message::user_text("hi")],
+                ..Default::default()
+            },
+            Some(StreamOptions {
+                env: [(
+                    "AWS_BEARER_TOKEN_BEDROCK".to_string(),
+                    "env-token".to_string(),
+                )]
+                .into_iter()
+                .collect(),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(message.stop_reason, StopReason::Stop, "{message:?}");
+        request.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn provider_credentials_win_over_env_bearer_token() {
+        let request = request_with_env_bearer_token(Some(credentials())).await;
+
+        assert!(!request.contains("Bearer env-token"));
+        assert!(request.contains("x-amz-date"));
+    }
+
+    #[tokio::test]
+    async fn env_bearer_token_is_used_without_provider_credentials() {
+        let request = request_with_env_bearer_token(None).await;
+
+        assert!(request.contains("authorization: Bearer env-token"));
+        assert!(!request.contains("x-amz-date"));
+    }
+
+    #[tokio::test]
+    async fn claude_stop_sequence_is_reported_on_the_final_message() {
+        let body = [
+            event(
+                "contentBlockDelta",
+                json!({ "contentBlockIndex": 0, "delta": { "text": "1 2 3" } }),
+            ),
+            event("contentBlockStop", json!({ "contentBlockIndex": 0 })),
+            event(
+                "messageStop",
+                json!({
+                    "stopReason": "stop_sequence",
+                    "additionalModelResponseFields": { "stop_sequence": "END" }
+                }),
+            ),
+        ]
+        .concat();
+        let (base_url, _request) = spawn_event_stream_server(body).await;
+        let mut model = bedrock_model(CLAUDE_SONNET);
+        model.base_url = base_url;
+
+        let message = crate::stream::final_message_from_stream(stream_bedrock(
+            model,
+            Context {
+                messages: vec![Message::user_text("Count.")],
+                ..Default::default()
+            },
+            BedrockOptions {
+                base: StreamOptions {
+                    stop: vec!["END".to_string()],
+                    ..Default::default()
+                },
+                credentials: Some(credentials()),
+                ..Default::default()
+            },
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(message.stop_reason, StopReason::Stop);
+        assert_eq!(message.stop_sequence.as_deref(), Some("END"));
+    }
+
+    #[tokio::test]
+    async fn exception_frames_and_guardrails_report_errors() {
+        let exception = encode_message(
+            &[
+                (":message-type", "exception"),
+                (":exception-type", "throttlingException"),
+                (":content-type", "application/json"),
+            ],
+            br#"{"message":"Too many requests"}"#,
+        );
+        let guardrail = [
+            event(
+                "contentBlockDelta",
+                json!({ "contentBlockIndex": 0, "delta": { "text": "Partial" } }),
+            ),
+            event(
+                "messageStop",
+                json!({ "stopReason": "guardrail_intervened" }),
+            ),
+        ]
+        .concat();
+
+        for (body, expected) in [
+            (exception, "throttlingException: Too many requests"),
+            (guardrail, "Bedrock guardrail intervened"),
+        ] {
+            let (base_url, _request) = spawn_event_stream_server(body).await;
+            let mut model = bedrock_model(CLAUDE_SONNET);
+            model.base_url = base_url;
+
+            let message = crate::stream::final_message_from_stream(stream_bedrock(
+                model,
+                Context {
+                    messages: vec![Message::user_text("hi")],
+                    ..Default::default()
+                },
+                BedrockOptions {
+                    credentials: Some(credentials()),
+                    ..Default::default()
+                },
+            ))
+            .await
+            .unwrap();
+
+            assert_eq!(message.stop_reason, StopReason::Error);
+            assert_eq!(message.error_message.as_deref(), Some(expected));
+        }
+    }
+
+    fn event(event_type: &str, payload: Value) -> Vec<u8> {
+        encode_message(
+            &[
+                (":event-type", event_type),
+                (":content-type", "application/json"),
+                (":message-type", "event"),
+            ],
+            payload.to_string().as_bytes(),
+        )
+    }
+
+    async fn spawn_event_stream_server(
+        body: Vec<u8>,
+    ) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 16 * 1024];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let _ = request_tx.send(String::from_utf8_lossy(&buffer[..read]).into_owned());
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/vnd.amazon.eventstream\r\ncontent-length: {}\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&body).await.unwrap();
+        });
+        (format!("http://{addr}"), request_rx)
+    }
+}
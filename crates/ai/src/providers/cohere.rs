@@ -0,0 +1,1458 @@
+use async_trait::async_trait;
+use futures::{StreamExt, pin_mut};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::env_api_keys::{KnownProvider, get_env_api_key};
+use crate::event_stream::AssistantMessageEventStreamSender;
+use crate::models::calculate_cost;
+use crate::provider::{
+    EmbeddingModelApi, LanguageModelApi, ModelBuilder, Provider, ProviderCapabilities,
+    RerankModelApi,
+};
+use crate::providers::sampling::{SamplingSupport, sampling};
+use crate::providers::simple_options;
+use crate::providers::simple_options::{
+    adjust_max_tokens_for_thinking, build_base_options, clamped_reasoning,
+};
+use crate::providers::transform_messages::transform_messages;
+use crate::types::{
+    AssistantContent, AssistantMessage, AssistantMessageEvent, Context, EmbeddingBatch,
+    EmbeddingEncodingFormat, EmbeddingOptions, EmbeddingUsage, EmbeddingVector, KnownApi, Message,
+    Model, ModelInput, ProviderHeaders, ProviderResponse, RankedDocument, RerankOptions,
+    RerankUsage, Reranking, SimpleStreamOptions, StopReason, StreamOptions, TextContent,
+    ThinkingContent, Tool, ToolCall, ToolResultContent, UserContent, UserMessageContent,
+};
+use crate::utils::circuit_breaker::model_circuit;
+use crate::utils::headers::headers_to_record;
+use crate::utils::http::{request_timeout, send_request_with_retries, send_with_retries};
+use crate::utils::json::{parse_json_with_repair, parse_streaming_json};
+use crate::utils::sse;
+use crate::{Error, Result};
+
+const DEFAULT_PROVIDER_ID: KnownProvider = KnownProvider::Cohere;
+const DEFAULT_BASE_URL: &str = "https://api.cohere.com/v2";
+
+/// Cohere API v2 handle for chat (`/chat`), embeddings (`/embed`), and
+/// reranking (`/rerank`).
+#[derive(Clone)]
+pub struct Cohere {
+    provider_id: String,
+    api_key: Option<String>,
+    base_url: String,
+    http_client: Option<reqwest::Client>,
+}
+
+impl Cohere {
+    pub fn builder() -> CohereBuilder {
+        CohereBuilder::default()
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let api_key = get_env_api_key(DEFAULT_PROVIDER_ID)
+            .ok_or_else(|| Error::MissingApiKey(DEFAULT_PROVIDER_ID.into()))?;
+        Self::builder().api_key(Some(api_key.as_str())).build()
+    }
+
+    pub fn model(&self, id: &str) -> ModelBuilder {
+        <Self as Provider>::model(self, id)
+    }
+
+    /// Embedding models need `EmbeddingOptions::input_type`.
+    pub fn embedding_model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(CohereEmbeddingModelApi {
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new_embedding(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Text])
+    }
+
+    pub fn rerank_model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(CohereRerankModelApi {
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new_rerank(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Text])
+    }
+}
+
+impl Provider for Cohere {
+    fn id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            language_models: true,
+            image_models: false,
+            embedding_models: true,
+            fim_models: false,
+            rerank_models: true,
+            transcription_models: false,
+            speech_models: false,
+        }
+    }
+
+    fn model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(CohereLanguageModelApi {
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Text])
+            .context_window(256_000)
+            .max_tokens(8_000)
+    }
+}
+
+#[derive(Default)]
+pub struct CohereBuilder {
+    provider_id: Option<String>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl CohereBuilder {
+    pub fn provider_id(mut self, provider_id: impl Into<String>) -> Self {
+        self.provider_id = Some(provider_id.into());
+        self
+    }
+
+    pub fn api_key(mut self, api_key: Option<&str>) -> Self {
+        self.api_key = api_key
+            .map(str::trim)
+            .filter(|api_key| !api_key.is_empty())
+            .map(str::to_string);
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn build(self) -> Result<Cohere> {
+        Ok(Cohere {
+            provider_id: self
+                .provider_id
+                .unwrap_or_else(|| DEFAULT_PROVIDER_ID.into()),
+            api_key: self.api_key,
+            base_url: self
+                .base_url
+                .as_deref()
+                .unwrap_or(DEFAULT_BASE_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            http_client: self.http_client,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct CohereLanguageModelApi {
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl CohereLanguageModelApi {
+    fn with_api_key(&self, mut options: StreamOptions) -> StreamOptions {
+        if options
+            .api_key
+            .as_deref()
+            .is_none_or(|api_key| api_key.trim().is_empty())
+        {
+            options.api_key = self.api_key.clone();
+        }
+        if options.http_client.is_none() {
+            options.http_client = self.http_client.clone();
+        }
+        options
+    }
+
+    fn with_api_key_simple(&self, mut options: SimpleStreamOptions) -> SimpleStreamOptions {
+        options.stream = self.with_api_key(options.stream);
+        options
+    }
+}
+
+impl LanguageModelApi for CohereLanguageModelApi {
+    fn id(&self) -> &str {
+        KnownApi::CohereChat.as_str()
+    }
+
+    fn stream(
+        &self,
+        model: Model,
+        context: Context,
+        options: StreamOptions,
+    ) -> Result<crate::AssistantEventStream> {
+        Ok(stream_cohere(
+            model,
+            context,
+            simple_options::cohere_options_from_stream_options(self.with_api_key(options)),
+        ))
+    }
+
+    fn stream_simple(
+        &self,
+        model: Model,
+        context: Context,
+        options: SimpleStreamOptions,
+    ) -> Result<crate::AssistantEventStream> {
+        stream_simple_cohere(model, context, self.with_api_key_simple(options))
+    }
+}
+
+#[derive(Clone)]
+struct CohereEmbeddingModelApi {
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+#[async_trait]
+impl EmbeddingModelApi for CohereEmbeddingModelApi {
+    fn id(&self) -> &str {
+        KnownApi::CohereEmbed.as_str()
+    }
+
+    async fn embed_many(
+        &self,
+        model: Model,
+        inputs: Vec<String>,
+        mut options: EmbeddingOptions,
+    ) -> Result<EmbeddingBatch> {
+        if options
+            .base
+            .api_key
+            .as_deref()
+            .is_none_or(|api_key| api_key.trim().is_empty())
+        {
+            options.base.api_key = self.api_key.clone();
+        }
+        if options.base.http_client.is_none() {
+            options.base.http_client = self.http_client.clone();
+        }
+        embed_many_cohere(model, inputs, options).await
+    }
+}
+
+#[derive(Clone)]
+struct CohereRerankModelApi {
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+#[async_trait]
+impl RerankModelApi for CohereRerankModelApi {
+    fn id(&self) -> &str {
+        KnownApi::CohereRerank.as_str()
+    }
+
+    async fn rerank(
+        &self,
+        model: Model,
+        query: String,
+        documents: Vec<String>,
+        top_n: Option<u32>,
+        mut options: RerankOptions,
+    ) -> Result<Reranking> {
+        if options
+            .base
+            .api_key
+            .as_deref()
+            .is_none_or(|api_key| api_key.trim().is_empty())
+        {
+            options.base.api_key = self.api_key.clone();
+        }
+        if options.base.http_client.is_none() {
+            options.base.http_client = self.http_client.clone();
+        }
+        rerank_cohere(model, query, documents, top_n, options).await
+    }
+}
+
+pub fn builder() -> CohereBuilder {
+    Cohere::builder()
+}
+
+pub fn from_env() -> Result<Cohere> {
+    Cohere::from_env()
+}
+
+#[derive(Clone, Default)]
+pub struct CohereOptions {
+    pub base: StreamOptions,
+    /// Sends `thinking.type`; reasoning models think by default.
+    pub thinking_enabled: Option<bool>,
+    pub thinking_budget_tokens: Option<u32>,
+}
+
+pub fn stream_simple_cohere(
+    model: Model,
+    context: Context,
+    options: SimpleStreamOptions,
+) -> Result<crate::AssistantEventStream> {
+    let api_key = options
+        .stream
+        .api_key
+        .clone()
+        .filter(|key| !key.trim().is_empty());
+    let base = build_base_options(&model, &context, &options, api_key);
+    let mut cohere_options = simple_options::cohere_options_from_stream_options(base);
+    if model.reasoning && cohere_options.thinking_enabled.is_none() {
+        match clamped_reasoning(&model, &options) {
+            None => cohere_options.thinking_enabled = Some(false),
+            Some(reasoning) => {
+                let adjusted = adjust_max_tokens_for_thinking(
+                    cohere_options.base.max_tokens,
+                    model.max_tokens,
+                    Some(reasoning),
+                    options.thinking_budgets.as_ref(),
+                );
+                cohere_options.base.max_tokens = adjusted.max_tokens;
+                cohere_options.thinking_enabled = Some(true);
+                cohere_options.thinking_budget_tokens = Some(adjusted.thinking_budget);
+            }
+        }
+    }
+    Ok(stream_cohere(model, context, cohere_options))
+}
+
+pub fn stream_cohere(
+    model: Model,
+    context: Context,
+    options: CohereOptions,
+) -> crate::AssistantEventStream {
+    crate::event_stream::stream_from_producer(
+        move |mut sender| async move {
+            let output = AssistantMessage::empty_for(&model);
+            run_stream(model, context, options, output, &mut sender).await?;
+            Ok(())
+        },
+        |error: StreamFailure| {
+            let mut message = error.output;
+            message.stop_reason = if error.cancelled {
+                StopReason::Aborted
+            } else {
+                StopReason::Error
+            };
+            message.error_message = Some(error.message);
+            AssistantMessageEvent::Error {
+                reason: message.stop_reason,
+                error: message,
+            }
+        },
+    )
+}
+
+struct StreamFailure {
+    output: AssistantMessage,
+    message: String,
+    cancelled: bool,
+}
+
+impl StreamFailure {
+    fn new(output: AssistantMessage, error: impl std::fmt::Display) -> Self {
+        Self {
+            output,
+            message: error.to_string(),
+            cancelled: false,
+        }
+    }
+
+    fn cancelled(output: AssistantMessage) -> Self {
+        Self {
+            output,
+            message: "Request was aborted".to_string(),
+            cancelled: true,
+        }
+    }
+}
+
+/// Block currently receiving deltas. Tool calls keep their raw argument JSON
+/// so partial arguments can be re-parsed on every delta.
+enum OpenBlock {
+    Content(usize),
+    ToolCall { index: usize, arguments: String },
+}
+
+async fn run_stream(
+    model: Model,
+    context: Context,
+    options: CohereOptions,
+    mut output: AssistantMessage,
+    sender: &mut AssistantMessageEventStreamSender,
+) -> std::result::Result<(), StreamFailure> {
+    let is_cancelled = || {
+        options
+            .base
+            .cancellation_token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    };
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+    let Some(api_key) = options
+        .base
+        .api_key
+        .clone()
+        .filter(|key| !key.trim().is_empty())
+    else {
+        return Err(StreamFailure::new(
+            output,
+            Error::MissingApiKey(model.provider.clone()),
+        ));
+    };
+
+    let mut payload = build_cohere_payload(&model, &context, &options);
+    if let Some(on_payload) = &options.base.on_payload {
+        match on_payload(payload.clone(), &model).await {
+            Ok(Some(next)) => payload = next,
+            Ok(None) => {}
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        }
+    }
+
+    let request_url = format!("{}/chat", model.base_url.trim_end_matches('/'));
+    let request_headers = match headers(&api_key, &model, &options.base.headers) {
+        Ok(headers) => headers,
+        Err(error) => return Err(StreamFailure::new(output, error)),
+    };
+    let client = options.base.http_client.clone().unwrap_or_default();
+    let response = match send_with_retries(&options.base, &model_circuit(&model), || {
+        client
+            .post(request_url.as_str())
+            .headers(request_headers.clone())
+            .json(&payload)
+            .timeout(request_timeout(options.base.timeout_ms))
+    })
+    .await
+    {
+        Ok(response) => response,
+        Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
+    };
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        output.error_status = Some(status.as_u16());
+        return Err(StreamFailure::new(
+            output,
+            Error::ApiStatus { status, body },
+        ));
+    }
+    if let Some(on_response) = &options.base.on_response {
+        let provider_response = ProviderResponse {
+            status: response.status().as_u16(),
+            headers: headers_to_record(response.headers()),
+        };
+        if let Err(error) = on_response(provider_response, &model).await {
+            return Err(StreamFailure::new(output, error));
+        }
+    }
+
+    sender.push(AssistantMessageEvent::Start {
+        partial: output.clone(),
+    });
+
+    let mut open_block: Option<OpenBlock> = None;
+    let mut finish_reason: Option<String> = None;
+    let events = sse::events(response, options.base.cancellation_token.clone());
+    pin_mut!(events);
+    while let Some(event) = events.next().await {
+        if is_cancelled() {
+            return Err(StreamFailure::cancelled(output));
+        }
+        let event = match event {
+            Ok(event) => event,
+            Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        };
+        let data = event.data.trim();
+        if data.is_empty() || data == "[DONE]" {
+            continue;
+        }
+        let chunk: Value = match parse_json_with_repair(data) {
+            Ok(value) => value,
+            Err(error) => {
+                return Err(StreamFailure::new(
+                    output,
+                    format!("Could not parse Cohere stream event: {error}; data={data}"),
+                ));
+            }
+        };
+        let delta = chunk.pointer("/delta/message").unwrap_or(&Value::Null);
+        match chunk
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+        {
+            "message-start" => {
+                if let Some(id) = chunk.get("id").and_then(Value::as_str) {
+                    output.response_id = Some(id.to_string());
+                }
+            }
+            "content-start" | "content-delta" => {
+                let content = delta.get("content").unwrap_or(&Value::Null);
+                if let Some(thinking) = content.get("thinking").and_then(Value::as_str) {
+                    push_delta(&mut output, &mut open_block, sender, true, thinking);
+                } else if let Some(text) = content.get("text").and_then(Value::as_str) {
+                    push_delta(&mut output, &mut open_block, sender, false, text);
+                }
+            }
+            "tool-plan-delta" => {
+                if let Some(plan) = delta.get("tool_plan").and_then(Value::as_str) {
+                    push_delta(&mut output, &mut open_block, sender, false, plan);
+                }
+            }
+            "content-end" => close_open_block(&mut output, &mut open_block, sender),
+            "tool-call-start" => {
+                close_open_block(&mut output, &mut open_block, sender);
+                let tool_call = delta.get("tool_calls").unwrap_or(&Value::Null);
+                let name = tool_call
+                    .pointer("/function/name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+                let id = tool_call
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("{name}_{}", output.content.len()));
+                output.content.push(AssistantContent::ToolCall(ToolCall {
+                    id,
+                    name,
+                    arguments: json!({}),
+                    thought_signature: None,
+                }));
+                let index = output.content.len() - 1;
+                open_block = Some(OpenBlock::ToolCall {
+                    index,
+                    arguments: String::new(),
+                });
+                sender.push(AssistantMessageEvent::ToolCallStart {
+                    content_index: index,
+                    partial: output.clone(),
+                });
+                if let Some(arguments) = tool_call
+                    .pointer("/function/arguments")
+                    .and_then(Value::as_str)
+                {
+                    push_tool_arguments(&mut output, &mut open_block, sender, arguments);
+                }
+            }
+            "tool-call-delta" => {
+                if let Some(arguments) = delta
+                    .pointer("/tool_calls/function/arguments")
+                    .and_then(Value::as_str)
+                {
+                    push_tool_arguments(&mut output, &mut open_block, sender, arguments);
+                }
+            }
+            "tool-call-end" => close_open_block(&mut output, &mut open_block, sender),
+            "message-end" => {
+                let end = chunk.get("delta").unwrap_or(&Value::Null);
+                if let Some(reason) = end.get("finish_reason").and_then(Value::as_str) {
+                    finish_reason = Some(reason.to_string());
+                }
+                if let Some(usage) = end.get("usage") {
+                    update_cohere_usage(&mut output, usage, &model);
+                }
+                if let Some(error) = end.get("error").and_then(Value::as_str) {
+                    output.error_message = Some(error.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    close_open_block(&mut output, &mut open_block, sender);
+
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+    let Some(finish_reason) = finish_reason else {
+        return Err(StreamFailure::new(
+            output,
+            "Cohere stream ended before message-end",
+        ));
+    };
+    output.stop_reason = match finish_reason.as_str() {
+        "COMPLETE" | "STOP_SEQUENCE" => {
+            if output
+                .content
+                .iter()
+                .any(|block| matches!(block, AssistantContent::ToolCall(_)))
+            {
+                StopReason::ToolUse
+            } else {
+                StopReason::Stop
+            }
+        }
+        "TOOL_CALL" => StopReason::ToolUse,
+        "MAX_TOKENS" => StopReason::Length,
+        other => {
+            let message = output
+                .error_message
+                .take()
+                .unwrap_or_else(|| format!("Provider finish_reason: {other}"));
+            return Err(StreamFailure::new(output, message));
+        }
+    };
+    sender.push(AssistantMessageEvent::Done {
+        reason: output.stop_reason,
+        message: output,
+    });
+    Ok(())
+}
+
+fn push_delta(
+    output: &mut AssistantMessage,
+    open_block: &mut Option<OpenBlock>,
+    sender: &mut AssistantMessageEventStreamSender,
+    is_thinking: bool,
+    delta: &str,
+) {
+    let continues_open_block = match open_block {
+        Some(OpenBlock::Content(index)) => {
+            output.content.get(*index).is_some_and(|block| match block {
+                AssistantContent::Thinking(_) => is_thinking,
+                AssistantContent::Text(_) => !is_thinking,
+                AssistantContent::ToolCall(_) | AssistantContent::Audio(_) => false,
+            })
+        }
+        _ => false,
+    };
+    if !continues_open_block {
+        close_open_block(output, open_block, sender);
+        output.content.push(if is_thinking {
+            AssistantContent::Thinking(ThinkingContent {
+                thinking: String::new(),
+                thinking_signature: None,
+                redacted: None,
+            })
+        } else {
+            AssistantContent::Text(TextContent {
+                text: String::new(),
+                text_signature: None,
+            })
+        });
+        let content_index = output.content.len() - 1;
+        *open_block = Some(OpenBlock::Content(content_index));
+        sender.push(if is_thinking {
+            AssistantMessageEvent::ThinkingStart {
+                content_index,
+                partial: output.clone(),
+            }
+        } else {
+            AssistantMessageEvent::TextStart {
+                content_index,
+                partial: output.clone(),
+            }
+        });
+    }
+    if delta.is_empty() {
+        return;
+    }
+    let Some(OpenBlock::Content(content_index)) = *open_block else {
+        return;
+    };
+    match output.content.get_mut(content_index) {
+        Some(AssistantContent::Thinking(block)) => block.thinking.push_str(delta),
+        Some(AssistantContent::Text(block)) => block.text.push_str(delta),
+        _ => return,
+    }
+    sender.push(if is_thinking {
+        AssistantMessageEvent::ThinkingDelta {
+            content_index,
+            delta: delta.to_string(),
+            partial: output.clone(),
+        }
+    } else {
+        AssistantMessageEvent::TextDelta {
+            content_index,
+            delta: delta.to_string(),
+            partial: output.clone(),
+        }
+    });
+}
+
+fn push_tool_arguments(
+    output: &mut AssistantMessage,
+    open_block: &mut Option<OpenBlock>,
+    sender: &mut AssistantMessageEventStreamSender,
+    delta: &str,
+) {
+    let Some(OpenBlock::ToolCall { index, arguments }) = open_block else {
+        return;
+    };
+    if delta.is_empty() {
+        return;
+    }
+    arguments.push_str(delta);
+    if let Some(AssistantContent::ToolCall(tool_call)) = output.content.get_mut(*index) {
+        tool_call.arguments = parse_streaming_json(Some(arguments));
+    }
+    sender.push(AssistantMessageEvent::ToolCallDelta {
+        content_index: *index,
+        delta: delta.to_string(),
+        partial: output.clone(),
+    });
+}
+
+fn close_open_block(
+    output: &mut AssistantMessage,
+    open_block: &mut Option<OpenBlock>,
+    sender: &mut AssistantMessageEventStreamSender,
+) {
+    match open_block.take() {
+        Some(OpenBlock::Content(content_index)) => match output.content.get(content_index) {
+            Some(AssistantContent::Text(block)) => sender.push(AssistantMessageEvent::TextEnd {
+                content_index,
+                content: block.text.clone(),
+                partial: output.clone(),
+            }),
+            Some(AssistantContent::Thinking(block)) => {
+                sender.push(AssistantMessageEvent::ThinkingEnd {
+                    content_index,
+                    content: block.thinking.clone(),
+                    partial: output.clone(),
+                })
+            }
+            _ => {}
+        },
+        Some(OpenBlock::ToolCall { index, arguments }) => {
+            let Some(AssistantContent::ToolCall(tool_call)) = output.content.get_mut(index) else {
+                return;
+            };
+            tool_call.arguments = parse_json_with_repair(&arguments).unwrap_or_else(|_| json!({}));
+            let tool_call = tool_call.clone();
+            sender.push(AssistantMessageEvent::ToolCallEnd {
+                content_index: index,
+                tool_call,
+                partial: output.clone(),
+            });
+        }
+        None => {}
+    }
+}
+
+fn build_cohere_payload(model: &Model, context: &Context, options: &CohereOptions) -> Value {
+    let transformed = transform_messages(&context.messages, model, |id, _model, _source| {
+        id.to_string()
+    });
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = &context.system_prompt
+        && !system_prompt.is_empty()
+    {
+        messages.push(json!({ "role": "system", "content": system_prompt }));
+    }
+    messages.extend(convert_messages(&transformed));
+
+    let mut payload = json!({
+        "model": model.id,
+        "messages": messages,
+        "stream": true,
+    });
+    if let Some(max_tokens) = options.base.max_tokens {
+        payload["max_tokens"] = json!(max_tokens);
+    }
+    if let Some(temperature) = options.base.temperature {
+        payload["temperature"] = json!(temperature);
+    }
+    let sampling = sampling(model, &options.base, SamplingSupport::ALL);
+    if let Some(top_p) = sampling.top_p {
+        payload["p"] = json!(top_p);
+    }
+    if let Some(top_k) = sampling.top_k {
+        payload["k"] = json!(top_k);
+    }
+    if !sampling.stop.is_empty() {
+        payload["stop_sequences"] = json!(sampling.stop);
+    }
+    if let Some(seed) = sampling.seed {
+        payload["seed"] = json!(seed);
+    }
+    if let Some(frequency_penalty) = sampling.frequency_penalty {
+        payload["frequency_penalty"] = json!(frequency_penalty);
+    }
+    if let Some(presence_penalty) = sampling.presence_penalty {
+        payload["presence_penalty"] = json!(presence_penalty);
+    }
+    match options.thinking_enabled {
+        Some(true) => {
+            let mut thinking = json!({ "type": "enabled" });
+            if let Some(budget) = options.thinking_budget_tokens {
+                thinking["token_budget"] = json!(budget);
+            }
+            payload["thinking"] = thinking;
+        }
+        Some(false) => payload["thinking"] = json!({ "type": "disabled" }),
+        None => {}
+    }
+    if !context.tools.is_empty() {
+        payload["tools"] = json!(convert_tools(&context.tools));
+    }
+    payload
+}
+
+fn convert_messages(messages: &[Message]) -> Vec<Value> {
+    let mut converted = Vec::new();
+    for message in messages {
+        match message {
+            Message::User(user) => {
+                let content = match &user.content {
+                    UserMessageContent::Text(text) => json!(text),
+                    UserMessageContent::Parts(parts) => json!(
+                        parts
+                            .iter()
+                            .filter_map(|part| match part {
+                                UserContent::Text(part) => {
+                                    Some(json!({ "type": "text", "text": part.text }))
+                                }
+                                UserContent::Image(image) => Some(json!({
+                                    "type": "image_url",
+                                    "image_url": {
+                                        "url": format!(
+                                            "data:{};base64,{}",
+                                            image.mime_type, image.data
+                                        )
+                                    }
+                                })),
+                                // Cohere chat takes text and images only.
+                                UserContent::Document(_) | UserContent::Audio(_) => None,
+                            })
+                            .collect::<Vec<_>>()
+                    ),
+                };
+                converted.push(json!({ "role": "user", "content": content }));
+            }
+            Message::Assistant(assistant) => {
+                let mut content = Vec::new();
+                let mut text = String::new();
+                let mut tool_calls = Vec::new();
+                for block in &assistant.content {
+                    match block {
+                        AssistantContent::Text(block) => text.push_str(&block.text),
+                        AssistantContent::Audio(block) => text.push_str(&block.transcript),
+                        AssistantContent::Thinking(block) if block.redacted != Some(true) => {
+                            content.push(json!({ "type": "thinking", "thinking": block.thinking }))
+                        }
+                        AssistantContent::Thinking(_) => {}
+                        AssistantContent::ToolCall(tool_call) => tool_calls.push(json!({
+                            "id": tool_call.id,
+                            "type": "function",
+                            "function": {
+                                "name": tool_call.name,
+                                "arguments": tool_call.arguments.to_string()
+                            }
+                        })),
+                    }
+                }
+                let mut message = json!({ "role": "assistant" });
+                if tool_calls.is_empty() {
+                    if !text.is_empty() {
+                        content.push(json!({ "type": "text", "text": text }));
+                    }
+                } else {
+                    // Text streamed alongside tool calls is Cohere's tool plan.
+                    if !text.is_empty() {
+                        message["tool_plan"] = json!(text);
+                    }
+                    message["tool_calls"] = json!(tool_calls);
+                }
+                if content.is_empty() && tool_calls.is_empty() {
+                    continue;
+                }
+                if !content.is_empty() {
+                    message["content"] = json!(content);
+                }
+                converted.push(message);
+            }
+            Message::ToolResult(tool_result) => {
+                let content = tool_result
+                    .content
+                    .iter()
+                    .filter_map(|content| match content {
+                        ToolResultContent::Text(content) => {
+                            Some(json!({ "type": "text", "text": content.text }))
+                        }
+                        // Cohere tool results accept text and documents only.
+                        ToolResultContent::Image(_) => None,
+                        // Cohere documents are text records, not files.
+                        ToolResultContent::Document(_) => None,
+                    })
+                    .collect::<Vec<_>>();
+                converted.push(json!({
+                    "role": "tool",
+                    "tool_call_id": tool_result.tool_call_id,
+                    "content": content,
+                }));
+            }
+            Message::Custom(_) => {}
+        }
+    }
+    converted
+}
+
+fn convert_tools(tools: &[Tool]) -> Vec<Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters
+                }
+            })
+        })
+        .collect()
+}
+
+fn update_cohere_usage(output: &mut AssistantMessage, usage: &Value, model: &Model) {
+    let count = |name: &str| {
+        usage
+            .pointer(&format!("/tokens/{name}"))
+            .or_else(|| usage.pointer(&format!("/billed_units/{name}")))
+            .and_then(Value::as_f64)
+            .map(|value| value as u32)
+            .unwrap_or(0)
+    };
+    output.usage.input = count("input_tokens");
+    output.usage.output = count("output_tokens");
+    output.usage.total_tokens = output.usage.input + output.usage.output;
+    calculate_cost(model, &mut output.usage);
+}
+
+#[derive(Deserialize)]
+struct CohereEmbedResponse {
+    #[serde(default)]
+    embeddings: CohereEmbeddings,
+    #[serde(default)]
+    meta: CohereMeta,
+}
+
+#[derive(Default, Deserialize)]
+struct CohereEmbeddings {
+    #[serde(default)]
+    float: Vec<Vec<f32>>,
+    #[serde(default)]
+    base64: Vec<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct CohereMeta {
+    #[serde(default)]
+    billed_units: CohereBilledUnits,
+}
+
+#[derive(Default, Deserialize)]
+struct CohereBilledUnits {
+    #[serde(default)]
+    input_tokens: f64,
+    #[serde(default)]
+    search_units: f64,
+}
+
+async fn embed_many_cohere(
+    model: Model,
+    inputs: Vec<String>,
+    options: EmbeddingOptions,
+) -> Result<EmbeddingBatch> {
+    let Some(api_key) = options
+        .base
+        .api_key
+        .clone()
+        .filter(|api_key| !api_key.trim().is_empty())
+    else {
+        return Err(Error::MissingApiKey(model.provider.clone()));
+    };
+    let Some(input_type) = options.input_type else {
+        return Err(Error::Validation(
+            "Cohere embeddings require EmbeddingOptions::input_type".to_string(),
+        ));
+    };
+    let expected_count = inputs.len();
+    let encoding_format = options.encoding_format.unwrap_or_default();
+    let mut payload = json!({
+        "model": model.id,
+        "texts": inputs,
+        "input_type": input_type.as_str(),
+        "embedding_types": [encoding_format.as_str()],
+    });
+    if let Some(dimensions) = options.dimensions {
+        payload["output_dimension"] = json!(dimensions);
+    }
+    let response: CohereEmbedResponse =
+        post_json(&model, &api_key, "embed", payload, &options.base)
+            .await
+            .and_then(|body| {
+                serde_json::from_value(body).map_err(|error| {
+                    Error::InvalidProviderResponse(format!(
+                        "could not decode embeddings response: {error}"
+                    ))
+                })
+            })?;
+    let embeddings = match encoding_format {
+        EmbeddingEncodingFormat::Float => response
+            .embeddings
+            .float
+            .into_iter()
+            .map(EmbeddingVector::Float)
+            .collect::<Vec<_>>(),
+        EmbeddingEncodingFormat::Base64 => response
+            .embeddings
+            .base64
+            .into_iter()
+            .map(EmbeddingVector::Base64)
+            .collect(),
+    };
+    if embeddings.len() != expected_count {
+        return Err(Error::InvalidProviderResponse(format!(
+            "expected {expected_count} embeddings, received {}",
+            embeddings.len()
+        )));
+    }
+    let prompt_tokens = response.meta.billed_units.input_tokens as u32;
+    Ok(EmbeddingBatch {
+        embeddings,
+        model: model.id,
+        usage: EmbeddingUsage {
+            prompt_tokens,
+            total_tokens: prompt_tokens,
+        },
+    })
+}
+
+#[derive(Deserialize)]
+struct CohereRerankResponse {
+    results: Vec<CohereRerankResult>,
+    #[serde(default)]
+    meta: CohereMeta,
+}
+
+#[derive(Deserialize)]
+struct CohereRerankResult {
+    index: usize,
+    relevance_score: f64,
+}
+
+async fn rerank_cohere(
+    model: Model,
+    query: String,
+    documents: Vec<String>,
+    top_n: Option<u32>,
+    options: RerankOptions,
+) -> Result<Reranking> {
+    let Some(api_key) = options
+        .base
+        .api_key
+        .clone()
+        .filter(|api_key| !api_key.trim().is_empty())
+    else {
+        return Err(Error::MissingApiKey(model.provider.clone()));
+    };
+    let mut payload = json!({
+        "model": model.id,
+        "query": query,
+        "documents": documents,
+    });
+    if let Some(top_n) = top_n {
+        payload["top_n"] = json!(top_n);
+    }
+    if let Some(max_tokens_per_doc) = options.max_tokens_per_doc {
+        payload["max_tokens_per_doc"] = json!(max_tokens_per_doc);
+    }
+    let response: CohereRerankResponse =
+        post_json(&model, &api_key, "rerank", payload, &options.base)
+            .await
+            .and_then(|body| {
+                serde_json::from_value(body).map_err(|error| {
+                    Error::InvalidProviderResponse(format!(
+                        "could not decode rerank response: {error}"
+                    ))
+                })
+            })?;
+    let mut results = response
+        .results
+        .into_iter()
+        .map(|result| {
+            let document = documents.get(result.index).cloned().ok_or_else(|| {
+                Error::InvalidProviderResponse(format!(
+                    "rerank result index {} is out of range",
+                    result.index
+                ))
+            })?;
+            Ok(RankedDocument {
+                index: result.index,
+                document,
+                relevance_score: result.relevance_score,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    results.sort_by(|left, right| right.relevance_score.total_cmp(&left.relevance_score));
+    Ok(Reranking {
+        results,
+        model: model.id,
+        usage: RerankUsage {
+            search_units: response.meta.billed_units.search_units as u32,
+        },
+    })
+}
+
+async fn post_json(
+    model: &Model,
+    api_key: &str,
+    path: &str,
+    mut payload: Value,
+    options: &crate::types::RequestOptions,
+) -> Result<Value> {
+    if let Some(on_payload) = &options.on_payload
+        && let Some(next_payload) = on_payload(payload.clone(), model).await?
+    {
+        payload = next_payload;
+    }
+    let client = options.http_client.clone().unwrap_or_default();
+    let url = format!("{}/{path}", model.base_url.trim_end_matches('/'));
+    let headers = headers(api_key, model, &options.headers)?;
+    let response = send_request_with_retries(options, &model_circuit(model), || {
+        client
+            .post(&url)
+            .headers(headers.clone())
+            .json(&payload)
+            .timeout(request_timeout(options.timeout_ms))
+    })
+    .await?;
+    let status = response.status();
+    let response_headers = response.headers().clone();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(Error::ApiStatus { status, body });
+    }
+    if let Some(on_response) = &options.on_response {
+        on_response(
+            ProviderResponse {
+                status: status.as_u16(),
+                headers: headers_to_record(&response_headers),
+            },
+            model,
+        )
+        .await?;
+    }
+    serde_json::from_str(&body).map_err(|error| {
+        Error::InvalidProviderResponse(format!("could not decode Cohere response: {error}"))
+    })
+}
+
+fn headers(api_key: &str, model: &Model, overrides: &ProviderHeaders) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {api_key}"))
+            .map_err(|error| Error::InvalidHeaderValue("authorization".to_string(), error))?,
+    );
+    for (name, value) in &model.headers {
+        let Ok(name) = HeaderName::from_bytes(name.as_bytes()) else {
+            continue;
+        };
+        let value = HeaderValue::from_str(value)
+            .map_err(|error| Error::InvalidHeaderValue(name.to_string(), error))?;
+        headers.insert(name, value);
+    }
+    crate::utils::headers::apply_provider_headers(&mut headers, overrides)?;
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::types::{EmbeddingInputType, TextContent, ToolResultMessage, Usage};
+
+    fn cohere(base_url: &str) -> Cohere {
+        builder()
+            .api_key(Some("cohere-key"))
+            .base_url(base_url)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn payload_replays_tool_plan_calls_and_results() {
+        let model = cohere("http://127.0.0.1:9")
+            .model("command-a-03-2025")
+            .build()
+            .unwrap();
+        let context = Context {
+            system_prompt: Some("Be brief.".to_string()),
+            messages: vec![
+                Message::user_text("weather?"),
+                Message::Assistant(AssistantMessage {
+                    content: vec![
+                        AssistantContent::Text(TextContent {
+                            text: "I will look it up.".to_string(),
+                            text_signature: None,
+                        }),
+                        AssistantContent::ToolCall(ToolCall {
+                            id: "weather_1".to_string(),
+                            name: "weather".to_string(),
+                            arguments: json!({ "city": "Paris" }),
+                            thought_signature: None,
+                        }),
+                    ],
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage::default(),
+                    ..AssistantMessage::empty_for(&model)
+                }),
+                Message::ToolResult(ToolResultMessage {
+                    tool_call_id: "weather_1".to_string(),
+                    tool_name: "weather".to_string(),
+                    content: vec![ToolResultContent::Text(TextContent {
+                        text: "sunny".to_string(),
+                        text_signature: None,
+                    })],
+                    details: None,
+                    usage: None,
+                    added_tool_names: Vec::new(),
+                    is_error: false,
+                    timestamp: 0,
+                }),
+            ],
+            tools: vec![Tool {
+                name: "weather".to_string(),
+                description: "Get weather".to_string(),
+                parameters: json!({ "type": "object" }),
+                constrained_sampling: None,
+            }],
+        };
+
+        let payload = build_cohere_payload(
+            &model,
+            &context,
+            &CohereOptions {
+                thinking_enabled: Some(true),
+                thinking_budget_tokens: Some(2_048),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(payload["messages"][0]["role"], "system");
+        assert_eq!(
+            payload["messages"][2],
+            json!({
+                "role": "assistant",
+                "tool_plan": "I will look it up.",
+                "tool_calls": [{
+                    "id": "weather_1",
+                    "type": "function",
+                    "function": { "name": "weather", "arguments": "{\"city\":\"Paris\"}" }
+                }]
+            })
+        );
+        assert_eq!(
+            payload["messages"][3],
+            json!({
+                "role": "tool",
+                "tool_call_id": "weather_1",
+                "content": [{ "type": "text", "text": "sunny" }]
+            })
+        );
+        assert_eq!(
+            payload["thinking"],
+            json!({ "type": "enabled", "token_budget": 2048 })
+        );
+        assert_eq!(payload["tools"][0]["function"]["name"], "weather");
+    }
+
+    #[tokio::test]
+    async fn stream_parses_text_and_tool_calls() {
+        let body = [
+            json!({ "type": "message-start", "id": "msg_1", "delta": { "message": { "role": "assistant" } } }),
+            json!({ "type": "tool-plan-delta", "delta": { "message": { "tool_plan": "Checking." } } }),
+            json!({ "type": "tool-call-start", "index": 0, "delta": { "message": { "tool_calls": {
+                "id": "weather_1", "type": "function", "function": { "name": "weather", "arguments": "" }
+            } } } }),
+            json!({ "type": "tool-call-delta", "index": 0, "delta": { "message": { "tool_calls": {
+                "function": { "arguments": "{\"city\":" }
+            } } } }),
+            json!({ "type": "tool-call-delta", "index": 0, "delta": { "message": { "tool_calls": {
+                "function": { "arguments": "\"Paris\"}" }
+            } } } }),
+            json!({ "type": "tool-call-end", "index": 0 }),
+            json!({ "type": "message-end", "delta": {
+                "finish_reason": "TOOL_CALL",
+                "usage": {
+                    "billed_units": { "input_tokens": 10, "output_tokens": 5 },
+                    "tokens": { "input_tokens": 20, "output_tokens": 7 }
+                }
+            } }),
+        ]
+        .iter()
+        .map(|event| format!("event: {}\ndata: {event}\n\n", event["type"].as_str().unwrap()))
+        .collect::<String>();
+        let (base_url, request) = spawn_server("200 OK", "text/event-stream", &body).await;
+        let model = cohere(&base_url)
+            .model("command-a-03-2025")
+            .build()
+            .unwrap();
+
+        let message = crate::complete(model, Context::default(), None)
+            .await
+            .unwrap();
+        let request = request.await.unwrap();
+
+        assert!(request.starts_with("POST /chat HTTP/1.1"));
+        assert!(request.contains("authorization: Bearer cohere-key"));
+        assert_eq!(message.stop_reason, StopReason::ToolUse);
+        assert_eq!(message.response_id.as_deref(), Some("msg_1"));
+        assert_eq!(message.usage.input, 20);
+        assert_eq!(message.usage.output, 7);
+        assert!(matches!(
+            &message.content[..],
+            [AssistantContent::Text(plan), AssistantContent::ToolCall(call)]
+                if plan.text == "Checking."
+                    && call.id == "weather_1"
+                    && call.arguments == json!({ "city": "Paris" })
+        ));
+    }
+
+    #[tokio::test]
+    async fn embeddings_send_input_type() {
+        let body = json!({
+            "id": "e1",
+            "embeddings": { "float": [[0.1, 0.2], [0.3, 0.4]] },
+            "meta": { "billed_units": { "input_tokens": 6 } }
+        })
+        .to_string();
+        let (base_url, request) = spawn_server("200 OK", "application/json", &body).await;
+        let model = cohere(&base_url)
+            .embedding_model("embed-v4.0")
+            .build_embedding()
+            .unwrap();
+
+        let batch = crate::embed_many(
+            model,
+            ["first", "second"],
+            Some(EmbeddingOptions {
+                input_type: Some(EmbeddingInputType::SearchQuery),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        let request = request.await.unwrap();
+        let payload: Value =
+            serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+
+        assert!(request.starts_with("POST /embed HTTP/1.1"));
+        assert_eq!(payload["input_type"], "search_query");
+        assert_eq!(payload["embedding_types"], json!(["float"]));
+        assert_eq!(batch.embeddings[1], EmbeddingVector::Float(vec![0.3, 0.4]));
+        assert_eq!(batch.usage.prompt_tokens, 6);
+    }
+
+    #[tokio::test]
+    async fn embeddings_require_input_type() {
+        let model = cohere("http://127.0.0.1:9")
+            .embedding_model("embed-v4.0")
+            .build_embedding()
+            .unwrap();
+
+        let error = crate::embed(model, "hello", None).await.unwrap_err();
+
+        assert!(matches!(error, Error::Validation(_)));
+    }
+
+    #[tokio::test]
+    async fn rerank_returns_documents_by_relevance() {
+        let body = json!({
+            "id": "r1",
+            "results": [
+                { "index": 2, "relevance_score": 0.91 },
+                { "index": 0, "relevance_score": 0.42 }
+            ],
+            "meta": { "billed_units": { "search_units": 1 } }
+        })
+        .to_string();
+        let (base_url, request) = spawn_server("200 OK", "application/json", &body).await;
+        let model = cohere(&base_url)
+            .rerank_model("rerank-v3.5")
+            .build_rerank()
+            .unwrap();
+
+        let reranking = crate::rerank(
+            model,
+            "capital of France",
+            [
+                "Paris is in France",
+                "Berlin",
+                "Paris is the capital of France",
+            ],
+            Some(2),
+            None,
+        )
+        .await
+        .unwrap();
+        let request = request.await.unwrap();
+        let payload: Value =
+            serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+
+        assert!(request.starts_with("POST /rerank HTTP/1.1"));
+        assert_eq!(payload["top_n"], 2);
+        assert_eq!(payload["query"], "capital of France");
+        assert_eq!(reranking.results.len(), 2);
+        assert_eq!(reranking.results[0].index, 2);
+        assert_eq!(
+            reranking.results[0].document,
+            "Paris is the capital of France"
+        );
+        assert_eq!(reranking.usage.search_units, 1);
+    }
+
+    async fn spawn_server(
+        status: &'static str,
+        content_type: &'static str,
+        body: &str,
+    ) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = tokio::sync::oneshot::channel();
+        let body = body.to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 16 * 1024];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let _ = request_tx.send(String::from_utf8_lossy(&buffer[..read]).into_owned());
+            let response = format!(
+                "HTTP/1.1 {status}\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        (format!("http://{addr}"), request_rx)
+    }
+}
@@ -46,7 +46,10 @@ fn assistant_tool_call() -> Message {
         diagnostics: Vec::new(),
         usage: Usage::default(),
         stop_reason: StopReason::ToolUse,
+        stop_sequence: None,
         error_message: None,
+        error_status: None,
+        circuit_open: None,
         timestamp: 2,
     })
 }
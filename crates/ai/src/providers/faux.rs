@@ -10,10 +10,11 @@ use serde_json::{Value, json};
 use crate::event_stream::{AssistantEventStream, AssistantMessageEventStreamSender};
 use crate::provider::LanguageModelApi;
 use crate::types::{
-    AssistantContent, AssistantMessage, AssistantMessageEvent, CacheRetention, Context,
-    ImageContent, Message, Model, ModelCost, ModelInput, ProviderResponse, SimpleStreamOptions,
-    StopReason, StreamOptions, TextContent, ThinkingContent, ToolCall, ToolResultContent,
-    ToolResultMessage, Usage, UsageCost, UserContent, UserMessageContent,
+    AssistantAudioContent, AssistantContent, AssistantMessage, AssistantMessageEvent,
+    CacheRetention, Context, DocumentContent, DocumentSource, ImageContent, Message, Model,
+    ModelCost, ModelInput, ProviderResponse, SimpleStreamOptions, StopReason, StreamOptions,
+    TextContent, ThinkingContent, ToolCall, ToolChoice, ToolResultContent, ToolResultMessage,
+    Usage, UsageCost, UserContent, UserMessageContent,
 };
 use crate::{Error, Result};
 
@@ -235,7 +236,10 @@ pub fn faux_assistant_message(
         diagnostics: Vec::new(),
         usage: default_usage(),
         stop_reason: options.stop_reason.unwrap_or(StopReason::Stop),
+        stop_sequence: None,
         error_message: options.error_message,
+        error_status: None,
+        circuit_open: None,
         timestamp: options
             .timestamp
             .unwrap_or_else(crate::utils::time::now_millis),
@@ -462,6 +466,9 @@ async fn stream_faux_response(
                 .await
             }
         }?;
+        if let Some(tool_choice) = &stream_options.tool_choice {
+            check_tool_choice(&resolved, tool_choice)?;
+        }
         let message = clone_message(resolved, &api, &provider, &request_model.id);
         Ok(TerminalFauxMessage::Stream(with_usage_estimate(
             message,
@@ -491,12 +498,16 @@ async fn stream_faux_response(
             .await;
         }
         Err(error) => {
-            let message = create_error_message(
+            let mut message = create_error_message(
                 faux_error_message(&error),
                 &api,
                 &provider,
                 &request_model.id,
             );
+            if let Error::ApiStatus { status, .. } = &error {
+                message.error_status = Some(status.as_u16());
+            }
+            message.circuit_open = error.open_circuit();
             sender.push(AssistantMessageEvent::Error {
                 reason: StopReason::Error,
                 error: message,
@@ -625,6 +636,58 @@ async fn stream_with_deltas(
                     partial: partial.clone(),
                 });
             }
+            AssistantContent::Audio(audio) => {
+                partial
+                    .content
+                    .push(AssistantContent::Audio(AssistantAudioContent {
+                        data: String::new(),
+                        transcript: String::new(),
+                        ..audio.clone()
+                    }));
+                sender.push(AssistantMessageEvent::AudioStart {
+                    content_index: index,
+                    partial: partial.clone(),
+                });
+                if let Some(AssistantContent::Audio(partial_audio)) = partial.content.get_mut(index)
+                {
+                    partial_audio.data = audio.data.clone();
+                }
+                sender.push(AssistantMessageEvent::AudioDelta {
+                    content_index: index,
+                    data: audio.data.clone(),
+                    transcript: String::new(),
+                    partial: partial.clone(),
+                });
+                for chunk in
+                    split_string_by_token_size(&audio.transcript, min_token_size, max_token_size)
+                {
+                    schedule_chunk(&chunk, tokens_per_second).await;
+                    if is_cancelled(&stream_options) {
+                        let aborted = create_aborted_message(partial);
+                        sender.push(AssistantMessageEvent::Error {
+                            reason: StopReason::Aborted,
+                            error: aborted,
+                        });
+                        return;
+                    }
+                    if let Some(AssistantContent::Audio(partial_audio)) =
+                        partial.content.get_mut(index)
+                    {
+                        partial_audio.transcript.push_str(&chunk);
+                    }
+                    sender.push(AssistantMessageEvent::AudioDelta {
+                        content_index: index,
+                        data: String::new(),
+                        transcript: chunk,
+                        partial: partial.clone(),
+                    });
+                }
+                sender.push(AssistantMessageEvent::AudioEnd {
+                    content_index: index,
+                    content: audio.clone(),
+                    partial: partial.clone(),
+                });
+            }
             AssistantContent::ToolCall(tool_call) => {
                 partial.content.push(AssistantContent::ToolCall(ToolCall {
                     id: tool_call.id.clone(),
@@ -742,6 +805,8 @@ fn user_content_block_to_text(block: &UserContent) -> String {
     match block {
         UserContent::Text(text) => text.text.clone(),
         UserContent::Image(image) => image_to_text(image),
+        UserContent::Document(document) => document_to_text(document),
+        UserContent::Audio(audio) => format!("[audio:{}:{}]", audio.mime_type, audio.data.len()),
     }
 }
 
@@ -749,6 +814,7 @@ fn tool_result_block_to_text(block: &ToolResultContent) -> String {
     match block {
         ToolResultContent::Text(text) => text.text.clone(),
         ToolResultContent::Image(image) => image_to_text(image),
+        ToolResultContent::Document(document) => document_to_text(document),
     }
 }
 
@@ -756,12 +822,22 @@ fn image_to_text(image: &ImageContent) -> String {
     format!("[image:{}:{}]", image.mime_type, image.data.len())
 }
 
+fn document_to_text(document: &DocumentContent) -> String {
+    match &document.source {
+        DocumentSource::Base64 { data } => {
+            format!("[document:{}:{}]", document.mime_type, data.len())
+        }
+        DocumentSource::Url { url } => format!("[document:{}:{url}]", document.mime_type),
+    }
+}
+
 fn assistant_content_to_text(content: &[AssistantContent]) -> String {
     content
         .iter()
         .map(|block| match block {
             AssistantContent::Text(text) => text.text.clone(),
             AssistantContent::Thinking(thinking) => thinking.thinking.clone(),
+            AssistantContent::Audio(audio) => audio.transcript.clone(),
             AssistantContent::ToolCall(tool_call) => {
                 let arguments = serde_json::to_string(&tool_call.arguments)
                     .unwrap_or_else(|_| "null".to_string());
@@ -893,11 +969,45 @@ fn create_error_message(
         diagnostics: Vec::new(),
         usage: default_usage(),
         stop_reason: StopReason::Error,
+        stop_sequence: None,
         error_message: Some(error.into()),
+        error_status: None,
+        circuit_open: None,
         timestamp: crate::utils::time::now_millis(),
     }
 }
 
+/// Fails scripted responses that a real provider could not return under
+/// `tool_choice`, so tests catch flows that ask for the wrong choice.
+fn check_tool_choice(message: &AssistantMessage, tool_choice: &ToolChoice) -> Result<()> {
+    if message.stop_reason == StopReason::Error || message.stop_reason == StopReason::Aborted {
+        return Ok(());
+    }
+    let tool_names = message
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            AssistantContent::ToolCall(tool_call) => Some(tool_call.name.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let violation = match tool_choice {
+        ToolChoice::Auto => None,
+        ToolChoice::None => (!tool_names.is_empty())
+            .then(|| "faux response calls a tool but tool choice is none".to_string()),
+        ToolChoice::Required => tool_names
+            .is_empty()
+            .then(|| "faux response calls no tool but tool choice is required".to_string()),
+        ToolChoice::Tool(name) => (tool_names.is_empty()
+            || tool_names.iter().any(|tool_name| tool_name != name))
+        .then(|| format!("faux response must call only the \"{name}\" tool")),
+    };
+    match violation {
+        Some(message) => Err(Error::Provider(message)),
+        None => Ok(()),
+    }
+}
+
 fn faux_error_message(error: &Error) -> String {
     match error {
         Error::Provider(message) | Error::Validation(message) => message.clone(),
@@ -1290,6 +1400,63 @@ mod tests {
         registration.unregister();
     }
 
+    #[tokio::test]
+    async fn rejects_responses_that_break_the_tool_choice() {
+        let registration = register_faux_provider(None);
+        let lookup = || {
+            faux_assistant_message(
+                vec![faux_tool_call("lookup", json!({}), None)],
+                Some(FauxAssistantMessageOptions {
+                    stop_reason: Some(StopReason::ToolUse),
+                    ..Default::default()
+                }),
+            )
+        };
+        registration.set_responses([lookup(), lookup(), faux_assistant_message("no tools", None)]);
+        let context = Context {
+            messages: vec![Message::user_text("hi")],
+            ..Context::default()
+        };
+        let options = |tool_choice| StreamOptions {
+            tool_choice: Some(tool_choice),
+            ..Default::default()
+        };
+
+        let forced = complete(
+            registration.get_model(),
+            context.clone(),
+            Some(options(ToolChoice::tool("lookup"))),
+        )
+        .await
+        .expect("forced response");
+        let other = complete(
+            registration.get_model(),
+            context.clone(),
+            Some(options(ToolChoice::tool("search"))),
+        )
+        .await
+        .expect("mismatched response");
+        let required = complete(
+            registration.get_model(),
+            context,
+            Some(options(ToolChoice::Required)),
+        )
+        .await
+        .expect("required response");
+
+        assert_eq!(forced.stop_reason, StopReason::ToolUse);
+        assert_eq!(other.stop_reason, StopReason::Error);
+        assert_eq!(
+            other.error_message.as_deref(),
+            Some("faux response must call only the \"search\" tool")
+        );
+        assert_eq!(
+            required.error_message.as_deref(),
+            Some("faux response calls no tool but tool choice is required")
+        );
+        registration.unregister();
+    }
+
     #[tokio::test]
     async fn supports_async_response_factories() {
         let registration = register_faux_provider(None);
@@ -1571,7 +1738,11 @@ mod tests {
                     AssistantMessageEvent::ThinkingEnd { .. } => "thinking_end",
                     AssistantMessageEvent::TextStart { .. } => "text_start",
                     AssistantMessageEvent::TextDelta { .. } => "text_delta",
+                    AssistantMessageEvent::TextLogprobs { .. } => "text_logprobs",
                     AssistantMessageEvent::TextEnd { .. } => "text_end",
+                    AssistantMessageEvent::AudioStart { .. } => "audio_start",
+                    AssistantMessageEvent::AudioDelta { .. } => "audio_delta",
+                    AssistantMessageEvent::AudioEnd { .. } => "audio_end",
                     AssistantMessageEvent::ToolCallStart { .. } => "toolcall_start",
                     AssistantMessageEvent::ToolCallDelta { .. } => "toolcall_delta",
                     AssistantMessageEvent::ToolCallEnd { .. } => "toolcall_end",
@@ -1758,6 +1929,7 @@ mod tests {
                     AssistantMessageEvent::Start { .. } => "start",
                     AssistantMessageEvent::TextStart { .. } => "text_start",
                     AssistantMessageEvent::TextDelta { .. } => "text_delta",
+                    AssistantMessageEvent::TextLogprobs { .. } => "text_logprobs",
                     AssistantMessageEvent::TextEnd { .. } => "text_end",
                     AssistantMessageEvent::Error { .. } => "error",
                     _ => "other",
@@ -1816,6 +1988,7 @@ mod tests {
                     AssistantMessageEvent::Start { .. } => "start",
                     AssistantMessageEvent::TextStart { .. } => "text_start",
                     AssistantMessageEvent::TextDelta { .. } => "text_delta",
+                    AssistantMessageEvent::TextLogprobs { .. } => "text_logprobs",
                     AssistantMessageEvent::TextEnd { .. } => "text_end",
                     AssistantMessageEvent::Error { .. } => "error",
                     _ => "other",
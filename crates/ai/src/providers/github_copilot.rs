@@ -1,5 +1,9 @@
 use std::sync::Arc;
 
+use async_trait::async_trait;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+
 use crate::env_api_keys::{KnownProvider, get_env_api_key};
 use crate::event_stream::AssistantEventStream;
 use crate::oauth::{GitHubCopilotOAuthProvider, OAuthApiKey, OAuthCredentials};
@@ -12,6 +16,7 @@ use crate::types::{
     Context, Model, ModelCompat, ModelInput, OpenAICompletionsCompat, OpenAIResponsesCompat,
     SimpleStreamOptions, StreamOptions,
 };
+use crate::utils::http::get_json;
 use crate::{Error, Result};
 
 const DEFAULT_PROVIDER_ID: KnownProvider = KnownProvider::GitHubCopilot;
@@ -77,6 +82,7 @@ impl GitHubCopilot {
     }
 }
 
+#[async_trait]
 impl Provider for GitHubCopilot {
     fn id(&self) -> &str {
         &self.provider_id
@@ -87,6 +93,10 @@ impl Provider for GitHubCopilot {
             language_models: true,
             image_models: false,
             embedding_models: true,
+            fim_models: false,
+            rerank_models: false,
+            transcription_models: false,
+            speech_models: false,
         }
     }
 
@@ -132,6 +142,86 @@ impl Provider for GitHubCopilot {
             .max_tokens(16_384)
             .compat(compat)
     }
+
+    /// Lists `/models` for the signed-in account. Chat models keep the API
+    /// chosen by [`GitHubCopilotBuilder::api`] or by model family; embedding
+    /// models are bound to the embeddings API. Copilot reports limits and
+    /// feature support but no pricing.
+    async fn list_models(&self) -> Result<Vec<Model>> {
+        let api_key = self
+            .api_key
+            .as_deref()
+            .ok_or_else(|| Error::MissingApiKey(self.provider_id.clone()))?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in copilot_static_headers() {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes()).map_err(|error| {
+                    Error::Validation(format!("invalid Copilot header {name:?}: {error}"))
+                })?,
+                HeaderValue::from_str(&value)
+                    .map_err(|error| Error::InvalidHeaderValue(name.clone(), error))?,
+            );
+        }
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {api_key}"))
+                .map_err(|error| Error::InvalidHeaderValue("authorization".to_string(), error))?,
+        );
+        let client = self.http_client.clone().unwrap_or_default();
+        let base_url = self.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+        let url = format!("{}/models", base_url.trim_end_matches('/'));
+        let body = get_json(&client, &url, headers).await?;
+
+        let mut models = Vec::new();
+        for entry in body
+            .get("data")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some(id) = entry.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            let capabilities = entry.get("capabilities").unwrap_or(&Value::Null);
+            let mut builder = match capabilities.get("type").and_then(Value::as_str) {
+                Some("chat") => self.model(id),
+                Some("embeddings") => self.embedding_model(id),
+                _ => continue,
+            };
+            if let Some(name) = entry.get("name").and_then(Value::as_str) {
+                builder = builder.name(name);
+            }
+            let limit = |field: &str| {
+                capabilities
+                    .pointer(&format!("/limits/{field}"))
+                    .and_then(Value::as_u64)
+                    .map(|limit| limit as u32)
+            };
+            if let Some(context_window) = limit("max_context_window_tokens") {
+                builder = builder.context_window(context_window);
+            }
+            if let Some(max_tokens) = limit("max_output_tokens") {
+                builder = builder.max_tokens(max_tokens);
+            }
+            let supports = capabilities.get("supports").unwrap_or(&Value::Null);
+            if capabilities.get("type").and_then(Value::as_str) == Some("chat") {
+                let mut input = vec![ModelInput::Text];
+                if supports.get("vision").and_then(Value::as_bool) == Some(true) {
+                    input.push(ModelInput::Image);
+                }
+                builder = builder.input(input);
+            }
+            let reasoning = [
+                "reasoning_effort",
+                "max_thinking_budget",
+                "adaptive_thinking",
+            ]
+            .iter()
+            .any(|field| supports.get(field).is_some_and(|value| !value.is_null()));
+            models.push(builder.reasoning(reasoning).build()?);
+        }
+        Ok(models)
+    }
 }
 
 fn is_gpt_5_or_newer(id: &str) -> bool {
@@ -375,8 +465,80 @@ pub async fn get_oauth_api_key(credentials: &OAuthCredentials) -> Result<OAuthAp
 
 #[cfg(test)]
 mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
     use super::*;
 
+    #[tokio::test]
+    async fn list_models_reads_copilot_capabilities() {
+        let body = r#"{
+            "data": [
+                {
+                    "id": "claude-sonnet-4.5",
+                    "name": "Claude Sonnet 4.5",
+                    "capabilities": {
+                        "type": "chat",
+                        "limits": { "max_context_window_tokens": 144000, "max_output_tokens": 16000 },
+                        "supports": { "vision": true, "max_thinking_budget": 32000 }
+                    }
+                },
+                {
+                    "id": "gpt-4.1",
+                    "name": "GPT-4.1",
+                    "capabilities": {
+                        "type": "chat",
+                        "limits": { "max_context_window_tokens": 128000 },
+                        "supports": { "tool_calls": true }
+                    }
+                },
+                {
+                    "id": "text-embedding-3-small",
+                    "name": "Embedding V3 small",
+                    "capabilities": { "type": "embeddings" }
+                },
+                { "id": "copilot-completions", "capabilities": { "type": "completion" } }
+            ]
+        }"#;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let _ = request_tx.send(String::from_utf8_lossy(&buffer[..read]).into_owned());
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        let provider = builder()
+            .api_key("copilot-token")
+            .base_url(format!("http://{addr}"))
+            .build()
+            .unwrap();
+
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 3);
+        assert_eq!(models[0].api, "anthropic-messages");
+        assert_eq!(models[0].name, "Claude Sonnet 4.5");
+        assert_eq!(models[0].context_window, 144_000);
+        assert_eq!(models[0].max_tokens, 16_000);
+        assert_eq!(models[0].input, vec![ModelInput::Text, ModelInput::Image]);
+        assert!(models[0].reasoning);
+        assert_eq!(models[1].api, "openai-completions");
+        assert_eq!(models[1].input, vec![ModelInput::Text]);
+        assert!(!models[1].reasoning);
+        assert!(models[2].embedding_api().is_some());
+        let request = request_rx.await.unwrap();
+        assert!(request.starts_with("GET /models "));
+        assert!(request.contains("authorization: Bearer copilot-token"));
+    }
+
     #[test]
     fn selects_the_api_pi_assigns_to_each_copilot_model() {
         for id in [
@@ -0,0 +1,1735 @@
+use futures::{StreamExt, pin_mut};
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde_json::{Map, Value, json};
+use std::sync::Arc;
+
+use crate::env_api_keys::{KnownProvider, get_env_api_key};
+use crate::event_stream::AssistantMessageEventStreamSender;
+use crate::models::calculate_cost;
+use crate::provider::{LanguageModelApi, ModelBuilder, Provider, ProviderCapabilities};
+use crate::providers::sampling::{SamplingSupport, sampling};
+use crate::providers::simple_options;
+use crate::providers::simple_options::{build_base_options, clamped_reasoning};
+use crate::providers::transform_messages::transform_messages;
+use crate::types::{
+    AssistantContent, AssistantMessage, AssistantMessageEvent, Context, DocumentContent,
+    DocumentSource, KnownApi, Message, Model, ModelInput, ModelThinkingLevel, ProviderResponse,
+    SimpleStreamOptions, StopReason, StreamOptions, TextContent, ThinkingContent, Tool, ToolCall,
+    ToolChoice, ToolResultContent, UserContent, UserMessageContent,
+};
+use crate::utils::circuit_breaker::model_circuit;
+use crate::utils::headers::headers_to_record;
+use crate::utils::http::{request_timeout, send_with_retries};
+use crate::utils::json::parse_json_with_repair;
+use crate::utils::sse;
+use crate::{Error, Result};
+
+const DEFAULT_PROVIDER_ID: KnownProvider = KnownProvider::Google;
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+/// Gemini 3 rejects replayed function calls without a signature. Google
+/// documents this value for history that was not produced by Gemini.
+const SKIP_THOUGHT_SIGNATURE: &str = "skip_thought_signature_validator";
+
+#[derive(Clone)]
+pub struct Google {
+    provider_id: String,
+    api_key: Option<String>,
+    base_url: String,
+    http_client: Option<reqwest::Client>,
+}
+
+impl Google {
+    pub fn builder() -> GoogleBuilder {
+        GoogleBuilder::default()
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let api_key = get_env_api_key(DEFAULT_PROVIDER_ID)
+            .ok_or_else(|| Error::MissingApiKey(DEFAULT_PROVIDER_ID.into()))?;
+        Self::builder().api_key(Some(api_key.as_str())).build()
+    }
+
+    pub fn model(&self, id: &str) -> ModelBuilder {
+        <Self as Provider>::model(self, id)
+    }
+}
+
+impl Provider for Google {
+    fn id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            language_models: true,
+            image_models: false,
+            embedding_models: false,
+            fim_models: false,
+            rerank_models: false,
+            transcription_models: false,
+            speech_models: false,
+        }
+    }
+
+    fn model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(GoogleLanguageModelApi {
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .reasoning(is_thinking_model(id))
+            .input(vec![
+                ModelInput::Text,
+                ModelInput::Image,
+                ModelInput::Pdf,
+                ModelInput::Audio,
+            ])
+            .context_window(1_048_576)
+            .max_tokens(65_536)
+    }
+}
+
+#[derive(Default)]
+pub struct GoogleBuilder {
+    provider_id: Option<String>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl GoogleBuilder {
+    pub fn provider_id(mut self, provider_id: impl Into<String>) -> Self {
+        self.provider_id = Some(provider_id.into());
+        self
+    }
+
+    pub fn api_key(mut self, api_key: Option<&str>) -> Self {
+        self.api_key = api_key
+            .map(str::trim)
+            .filter(|api_key| !api_key.is_empty())
+            .map(str::to_string);
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn build(self) -> Result<Google> {
+        Ok(Google {
+            provider_id: self
+                .provider_id
+                .unwrap_or_else(|| DEFAULT_PROVIDER_ID.into()),
+            api_key: self.api_key,
+            base_url: self
+                .base_url
+                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            http_client: self.http_client,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct GoogleLanguageModelApi {
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl GoogleLanguageModelApi {
+    fn with_api_key(&self, mut options: StreamOptions) -> StreamOptions {
+        if options
+            .api_key
+            .as_deref()
+            .is_none_or(|api_key| api_key.trim().is_empty())
+        {
+            options.api_key = self.api_key.clone();
+        }
+        if options.http_client.is_none() {
+            options.http_client = self.http_client.clone();
+        }
+        options
+    }
+
+    fn with_api_key_simple(&self, mut options: SimpleStreamOptions) -> SimpleStreamOptions {
+        options.stream = self.with_api_key(options.stream);
+        options
+    }
+}
+
+impl LanguageModelApi for GoogleLanguageModelApi {
+    fn id(&self) -> &str {
+        KnownApi::GoogleGenerativeAi.as_str()
+    }
+
+    fn stream(
+        &self,
+        model: Model,
+        context: Context,
+        options: StreamOptions,
+    ) -> Result<crate::AssistantEventStream> {
+        Ok(stream_google(
+            model,
+            context,
+            simple_options::google_options_from_stream_options(self.with_api_key(options)),
+        ))
+    }
+
+    fn stream_simple(
+        &self,
+        model: Model,
+        context: Context,
+        options: SimpleStreamOptions,
+    ) -> Result<crate::AssistantEventStream> {
+        stream_simple_google(model, context, self.with_api_key_simple(options))
+    }
+}
+
+pub fn builder() -> GoogleBuilder {
+    Google::builder()
+}
+
+pub fn from_env() -> Result<Google> {
+    Google::from_env()
+}
+
+/// Gemini 3 thinking levels. Gemini 2.5 models use a token budget instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoogleThinkingLevel {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+impl GoogleThinkingLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value {
+            "minimal" => Some(Self::Minimal),
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct GoogleOptions {
+    pub base: StreamOptions,
+    pub thinking_enabled: Option<bool>,
+    pub thinking_budget_tokens: Option<u32>,
+    pub thinking_level: Option<GoogleThinkingLevel>,
+    pub tool_choice: Option<Value>,
+}
+
+pub fn stream_simple_google(
+    model: Model,
+    context: Context,
+    options: SimpleStreamOptions,
+) -> Result<crate::AssistantEventStream> {
+    let api_key = options
+        .stream
+        .api_key
+        .clone()
+        .filter(|key| !key.trim().is_empty());
+    if api_key.is_none() {
+        return Err(Error::MissingApiKey(model.provider));
+    }
+    let base = build_base_options(&model, &context, &options, api_key);
+
+    let Some(reasoning) = clamped_reasoning(&model, &options) else {
+        return Ok(stream_google(
+            model,
+            context,
+            GoogleOptions {
+                base,
+                thinking_enabled: Some(false),
+                ..Default::default()
+            },
+        ));
+    };
+
+    let options = if is_gemini_3(&model.id) {
+        GoogleOptions {
+            base,
+            thinking_enabled: Some(true),
+            thinking_level: Some(map_thinking_level(&model, reasoning)),
+            ..Default::default()
+        }
+    } else {
+        GoogleOptions {
+            base,
+            thinking_enabled: Some(true),
+            thinking_budget_tokens: Some(thinking_budget(
+                &model,
+                reasoning,
+                options.thinking_budgets.as_ref(),
+            )),
+            ..Default::default()
+        }
+    };
+    Ok(stream_google(model, context, options))
+}
+
+pub fn stream_google(
+    model: Model,
+    context: Context,
+    options: GoogleOptions,
+) -> crate::AssistantEventStream {
+    crate::event_stream::stream_from_producer(
+        move |mut sender| async move {
+            let output = AssistantMessage::empty_for(&model);
+            run_stream(model, context, options, output, &mut sender).await?;
+            Ok(())
+        },
+        |error: StreamFailure| {
+            let mut message = error.output;
+            message.stop_reason = if error.cancelled {
+                StopReason::Aborted
+            } else {
+                StopReason::Error
+            };
+            message.error_message = Some(error.message);
+            AssistantMessageEvent::Error {
+                reason: message.stop_reason,
+                error: message,
+            }
+        },
+    )
+}
+
+struct StreamFailure {
+    output: AssistantMessage,
+    message: String,
+    cancelled: bool,
+}
+
+impl StreamFailure {
+    fn new(output: AssistantMessage, error: impl std::fmt::Display) -> Self {
+        Self {
+            output,
+            message: error.to_string(),
+            cancelled: false,
+        }
+    }
+
+    fn cancelled(output: AssistantMessage) -> Self {
+        Self {
+            output,
+            message: "Request was aborted".to_string(),
+            cancelled: true,
+        }
+    }
+}
+
+async fn run_stream(
+    model: Model,
+    context: Context,
+    options: GoogleOptions,
+    mut output: AssistantMessage,
+    sender: &mut AssistantMessageEventStreamSender,
+) -> std::result::Result<(), StreamFailure> {
+    let is_cancelled = || {
+        options
+            .base
+            .cancellation_token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    };
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+
+    let Some(api_key) = options
+        .base
+        .api_key
+        .clone()
+        .filter(|key| !key.trim().is_empty())
+    else {
+        return Err(StreamFailure::new(
+            output,
+            Error::MissingApiKey(model.provider.clone()),
+        ));
+    };
+    let mut payload = match try_build_google_payload(&model, &context, &options) {
+        Ok(payload) => payload,
+        Err(error) => return Err(StreamFailure::new(output, error)),
+    };
+    if let Some(on_payload) = &options.base.on_payload {
+        match on_payload(payload.clone(), &model).await {
+            Ok(Some(next)) => payload = next,
+            Ok(None) => {}
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        }
+    }
+
+    let request_url = format!(
+        "{}/models/{}:streamGenerateContent?alt=sse",
+        model.base_url.trim_end_matches('/'),
+        model_name(&model.id)
+    );
+    let request_headers = match headers(&model, &options, &api_key) {
+        Ok(headers) => headers,
+        Err(error) => return Err(StreamFailure::new(output, error)),
+    };
+    let client = options.base.http_client.clone().unwrap_or_default();
+    let response = match send_with_retries(&options.base, &model_circuit(&model), || {
+        client
+            .post(request_url.as_str())
+            .headers(request_headers.clone())
+            .json(&payload)
+            .timeout(request_timeout(options.base.timeout_ms))
+    })
+    .await
+    {
+        Ok(response) => response,
+        Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
+    };
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        output.error_status = Some(status.as_u16());
+        return Err(StreamFailure::new(
+            output,
+            Error::ApiStatus { status, body },
+        ));
+    }
+    if let Some(on_response) = &options.base.on_response {
+        let provider_response = ProviderResponse {
+            status: response.status().as_u16(),
+            headers: headers_to_record(response.headers()),
+        };
+        if let Err(error) = on_response(provider_response, &model).await {
+            return Err(StreamFailure::new(output, error));
+        }
+    }
+
+    sender.push(AssistantMessageEvent::Start {
+        partial: output.clone(),
+    });
+
+    let mut open_block: Option<usize> = None;
+    let mut finish_reason: Option<String> = None;
+    let events = sse::events(response, options.base.cancellation_token.clone());
+    pin_mut!(events);
+    while let Some(event) = events.next().await {
+        if is_cancelled() {
+            return Err(StreamFailure::cancelled(output));
+        }
+        let event = match event {
+            Ok(event) => event,
+            Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        };
+        if event.data.trim().is_empty() {
+            continue;
+        }
+        let chunk: Value = match parse_json_with_repair(&event.data) {
+            Ok(value) => value,
+            Err(error) => {
+                return Err(StreamFailure::new(
+                    output,
+                    format!(
+                        "Could not parse Google SSE event: {error}; data={}",
+                        event.data
+                    ),
+                ));
+            }
+        };
+        if let Some(error) = chunk.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            return Err(StreamFailure::new(output, message));
+        }
+        if let Some(id) = chunk.get("responseId").and_then(Value::as_str) {
+            output.response_id = Some(id.to_string());
+        }
+        if let Some(version) = chunk.get("modelVersion").and_then(Value::as_str)
+            && version != model.id
+        {
+            output.response_model = Some(version.to_string());
+        }
+        if let Some(usage) = chunk.get("usageMetadata") {
+            update_google_usage(&mut output, usage, &model);
+        }
+        if let Some(reason) = chunk
+            .pointer("/promptFeedback/blockReason")
+            .and_then(Value::as_str)
+        {
+            return Err(StreamFailure::new(
+                output,
+                format!("Prompt was blocked by Google: {reason}"),
+            ));
+        }
+        let Some(candidate) = chunk
+            .get("candidates")
+            .and_then(Value::as_array)
+            .and_then(|candidates| candidates.first())
+        else {
+            continue;
+        };
+        for part in candidate
+            .pointer("/content/parts")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            handle_part(part, &mut output, &mut open_block, sender);
+        }
+        if let Some(reason) = candidate.get("finishReason").and_then(Value::as_str) {
+            finish_reason = Some(reason.to_string());
+        }
+    }
+    close_open_block(&mut output, &mut open_block, sender);
+
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+    let has_tool_calls = output
+        .content
+        .iter()
+        .any(|block| matches!(block, AssistantContent::ToolCall(_)));
+    match finish_reason.as_deref() {
+        Some(reason) => match map_finish_reason(reason, has_tool_calls) {
+            Ok(stop_reason) => output.stop_reason = stop_reason,
+            Err(message) => return Err(StreamFailure::new(output, message)),
+        },
+        None => {
+            return Err(StreamFailure::new(
+                output,
+                "Google stream ended without a finish reason",
+            ));
+        }
+    }
+    sender.push(AssistantMessageEvent::Done {
+        reason: output.stop_reason,
+        message: output,
+    });
+    Ok(())
+}
+
+fn handle_part(
+    part: &Value,
+    output: &mut AssistantMessage,
+    open_block: &mut Option<usize>,
+    sender: &mut AssistantMessageEventStreamSender,
+) {
+    let signature = part
+        .get("thoughtSignature")
+        .and_then(Value::as_str)
+        .filter(|signature| !signature.is_empty())
+        .map(str::to_string);
+
+    if let Some(function_call) = part.get("functionCall") {
+        close_open_block(output, open_block, sender);
+        let name = function_call
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let arguments = function_call
+            .get("args")
+            .cloned()
+            .unwrap_or_else(|| Value::Object(Map::new()));
+        let id = function_call
+            .get("id")
+            .and_then(Value::as_str)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{name}_{}", output.content.len()));
+        output.content.push(AssistantContent::ToolCall(ToolCall {
+            id,
+            name,
+            arguments: arguments.clone(),
+            thought_signature: signature,
+        }));
+        let content_index = output.content.len() - 1;
+        sender.push(AssistantMessageEvent::ToolCallStart {
+            content_index,
+            partial: output.clone(),
+        });
+        sender.push(AssistantMessageEvent::ToolCallDelta {
+            content_index,
+            delta: arguments.to_string(),
+            partial: output.clone(),
+        });
+        if let Some(AssistantContent::ToolCall(tool_call)) = output.content.get(content_index) {
+            sender.push(AssistantMessageEvent::ToolCallEnd {
+                content_index,
+                tool_call: tool_call.clone(),
+                partial: output.clone(),
+            });
+        }
+        return;
+    }
+
+    let Some(text) = part.get("text").and_then(Value::as_str) else {
+        return;
+    };
+    let is_thought = part.get("thought").and_then(Value::as_bool) == Some(true);
+    let continues_open_block = open_block
+        .and_then(|index| output.content.get(index))
+        .is_some_and(|block| match block {
+            AssistantContent::Thinking(_) => is_thought,
+            AssistantContent::Text(_) => !is_thought,
+            AssistantContent::ToolCall(_) | AssistantContent::Audio(_) => false,
+        });
+    if text.is_empty() && !continues_open_block {
+        // Gemini can send a trailing signature-only part for the block
+        // that just finished.
+        if let Some(signature) = signature {
+            attach_signature(output.content.last_mut(), signature);
+        }
+        return;
+    }
+    if !continues_open_block {
+        close_open_block(output, open_block, sender);
+        output.content.push(if is_thought {
+            AssistantContent::Thinking(ThinkingContent {
+                thinking: String::new(),
+                thinking_signature: None,
+                redacted: None,
+            })
+        } else {
+            AssistantContent::Text(TextContent {
+                text: String::new(),
+                text_signature: None,
+            })
+        });
+        let content_index = output.content.len() - 1;
+        *open_block = Some(content_index);
+        sender.push(if is_thought {
+            AssistantMessageEvent::ThinkingStart {
+                content_index,
+                partial: output.clone(),
+            }
+        } else {
+            AssistantMessageEvent::TextStart {
+                content_index,
+                partial: output.clone(),
+            }
+        });
+    }
+    let Some(content_index) = *open_block else {
+        return;
+    };
+    match output.content.get_mut(content_index) {
+        Some(AssistantContent::Thinking(block)) => block.thinking.push_str(text),
+        Some(AssistantContent::Text(block)) => block.text.push_str(text),
+        _ => return,
+    }
+    if let Some(signature) = signature {
+        attach_signature(output.content.get_mut(content_index), signature);
+    }
+    if text.is_empty() {
+        return;
+    }
+    sender.push(if is_thought {
+        AssistantMessageEvent::ThinkingDelta {
+            content_index,
+            delta: text.to_string(),
+            partial: output.clone(),
+        }
+    } else {
+        AssistantMessageEvent::TextDelta {
+            content_index,
+            delta: text.to_string(),
+            partial: output.clone(),
+        }
+    });
+}
+
+fn attach_signature(block: Option<&mut AssistantContent>, signature: String) {
+    match block {
+        Some(AssistantContent::Thinking(block)) => block.thinking_signature = Some(signature),
+        Some(AssistantContent::Text(block)) => block.text_signature = Some(signature),
+        Some(AssistantContent::ToolCall(block)) => block.thought_signature = Some(signature),
+        Some(AssistantContent::Audio(_)) | None => {}
+    }
+}
+
+fn close_open_block(
+    output: &mut AssistantMessage,
+    open_block: &mut Option<usize>,
+    sender: &mut AssistantMessageEventStreamSender,
+) {
+    let Some(content_index) = open_block.take() else {
+        return;
+    };
+    match output.content.get(content_index) {
+        Some(AssistantContent::Text(block)) => sender.push(AssistantMessageEvent::TextEnd {
+            content_index,
+            content: block.text.clone(),
+            partial: output.clone(),
+        }),
+        Some(AssistantContent::Thinking(block)) => {
+            sender.push(AssistantMessageEvent::ThinkingEnd {
+                content_index,
+                content: block.thinking.clone(),
+                partial: output.clone(),
+            })
+        }
+        _ => {}
+    }
+}
+
+fn try_build_google_payload(
+    model: &Model,
+    context: &Context,
+    options: &GoogleOptions,
+) -> Result<Value> {
+    let transformed = transform_messages(&context.messages, model, |id, _model, _source| {
+        id.to_string()
+    });
+    let mut payload = json!({
+        "contents": convert_messages(&transformed, model),
+    });
+    let object = payload.as_object_mut().expect("payload object");
+    if let Some(system_prompt) = &context.system_prompt
+        && !system_prompt.is_empty()
+    {
+        object.insert(
+            "systemInstruction".to_string(),
+            json!({ "parts": [{ "text": system_prompt }] }),
+        );
+    }
+
+    let mut generation_config = Map::new();
+    if let Some(max_tokens) = options.base.max_tokens {
+        generation_config.insert("maxOutputTokens".to_string(), json!(max_tokens));
+    }
+    if let Some(temperature) = options.base.temperature {
+        generation_config.insert("temperature".to_string(), json!(temperature));
+    }
+    let sampling = sampling(model, &options.base, SamplingSupport::ALL);
+    if let Some(top_p) = sampling.top_p {
+        generation_config.insert("topP".to_string(), json!(top_p));
+    }
+    if let Some(top_k) = sampling.top_k {
+        generation_config.insert("topK".to_string(), json!(top_k));
+    }
+    if !sampling.stop.is_empty() {
+        generation_config.insert("stopSequences".to_string(), json!(sampling.stop));
+    }
+    if let Some(seed) = sampling.seed {
+        generation_config.insert("seed".to_string(), json!(seed));
+    }
+    if let Some(frequency_penalty) = sampling.frequency_penalty {
+        generation_config.insert("frequencyPenalty".to_string(), json!(frequency_penalty));
+    }
+    if let Some(presence_penalty) = sampling.presence_penalty {
+        generation_config.insert("presencePenalty".to_string(), json!(presence_penalty));
+    }
+    if let Some(thinking_config) = thinking_config(model, options) {
+        generation_config.insert("thinkingConfig".to_string(), thinking_config);
+    }
+    if !generation_config.is_empty() {
+        object.insert(
+            "generationConfig".to_string(),
+            Value::Object(generation_config),
+        );
+    }
+
+    if !context.tools.is_empty() {
+        object.insert(
+            "tools".to_string(),
+            json!([{ "functionDeclarations": convert_tools(&context.tools) }]),
+        );
+    }
+    // Gemini has no switch for parallel function calls, so only the choice
+    // itself is translated.
+    if let Some(tool_choice) = &options.tool_choice {
+        object.insert("toolConfig".to_string(), convert_tool_choice(tool_choice)?);
+    } else if let Some(tool_choice) = &options.base.tool_choice {
+        object.insert(
+            "toolConfig".to_string(),
+            convert_typed_tool_choice(tool_choice),
+        );
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+fn build_google_payload(model: &Model, context: &Context, options: &GoogleOptions) -> Value {
+    try_build_google_payload(model, context, options).unwrap()
+}
+
+fn thinking_config(model: &Model, options: &GoogleOptions) -> Option<Value> {
+    if !model.reasoning {
+        return None;
+    }
+    match options.thinking_enabled {
+        Some(true) => {
+            let mut config = json!({ "includeThoughts": true });
+            if let Some(level) = options.thinking_level {
+                config["thinkingLevel"] = json!(level.as_str());
+            } else if let Some(budget) = options.thinking_budget_tokens {
+                config["thinkingBudget"] = json!(budget);
+            }
+            Some(config)
+        }
+        Some(false) if model.thinking_level_map.get("off") != Some(&None) => {
+            if is_gemini_3(&model.id) {
+                Some(json!({ "thinkingLevel": GoogleThinkingLevel::Minimal.as_str() }))
+            } else {
+                Some(json!({ "thinkingBudget": 0 }))
+            }
+        }
+        _ => None,
+    }
+}
+
+fn convert_messages(messages: &[Message], model: &Model) -> Vec<Value> {
+    let requires_signatures = is_gemini_3(&model.id);
+    let mut contents: Vec<Value> = Vec::new();
+    for message in messages {
+        match message {
+            Message::User(user) => {
+                let parts = match &user.content {
+                    UserMessageContent::Text(text) => {
+                        if text.trim().is_empty() {
+                            Vec::new()
+                        } else {
+                            vec![json!({ "text": text })]
+                        }
+                    }
+                    UserMessageContent::Parts(parts) => parts
+                        .iter()
+                        .filter_map(|part| match part {
+                            UserContent::Text(text) => (!text.text.trim().is_empty())
+                                .then(|| json!({ "text": &text.text })),
+                            UserContent::Image(image) => Some(json!({
+                                "inlineData": {
+                                    "mimeType": image.mime_type,
+                                    "data": image.data
+                                }
+                            })),
+                            UserContent::Document(document) => Some(document_part(document)),
+                            UserContent::Audio(audio) => Some(json!({
+                                "inlineData": {
+                                    "mimeType": audio.mime_type,
+                                    "data": audio.data
+                                }
+                            })),
+                        })
+                        .collect(),
+                };
+                push_parts(&mut contents, "user", parts);
+            }
+            Message::Assistant(assistant) => {
+                let mut parts = Vec::new();
+                for block in &assistant.content {
+                    match block {
+                        AssistantContent::Text(text) => {
+                            if text.text.trim().is_empty() && text.text_signature.is_none() {
+                                continue;
+                            }
+                            let mut part = json!({ "text": &text.text });
+                            if let Some(signature) = &text.text_signature {
+                                part["thoughtSignature"] = json!(signature);
+                            }
+                            parts.push(part);
+                        }
+                        AssistantContent::Audio(audio) => {
+                            if !audio.transcript.trim().is_empty() {
+                                parts.push(json!({ "text": &audio.transcript }));
+                            }
+                        }
+                        AssistantContent::Thinking(thinking) => {
+                            if thinking.redacted == Some(true) {
+                                continue;
+                            }
+                            let mut part = json!({ "text": &thinking.thinking, "thought": true });
+                            if let Some(signature) = &thinking.thinking_signature {
+                                part["thoughtSignature"] = json!(signature);
+                            } else if thinking.thinking.trim().is_empty() {
+                                continue;
+                            }
+                            parts.push(part);
+                        }
+                        AssistantContent::ToolCall(tool_call) => {
+                            let mut part = json!({
+                                "functionCall": {
+                                    "id": &tool_call.id,
+                                    "name": &tool_call.name,
+                                    "args": &tool_call.arguments
+                                }
+                            });
+                            match &tool_call.thought_signature {
+                                Some(signature) => part["thoughtSignature"] = json!(signature),
+                                None if requires_signatures => {
+                                    part["thoughtSignature"] = json!(SKIP_THOUGHT_SIGNATURE);
+                                }
+                                None => {}
+                            }
+                            parts.push(part);
+                        }
+                    }
+                }
+                push_parts(&mut contents, "model", parts);
+            }
+            Message::ToolResult(tool_result) => {
+                let text = tool_result
+                    .content
+                    .iter()
+                    .filter_map(|content| match content {
+                        ToolResultContent::Text(text) => Some(text.text.as_str()),
+                        ToolResultContent::Image(_) | ToolResultContent::Document(_) => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let response = if tool_result.is_error {
+                    json!({ "error": text })
+                } else {
+                    json!({ "output": text })
+                };
+                let mut parts = vec![json!({
+                    "functionResponse": {
+                        "id": &tool_result.tool_call_id,
+                        "name": &tool_result.tool_name,
+                        "response": response
+                    }
+                })];
+                parts.extend(
+                    tool_result
+                        .content
+                        .iter()
+                        .filter_map(|content| match content {
+                            ToolResultContent::Image(image) => Some(json!({
+                                "inlineData": {
+                                    "mimeType": image.mime_type,
+                                    "data": image.data
+                                }
+                            })),
+                            ToolResultContent::Document(document) => Some(document_part(document)),
+                            ToolResultContent::Text(_) => None,
+                        }),
+                );
+                push_parts(&mut contents, "user", parts);
+            }
+            Message::Custom(_) => {}
+        }
+    }
+    contents
+}
+
+fn document_part(document: &DocumentContent) -> Value {
+    match &document.source {
+        DocumentSource::Base64 { data } => json!({
+            "inlineData": { "mimeType": document.mime_type, "data": data }
+        }),
+        DocumentSource::Url { url } => json!({
+            "fileData": { "mimeType": document.mime_type, "fileUri": url }
+        }),
+    }
+}
+
+/// Gemini expects alternating turns, so consecutive parts for the same role
+/// (for example parallel tool results) are merged into one content entry.
+fn push_parts(contents: &mut Vec<Value>, role: &str, parts: Vec<Value>) {
+    if parts.is_empty() {
+        return;
+    }
+    if let Some(last) = contents.last_mut()
+        && last.get("role").and_then(Value::as_str) == Some(role)
+        && let Some(existing) = last.get_mut("parts").and_then(Value::as_array_mut)
+    {
+        existing.extend(parts);
+        return;
+    }
+    contents.push(json!({ "role": role, "parts": parts }));
+}
+
+fn convert_tools(tools: &[Tool]) -> Vec<Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "parametersJsonSchema": tool.parameters
+            })
+        })
+        .collect()
+}
+
+fn convert_tool_choice(tool_choice: &Value) -> Result<Value> {
+    if let Some(choice) = tool_choice.as_str() {
+        let mode = match choice {
+            "auto" => "AUTO",
+            "none" => "NONE",
+            "any" | "required" => "ANY",
+            other => {
+                return Err(Error::Validation(format!(
+                    "unsupported Google tool choice: {other}"
+                )));
+            }
+        };
+        return Ok(json!({ "functionCallingConfig": { "mode": mode } }));
+    }
+    Ok(tool_choice.clone())
+}
+
+fn convert_typed_tool_choice(tool_choice: &ToolChoice) -> Value {
+    let config = match tool_choice {
+        ToolChoice::Auto => json!({ "mode": "AUTO" }),
+        ToolChoice::None => json!({ "mode": "NONE" }),
+        ToolChoice::Required => json!({ "mode": "ANY" }),
+        ToolChoice::Tool(name) => json!({ "mode": "ANY", "allowedFunctionNames": [name] }),
+    };
+    json!({ "functionCallingConfig": config })
+}
+
+fn update_google_usage(output: &mut AssistantMessage, usage: &Value, model: &Model) {
+    let count = |name: &str| {
+        usage
+            .get(name)
+            .and_then(Value::as_u64)
+            .map(|value| value as u32)
+    };
+    let prompt = count("promptTokenCount").unwrap_or(0);
+    let cached = count("cachedContentTokenCount").unwrap_or(0);
+    let candidates = count("candidatesTokenCount").unwrap_or(0);
+    let thoughts = count("thoughtsTokenCount");
+    output.usage.input = prompt.saturating_sub(cached);
+    output.usage.cache_read = cached;
+    output.usage.output = candidates + thoughts.unwrap_or(0);
+    output.usage.reasoning = thoughts;
+    output.usage.total_tokens = count("totalTokenCount")
+        .unwrap_or(output.usage.input + output.usage.cache_read + output.usage.output);
+    calculate_cost(model, &mut output.usage);
+}
+
+fn map_finish_reason(
+    reason: &str,
+    has_tool_calls: bool,
+) -> std::result::Result<StopReason, String> {
+    match reason {
+        "STOP" if has_tool_calls => Ok(StopReason::ToolUse),
+        "STOP" | "FINISH_REASON_UNSPECIFIED" => Ok(StopReason::Stop),
+        "MAX_TOKENS" => Ok(StopReason::Length),
+        "MALFORMED_FUNCTION_CALL" | "UNEXPECTED_TOOL_CALL" => Err(format!(
+            "Google returned an invalid function call ({reason})"
+        )),
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" | "IMAGE_SAFETY"
+        | "LANGUAGE" | "OTHER" => Err(format!("Google stopped generation: {reason}")),
+        _ => Err(format!("Unhandled finish reason: {reason}")),
+    }
+}
+
+fn headers(model: &Model, options: &GoogleOptions, api_key: &str) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(
+        HeaderName::from_static("x-goog-api-key"),
+        HeaderValue::from_str(api_key)
+            .map_err(|e| Error::InvalidHeaderValue("x-goog-api-key".to_string(), e))?,
+    );
+    for (name, value) in &model.headers {
+        let Ok(name) = HeaderName::from_bytes(name.as_bytes()) else {
+            continue;
+        };
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| Error::InvalidHeaderValue(name.to_string(), e))?;
+        headers.insert(name, value);
+    }
+    crate::utils::headers::apply_provider_headers(&mut headers, &options.base.headers)?;
+    Ok(headers)
+}
+
+fn model_name(id: &str) -> &str {
+    id.strip_prefix("models/").unwrap_or(id)
+}
+
+fn is_gemini_3(id: &str) -> bool {
+    model_name(id).starts_with("gemini-3")
+}
+
+fn is_thinking_model(id: &str) -> bool {
+    let id = model_name(id);
+    id.starts_with("gemini-2.5") || id.starts_with("gemini-3")
+}
+
+fn map_thinking_level(model: &Model, level: ModelThinkingLevel) -> GoogleThinkingLevel {
+    if let Some(Some(mapped)) = model.thinking_level_map.get(level.as_str())
+        && let Some(mapped) = GoogleThinkingLevel::parse(mapped)
+    {
+        return mapped;
+    }
+    match level {
+        ModelThinkingLevel::Off | ModelThinkingLevel::Minimal => GoogleThinkingLevel::Minimal,
+        ModelThinkingLevel::Low => GoogleThinkingLevel::Low,
+        ModelThinkingLevel::Medium => GoogleThinkingLevel::Medium,
+        ModelThinkingLevel::High | ModelThinkingLevel::Xhigh | ModelThinkingLevel::Max => {
+            GoogleThinkingLevel::High
+        }
+    }
+}
+
+fn thinking_budget(
+    model: &Model,
+    level: ModelThinkingLevel,
+    budgets: Option<&crate::types::ThinkingBudgets>,
+) -> u32 {
+    let high = if model_name(&model.id).contains("pro") {
+        32_768
+    } else {
+        24_576
+    };
+    match level {
+        ModelThinkingLevel::Off | ModelThinkingLevel::Minimal => {
+            budgets.and_then(|b| b.minimal).unwrap_or(128)
+        }
+        ModelThinkingLevel::Low => budgets.and_then(|b| b.low).unwrap_or(2_048),
+        ModelThinkingLevel::Medium => budgets.and_then(|b| b.medium).unwrap_or(8_192),
+        ModelThinkingLevel::High | ModelThinkingLevel::Xhigh | ModelThinkingLevel::Max => {
+            budgets.and_then(|b| b.high).unwrap_or(high)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::types::{ImageContent, ToolResultMessage, Usage, UserMessage};
+
+    fn gemini_model(id: &str) -> Model {
+        builder()
+            .api_key(Some("test-key"))
+            .base_url("http://127.0.0.1:9")
+            .build()
+            .expect("provider")
+            .model(id)
+            .build()
+            .expect("model")
+    }
+
+    fn assistant(model: &Model, content: Vec<AssistantContent>) -> Message {
+        Message::Assistant(AssistantMessage {
+            content,
+            stop_reason: StopReason::ToolUse,
+            usage: Usage::default(),
+            ..AssistantMessage::empty_for(model)
+        })
+    }
+
+    fn lookup_tool() -> Tool {
+        Tool::builder("lookup")
+            .description("Lookup a value.")
+            .parameters(json!({
+                "type": "object",
+                "properties": { "key": { "type": "string" } },
+                "required": ["key"]
+            }))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn provider_models_target_the_generative_language_api() {
+        let model = gemini_model("gemini-2.5-flash");
+
+        assert_eq!(model.api_id(), "google-generative-ai");
+        assert_eq!(model.provider_id(), "google");
+        assert!(model.reasoning);
+        assert!(!gemini_model("gemini-2.0-flash").reasoning);
+    }
+
+    #[test]
+    fn payload_maps_sampling_options_to_generation_config() {
+        let model = gemini_model("gemini-2.5-flash");
+        let payload = build_google_payload(
+            &model,
+            &Context::default(),
+            &GoogleOptions {
+                base: StreamOptions {
+                    top_p: Some(0.9),
+                    top_k: Some(40),
+                    stop: vec!["END".to_string()],
+                    seed: Some(7),
+                    frequency_penalty: Some(0.5),
+                    presence_penalty: Some(0.25),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            payload["generationConfig"],
+            json!({
+                "topP": 0.9,
+                "topK": 40,
+                "stopSequences": ["END"],
+                "seed": 7,
+                "frequencyPenalty": 0.5,
+                "presencePenalty": 0.25
+            })
+        );
+    }
+
+    #[test]
+    fn documents_map_to_inline_and_file_data() {
+        let model = gemini_model("gemini-2.5-flash");
+        let payload = build_google_payload(
+            &model,
+            &Context {
+                messages: vec![Message::User(UserMessage {
+                    content: UserMessageContent::Parts(vec![
+                        UserContent::Document(DocumentContent::pdf("JVBERi0=")),
+                        UserContent::Document(DocumentContent::url(
+                            "https://generativelanguage.googleapis.com/v1beta/files/abc",
+                            "application/pdf",
+                        )),
+                    ]),
+                    timestamp: 1,
+                })],
+                ..Default::default()
+            },
+            &GoogleOptions::default(),
+        );
+
+        assert_eq!(
+            payload["contents"][0]["parts"],
+            json!([
+                { "inlineData": { "mimeType": "application/pdf", "data": "JVBERi0=" } },
+                {
+                    "fileData": {
+                        "mimeType": "application/pdf",
+                        "fileUri": "https://generativelanguage.googleapis.com/v1beta/files/abc"
+                    }
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn payload_maps_system_prompt_tools_and_generation_config() {
+        let model = gemini_model("gemini-2.5-flash");
+        let context = Context::builder()
+            .system_prompt("Be brief.")
+            .message(Message::User(UserMessage {
+                content: UserMessageContent::Parts(vec![
+                    UserContent::text("What is this?"),
+                    UserContent::Image(ImageContent {
+                        data: "aW1n".to_string(),
+                        mime_type: "image/png".to_string(),
+                    }),
+                ]),
+                timestamp: 1,
+            }))
+            .tool(lookup_tool())
+            .build();
+
+        let payload = build_google_payload(
+            &model,
+            &context,
+            &GoogleOptions {
+                base: StreamOptions {
+                    max_tokens: Some(1024),
+                    temperature: Some(0.2),
+                    ..Default::default()
+                },
+                thinking_enabled: Some(true),
+                thinking_budget_tokens: Some(2048),
+                tool_choice: Some(json!("required")),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            payload["systemInstruction"],
+            json!({ "parts": [{ "text": "Be brief." }] })
+        );
+        assert_eq!(
+            payload["contents"],
+            json!([{
+                "role": "user",
+                "parts": [
+                    { "text": "What is this?" },
+                    { "inlineData": { "mimeType": "image/png", "data": "aW1n" } }
+                ]
+            }])
+        );
+        assert_eq!(
+            payload["generationConfig"],
+            json!({
+                "maxOutputTokens": 1024,
+                "temperature": 0.2,
+                "thinkingConfig": { "includeThoughts": true, "thinkingBudget": 2048 }
+            })
+        );
+        assert_eq!(
+            payload["tools"][0]["functionDeclarations"][0],
+            json!({
+                "name": "lookup",
+                "description": "Lookup a value.",
+                "parametersJsonSchema": lookup_tool().parameters
+            })
+        );
+        assert_eq!(
+            payload["toolConfig"],
+            json!({ "functionCallingConfig": { "mode": "ANY" } })
+        );
+    }
+
+    #[test]
+    fn typed_tool_choice_maps_to_function_calling_config() {
+        let model = gemini_model("gemini-2.5-flash");
+        let context = Context::builder()
+            .message(Message::user_text("Use lookup"))
+            .tool(lookup_tool())
+            .build();
+        let config = |tool_choice: ToolChoice| {
+            build_google_payload(
+                &model,
+                &context,
+                &GoogleOptions {
+                    base: StreamOptions {
+                        tool_choice: Some(tool_choice),
+                        parallel_tool_calls: Some(false),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )["toolConfig"]["functionCallingConfig"]
+                .clone()
+        };
+
+        assert_eq!(config(ToolChoice::Auto), json!({ "mode": "AUTO" }));
+        assert_eq!(config(ToolChoice::None), json!({ "mode": "NONE" }));
+        assert_eq!(config(ToolChoice::Required), json!({ "mode": "ANY" }));
+        assert_eq!(
+            config(ToolChoice::tool("lookup")),
+            json!({ "mode": "ANY", "allowedFunctionNames": ["lookup"] })
+        );
+
+        let payload = build_google_payload(
+            &model,
+            &context,
+            &GoogleOptions {
+                base: StreamOptions {
+                    tool_choice: Some(ToolChoice::None),
+                    ..Default::default()
+                },
+                tool_choice: Some(json!("required")),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            payload["toolConfig"],
+            json!({ "functionCallingConfig": { "mode": "ANY" } })
+        );
+    }
+
+    #[test]
+    fn thinking_config_uses_levels_for_gemini_3_and_budget_zero_when_off() {
+        let gemini_3 = gemini_model("gemini-3-pro-preview");
+        let flash = gemini_model("gemini-2.5-flash");
+        let mut pro = gemini_model("gemini-2.5-pro");
+        pro.thinking_level_map.insert("off".to_string(), None);
+
+        let enabled = GoogleOptions {
+            thinking_enabled: Some(true),
+            thinking_level: Some(GoogleThinkingLevel::High),
+            ..Default::default()
+        };
+        let disabled = GoogleOptions {
+            thinking_enabled: Some(false),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            build_google_payload(&gemini_3, &Context::default(), &enabled)["generationConfig"]["thinkingConfig"],
+            json!({ "includeThoughts": true, "thinkingLevel": "high" })
+        );
+        assert_eq!(
+            build_google_payload(&flash, &Context::default(), &disabled)["generationConfig"]["thinkingConfig"],
+            json!({ "thinkingBudget": 0 })
+        );
+        assert!(
+            build_google_payload(&pro, &Context::default(), &disabled)
+                .get("generationConfig")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn stream_simple_maps_reasoning_to_level_or_budget() {
+        let mut gemini_3 = gemini_model("gemini-3-pro-preview");
+        gemini_3
+            .thinking_level_map
+            .insert("medium".to_string(), Some("high".to_string()));
+
+        assert_eq!(
+            map_thinking_level(&gemini_3, ModelThinkingLevel::Medium),
+            GoogleThinkingLevel::High
+        );
+        assert_eq!(
+            map_thinking_level(&gemini_3, ModelThinkingLevel::Xhigh),
+            GoogleThinkingLevel::High
+        );
+        assert_eq!(
+            thinking_budget(
+                &gemini_model("gemini-2.5-pro"),
+                ModelThinkingLevel::High,
+                None
+            ),
+            32_768
+        );
+        assert_eq!(
+            thinking_budget(
+                &gemini_model("gemini-2.5-flash"),
+                ModelThinkingLevel::Low,
+                Some(&crate::types::ThinkingBudgets {
+                    low: Some(512),
+                    ..Default::default()
+                })
+            ),
+            512
+        );
+    }
+
+    #[test]
+    fn thought_signatures_round_trip_into_model_parts() {
+        let model = gemini_model("gemini-3-pro-preview");
+        let context = Context {
+            messages: vec![
+                Message::user_text("look it up"),
+                assistant(
+                    &model,
+                    vec![
+                        AssistantContent::Thinking(ThinkingContent {
+                            thinking: "Need the lookup tool.".to_string(),
+                            thinking_signature: Some("sig-thought".to_string()),
+                            redacted: None,
+                        }),
+                        AssistantContent::ToolCall(ToolCall {
+                            id: "call_1".to_string(),
+                            name: "lookup".to_string(),
+                            arguments: json!({ "key": "a" }),
+                            thought_signature: Some("sig-call".to_string()),
+                        }),
+                    ],
+                ),
+                Message::ToolResult(ToolResultMessage {
+                    tool_call_id: "call_1".to_string(),
+                    tool_name: "lookup".to_string(),
+                    content: vec![ToolResultContent::text("42")],
+                    details: None,
+                    usage: None,
+                    added_tool_names: Vec::new(),
+                    is_error: false,
+                    timestamp: 2,
+                }),
+            ],
+            ..Default::default()
+        };
+
+        let payload = build_google_payload(&model, &context, &GoogleOptions::default());
+
+        assert_eq!(
+            payload["contents"][1],
+            json!({
+                "role": "model",
+                "parts": [
+                    {
+                        "text": "Need the lookup tool.",
+                        "thought": true,
+                        "thoughtSignature": "sig-thought"
+                    },
+                    {
+                        "functionCall": { "id": "call_1", "name": "lookup", "args": { "key": "a" } },
+                        "thoughtSignature": "sig-call"
+                    }
+                ]
+            })
+        );
+        assert_eq!(
+            payload["contents"][2],
+            json!({
+                "role": "user",
+                "parts": [{
+                    "functionResponse": {
+                        "id": "call_1",
+                        "name": "lookup",
+                        "response": { "output": "42" }
+                    }
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn foreign_tool_calls_use_the_skip_signature_for_gemini_3() {
+        let model = gemini_model("gemini-3-pro-preview");
+        let mut foreign = AssistantMessage::empty_for(&model);
+        foreign.provider = "anthropic".to_string();
+        foreign.api = "anthropic-messages".to_string();
+        foreign.content = vec![AssistantContent::ToolCall(ToolCall {
+            id: "toolu_1".to_string(),
+            name: "lookup".to_string(),
+            arguments: json!({}),
+            thought_signature: Some("not-a-gemini-signature".to_string()),
+        })];
+        let context = Context {
+            messages: vec![Message::user_text("hi"), Message::Assistant(foreign)],
+            ..Default::default()
+        };
+
+        let payload = build_google_payload(&model, &context, &GoogleOptions::default());
+
+        assert_eq!(
+            payload["contents"][1]["parts"][0]["thoughtSignature"],
+            json!(SKIP_THOUGHT_SIGNATURE)
+        );
+    }
+
+    #[test]
+    fn parallel_tool_results_share_one_user_turn() {
+        let model = gemini_model("gemini-2.5-flash");
+        let result = |id: &str, is_error: bool| {
+            Message::ToolResult(ToolResultMessage {
+                tool_call_id: id.to_string(),
+                tool_name: "lookup".to_string(),
+                content: vec![ToolResultContent::text("boom")],
+                details: None,
+                usage: None,
+                added_tool_names: Vec::new(),
+                is_error,
+                timestamp: 1,
+            })
+        };
+        let context = Context {
+            messages: vec![result("a", false), result("b", true)],
+            ..Default::default()
+        };
+
+        let payload = build_google_payload(&model, &context, &GoogleOptions::default());
+
+        assert_eq!(payload["contents"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            payload["contents"][0]["parts"][1]["functionResponse"]["response"],
+            json!({ "error": "boom" })
+        );
+    }
+
+    #[tokio::test]
+    async fn streams_thoughts_text_and_function_calls_with_signatures() {
+        let body = sse_body(&[
+            json!({
+                "responseId": "resp_1",
+                "modelVersion": "gemini-3-pro-preview-001",
+                "candidates": [{
+                    "content": { "role": "model", "parts": [
+                        { "text": "Thinking", "thought": true },
+                        { "text": " more", "thought": true, "thoughtSignature": "sig-1" }
+                    ]}
+                }]
+            }),
+            json!({
+                "candidates": [{
+                    "content": { "role": "model", "parts": [
+                        { "text": "Checking." },
+                        {
+                            "functionCall": { "name": "lookup", "args": { "key": "a" } },
+                            "thoughtSignature": "sig-2"
+                        }
+                    ]},
+                    "finishReason": "STOP"
+                }],
+                "usageMetadata": {
+                    "promptTokenCount": 100,
+                    "cachedContentTokenCount": 40,
+                    "candidatesTokenCount": 10,
+                    "thoughtsTokenCount": 5,
+                    "totalTokenCount": 115
+                }
+            }),
+        ]);
+        let (base_url, request) = spawn_sse_server(body).await;
+        let mut model = gemini_model("gemini-3-pro-preview");
+        model.base_url = base_url;
+
+        let mut stream = stream_google(
+            model,
+            Context {
+                messages: vec![Message::user_text("hi")],
+                tools: vec![lookup_tool()],
+                ..Default::default()
+            },
+            GoogleOptions {
+                base: StreamOptions {
+                    api_key: Some("test-key".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let mut event_types = Vec::new();
+        let mut message = None;
+        while let Some(event) = stream.next().await {
+            let event = event.unwrap();
+            event_types.push(serde_json::to_value(&event).unwrap()["type"].clone());
+            if let AssistantMessageEvent::Done { message: done, .. } = event {
+                message = Some(done);
+            }
+        }
+        let message = message.expect("done");
+        let request = request.await.unwrap();
+
+        assert!(request.starts_with(
+            "POST /models/gemini-3-pro-preview:streamGenerateContent?alt=sse HTTP/1.1"
+        ));
+        assert!(request.contains("x-goog-api-key: test-key"));
+        assert_eq!(
+            event_types,
+            vec![
+                json!("start"),
+                json!("thinking_start"),
+                json!("thinking_delta"),
+                json!("thinking_delta"),
+                json!("thinking_end"),
+                json!("text_start"),
+                json!("text_delta"),
+                json!("text_end"),
+                json!("toolcall_start"),
+                json!("toolcall_delta"),
+                json!("toolcall_end"),
+                json!("done"),
+            ]
+        );
+        assert_eq!(message.stop_reason, StopReason::ToolUse);
+        assert_eq!(message.response_id.as_deref(), Some("resp_1"));
+        assert_eq!(
+            message.response_model.as_deref(),
+            Some("gemini-3-pro-preview-001")
+        );
+        assert_eq!(
+            message.content[0],
+            AssistantContent::Thinking(ThinkingContent {
+                thinking: "Thinking more".to_string(),
+                thinking_signature: Some("sig-1".to_string()),
+                redacted: None,
+            })
+        );
+        let AssistantContent::ToolCall(tool_call) = &message.content[2] else {
+            panic!("expected tool call");
+        };
+        assert_eq!(tool_call.name, "lookup");
+        assert_eq!(tool_call.arguments, json!({ "key": "a" }));
+        assert_eq!(tool_call.thought_signature.as_deref(), Some("sig-2"));
+        assert_eq!(message.usage.input, 60);
+        assert_eq!(message.usage.cache_read, 40);
+        assert_eq!(message.usage.output, 15);
+        assert_eq!(message.usage.reasoning, Some(5));
+        assert_eq!(message.usage.total_tokens, 115);
+    }
+
+    #[tokio::test]
+    async fn safety_finish_reason_reports_an_error() {
+        let body = sse_body(&[json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [{ "text": "Partial" }] },
+                "finishReason": "SAFETY"
+            }]
+        })]);
+        let (base_url, _request) = spawn_sse_server(body).await;
+        let mut model = gemini_model("gemini-2.5-flash");
+        model.base_url = base_url;
+
+        let message = crate::stream::final_message_from_stream(stream_google(
+            model,
+            Context {
+                messages: vec![Message::user_text("hi")],
+                ..Default::default()
+            },
+            GoogleOptions {
+                base: StreamOptions {
+                    api_key: Some("test-key".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(message.stop_reason, StopReason::Error);
+        assert_eq!(
+            message.error_message.as_deref(),
+            Some("Google stopped generation: SAFETY")
+        );
+    }
+
+    #[test]
+    fn stream_simple_requires_an_api_key() {
+        let model = builder()
+            .build()
+            .expect("provider")
+            .model("gemini-2.5-flash")
+            .build()
+            .expect("model");
+
+        let error =
+            match stream_simple_google(model, Context::default(), SimpleStreamOptions::default()) {
+                Ok(_) => panic!("missing API key should fail"),
+                Err(error) => error,
+            };
+
+        assert!(matches!(error, Error::MissingApiKey(provider) if provider == "google"));
+    }
+
+    fn sse_body(chunks: &[Value]) -> String {
+        chunks
+            .iter()
+            .map(|chunk| format!("data: {chunk}\n\n"))
+            .collect()
+    }
+
+    async fn spawn_sse_server(body: String) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 16 * 1024];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let _ = request_tx.send(String::from_utf8_lossy(&buffer[..read]).into_owned());
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        (format!("http://{addr}"), request_rx)
+    }
+}
@@ -0,0 +1,121 @@
+//! Token log-probabilities as OpenAI Chat Completions and Responses report
+//! them.
+
+use serde_json::{Map, Value, json};
+
+use crate::types::{StreamOptions, TokenLogprob, TopLogprob};
+
+/// Whether the caller asked for log-probabilities at all.
+pub(crate) fn requested(options: &StreamOptions) -> bool {
+    options.logprobs || options.top_logprobs.is_some()
+}
+
+/// Writes the Chat Completions request fields.
+pub(crate) fn insert_chat_fields(object: &mut Map<String, Value>, options: &StreamOptions) {
+    if !requested(options) {
+        return;
+    }
+    object.insert("logprobs".to_string(), json!(true));
+    if let Some(top_logprobs) = options.top_logprobs {
+        object.insert("top_logprobs".to_string(), json!(top_logprobs));
+    }
+}
+
+/// Parses an array of `{ token, logprob, bytes, top_logprobs }` entries.
+/// Malformed entries are skipped.
+pub(crate) fn parse_token_logprobs(value: Option<&Value>) -> Vec<TokenLogprob> {
+    let Some(entries) = value.and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let (token, logprob, bytes) = parse_entry(entry)?;
+            let top_logprobs = entry
+                .get("top_logprobs")
+                .and_then(Value::as_array)
+                .map(|alternatives| {
+                    alternatives
+                        .iter()
+                        .filter_map(parse_entry)
+                        .map(|(token, logprob, bytes)| TopLogprob {
+                            token,
+                            logprob,
+                            bytes,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(TokenLogprob {
+                token,
+                logprob,
+                bytes,
+                top_logprobs,
+            })
+        })
+        .collect()
+}
+
+fn parse_entry(entry: &Value) -> Option<(String, f64, Option<Vec<u8>>)> {
+    let token = entry.get("token")?.as_str()?.to_string();
+    let logprob = entry.get("logprob")?.as_f64()?;
+    let bytes = entry.get("bytes").and_then(Value::as_array).map(|bytes| {
+        bytes
+            .iter()
+            .filter_map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect()
+    });
+    Some((token, logprob, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tokens_with_alternatives_and_skips_malformed_entries() {
+        let value = json!([
+            {
+                "token": "Hi",
+                "logprob": -0.1,
+                "bytes": [72, 105],
+                "top_logprobs": [
+                    { "token": "Hi", "logprob": -0.1, "bytes": [72, 105] },
+                    { "token": "Hello", "logprob": -2.5 }
+                ]
+            },
+            { "token": "!" },
+            { "token": "!", "logprob": -0.3, "bytes": null }
+        ]);
+
+        assert_eq!(
+            parse_token_logprobs(Some(&value)),
+            vec![
+                TokenLogprob {
+                    token: "Hi".to_string(),
+                    logprob: -0.1,
+                    bytes: Some(vec![72, 105]),
+                    top_logprobs: vec![
+                        TopLogprob {
+                            token: "Hi".to_string(),
+                            logprob: -0.1,
+                            bytes: Some(vec![72, 105]),
+                        },
+                        TopLogprob {
+                            token: "Hello".to_string(),
+                            logprob: -2.5,
+                            bytes: None,
+                        },
+                    ],
+                },
+                TokenLogprob {
+                    token: "!".to_string(),
+                    logprob: -0.3,
+                    bytes: None,
+                    top_logprobs: Vec::new(),
+                },
+            ]
+        );
+        assert!(parse_token_logprobs(None).is_empty());
+    }
+}
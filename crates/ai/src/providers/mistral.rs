@@ -0,0 +1,953 @@
+use async_trait::async_trait;
+use futures::{StreamExt, pin_mut};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::env_api_keys::{KnownProvider, get_env_api_key};
+use crate::event_stream::{AssistantEventStream, AssistantMessageEventStreamSender};
+use crate::models::calculate_cost;
+use crate::provider::{
+    EmbeddingModelApi, FimModelApi, LanguageModelApi, ModelBuilder, Provider, ProviderCapabilities,
+};
+use crate::providers::sampling::{SamplingSupport, sampling};
+use crate::providers::{openai_completions, simple_options};
+use crate::types::{
+    AssistantContent, AssistantMessage, AssistantMessageEvent, Context, EmbeddingBatch,
+    EmbeddingOptions, EmbeddingUsage, EmbeddingVector, FimContext, KnownApi, MaxTokensField, Model,
+    ModelCompat, ModelInput, OpenAICompletionsCompat, ProviderHeaders, ProviderResponse, SeedField,
+    SimpleStreamOptions, StopReason, StreamOptions, TextContent,
+};
+use crate::utils::circuit_breaker::model_circuit;
+use crate::utils::hash::short_hash;
+use crate::utils::headers::headers_to_record;
+use crate::utils::http::{request_timeout, send_request_with_retries, send_with_retries};
+use crate::utils::json::parse_json_with_repair;
+use crate::utils::sse;
+use crate::{Error, Result};
+
+const DEFAULT_PROVIDER_ID: KnownProvider = KnownProvider::Mistral;
+const DEFAULT_BASE_URL: &str = "https://api.mistral.ai/v1";
+/// Dedicated Codestral endpoint, which uses its own API keys.
+const CODESTRAL_BASE_URL: &str = "https://codestral.mistral.ai/v1";
+const TOOL_CALL_ID_LEN: usize = 9;
+
+/// Mistral La Plateforme handle.
+///
+/// Chat models use the OpenAI-compatible Chat Completions wire format.
+/// Embedding models use `/embeddings`, and FIM models (Codestral) use
+/// `/fim/completions`.
+#[derive(Clone)]
+pub struct Mistral {
+    provider_id: String,
+    api_key: Option<String>,
+    base_url: String,
+    http_client: Option<reqwest::Client>,
+}
+
+impl Mistral {
+    pub fn builder() -> MistralBuilder {
+        MistralBuilder::default()
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let api_key = get_env_api_key(DEFAULT_PROVIDER_ID)
+            .ok_or_else(|| Error::MissingApiKey(DEFAULT_PROVIDER_ID.into()))?;
+        Self::builder().api_key(Some(api_key.as_str())).build()
+    }
+
+    pub fn model(&self, id: &str) -> ModelBuilder {
+        <Self as Provider>::model(self, id)
+    }
+
+    pub fn embedding_model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(MistralEmbeddingModelApi {
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new_embedding(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Text])
+    }
+
+    pub fn fim_model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(MistralFimModelApi {
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new_fim(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Text])
+            .context_window(256_000)
+            .max_tokens(8_192)
+    }
+}
+
+impl Provider for Mistral {
+    fn id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            language_models: true,
+            image_models: false,
+            embedding_models: true,
+            fim_models: true,
+            rerank_models: false,
+            transcription_models: false,
+            speech_models: false,
+        }
+    }
+
+    fn model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(MistralLanguageModelApi {
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Text, ModelInput::Image])
+            .context_window(128_000)
+            .max_tokens(16_384)
+            .compat(ModelCompat {
+                openai_completions: OpenAICompletionsCompat {
+                    supports_store: Some(false),
+                    supports_developer_role: Some(false),
+                    supports_reasoning_effort: Some(false),
+                    // Mistral reports usage on the final chunk without
+                    // `stream_options` and rejects unknown request fields.
+                    supports_usage_in_streaming: Some(false),
+                    max_tokens_field: Some(MaxTokensField::MaxTokens),
+                    seed_field: Some(SeedField::RandomSeed),
+                    supports_strict_mode: Some(false),
+                    supports_long_cache_retention: Some(false),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+    }
+}
+
+#[derive(Default)]
+pub struct MistralBuilder {
+    provider_id: Option<String>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl MistralBuilder {
+    pub fn provider_id(mut self, provider_id: impl Into<String>) -> Self {
+        self.provider_id = Some(provider_id.into());
+        self
+    }
+
+    pub fn api_key(mut self, api_key: Option<&str>) -> Self {
+        self.api_key = api_key
+            .map(str::trim)
+            .filter(|api_key| !api_key.is_empty())
+            .map(str::to_string);
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Targets `https://codestral.mistral.ai/v1`, which requires a Codestral
+    /// API key.
+    pub fn codestral(self) -> Self {
+        self.base_url(CODESTRAL_BASE_URL)
+    }
+
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn build(self) -> Result<Mistral> {
+        Ok(Mistral {
+            provider_id: self
+                .provider_id
+                .unwrap_or_else(|| DEFAULT_PROVIDER_ID.into()),
+            api_key: self.api_key,
+            base_url: self
+                .base_url
+                .as_deref()
+                .unwrap_or(DEFAULT_BASE_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            http_client: self.http_client,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct MistralLanguageModelApi {
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl MistralLanguageModelApi {
+    fn with_api_key(&self, mut options: StreamOptions) -> StreamOptions {
+        with_runtime_options(&self.api_key, &self.http_client, &mut options);
+        options
+    }
+
+    fn with_api_key_simple(&self, mut options: SimpleStreamOptions) -> SimpleStreamOptions {
+        options.stream = self.with_api_key(options.stream);
+        options
+    }
+}
+
+impl LanguageModelApi for MistralLanguageModelApi {
+    fn id(&self) -> &str {
+        KnownApi::OpenaiCompletions.as_str()
+    }
+
+    fn stream(
+        &self,
+        model: Model,
+        context: Context,
+        options: StreamOptions,
+    ) -> Result<AssistantEventStream> {
+        Ok(openai_completions::stream_openai_completions(
+            model,
+            context,
+            simple_options::openai_completions_options_from_stream_options(
+                self.with_api_key(options),
+            ),
+        ))
+    }
+
+    fn stream_simple(
+        &self,
+        model: Model,
+        context: Context,
+        options: SimpleStreamOptions,
+    ) -> Result<AssistantEventStream> {
+        openai_completions::stream_simple_openai_completions(
+            model,
+            context,
+            self.with_api_key_simple(options),
+        )
+    }
+}
+
+#[derive(Clone)]
+struct MistralFimModelApi {
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl FimModelApi for MistralFimModelApi {
+    fn id(&self) -> &str {
+        KnownApi::MistralFim.as_str()
+    }
+
+    fn stream_fim(
+        &self,
+        model: Model,
+        context: FimContext,
+        mut options: StreamOptions,
+    ) -> Result<AssistantEventStream> {
+        with_runtime_options(&self.api_key, &self.http_client, &mut options);
+        Ok(stream_mistral_fim(model, context, options))
+    }
+}
+
+#[derive(Clone)]
+struct MistralEmbeddingModelApi {
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+#[async_trait]
+impl EmbeddingModelApi for MistralEmbeddingModelApi {
+    fn id(&self) -> &str {
+        KnownApi::MistralEmbeddings.as_str()
+    }
+
+    async fn embed_many(
+        &self,
+        model: Model,
+        inputs: Vec<String>,
+        mut options: EmbeddingOptions,
+    ) -> Result<EmbeddingBatch> {
+        if options
+            .base
+            .api_key
+            .as_deref()
+            .is_none_or(|api_key| api_key.trim().is_empty())
+        {
+            options.base.api_key = self.api_key.clone();
+        }
+        if options.base.http_client.is_none() {
+            options.base.http_client = self.http_client.clone();
+        }
+        embed_many_mistral(model, inputs, options).await
+    }
+}
+
+fn with_runtime_options(
+    api_key: &Option<String>,
+    http_client: &Option<reqwest::Client>,
+    options: &mut StreamOptions,
+) {
+    if options
+        .api_key
+        .as_deref()
+        .is_none_or(|api_key| api_key.trim().is_empty())
+    {
+        options.api_key = api_key.clone();
+    }
+    if options.http_client.is_none() {
+        options.http_client = http_client.clone();
+    }
+}
+
+pub fn builder() -> MistralBuilder {
+    Mistral::builder()
+}
+
+pub fn from_env() -> Result<Mistral> {
+    Mistral::from_env()
+}
+
+/// Mistral only accepts tool call ids of nine ASCII letters or digits.
+pub(crate) fn normalize_tool_call_id(id: &str) -> String {
+    let alphanumeric = id
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>();
+    if alphanumeric.len() == TOOL_CALL_ID_LEN {
+        return alphanumeric;
+    }
+    let mut normalized = short_hash(id);
+    while normalized.len() < TOOL_CALL_ID_LEN {
+        normalized.push('0');
+    }
+    normalized.truncate(TOOL_CALL_ID_LEN);
+    normalized
+}
+
+/// Streams a Codestral fill-in-the-middle completion as a single text block.
+///
+/// `provider_options` may carry `stop` (a string or list of strings) and
+/// `minTokens`.
+pub fn stream_mistral_fim(
+    model: Model,
+    context: FimContext,
+    options: StreamOptions,
+) -> AssistantEventStream {
+    crate::event_stream::stream_from_producer(
+        move |mut sender| async move {
+            let output = AssistantMessage::empty_for(&model);
+            run_fim_stream(model, context, options, output, &mut sender).await?;
+            Ok(())
+        },
+        |error: StreamFailure| {
+            let mut message = error.output;
+            message.stop_reason = if error.cancelled {
+                StopReason::Aborted
+            } else {
+                StopReason::Error
+            };
+            message.error_message = Some(error.message);
+            AssistantMessageEvent::Error {
+                reason: message.stop_reason,
+                error: message,
+            }
+        },
+    )
+}
+
+struct StreamFailure {
+    output: AssistantMessage,
+    message: String,
+    cancelled: bool,
+}
+
+impl StreamFailure {
+    fn new(output: AssistantMessage, error: impl std::fmt::Display) -> Self {
+        Self {
+            output,
+            message: error.to_string(),
+            cancelled: false,
+        }
+    }
+
+    fn cancelled(output: AssistantMessage) -> Self {
+        Self {
+            output,
+            message: "Request was aborted".to_string(),
+            cancelled: true,
+        }
+    }
+}
+
+async fn run_fim_stream(
+    model: Model,
+    context: FimContext,
+    options: StreamOptions,
+    mut output: AssistantMessage,
+    sender: &mut AssistantMessageEventStreamSender,
+) -> std::result::Result<(), StreamFailure> {
+    let is_cancelled = || {
+        options
+            .cancellation_token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    };
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+    let Some(api_key) = options.api_key.clone().filter(|key| !key.trim().is_empty()) else {
+        return Err(StreamFailure::new(
+            output,
+            Error::MissingApiKey(model.provider.clone()),
+        ));
+    };
+
+    let mut payload = build_fim_payload(&model, &context, &options);
+    if let Some(on_payload) = &options.on_payload {
+        match on_payload(payload.clone(), &model).await {
+            Ok(Some(next)) => payload = next,
+            Ok(None) => {}
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        }
+    }
+
+    let request_url = format!("{}/fim/completions", model.base_url.trim_end_matches('/'));
+    let request_headers = match headers(&api_key, &model, &options.headers) {
+        Ok(headers) => headers,
+        Err(error) => return Err(StreamFailure::new(output, error)),
+    };
+    let client = options.http_client.clone().unwrap_or_default();
+    let response = match send_with_retries(&options, &model_circuit(&model), || {
+        client
+            .post(request_url.as_str())
+            .headers(request_headers.clone())
+            .json(&payload)
+            .timeout(request_timeout(options.timeout_ms))
+    })
+    .await
+    {
+        Ok(response) => response,
+        Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
+    };
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        output.error_status = Some(status.as_u16());
+        return Err(StreamFailure::new(
+            output,
+            Error::ApiStatus { status, body },
+        ));
+    }
+    if let Some(on_response) = &options.on_response {
+        let provider_response = ProviderResponse {
+            status: response.status().as_u16(),
+            headers: headers_to_record(response.headers()),
+        };
+        if let Err(error) = on_response(provider_response, &model).await {
+            return Err(StreamFailure::new(output, error));
+        }
+    }
+
+    sender.push(AssistantMessageEvent::Start {
+        partial: output.clone(),
+    });
+
+    let mut finish_reason: Option<String> = None;
+    let events = sse::events(response, options.cancellation_token.clone());
+    pin_mut!(events);
+    while let Some(event) = events.next().await {
+        if is_cancelled() {
+            return Err(StreamFailure::cancelled(output));
+        }
+        let event = match event {
+            Ok(event) => event,
+            Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        };
+        let data = event.data.trim();
+        if data.is_empty() {
+            continue;
+        }
+        if data == "[DONE]" {
+            break;
+        }
+        let chunk: Value = match parse_json_with_repair(data) {
+            Ok(value) => value,
+            Err(error) => {
+                return Err(StreamFailure::new(
+                    output,
+                    format!("Could not parse Mistral FIM event: {error}; data={data}"),
+                ));
+            }
+        };
+        if let Some(id) = chunk.get("id").and_then(Value::as_str) {
+            output.response_id = Some(id.to_string());
+        }
+        if let Some(usage) = chunk.get("usage").filter(|usage| !usage.is_null()) {
+            update_usage(&mut output, usage, &model);
+        }
+        let Some(choice) = chunk
+            .get("choices")
+            .and_then(Value::as_array)
+            .and_then(|choices| choices.first())
+        else {
+            continue;
+        };
+        if let Some(delta) = choice.pointer("/delta/content").and_then(Value::as_str)
+            && !delta.is_empty()
+        {
+            push_text_delta(&mut output, sender, delta);
+        }
+        if let Some(reason) = choice.get("finish_reason").and_then(Value::as_str) {
+            finish_reason = Some(reason.to_string());
+        }
+    }
+    if let Some(AssistantContent::Text(block)) = output.content.first() {
+        sender.push(AssistantMessageEvent::TextEnd {
+            content_index: 0,
+            content: block.text.clone(),
+            partial: output.clone(),
+        });
+    }
+
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+    output.stop_reason = match finish_reason.as_deref() {
+        Some("stop") => StopReason::Stop,
+        Some("length" | "model_length") => StopReason::Length,
+        Some(other) => {
+            return Err(StreamFailure::new(
+                output,
+                format!("Provider finish_reason: {other}"),
+            ));
+        }
+        None => {
+            return Err(StreamFailure::new(
+                output,
+                "Mistral FIM stream ended without a finish reason",
+            ));
+        }
+    };
+    sender.push(AssistantMessageEvent::Done {
+        reason: output.stop_reason,
+        message: output,
+    });
+    Ok(())
+}
+
+fn push_text_delta(
+    output: &mut AssistantMessage,
+    sender: &mut AssistantMessageEventStreamSender,
+    delta: &str,
+) {
+    if output.content.is_empty() {
+        output.content.push(AssistantContent::Text(TextContent {
+            text: String::new(),
+            text_signature: None,
+        }));
+        sender.push(AssistantMessageEvent::TextStart {
+            content_index: 0,
+            partial: output.clone(),
+        });
+    }
+    if let Some(AssistantContent::Text(block)) = output.content.first_mut() {
+        block.text.push_str(delta);
+    }
+    sender.push(AssistantMessageEvent::TextDelta {
+        content_index: 0,
+        delta: delta.to_string(),
+        partial: output.clone(),
+    });
+}
+
+fn build_fim_payload(model: &Model, context: &FimContext, options: &StreamOptions) -> Value {
+    let mut payload = json!({
+        "model": model.id,
+        "prompt": context.prompt,
+        "stream": true,
+    });
+    if let Some(suffix) = &context.suffix {
+        payload["suffix"] = json!(suffix);
+    }
+    if let Some(max_tokens) = options.max_tokens {
+        payload["max_tokens"] = json!(max_tokens);
+    }
+    if let Some(temperature) = options.temperature {
+        payload["temperature"] = json!(temperature);
+    }
+    let sampling = sampling(
+        model,
+        options,
+        SamplingSupport {
+            top_k: false,
+            penalties: false,
+            ..SamplingSupport::ALL
+        },
+    );
+    if let Some(top_p) = sampling.top_p {
+        payload["top_p"] = json!(top_p);
+    }
+    if !sampling.stop.is_empty() {
+        payload["stop"] = json!(sampling.stop);
+    } else if let Some(stop) = options.provider_options.get("stop") {
+        payload["stop"] = stop.clone();
+    }
+    if let Some(seed) = sampling.seed {
+        payload["random_seed"] = json!(seed);
+    }
+    if let Some(min_tokens) = options.provider_options.get("minTokens") {
+        payload["min_tokens"] = min_tokens.clone();
+    }
+    payload
+}
+
+fn update_usage(output: &mut AssistantMessage, usage: &Value, model: &Model) {
+    let count = |name: &str| {
+        usage
+            .get(name)
+            .and_then(Value::as_u64)
+            .map(|value| value as u32)
+            .unwrap_or(0)
+    };
+    output.usage.input = count("prompt_tokens");
+    output.usage.output = count("completion_tokens");
+    output.usage.total_tokens = output.usage.input + output.usage.output;
+    calculate_cost(model, &mut output.usage);
+}
+
+#[derive(Deserialize)]
+struct MistralEmbeddingResponse {
+    data: Vec<MistralEmbeddingItem>,
+    model: Option<String>,
+    usage: Option<EmbeddingUsage>,
+}
+
+#[derive(Deserialize)]
+struct MistralEmbeddingItem {
+    embedding: EmbeddingVector,
+    index: usize,
+}
+
+async fn embed_many_mistral(
+    model: Model,
+    inputs: Vec<String>,
+    options: EmbeddingOptions,
+) -> Result<EmbeddingBatch> {
+    let Some(api_key) = options
+        .base
+        .api_key
+        .clone()
+        .filter(|api_key| !api_key.trim().is_empty())
+    else {
+        return Err(Error::MissingApiKey(model.provider.clone()));
+    };
+    let expected_count = inputs.len();
+    let mut payload = json!({ "model": model.id, "input": inputs });
+    if let Some(dimensions) = options.dimensions {
+        payload["output_dimension"] = json!(dimensions);
+    }
+    if let Some(encoding_format) = options.encoding_format {
+        payload["encoding_format"] = json!(encoding_format.as_str());
+    }
+    if let Some(on_payload) = &options.base.on_payload
+        && let Some(next_payload) = on_payload(payload.clone(), &model).await?
+    {
+        payload = next_payload;
+    }
+
+    let client = options.base.http_client.clone().unwrap_or_default();
+    let url = format!("{}/embeddings", model.base_url.trim_end_matches('/'));
+    let headers = headers(&api_key, &model, &options.base.headers)?;
+    let response = send_request_with_retries(&options.base, &model_circuit(&model), || {
+        client
+            .post(&url)
+            .headers(headers.clone())
+            .json(&payload)
+            .timeout(request_timeout(options.base.timeout_ms))
+    })
+    .await?;
+    let status = response.status();
+    let response_headers = response.headers().clone();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(Error::ApiStatus { status, body });
+    }
+    if let Some(on_response) = &options.base.on_response {
+        on_response(
+            ProviderResponse {
+                status: status.as_u16(),
+                headers: headers_to_record(&response_headers),
+            },
+            &model,
+        )
+        .await?;
+    }
+
+    let response: MistralEmbeddingResponse = serde_json::from_str(&body).map_err(|error| {
+        Error::InvalidProviderResponse(format!("could not decode embeddings response: {error}"))
+    })?;
+    let mut data = response.data;
+    data.sort_by_key(|item| item.index);
+    if data.len() != expected_count {
+        return Err(Error::InvalidProviderResponse(format!(
+            "expected {expected_count} embeddings, received {}",
+            data.len()
+        )));
+    }
+    Ok(EmbeddingBatch {
+        embeddings: data.into_iter().map(|item| item.embedding).collect(),
+        model: response.model.unwrap_or(model.id),
+        usage: response.usage.unwrap_or_default(),
+    })
+}
+
+fn headers(api_key: &str, model: &Model, overrides: &ProviderHeaders) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {api_key}"))
+            .map_err(|error| Error::InvalidHeaderValue("authorization".to_string(), error))?,
+    );
+    for (name, value) in &model.headers {
+        let Ok(name) = HeaderName::from_bytes(name.as_bytes()) else {
+            continue;
+        };
+        let value = HeaderValue::from_str(value)
+            .map_err(|error| Error::InvalidHeaderValue(name.to_string(), error))?;
+        headers.insert(name, value);
+    }
+    crate::utils::headers::apply_provider_headers(&mut headers, overrides)?;
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::types::{Message, ToolCall, Usage};
+
+    fn mistral(base_url: &str) -> Mistral {
+        builder()
+            .api_key(Some("mistral-key"))
+            .base_url(base_url)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn tool_call_ids_are_nine_alphanumerics() {
+        assert_eq!(normalize_tool_call_id("D681PevKs"), "D681PevKs");
+        let normalized = normalize_tool_call_id("call_abc|fc_123456789012345");
+        assert_eq!(normalized.len(), 9);
+        assert!(normalized.chars().all(|ch| ch.is_ascii_alphanumeric()));
+        assert_eq!(
+            normalized,
+            normalize_tool_call_id("call_abc|fc_123456789012345")
+        );
+    }
+
+    #[test]
+    fn fim_payload_carries_suffix_and_provider_options() {
+        let model = mistral("http://127.0.0.1:9")
+            .fim_model("codestral-latest")
+            .build_fim()
+            .unwrap();
+        let payload = build_fim_payload(
+            &model,
+            &FimContext::new("def add(a, b):\n", "\nprint(add(1, 2))"),
+            &StreamOptions {
+                max_tokens: Some(64),
+                provider_options: [("stop".to_string(), json!(["\n\n"]))]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            payload,
+            json!({
+                "model": "codestral-latest",
+                "prompt": "def add(a, b):\n",
+                "suffix": "\nprint(add(1, 2))",
+                "stream": true,
+                "max_tokens": 64,
+                "stop": ["\n\n"]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn fim_streams_middle_text() {
+        let body = concat!(
+            "data: {\"id\":\"f1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"    return\"}}]}\n\n",
+            "data: {\"id\":\"f1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\" a + b\"},\"finish_reason\":\"stop\"}],",
+            "\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":4,\"total_tokens\":16}}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let (base_url, request) = spawn_server("200 OK", "text/event-stream", body).await;
+        let model = mistral(&base_url)
+            .fim_model("codestral-latest")
+            .build()
+            .unwrap();
+
+        let message = crate::complete_fim(model, FimContext::new("def add(a, b):\n", "\n"), None)
+            .await
+            .unwrap();
+        let request = request.await.unwrap();
+
+        assert!(request.starts_with("POST /fim/completions HTTP/1.1"));
+        assert!(request.contains("authorization: Bearer mistral-key"));
+        assert_eq!(message.stop_reason, StopReason::Stop);
+        assert_eq!(message.response_id.as_deref(), Some("f1"));
+        assert_eq!(message.usage.input, 12);
+        assert!(matches!(
+            &message.content[..],
+            [AssistantContent::Text(block)] if block.text == "    return a + b"
+        ));
+    }
+
+    #[tokio::test]
+    async fn chat_uses_mistral_compat_and_tool_call_ids() {
+        let body = concat!(
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Done\"},\"finish_reason\":\"stop\"}],",
+            "\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":1,\"total_tokens\":6}}\n\n",
+            "data: [DONE]\n\n"
+        );
+        let (base_url, request) = spawn_server("200 OK", "text/event-stream", body).await;
+        let model = mistral(&base_url)
+            .model("mistral-small-latest")
+            .build()
+            .unwrap();
+        let other_model = Model {
+            provider: "openai".to_string(),
+            id: "gpt-5.5".to_string(),
+            ..model.clone()
+        };
+        let context = Context {
+            system_prompt: Some("Be brief.".to_string()),
+            messages: vec![
+                Message::user_text("look up a"),
+                Message::Assistant(AssistantMessage {
+                    content: vec![AssistantContent::ToolCall(ToolCall {
+                        id: "call_abc|fc_1".to_string(),
+                        name: "lookup".to_string(),
+                        arguments: json!({ "key": "a" }),
+                        thought_signature: None,
+                    })],
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage::default(),
+                    ..AssistantMessage::empty_for(&other_model)
+                }),
+            ],
+            ..Default::default()
+        };
+
+        let message = crate::complete(
+            model,
+            context,
+            Some(StreamOptions {
+                max_tokens: Some(100),
+                seed: Some(7),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        let request = request.await.unwrap();
+        let body: Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+
+        assert_eq!(message.stop_reason, StopReason::Stop);
+        assert_eq!(message.usage.input, 5);
+        assert!(request.starts_with("POST /chat/completions HTTP/1.1"));
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["max_tokens"], 100);
+        assert_eq!(body["random_seed"], 7);
+        assert!(body.get("seed").is_none());
+        assert!(body.get("max_completion_tokens").is_none());
+        assert!(body.get("stream_options").is_none());
+        assert!(body.get("store").is_none());
+        assert_eq!(
+            body["messages"][2]["tool_calls"][0]["id"],
+            normalize_tool_call_id("call_abc|fc_1")
+        );
+    }
+
+    #[tokio::test]
+    async fn embeddings_map_dimensions_to_output_dimension() {
+        let body = json!({
+            "model": "codestral-embed",
+            "data": [
+                { "index": 1, "embedding": [0.3, 0.4] },
+                { "index": 0, "embedding": [0.1, 0.2] }
+            ],
+            "usage": { "prompt_tokens": 4, "total_tokens": 4 }
+        })
+        .to_string();
+        let (base_url, request) = spawn_server("200 OK", "application/json", &body).await;
+        let model = mistral(&base_url)
+            .embedding_model("codestral-embed")
+            .build()
+            .unwrap();
+
+        let batch = crate::embed_many(
+            model,
+            ["a", "b"],
+            Some(EmbeddingOptions {
+                dimensions: Some(256),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        let request = request.await.unwrap();
+
+        assert!(request.starts_with("POST /embeddings HTTP/1.1"));
+        assert!(request.contains("\"output_dimension\":256"));
+        assert_eq!(batch.embeddings[0], EmbeddingVector::Float(vec![0.1, 0.2]));
+        assert_eq!(batch.usage.prompt_tokens, 4);
+    }
+
+    async fn spawn_server(
+        status: &'static str,
+        content_type: &'static str,
+        body: &str,
+    ) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = tokio::sync::oneshot::channel();
+        let body = body.to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 16 * 1024];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let _ = request_tx.send(String::from_utf8_lossy(&buffer[..read]).into_owned());
+            let response = format!(
+                "HTTP/1.1 {status}\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        (format!("http://{addr}"), request_rx)
+    }
+}
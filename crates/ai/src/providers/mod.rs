@@ -1,16 +1,26 @@
 pub mod anthropic;
+pub mod azure_openai;
+pub mod bedrock;
+pub mod cohere;
 pub(crate) mod constrained_sampling;
 pub(crate) mod deferred_tools;
 pub mod faux;
 pub mod github_copilot;
 pub(crate) mod github_copilot_headers;
+pub mod google;
+pub(crate) mod logprobs;
+pub mod mistral;
+pub mod ollama;
 pub mod openai;
+pub(crate) mod openai_audio;
 pub mod openai_completions;
 pub(crate) mod openai_embeddings;
 pub(crate) mod openai_images;
 pub(crate) mod openai_prompt_cache;
 pub mod openai_responses;
+pub(crate) mod openai_websocket;
 pub mod openrouter;
+pub(crate) mod sampling;
 pub(crate) mod simple_options;
 pub(crate) mod transform_messages;
 
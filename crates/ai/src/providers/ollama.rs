@@ -0,0 +1,1462 @@
+use async_trait::async_trait;
+use futures::{StreamExt, pin_mut};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde_json::{Map, Value, json};
+use std::sync::Arc;
+
+use crate::env_api_keys::{KnownProvider, get_env_api_key};
+use crate::event_stream::AssistantMessageEventStreamSender;
+use crate::models::calculate_cost;
+use crate::provider::{
+    EmbeddingModelApi, LanguageModelApi, ModelBuilder, Provider, ProviderCapabilities,
+};
+use crate::providers::sampling::{SamplingSupport, sampling};
+use crate::providers::simple_options;
+use crate::providers::simple_options::{build_base_options, clamped_reasoning};
+use crate::providers::transform_messages::transform_messages;
+use crate::types::{
+    AssistantContent, AssistantMessage, AssistantMessageEvent, Context, EmbeddingBatch,
+    EmbeddingEncodingFormat, EmbeddingOptions, EmbeddingUsage, EmbeddingVector, KnownApi, Message,
+    Model, ModelInput, ModelThinkingLevel, ProviderResponse, SimpleStreamOptions, StopReason,
+    StreamOptions, TextContent, ThinkingContent, Tool, ToolCall, ToolResultContent, UserContent,
+    UserMessageContent,
+};
+use crate::utils::circuit_breaker::model_circuit;
+use crate::utils::headers::headers_to_record;
+use crate::utils::http::{request_timeout, send_request_with_retries, send_with_retries};
+use crate::utils::json::parse_json_with_repair;
+use crate::utils::ndjson;
+use crate::utils::provider_env::get_provider_env_value;
+use crate::{Error, Result};
+
+const DEFAULT_PROVIDER_ID: KnownProvider = KnownProvider::Ollama;
+const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+const OLLAMA_HOST_ENV_VAR: &str = "OLLAMA_HOST";
+/// Used for models built by id and when `/api/show` does not report a
+/// context length.
+const DEFAULT_CONTEXT_WINDOW: u32 = 32_768;
+const DEFAULT_MAX_TOKENS: u32 = 8_192;
+/// How many `/api/show` requests `list_models` keeps in flight.
+const MAX_CONCURRENT_SHOW_REQUESTS: usize = 8;
+
+/// Local or hosted Ollama server using the native `/api/*` routes.
+///
+/// No API key is needed for a local server; when one is set (for example for
+/// `https://ollama.com`) it is sent as a bearer token.
+#[derive(Clone)]
+pub struct Ollama {
+    provider_id: String,
+    api_key: Option<String>,
+    base_url: String,
+    http_client: Option<reqwest::Client>,
+}
+
+impl Ollama {
+    pub fn builder() -> OllamaBuilder {
+        OllamaBuilder::default()
+    }
+
+    /// Reads the server address from `OLLAMA_HOST` and an optional
+    /// `OLLAMA_API_KEY`.
+    pub fn from_env() -> Result<Self> {
+        let api_key = get_env_api_key(DEFAULT_PROVIDER_ID);
+        let mut builder = Self::builder().api_key(api_key.as_deref());
+        if let Some(host) = get_provider_env_value(OLLAMA_HOST_ENV_VAR, &Default::default()) {
+            builder = builder.base_url(host_to_base_url(&host));
+        }
+        builder.build()
+    }
+
+    pub fn model(&self, id: &str) -> ModelBuilder {
+        <Self as Provider>::model(self, id)
+    }
+
+    pub fn embedding_model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(OllamaEmbeddingModelApi {
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new_embedding(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Text])
+    }
+
+    /// Lists installed models from `/api/tags`, filling in each model's
+    /// context window, vision input, and thinking support from `/api/show`.
+    /// A model whose `/api/show` call fails is listed as a chat model with
+    /// the defaults used for models built by id.
+    pub async fn list_models(&self) -> Result<Vec<Model>> {
+        let tags = self.get_json("api/tags").await?;
+        let names = tags
+            .get("models")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                entry
+                    .get("model")
+                    .or_else(|| entry.get("name"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .collect::<Vec<_>>();
+        futures::stream::iter(names)
+            .map(|name| async move {
+                match self.show_model(&name).await {
+                    Ok(model) => Ok(model),
+                    Err(_) => self.model(&name).build_language(),
+                }
+            })
+            .buffered(MAX_CONCURRENT_SHOW_REQUESTS)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    /// Builds a model from the metadata `/api/show` reports for `id`.
+    pub async fn show_model(&self, id: &str) -> Result<Model> {
+        let show = self.post_json("api/show", &json!({ "model": id })).await?;
+        let capabilities = show
+            .get("capabilities")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>();
+        let context_window = show
+            .get("model_info")
+            .and_then(context_length)
+            .unwrap_or(DEFAULT_CONTEXT_WINDOW);
+        if capabilities.contains(&"embedding") && !capabilities.contains(&"completion") {
+            return self
+                .embedding_model(id)
+                .context_window(context_window)
+                .build_embedding();
+        }
+        let mut input = vec![ModelInput::Text];
+        if capabilities.contains(&"vision") {
+            input.push(ModelInput::Image);
+        }
+        self.model(id)
+            .input(input)
+            .reasoning(capabilities.contains(&"thinking"))
+            .context_window(context_window)
+            .max_tokens(DEFAULT_MAX_TOKENS.min(context_window))
+            .build_language()
+    }
+
+    async fn get_json(&self, path: &str) -> Result<Value> {
+        let client = self.http_client.clone().unwrap_or_default();
+        let request = client
+            .get(format!("{}/{path}", self.base_url))
+            .headers(auth_headers(self.api_key.as_deref(), &Default::default())?);
+        read_json(request.send().await?).await
+    }
+
+    async fn post_json(&self, path: &str, body: &Value) -> Result<Value> {
+        let client = self.http_client.clone().unwrap_or_default();
+        let request = client
+            .post(format!("{}/{path}", self.base_url))
+            .headers(auth_headers(self.api_key.as_deref(), &Default::default())?)
+            .json(body);
+        read_json(request.send().await?).await
+    }
+}
+
+#[async_trait]
+impl Provider for Ollama {
+    fn id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            language_models: true,
+            image_models: false,
+            embedding_models: true,
+            fim_models: false,
+            rerank_models: false,
+            transcription_models: false,
+            speech_models: false,
+        }
+    }
+
+    fn model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(OllamaLanguageModelApi {
+            api_key: self.api_key.clone(),
+            http_client: self.http_client.clone(),
+        });
+        ModelBuilder::new(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Text])
+            .context_window(DEFAULT_CONTEXT_WINDOW)
+            .max_tokens(DEFAULT_MAX_TOKENS)
+    }
+
+    async fn list_models(&self) -> Result<Vec<Model>> {
+        Ollama::list_models(self).await
+    }
+}
+
+#[derive(Default)]
+pub struct OllamaBuilder {
+    provider_id: Option<String>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl OllamaBuilder {
+    pub fn provider_id(mut self, provider_id: impl Into<String>) -> Self {
+        self.provider_id = Some(provider_id.into());
+        self
+    }
+
+    pub fn api_key(mut self, api_key: Option<&str>) -> Self {
+        self.api_key = api_key
+            .map(str::trim)
+            .filter(|api_key| !api_key.is_empty())
+            .map(str::to_string);
+        self
+    }
+
+    /// Sets the server address, e.g. `http://localhost:11434`.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn build(self) -> Result<Ollama> {
+        Ok(Ollama {
+            provider_id: self
+                .provider_id
+                .unwrap_or_else(|| DEFAULT_PROVIDER_ID.into()),
+            api_key: self.api_key,
+            base_url: self
+                .base_url
+                .as_deref()
+                .unwrap_or(DEFAULT_BASE_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            http_client: self.http_client,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct OllamaLanguageModelApi {
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+impl OllamaLanguageModelApi {
+    fn with_api_key(&self, mut options: StreamOptions) -> StreamOptions {
+        if options
+            .api_key
+            .as_deref()
+            .is_none_or(|api_key| api_key.trim().is_empty())
+        {
+            options.api_key = self.api_key.clone();
+        }
+        if options.http_client.is_none() {
+            options.http_client = self.http_client.clone();
+        }
+        options
+    }
+
+    fn with_api_key_simple(&self, mut options: SimpleStreamOptions) -> SimpleStreamOptions {
+        options.stream = self.with_api_key(options.stream);
+        options
+    }
+}
+
+impl LanguageModelApi for OllamaLanguageModelApi {
+    fn id(&self) -> &str {
+        KnownApi::OllamaChat.as_str()
+    }
+
+    fn stream(
+        &self,
+        model: Model,
+        context: Context,
+        options: StreamOptions,
+    ) -> Result<crate::AssistantEventStream> {
+        Ok(stream_ollama(
+            model,
+            context,
+            simple_options::ollama_options_from_stream_options(self.with_api_key(options)),
+        ))
+    }
+
+    fn stream_simple(
+        &self,
+        model: Model,
+        context: Context,
+        options: SimpleStreamOptions,
+    ) -> Result<crate::AssistantEventStream> {
+        stream_simple_ollama(model, context, self.with_api_key_simple(options))
+    }
+}
+
+#[derive(Clone)]
+struct OllamaEmbeddingModelApi {
+    api_key: Option<String>,
+    http_client: Option<reqwest::Client>,
+}
+
+#[async_trait]
+impl EmbeddingModelApi for OllamaEmbeddingModelApi {
+    fn id(&self) -> &str {
+        KnownApi::OllamaEmbed.as_str()
+    }
+
+    async fn embed_many(
+        &self,
+        model: Model,
+        inputs: Vec<String>,
+        mut options: EmbeddingOptions,
+    ) -> Result<EmbeddingBatch> {
+        if options
+            .base
+            .api_key
+            .as_deref()
+            .is_none_or(|api_key| api_key.trim().is_empty())
+        {
+            options.base.api_key = self.api_key.clone();
+        }
+        if options.base.http_client.is_none() {
+            options.base.http_client = self.http_client.clone();
+        }
+        embed_many_ollama(model, inputs, options).await
+    }
+}
+
+pub fn builder() -> OllamaBuilder {
+    Ollama::builder()
+}
+
+pub fn from_env() -> Result<Ollama> {
+    Ollama::from_env()
+}
+
+/// Value of the native `think` request field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OllamaThink {
+    Enabled,
+    Disabled,
+    /// Effort levels accepted by models such as `gpt-oss`.
+    Low,
+    Medium,
+    High,
+}
+
+impl OllamaThink {
+    fn to_value(self) -> Value {
+        match self {
+            Self::Enabled => json!(true),
+            Self::Disabled => json!(false),
+            Self::Low => json!("low"),
+            Self::Medium => json!("medium"),
+            Self::High => json!("high"),
+        }
+    }
+
+    pub(crate) fn parse(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(true) => Some(Self::Enabled),
+            Value::Bool(false) => Some(Self::Disabled),
+            Value::String(level) => match level.as_str() {
+                "low" => Some(Self::Low),
+                "medium" => Some(Self::Medium),
+                "high" => Some(Self::High),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct OllamaOptions {
+    pub base: StreamOptions,
+    /// Context length the server loads the model with (`options.num_ctx`).
+    pub num_ctx: Option<u32>,
+    /// How long the model stays loaded after the request, e.g. `"10m"` or
+    /// `"-1"`.
+    pub keep_alive: Option<String>,
+    pub think: Option<OllamaThink>,
+}
+
+pub fn stream_simple_ollama(
+    model: Model,
+    context: Context,
+    options: SimpleStreamOptions,
+) -> Result<crate::AssistantEventStream> {
+    let api_key = options
+        .stream
+        .api_key
+        .clone()
+        .filter(|key| !key.trim().is_empty());
+    let base = build_base_options(&model, &context, &options, api_key);
+    let mut ollama_options = simple_options::ollama_options_from_stream_options(base);
+    if model.reasoning && ollama_options.think.is_none() {
+        ollama_options.think = Some(match clamped_reasoning(&model, &options) {
+            None => OllamaThink::Disabled,
+            Some(level) if uses_effort_levels(&model.id) => map_thinking_level(level),
+            Some(_) => OllamaThink::Enabled,
+        });
+    }
+    Ok(stream_ollama(model, context, ollama_options))
+}
+
+pub fn stream_ollama(
+    model: Model,
+    context: Context,
+    options: OllamaOptions,
+) -> crate::AssistantEventStream {
+    crate::event_stream::stream_from_producer(
+        move |mut sender| async move {
+            let output = AssistantMessage::empty_for(&model);
+            run_stream(model, context, options, output, &mut sender).await?;
+            Ok(())
+        },
+        |error: StreamFailure| {
+            let mut message = error.output;
+            message.stop_reason = if error.cancelled {
+                StopReason::Aborted
+            } else {
+                StopReason::Error
+            };
+            message.error_message = Some(error.message);
+            AssistantMessageEvent::Error {
+                reason: message.stop_reason,
+                error: message,
+            }
+        },
+    )
+}
+
+struct StreamFailure {
+    output: AssistantMessage,
+    message: String,
+    cancelled: bool,
+}
+
+impl StreamFailure {
+    fn new(output: AssistantMessage, error: impl std::fmt::Display) -> Self {
+        Self {
+            output,
+            message: error.to_string(),
+            cancelled: false,
+        }
+    }
+
+    fn cancelled(output: AssistantMessage) -> Self {
+        Self {
+            output,
+            message: "Request was aborted".to_string(),
+            cancelled: true,
+        }
+    }
+}
+
+async fn run_stream(
+    model: Model,
+    context: Context,
+    options: OllamaOptions,
+    mut output: AssistantMessage,
+    sender: &mut AssistantMessageEventStreamSender,
+) -> std::result::Result<(), StreamFailure> {
+    let is_cancelled = || {
+        options
+            .base
+            .cancellation_token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+    };
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+
+    let mut payload = build_ollama_payload(&model, &context, &options);
+    if let Some(on_payload) = &options.base.on_payload {
+        match on_payload(payload.clone(), &model).await {
+            Ok(Some(next)) => payload = next,
+            Ok(None) => {}
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        }
+    }
+
+    let request_url = format!("{}/api/chat", model.base_url.trim_end_matches('/'));
+    let request_headers = match headers(&model, &options.base) {
+        Ok(headers) => headers,
+        Err(error) => return Err(StreamFailure::new(output, error)),
+    };
+    let client = options.base.http_client.clone().unwrap_or_default();
+    let response = match send_with_retries(&options.base, &model_circuit(&model), || {
+        client
+            .post(request_url.as_str())
+            .headers(request_headers.clone())
+            .json(&payload)
+            .timeout(request_timeout(options.base.timeout_ms))
+    })
+    .await
+    {
+        Ok(response) => response,
+        Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
+    };
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        output.error_status = Some(status.as_u16());
+        return Err(StreamFailure::new(
+            output,
+            Error::ApiStatus { status, body },
+        ));
+    }
+    if let Some(on_response) = &options.base.on_response {
+        let provider_response = ProviderResponse {
+            status: response.status().as_u16(),
+            headers: headers_to_record(response.headers()),
+        };
+        if let Err(error) = on_response(provider_response, &model).await {
+            return Err(StreamFailure::new(output, error));
+        }
+    }
+
+    sender.push(AssistantMessageEvent::Start {
+        partial: output.clone(),
+    });
+
+    let mut open_block: Option<usize> = None;
+    let mut done_reason: Option<String> = None;
+    let lines = ndjson::lines(response, options.base.cancellation_token.clone());
+    pin_mut!(lines);
+    while let Some(line) = lines.next().await {
+        if is_cancelled() {
+            return Err(StreamFailure::cancelled(output));
+        }
+        let line = match line {
+            Ok(line) => line,
+            Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+            Err(error) => return Err(StreamFailure::new(output, error)),
+        };
+        let chunk: Value = match parse_json_with_repair(&line) {
+            Ok(value) => value,
+            Err(error) => {
+                return Err(StreamFailure::new(
+                    output,
+                    format!("Could not parse Ollama stream chunk: {error}; data={line}"),
+                ));
+            }
+        };
+        if let Some(error) = chunk.get("error") {
+            let message = error
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            return Err(StreamFailure::new(output, message));
+        }
+        if let Some(message) = chunk.get("message") {
+            handle_message(message, &mut output, &mut open_block, sender);
+        }
+        if chunk.get("done").and_then(Value::as_bool) == Some(true) {
+            done_reason = Some(
+                chunk
+                    .get("done_reason")
+                    .and_then(Value::as_str)
+                    .unwrap_or("stop")
+                    .to_string(),
+            );
+            update_ollama_usage(&mut output, &chunk, &model);
+        }
+    }
+    close_open_block(&mut output, &mut open_block, sender);
+
+    if is_cancelled() {
+        return Err(StreamFailure::cancelled(output));
+    }
+    let Some(done_reason) = done_reason else {
+        return Err(StreamFailure::new(
+            output,
+            "Ollama stream ended before the final chunk",
+        ));
+    };
+    let has_tool_calls = output
+        .content
+        .iter()
+        .any(|block| matches!(block, AssistantContent::ToolCall(_)));
+    output.stop_reason = map_done_reason(&done_reason, has_tool_calls);
+    sender.push(AssistantMessageEvent::Done {
+        reason: output.stop_reason,
+        message: output,
+    });
+    Ok(())
+}
+
+fn handle_message(
+    message: &Value,
+    output: &mut AssistantMessage,
+    open_block: &mut Option<usize>,
+    sender: &mut AssistantMessageEventStreamSender,
+) {
+    if let Some(thinking) = message.get("thinking").and_then(Value::as_str)
+        && !thinking.is_empty()
+    {
+        push_delta(output, open_block, sender, true, thinking);
+    }
+    if let Some(content) = message.get("content").and_then(Value::as_str)
+        && !content.is_empty()
+    {
+        push_delta(output, open_block, sender, false, content);
+    }
+    for tool_call in message
+        .get("tool_calls")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        close_open_block(output, open_block, sender);
+        let function = tool_call.get("function").unwrap_or(&Value::Null);
+        let name = function
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let arguments = match function.get("arguments") {
+            Some(Value::String(arguments)) => {
+                parse_json_with_repair(arguments).unwrap_or_else(|_| json!({}))
+            }
+            Some(arguments) if arguments.is_object() => arguments.clone(),
+            _ => Value::Object(Map::new()),
+        };
+        let id = tool_call
+            .get("id")
+            .and_then(Value::as_str)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{name}_{}", output.content.len()));
+        output.content.push(AssistantContent::ToolCall(ToolCall {
+            id,
+            name,
+            arguments: arguments.clone(),
+            thought_signature: None,
+        }));
+        let content_index = output.content.len() - 1;
+        sender.push(AssistantMessageEvent::ToolCallStart {
+            content_index,
+            partial: output.clone(),
+        });
+        sender.push(AssistantMessageEvent::ToolCallDelta {
+            content_index,
+            delta: arguments.to_string(),
+            partial: output.clone(),
+        });
+        if let Some(AssistantContent::ToolCall(tool_call)) = output.content.get(content_index) {
+            sender.push(AssistantMessageEvent::ToolCallEnd {
+                content_index,
+                tool_call: tool_call.clone(),
+                partial: output.clone(),
+            });
+        }
+    }
+}
+
+fn push_delta(
+    output: &mut AssistantMessage,
+    open_block: &mut Option<usize>,
+    sender: &mut AssistantMessageEventStreamSender,
+    is_thinking: bool,
+    delta: &str,
+) {
+    let continues_open_block = open_block
+        .and_then(|index| output.content.get(index))
+        .is_some_and(|block| match block {
+            AssistantContent::Thinking(_) => is_thinking,
+            AssistantContent::Text(_) => !is_thinking,
+            AssistantContent::ToolCall(_) | AssistantContent::Audio(_) => false,
+        });
+    if !continues_open_block {
+        close_open_block(output, open_block, sender);
+        output.content.push(if is_thinking {
+            AssistantContent::Thinking(ThinkingContent {
+                thinking: String::new(),
+                thinking_signature: None,
+                redacted: None,
+            })
+        } else {
+            AssistantContent::Text(TextContent {
+                text: String::new(),
+                text_signature: None,
+            })
+        });
+        let content_index = output.content.len() - 1;
+        *open_block = Some(content_index);
+        sender.push(if is_thinking {
+            AssistantMessageEvent::ThinkingStart {
+                content_index,
+                partial: output.clone(),
+            }
+        } else {
+            AssistantMessageEvent::TextStart {
+                content_index,
+                partial: output.clone(),
+            }
+        });
+    }
+    let Some(content_index) = *open_block else {
+        return;
+    };
+    match output.content.get_mut(content_index) {
+        Some(AssistantContent::Thinking(block)) => block.thinking.push_str(delta),
+        Some(AssistantContent::Text(block)) => block.text.push_str(delta),
+        _ => return,
+    }
+    sender.push(if is_thinking {
+        AssistantMessageEvent::ThinkingDelta {
+            content_index,
+            delta: delta.to_string(),
+            partial: output.clone(),
+        }
+    } else {
+        AssistantMessageEvent::TextDelta {
+            content_index,
+            delta: delta.to_string(),
+            partial: output.clone(),
+        }
+    });
+}
+
+fn close_open_block(
+    output: &mut AssistantMessage,
+    open_block: &mut Option<usize>,
+    sender: &mut AssistantMessageEventStreamSender,
+) {
+    let Some(content_index) = open_block.take() else {
+        return;
+    };
+    match output.content.get(content_index) {
+        Some(AssistantContent::Text(block)) => sender.push(AssistantMessageEvent::TextEnd {
+            content_index,
+            content: block.text.clone(),
+            partial: output.clone(),
+        }),
+        Some(AssistantContent::Thinking(block)) => {
+            sender.push(AssistantMessageEvent::ThinkingEnd {
+                content_index,
+                content: block.thinking.clone(),
+                partial: output.clone(),
+            })
+        }
+        _ => {}
+    }
+}
+
+fn build_ollama_payload(model: &Model, context: &Context, options: &OllamaOptions) -> Value {
+    let transformed = transform_messages(&context.messages, model, |id, _model, _source| {
+        id.to_string()
+    });
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = &context.system_prompt
+        && !system_prompt.is_empty()
+    {
+        messages.push(json!({ "role": "system", "content": system_prompt }));
+    }
+    messages.extend(convert_messages(&transformed));
+
+    let mut payload = json!({
+        "model": model.id,
+        "messages": messages,
+        "stream": true,
+    });
+    let object = payload.as_object_mut().expect("payload object");
+
+    let mut model_options = Map::new();
+    if let Some(num_ctx) = options.num_ctx {
+        model_options.insert("num_ctx".to_string(), json!(num_ctx));
+    }
+    if let Some(max_tokens) = options.base.max_tokens {
+        model_options.insert("num_predict".to_string(), json!(max_tokens));
+    }
+    if let Some(temperature) = options.base.temperature {
+        model_options.insert("temperature".to_string(), json!(temperature));
+    }
+    sampling(model, &options.base, SamplingSupport::ALL).insert_into(&mut model_options, "stop");
+    if !model_options.is_empty() {
+        object.insert("options".to_string(), Value::Object(model_options));
+    }
+    if let Some(keep_alive) = &options.keep_alive {
+        object.insert("keep_alive".to_string(), json!(keep_alive));
+    }
+    if let Some(think) = options.think {
+        object.insert("think".to_string(), think.to_value());
+    }
+    if !context.tools.is_empty() {
+        object.insert("tools".to_string(), json!(convert_tools(&context.tools)));
+    }
+    payload
+}
+
+fn convert_messages(messages: &[Message]) -> Vec<Value> {
+    let mut converted = Vec::new();
+    for message in messages {
+        match message {
+            Message::User(user) => {
+                let (text, images) = match &user.content {
+                    UserMessageContent::Text(text) => (text.clone(), Vec::new()),
+                    UserMessageContent::Parts(parts) => {
+                        let mut text = Vec::new();
+                        let mut images = Vec::new();
+                        for part in parts {
+                            match part {
+                                UserContent::Text(part) => text.push(part.text.as_str()),
+                                UserContent::Image(image) => images.push(image.data.clone()),
+                                // Ollama chat takes text and images only.
+                                UserContent::Document(_) | UserContent::Audio(_) => {}
+                            }
+                        }
+                        (text.join("\n"), images)
+                    }
+                };
+                let mut message = json!({ "role": "user", "content": text });
+                if !images.is_empty() {
+                    message["images"] = json!(images);
+                }
+                converted.push(message);
+            }
+            Message::Assistant(assistant) => {
+                let mut text = String::new();
+                let mut thinking = String::new();
+                let mut tool_calls = Vec::new();
+                for block in &assistant.content {
+                    match block {
+                        AssistantContent::Text(block) => text.push_str(&block.text),
+                        AssistantContent::Audio(block) => text.push_str(&block.transcript),
+                        AssistantContent::Thinking(block) if block.redacted != Some(true) => {
+                            thinking.push_str(&block.thinking)
+                        }
+                        AssistantContent::Thinking(_) => {}
+                        AssistantContent::ToolCall(tool_call) => tool_calls.push(json!({
+                            "function": {
+                                "name": &tool_call.name,
+                                "arguments": &tool_call.arguments
+                            }
+                        })),
+                    }
+                }
+                if text.is_empty() && thinking.is_empty() && tool_calls.is_empty() {
+                    continue;
+                }
+                let mut message = json!({ "role": "assistant", "content": text });
+                if !thinking.is_empty() {
+                    message["thinking"] = json!(thinking);
+                }
+                if !tool_calls.is_empty() {
+                    message["tool_calls"] = json!(tool_calls);
+                }
+                converted.push(message);
+            }
+            Message::ToolResult(tool_result) => {
+                let mut text = Vec::new();
+                let mut images = Vec::new();
+                for content in &tool_result.content {
+                    match content {
+                        ToolResultContent::Text(content) => text.push(content.text.as_str()),
+                        ToolResultContent::Image(image) => images.push(image.data.clone()),
+                        ToolResultContent::Document(_) => {}
+                    }
+                }
+                let mut message = json!({
+                    "role": "tool",
+                    "tool_name": &tool_result.tool_name,
+                    "content": text.join("\n"),
+                });
+                if !images.is_empty() {
+                    message["images"] = json!(images);
+                }
+                converted.push(message);
+            }
+            Message::Custom(_) => {}
+        }
+    }
+    converted
+}
+
+fn convert_tools(tools: &[Tool]) -> Vec<Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters
+                }
+            })
+        })
+        .collect()
+}
+
+fn update_ollama_usage(output: &mut AssistantMessage, chunk: &Value, model: &Model) {
+    let count = |name: &str| {
+        chunk
+            .get(name)
+            .and_then(Value::as_u64)
+            .map(|value| value as u32)
+            .unwrap_or(0)
+    };
+    output.usage.input = count("prompt_eval_count");
+    output.usage.output = count("eval_count");
+    output.usage.total_tokens = output.usage.input + output.usage.output;
+    calculate_cost(model, &mut output.usage);
+}
+
+fn map_done_reason(reason: &str, has_tool_calls: bool) -> StopReason {
+    match reason {
+        "length" => StopReason::Length,
+        _ if has_tool_calls => StopReason::ToolUse,
+        _ => StopReason::Stop,
+    }
+}
+
+async fn embed_many_ollama(
+    model: Model,
+    inputs: Vec<String>,
+    options: EmbeddingOptions,
+) -> Result<EmbeddingBatch> {
+    if options.encoding_format == Some(EmbeddingEncodingFormat::Base64) {
+        return Err(Error::Validation(
+            "Ollama embeddings only support float encoding".to_string(),
+        ));
+    }
+    let expected_count = inputs.len();
+    let mut payload = json!({ "model": model.id, "input": inputs });
+    if let Some(dimensions) = options.dimensions {
+        payload["dimensions"] = json!(dimensions);
+    }
+    if let Some(on_payload) = &options.base.on_payload
+        && let Some(next_payload) = on_payload(payload.clone(), &model).await?
+    {
+        payload = next_payload;
+    }
+
+    let client = options.base.http_client.clone().unwrap_or_default();
+    let url = format!("{}/api/embed", model.base_url.trim_end_matches('/'));
+    let mut headers = auth_headers(options.base.api_key.as_deref(), &model.headers)?;
+    crate::utils::headers::apply_provider_headers(&mut headers, &options.base.headers)?;
+    let response = send_request_with_retries(&options.base, &model_circuit(&model), || {
+        client
+            .post(&url)
+            .headers(headers.clone())
+            .json(&payload)
+            .timeout(request_timeout(options.base.timeout_ms))
+    })
+    .await?;
+    if let Some(on_response) = &options.base.on_response {
+        on_response(
+            ProviderResponse {
+                status: response.status().as_u16(),
+                headers: headers_to_record(response.headers()),
+            },
+            &model,
+        )
+        .await?;
+    }
+    let response = read_json(response).await?;
+
+    let embeddings = response
+        .get("embeddings")
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            Error::InvalidProviderResponse("Ollama embed response has no embeddings".to_string())
+        })?
+        .iter()
+        .map(|embedding| {
+            serde_json::from_value::<Vec<f32>>(embedding.clone())
+                .map(EmbeddingVector::Float)
+                .map_err(|error| {
+                    Error::InvalidProviderResponse(format!("could not decode embedding: {error}"))
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if embeddings.len() != expected_count {
+        return Err(Error::InvalidProviderResponse(format!(
+            "expected {expected_count} embeddings, received {}",
+            embeddings.len()
+        )));
+    }
+    let prompt_tokens = response
+        .get("prompt_eval_count")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32;
+    Ok(EmbeddingBatch {
+        embeddings,
+        model: response
+            .get("model")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or(model.id),
+        usage: EmbeddingUsage {
+            prompt_tokens,
+            total_tokens: prompt_tokens,
+        },
+    })
+}
+
+fn headers(model: &Model, options: &StreamOptions) -> Result<HeaderMap> {
+    let mut headers = auth_headers(options.api_key.as_deref(), &model.headers)?;
+    crate::utils::headers::apply_provider_headers(&mut headers, &options.headers)?;
+    Ok(headers)
+}
+
+fn auth_headers(
+    api_key: Option<&str>,
+    model_headers: &std::collections::HashMap<String, String>,
+) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    if let Some(api_key) = api_key.filter(|api_key| !api_key.trim().is_empty()) {
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {api_key}"))
+                .map_err(|error| Error::InvalidHeaderValue("authorization".to_string(), error))?,
+        );
+    }
+    for (name, value) in model_headers {
+        let Ok(name) = HeaderName::from_bytes(name.as_bytes()) else {
+            continue;
+        };
+        let value = HeaderValue::from_str(value)
+            .map_err(|error| Error::InvalidHeaderValue(name.to_string(), error))?;
+        headers.insert(name, value);
+    }
+    Ok(headers)
+}
+
+async fn read_json(response: reqwest::Response) -> Result<Value> {
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(Error::ApiStatus { status, body });
+    }
+    serde_json::from_str(&body).map_err(|error| {
+        Error::InvalidProviderResponse(format!("could not decode Ollama response: {error}"))
+    })
+}
+
+/// Reads `{architecture}.context_length` from an `/api/show` `model_info`
+/// object.
+fn context_length(model_info: &Value) -> Option<u32> {
+    let model_info = model_info.as_object()?;
+    let architecture = model_info
+        .get("general.architecture")
+        .and_then(Value::as_str);
+    architecture
+        .and_then(|architecture| model_info.get(&format!("{architecture}.context_length")))
+        .or_else(|| {
+            model_info
+                .iter()
+                .find(|(key, _)| key.ends_with(".context_length"))
+                .map(|(_, value)| value)
+        })
+        .and_then(Value::as_u64)
+        .and_then(|value| u32::try_from(value).ok())
+}
+
+/// `OLLAMA_HOST` may omit the scheme, e.g. `0.0.0.0:11434`.
+fn host_to_base_url(host: &str) -> String {
+    if host.starts_with("http://") || host.starts_with("https://") {
+        host.to_string()
+    } else {
+        format!("http://{host}")
+    }
+}
+
+fn uses_effort_levels(id: &str) -> bool {
+    id.starts_with("gpt-oss")
+}
+
+fn map_thinking_level(level: ModelThinkingLevel) -> OllamaThink {
+    match level {
+        ModelThinkingLevel::Off | ModelThinkingLevel::Minimal | ModelThinkingLevel::Low => {
+            OllamaThink::Low
+        }
+        ModelThinkingLevel::Medium => OllamaThink::Medium,
+        ModelThinkingLevel::High | ModelThinkingLevel::Xhigh | ModelThinkingLevel::Max => {
+            OllamaThink::High
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::types::{ImageContent, ToolResultMessage, Usage, UserMessage};
+
+    fn ollama_model(base_url: &str, id: &str) -> Model {
+        builder()
+            .base_url(base_url)
+            .build()
+            .expect("provider")
+            .model(id)
+            .build()
+            .expect("model")
+    }
+
+    #[test]
+    fn payload_maps_native_options_images_and_tool_history() {
+        let mut model = ollama_model("http://127.0.0.1:9", "gemma3");
+        model.input = vec![ModelInput::Text, ModelInput::Image];
+        let tool_call = ToolCall {
+            id: "lookup_0".to_string(),
+            name: "lookup".to_string(),
+            arguments: json!({ "key": "a" }),
+            thought_signature: None,
+        };
+        let context = Context::builder()
+            .system_prompt("Be brief.")
+            .message(Message::User(UserMessage {
+                content: UserMessageContent::Parts(vec![
+                    UserContent::text("What is this?"),
+                    UserContent::Image(ImageContent {
+                        data: "aW1n".to_string(),
+                        mime_type: "image/png".to_string(),
+                    }),
+                ]),
+                timestamp: 1,
+            }))
+            .message(Message::Assistant(AssistantMessage {
+                content: vec![AssistantContent::ToolCall(tool_call)],
+                stop_reason: StopReason::ToolUse,
+                usage: Usage::default(),
+                ..AssistantMessage::empty_for(&model)
+            }))
+            .message(Message::ToolResult(ToolResultMessage {
+                tool_call_id: "lookup_0".to_string(),
+                tool_name: "lookup".to_string(),
+                content: vec![ToolResultContent::text("found")],
+                details: None,
+                usage: None,
+                added_tool_names: Vec::new(),
+                is_error: false,
+                timestamp: 2,
+            }))
+            .build();
+
+        let payload = build_ollama_payload(
+            &model,
+            &context,
+            &OllamaOptions {
+                base: StreamOptions {
+                    max_tokens: Some(512),
+                    temperature: Some(0.1),
+                    ..Default::default()
+                },
+                num_ctx: Some(32_768),
+                keep_alive: Some("10m".to_string()),
+                think: Some(OllamaThink::Enabled),
+            },
+        );
+
+        assert_eq!(
+            payload["messages"],
+            json!([
+                { "role": "system", "content": "Be brief." },
+                { "role": "user", "content": "What is this?", "images": ["aW1n"] },
+                {
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{ "function": { "name": "lookup", "arguments": { "key": "a" } } }]
+                },
+                { "role": "tool", "tool_name": "lookup", "content": "found" }
+            ])
+        );
+        assert_eq!(
+            payload["options"],
+            json!({ "num_ctx": 32_768, "num_predict": 512, "temperature": 0.1 })
+        );
+        assert_eq!(payload["keep_alive"], "10m");
+        assert_eq!(payload["think"], true);
+        assert_eq!(payload["stream"], true);
+    }
+
+    #[test]
+    fn generic_provider_options_map_to_native_fields() {
+        let options = simple_options::ollama_options_from_stream_options(StreamOptions {
+            provider_options: [
+                ("numCtx".to_string(), json!(8192)),
+                ("keepAlive".to_string(), json!("-1")),
+                ("think".to_string(), json!("high")),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        });
+
+        assert_eq!(options.num_ctx, Some(8192));
+        assert_eq!(options.keep_alive.as_deref(), Some("-1"));
+        assert_eq!(options.think, Some(OllamaThink::High));
+    }
+
+    #[tokio::test]
+    async fn chat_stream_parses_ndjson_thinking_text_and_tool_calls() {
+        let body = concat!(
+            "{\"model\":\"qwen3\",\"message\":{\"role\":\"assistant\",\"content\":\"\",\"thinking\":\"Need a lookup.\"},\"done\":false}\n",
+            "{\"model\":\"qwen3\",\"message\":{\"role\":\"assistant\",\"content\":\"Checking\"},\"done\":false}\n",
+            "{\"model\":\"qwen3\",\"message\":{\"role\":\"assistant\",\"content\":\"\",\"tool_calls\":[{\"function\":{\"name\":\"lookup\",\"arguments\":{\"key\":\"a\"}}}]},\"done\":false}\n",
+            "{\"model\":\"qwen3\",\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"done_reason\":\"stop\",\"prompt_eval_count\":12,\"eval_count\":7}\n"
+        );
+        let (base_url, request) = spawn_server("200 OK", "application/x-ndjson", body).await;
+        let mut model = ollama_model(&base_url, "qwen3");
+        model.reasoning = true;
+
+        let message = crate::complete_simple(
+            model,
+            Context {
+                messages: vec![Message::user_text("look up a")],
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        let request = request.await.unwrap();
+
+        assert!(request.starts_with("POST /api/chat HTTP/1.1"));
+        assert!(!request.to_ascii_lowercase().contains("authorization:"));
+        assert!(request.contains("\"think\":false"));
+        assert_eq!(message.stop_reason, StopReason::ToolUse);
+        assert_eq!(message.usage.input, 12);
+        assert_eq!(message.usage.output, 7);
+        assert!(matches!(
+            &message.content[0],
+            AssistantContent::Thinking(block) if block.thinking == "Need a lookup."
+        ));
+        assert!(matches!(
+            &message.content[1],
+            AssistantContent::Text(block) if block.text == "Checking"
+        ));
+        assert!(matches!(
+            &message.content[2],
+            AssistantContent::ToolCall(call) if call.name == "lookup" && call.arguments == json!({ "key": "a" })
+        ));
+    }
+
+    #[tokio::test]
+    async fn chat_stream_surfaces_error_chunks() {
+        let body = "{\"error\":\"model 'missing' not found\"}\n";
+        let (base_url, _request) = spawn_server("200 OK", "application/x-ndjson", body).await;
+
+        let message = crate::complete(
+            ollama_model(&base_url, "missing"),
+            Context {
+                messages: vec![Message::user_text("hi")],
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(message.stop_reason, StopReason::Error);
+        assert_eq!(
+            message.error_message.as_deref(),
+            Some("model 'missing' not found")
+        );
+    }
+
+    #[tokio::test]
+    async fn embed_posts_inputs_and_reads_float_vectors() {
+        let body = json!({
+            "model": "nomic-embed-text",
+            "embeddings": [[0.1, 0.2], [0.3, 0.4]],
+            "prompt_eval_count": 6
+        })
+        .to_string();
+        let (base_url, request) = spawn_server("200 OK", "application/json", &body).await;
+        let model = builder()
+            .base_url(base_url)
+            .api_key(Some("ollama-key"))
+            .build()
+            .unwrap()
+            .embedding_model("nomic-embed-text")
+            .build()
+            .unwrap();
+
+        let batch = crate::embed_many(model, vec!["a".to_string(), "b".to_string()], None)
+            .await
+            .unwrap();
+        let request = request.await.unwrap();
+
+        assert!(request.starts_with("POST /api/embed HTTP/1.1"));
+        assert!(request.contains("authorization: Bearer ollama-key"));
+        assert!(request.contains("\"input\":[\"a\",\"b\"]"));
+        assert_eq!(batch.embeddings.len(), 2);
+        assert_eq!(batch.embeddings[1], EmbeddingVector::Float(vec![0.3, 0.4]));
+        assert_eq!(batch.usage.prompt_tokens, 6);
+    }
+
+    #[tokio::test]
+    async fn list_models_reads_context_length_and_capabilities() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for _ in 0..3 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0u8; 16 * 1024];
+                let read = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).into_owned();
+                let body = if request.starts_with("GET /api/tags") {
+                    json!({ "models": [{ "name": "gemma3:4b", "model": "gemma3:4b" }, { "name": "nomic-embed-text:latest" }] })
+                } else if request.contains("gemma3:4b") {
+                    json!({
+                        "capabilities": ["completion", "vision"],
+                        "model_info": {
+                            "general.architecture": "gemma3",
+                            "gemma3.context_length": 131_072
+                        }
+                    })
+                } else {
+                    json!({
+                        "capabilities": ["embedding"],
+                        "model_info": {
+                            "general.architecture": "nomic-bert",
+                            "nomic-bert.context_length": 2_048
+                        }
+                    })
+                }
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let models = builder()
+            .base_url(base_url)
+            .build()
+            .unwrap()
+            .list_models()
+            .await
+            .unwrap();
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].id, "gemma3:4b");
+        assert_eq!(models[0].api_id(), "ollama-chat");
+        assert_eq!(models[0].context_window, 131_072);
+        assert_eq!(models[0].input, vec![ModelInput::Text, ModelInput::Image]);
+        assert!(!models[0].reasoning);
+        assert_eq!(models[1].api_id(), "ollama-embed");
+        assert_eq!(models[1].context_window, 2_048);
+    }
+
+    #[tokio::test]
+    async fn list_models_falls_back_to_defaults_when_show_fails() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for _ in 0..3 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0u8; 16 * 1024];
+                let read = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).into_owned();
+                let (status, body) = if request.starts_with("GET /api/tags") {
+                    (
+                        "200 OK",
+                        json!({ "models": [{ "model": "broken:latest" }, { "model": "qwen3:8b" }] }),
+                    )
+                } else if request.contains("broken:latest") {
+                    (
+                        "500 Internal Server Error",
+                        json!({ "error": "model manifest is corrupt" }),
+                    )
+                } else {
+                    (
+                        "200 OK",
+                        json!({
+                            "capabilities": ["completion", "thinking"],
+                            "model_info": {
+                                "general.architecture": "qwen3",
+                                "qwen3.context_length": 40_960
+                            }
+                        }),
+                    )
+                };
+                let body = body.to_string();
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let models = builder()
+            .base_url(base_url)
+            .build()
+            .unwrap()
+            .list_models()
+            .await
+            .unwrap();
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].id, "broken:latest");
+        assert_eq!(models[0].api_id(), "ollama-chat");
+        assert_eq!(models[0].context_window, DEFAULT_CONTEXT_WINDOW);
+        assert_eq!(models[1].id, "qwen3:8b");
+        assert_eq!(models[1].context_window, 40_960);
+        assert!(models[1].reasoning);
+    }
+
+    #[test]
+    fn host_env_values_gain_a_scheme() {
+        assert_eq!(host_to_base_url("0.0.0.0:11434"), "http://0.0.0.0:11434");
+        assert_eq!(
+            host_to_base_url("https://ollama.example.com"),
+            "https://ollama.example.com"
+        );
+    }
+
+    async fn spawn_server(
+        status: &'static str,
+        content_type: &'static str,
+        body: &str,
+    ) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = tokio::sync::oneshot::channel();
+        let body = body.to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 16 * 1024];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let _ = request_tx.send(String::from_utf8_lossy(&buffer[..read]).into_owned());
+            let response = format!(
+                "HTTP/1.1 {status}\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        (format!("http://{addr}"), request_rx)
+    }
+}
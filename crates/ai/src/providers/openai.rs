@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use serde_json::Value;
 
 use crate::env_api_keys::{KnownProvider, get_env_api_key};
 use crate::event_stream::AssistantEventStream;
@@ -8,12 +10,14 @@ use crate::provider::{
     ImageModelApi, LanguageModelApi, ModelBuilder, Provider, ProviderCapabilities,
 };
 use crate::providers::{
-    openai_completions, openai_embeddings, openai_images, openai_responses, simple_options,
+    openai_audio, openai_completions, openai_embeddings, openai_images, openai_responses,
+    simple_options,
 };
 use crate::types::{
     AssistantImages, Context, ImageGenerationOptions, ImagesContext, Model, ModelCompat,
     ModelInput, ModelOutput, OpenAIResponsesCompat, SimpleStreamOptions, StreamOptions,
 };
+use crate::utils::http::{get_json, join_url_path};
 use crate::{Error, Result};
 
 const DEFAULT_PROVIDER_ID: KnownProvider = KnownProvider::OpenAi;
@@ -71,6 +75,31 @@ impl OpenAi {
         self.embedding_model_builder(id)
     }
 
+    /// A speech-to-text model such as `whisper-1` or `gpt-4o-transcribe`.
+    pub fn transcription_model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(openai_audio::OpenAiTranscriptionModelApi::new(
+            self.api_key.clone(),
+            self.api_key.is_none() && self.base_url != DEFAULT_BASE_URL,
+            self.http_client.clone(),
+        ));
+        ModelBuilder::new_transcription(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Audio])
+    }
+
+    /// A text-to-speech model such as `tts-1` or `gpt-4o-mini-tts`.
+    pub fn speech_model(&self, id: &str) -> ModelBuilder {
+        let runtime = Arc::new(openai_audio::OpenAiSpeechModelApi::new(
+            self.api_key.clone(),
+            self.api_key.is_none() && self.base_url != DEFAULT_BASE_URL,
+            self.http_client.clone(),
+        ));
+        ModelBuilder::new_speech(&self.provider_id, id, runtime)
+            .base_url(self.base_url.clone())
+            .input(vec![ModelInput::Text])
+            .output(vec![ModelOutput::Audio])
+    }
+
     fn embedding_model_builder(&self, id: &str) -> ModelBuilder {
         let runtime = Arc::new(openai_embeddings::OpenAiEmbeddingModelApi::new(
             self.api_key.clone(),
@@ -90,11 +119,12 @@ impl OpenAi {
         });
         ModelBuilder::new_image(&self.provider_id, id, runtime)
             .base_url(self.base_url.clone())
-            .input(vec![ModelInput::Text])
+            .input(vec![ModelInput::Text, ModelInput::Image])
             .output(vec![ModelOutput::Image])
     }
 }
 
+#[async_trait]
 impl Provider for OpenAi {
     fn id(&self) -> &str {
         &self.provider_id
@@ -105,6 +135,10 @@ impl Provider for OpenAi {
             language_models: !matches!(self.api, OpenAiApi::Embeddings | OpenAiApi::Images),
             image_models: true,
             embedding_models: true,
+            fim_models: false,
+            rerank_models: false,
+            transcription_models: true,
+            speech_models: true,
         }
     }
 
@@ -133,13 +167,106 @@ impl Provider for OpenAi {
                 ..Default::default()
             };
         }
+        let mut input = vec![ModelInput::Text, ModelInput::Image];
+        // Compatible servers rarely accept files, so only OpenAI itself
+        // advertises PDF input.
+        if self.base_url == DEFAULT_BASE_URL {
+            input.push(ModelInput::Pdf);
+        }
+        let mut output = vec![ModelOutput::Text];
+        // Only Chat Completions carries audio in and out of chat models.
+        if self.api == OpenAiApi::ChatCompletions && is_audio_chat_model(id) {
+            input.push(ModelInput::Audio);
+            output.push(ModelOutput::Audio);
+        }
         ModelBuilder::new(&self.provider_id, id, runtime)
             .base_url(self.base_url.clone())
-            .input(vec![ModelInput::Text, ModelInput::Image])
+            .input(input)
+            .output(output)
             .context_window(1_000_000)
             .max_tokens(16_384)
             .compat(compat)
     }
+
+    /// Lists `/models`. Embedding, image, transcription, and speech models
+    /// are bound to their own APIs; audio chat, moderation, realtime, and
+    /// legacy completion models are skipped. Context windows are read from the
+    /// `context_window`, `context_length`, or `max_model_len` fields that
+    /// compatible servers such as vLLM report.
+    async fn list_models(&self) -> Result<Vec<Model>> {
+        let mut headers = HeaderMap::new();
+        if let Some(api_key) = &self.api_key {
+            headers.insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {api_key}")).map_err(|error| {
+                    Error::InvalidHeaderValue("authorization".to_string(), error)
+                })?,
+            );
+        } else if self.base_url == DEFAULT_BASE_URL {
+            return Err(Error::MissingApiKey(self.provider_id.clone()));
+        }
+        let client = self.http_client.clone().unwrap_or_default();
+        let body = get_json(&client, &join_url_path(&self.base_url, "models"), headers).await?;
+        let mut models = Vec::new();
+        for entry in body
+            .get("data")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some(id) = entry.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            if is_unsupported_listed_model(id) {
+                continue;
+            }
+            let mut builder = if is_embedding_model(id) {
+                self.embedding_model_builder(id)
+            } else if is_image_model(id) {
+                self.image_model_builder(id)
+            } else if is_transcription_model(id) {
+                self.transcription_model(id)
+            } else if is_speech_model(id) {
+                self.speech_model(id)
+            } else {
+                self.model(id)
+            };
+            if let Some(context_window) = ["context_window", "context_length", "max_model_len"]
+                .into_iter()
+                .find_map(|field| entry.get(field).and_then(Value::as_u64))
+            {
+                builder = builder.context_window(context_window as u32);
+            }
+            models.push(builder.build()?);
+        }
+        Ok(models)
+    }
+}
+
+fn is_embedding_model(id: &str) -> bool {
+    id.contains("embedding")
+}
+
+fn is_image_model(id: &str) -> bool {
+    id.starts_with("dall-e") || id.starts_with("gpt-image")
+}
+
+fn is_transcription_model(id: &str) -> bool {
+    id.starts_with("whisper") || id.contains("transcribe")
+}
+
+fn is_speech_model(id: &str) -> bool {
+    id.starts_with("tts") || id.ends_with("-tts") || id.contains("-tts-")
+}
+
+fn is_audio_chat_model(id: &str) -> bool {
+    id.starts_with("gpt-audio") || id.contains("-audio-preview")
+}
+
+fn is_unsupported_listed_model(id: &str) -> bool {
+    ["audio", "realtime", "moderation", "davinci", "babbage"]
+        .iter()
+        .any(|marker| id.contains(marker))
 }
 
 fn is_gpt_5_or_newer(id: &str) -> bool {
@@ -374,10 +501,89 @@ pub fn from_env() -> Result<OpenAi> {
 
 #[cfg(test)]
 mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
     use crate::types::Context;
 
     use super::*;
 
+    async fn spawn_json_server(
+        body: &'static str,
+    ) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let _ = request_tx.send(String::from_utf8_lossy(&buffer[..read]).into_owned());
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        (format!("http://{addr}"), request_rx)
+    }
+
+    #[tokio::test]
+    async fn list_models_binds_each_model_to_its_api() {
+        let (base_url, request) = spawn_json_server(
+            r#"{
+                "object": "list",
+                "data": [
+                    { "id": "gpt-4.1", "object": "model" },
+                    { "id": "text-embedding-3-small", "object": "model" },
+                    { "id": "gpt-image-1", "object": "model" },
+                    { "id": "whisper-1", "object": "model" },
+                    { "id": "gpt-4o-mini-tts", "object": "model" },
+                    { "id": "gpt-4o-realtime-preview", "object": "model" },
+                    { "id": "Qwen/Qwen3-8B", "object": "model", "max_model_len": 32768 }
+                ]
+            }"#,
+        )
+        .await;
+        let provider = builder()
+            .api_key(Some("test-token"))
+            .base_url(base_url)
+            .build()
+            .expect("provider");
+
+        let models = provider.list_models().await.expect("list models");
+
+        assert_eq!(
+            models
+                .iter()
+                .map(|model| (model.id.as_str(), model.api.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("gpt-4.1", "openai-responses"),
+                ("text-embedding-3-small", "openai-embeddings"),
+                ("gpt-image-1", "openai-images"),
+                ("whisper-1", "openai-transcriptions"),
+                ("gpt-4o-mini-tts", "openai-speech"),
+                ("Qwen/Qwen3-8B", "openai-responses"),
+            ]
+        );
+        assert_eq!(models[5].context_window, 32_768);
+        let request = request.await.unwrap();
+        assert!(request.starts_with("GET /models "));
+        assert!(request.contains("authorization: Bearer test-token"));
+    }
+
+    #[tokio::test]
+    async fn list_models_requires_an_api_key_for_the_default_endpoint() {
+        let provider = builder().build().expect("provider");
+
+        assert!(matches!(
+            provider.list_models().await,
+            Err(Error::MissingApiKey(_))
+        ));
+    }
+
     #[test]
     fn responses_models_apply_pi_constrained_sampling_metadata() {
         let openai = builder()
@@ -401,6 +607,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn chat_completions_audio_models_accept_and_produce_audio() {
+        let chat = builder()
+            .api_key(Some("test-token"))
+            .chat_completions()
+            .build()
+            .expect("provider");
+        let responses = builder()
+            .api_key(Some("test-token"))
+            .build()
+            .expect("provider");
+
+        let audio = chat.model("gpt-4o-audio-preview").build().expect("model");
+        assert!(audio.input.contains(&ModelInput::Audio));
+        assert_eq!(audio.output, vec![ModelOutput::Text, ModelOutput::Audio]);
+        let text = chat.model("gpt-4.1").build().expect("model");
+        assert_eq!(text.output, vec![ModelOutput::Text]);
+        let responses_audio = responses
+            .model("gpt-4o-audio-preview")
+            .build()
+            .expect("model");
+        assert!(!responses_audio.input.contains(&ModelInput::Audio));
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn model_carries_runtime_dispatch() {
         let openai = builder()
@@ -0,0 +1,553 @@
+use async_stream::try_stream;
+use async_trait::async_trait;
+use base64::Engine;
+use futures::StreamExt;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::provider::{SpeechModelApi, TranscriptionModelApi};
+use crate::types::{
+    AudioContent, GeneratedSpeech, KnownApi, Model, ProviderResponse, RequestOptions,
+    SpeechOptions, Transcription, TranscriptionOptions,
+};
+use crate::utils::circuit_breaker::model_circuit;
+use crate::utils::headers::headers_to_record;
+use crate::utils::http::{join_url_path, request_timeout, send_request_with_retries};
+use crate::{Error, Result};
+
+#[derive(Clone)]
+pub(crate) struct OpenAiTranscriptionModelApi {
+    api_key: Option<String>,
+    allow_missing_api_key: bool,
+    http_client: Option<reqwest::Client>,
+}
+
+impl OpenAiTranscriptionModelApi {
+    pub(crate) fn new(
+        api_key: Option<String>,
+        allow_missing_api_key: bool,
+        http_client: Option<reqwest::Client>,
+    ) -> Self {
+        Self {
+            api_key,
+            allow_missing_api_key,
+            http_client,
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionModelApi for OpenAiTranscriptionModelApi {
+    fn id(&self) -> &str {
+        KnownApi::OpenaiTranscriptions.as_str()
+    }
+
+    async fn transcribe(
+        &self,
+        model: Model,
+        audio: AudioContent,
+        mut options: TranscriptionOptions,
+    ) -> Result<Transcription> {
+        with_runtime_options(&mut options.base, &self.api_key, &self.http_client);
+        transcribe_openai(model, audio, options, self.allow_missing_api_key).await
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct OpenAiSpeechModelApi {
+    api_key: Option<String>,
+    allow_missing_api_key: bool,
+    http_client: Option<reqwest::Client>,
+}
+
+impl OpenAiSpeechModelApi {
+    pub(crate) fn new(
+        api_key: Option<String>,
+        allow_missing_api_key: bool,
+        http_client: Option<reqwest::Client>,
+    ) -> Self {
+        Self {
+            api_key,
+            allow_missing_api_key,
+            http_client,
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechModelApi for OpenAiSpeechModelApi {
+    fn id(&self) -> &str {
+        KnownApi::OpenaiSpeech.as_str()
+    }
+
+    async fn generate_speech(
+        &self,
+        model: Model,
+        text: String,
+        voice: String,
+        mut options: SpeechOptions,
+    ) -> Result<GeneratedSpeech> {
+        with_runtime_options(&mut options.base, &self.api_key, &self.http_client);
+        generate_speech_openai(model, text, voice, options, self.allow_missing_api_key).await
+    }
+}
+
+fn with_runtime_options(
+    options: &mut RequestOptions,
+    api_key: &Option<String>,
+    http_client: &Option<reqwest::Client>,
+) {
+    if options
+        .api_key
+        .as_deref()
+        .is_none_or(|api_key| api_key.trim().is_empty())
+        && let Some(api_key) = api_key
+    {
+        options.api_key = Some(api_key.clone());
+    }
+    if options.http_client.is_none() {
+        options.http_client = http_client.clone();
+    }
+}
+
+/// Sends `audio` to `/audio/transcriptions` as a multipart upload. The
+/// payload hook sees the form fields, without the file, as a JSON object.
+async fn transcribe_openai(
+    model: Model,
+    audio: AudioContent,
+    options: TranscriptionOptions,
+    allow_missing_api_key: bool,
+) -> Result<Transcription> {
+    check_api(&model, KnownApi::OpenaiTranscriptions)?;
+    let api_key = api_key(&model, &options.base, allow_missing_api_key)?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(&audio.data)
+        .map_err(|error| Error::Validation(format!("audio is not valid base64: {error}")))?;
+    let mut fields = Map::new();
+    fields.insert("model".to_string(), json!(model.id));
+    fields.insert("response_format".to_string(), json!("json"));
+    if let Some(language) = &options.language {
+        fields.insert("language".to_string(), json!(language));
+    }
+    if let Some(prompt) = &options.prompt {
+        fields.insert("prompt".to_string(), json!(prompt));
+    }
+    if let Some(temperature) = options.temperature {
+        fields.insert("temperature".to_string(), json!(temperature));
+    }
+    let fields = apply_payload_hook(fields, &model, &options.base, "transcription").await?;
+
+    let client = options.base.http_client.clone().unwrap_or_default();
+    let url = join_url_path(&model.base_url, "audio/transcriptions");
+    let headers = build_headers(api_key, &model.headers, &options.base.headers)?;
+    // OpenAI detects the audio format from the file name.
+    let file_name = format!("audio.{}", file_extension(&audio.mime_type));
+    let response = send_request_with_retries(&options.base, &model_circuit(&model), || {
+        let mut form = Form::new();
+        for (name, value) in &fields {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            form = form.text(name.clone(), value);
+        }
+        let file = Part::bytes(bytes.clone()).file_name(file_name.clone());
+        client
+            .post(&url)
+            .headers(headers.clone())
+            .multipart(form.part("file", file))
+            .timeout(request_timeout(options.base.timeout_ms))
+    })
+    .await?;
+    let status = response.status();
+    let response_headers = response.headers().clone();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(Error::ApiStatus { status, body });
+    }
+    notify_response(status, &response_headers, &model, &options.base).await?;
+
+    let response: OpenAiTranscriptionResponse = serde_json::from_str(&body).map_err(|error| {
+        Error::InvalidProviderResponse(format!("could not decode transcription response: {error}"))
+    })?;
+    Ok(Transcription {
+        text: response.text,
+        language: response.language,
+        duration: response.duration,
+        model: model.id,
+    })
+}
+
+/// Posts to `/audio/speech` and hands back the body as it arrives.
+async fn generate_speech_openai(
+    model: Model,
+    text: String,
+    voice: String,
+    options: SpeechOptions,
+    allow_missing_api_key: bool,
+) -> Result<GeneratedSpeech> {
+    check_api(&model, KnownApi::OpenaiSpeech)?;
+    let api_key = api_key(&model, &options.base, allow_missing_api_key)?;
+    let mut payload = Map::new();
+    payload.insert("model".to_string(), json!(model.id));
+    payload.insert("input".to_string(), json!(text));
+    payload.insert("voice".to_string(), json!(voice));
+    payload.insert(
+        "response_format".to_string(),
+        json!(options.format.as_str()),
+    );
+    if let Some(speed) = options.speed {
+        payload.insert("speed".to_string(), json!(speed));
+    }
+    if let Some(instructions) = &options.instructions {
+        payload.insert("instructions".to_string(), json!(instructions));
+    }
+    let payload = apply_payload_hook(payload, &model, &options.base, "speech").await?;
+
+    let client = options.base.http_client.clone().unwrap_or_default();
+    let url = join_url_path(&model.base_url, "audio/speech");
+    let headers = build_headers(api_key, &model.headers, &options.base.headers)?;
+    let response = send_request_with_retries(&options.base, &model_circuit(&model), || {
+        client
+            .post(&url)
+            .headers(headers.clone())
+            .json(&payload)
+            .timeout(request_timeout(options.base.timeout_ms))
+    })
+    .await?;
+    let status = response.status();
+    let response_headers = response.headers().clone();
+    if !status.is_success() {
+        let body = response.text().await?;
+        return Err(Error::ApiStatus { status, body });
+    }
+    notify_response(status, &response_headers, &model, &options.base).await?;
+
+    let cancellation_token = options.base.cancellation_token.clone();
+    let audio = try_stream! {
+        let mut chunks = response.bytes_stream();
+        loop {
+            let chunk = if let Some(cancellation_token) = cancellation_token.as_ref() {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => Err(Error::Cancelled),
+                    chunk = chunks.next() => Ok(chunk),
+                }?
+            } else {
+                chunks.next().await
+            };
+            let Some(chunk) = chunk else {
+                break;
+            };
+            yield chunk?.to_vec();
+        }
+    };
+    Ok(GeneratedSpeech {
+        mime_type: options.format.mime_type().to_string(),
+        audio: Box::pin(audio),
+    })
+}
+
+fn check_api(model: &Model, expected: KnownApi) -> Result<()> {
+    if model.api != expected.as_str() {
+        return Err(Error::UnsupportedApi(format!(
+            "Mismatched api: {} expected {}",
+            model.api,
+            expected.as_str()
+        )));
+    }
+    Ok(())
+}
+
+fn api_key<'a>(
+    model: &Model,
+    options: &'a RequestOptions,
+    allow_missing_api_key: bool,
+) -> Result<Option<&'a str>> {
+    let api_key = options
+        .api_key
+        .as_deref()
+        .filter(|api_key| !api_key.trim().is_empty());
+    if api_key.is_none() && !allow_missing_api_key {
+        return Err(Error::MissingApiKey(model.provider.clone()));
+    }
+    Ok(api_key)
+}
+
+async fn apply_payload_hook(
+    payload: Map<String, Value>,
+    model: &Model,
+    options: &RequestOptions,
+    kind: &str,
+) -> Result<Map<String, Value>> {
+    let Some(on_payload) = &options.on_payload else {
+        return Ok(payload);
+    };
+    match on_payload(Value::Object(payload.clone()), model).await? {
+        Some(next_payload) => next_payload.as_object().cloned().ok_or_else(|| {
+            Error::Provider(format!(
+                "OpenAI {kind} payload hook must return a JSON object"
+            ))
+        }),
+        None => Ok(payload),
+    }
+}
+
+async fn notify_response(
+    status: reqwest::StatusCode,
+    headers: &HeaderMap,
+    model: &Model,
+    options: &RequestOptions,
+) -> Result<()> {
+    if let Some(on_response) = &options.on_response {
+        on_response(
+            ProviderResponse {
+                status: status.as_u16(),
+                headers: headers_to_record(headers),
+            },
+            model,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+fn file_extension(mime_type: &str) -> &str {
+    match mime_type {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" => "m4a",
+        "audio/ogg" | "audio/opus" => "ogg",
+        other => other.rsplit('/').next().unwrap_or(other),
+    }
+}
+
+fn build_headers(
+    api_key: Option<&str>,
+    model_headers: &std::collections::HashMap<String, String>,
+    option_headers: &crate::types::ProviderHeaders,
+) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    if let Some(api_key) = api_key {
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {api_key}"))
+                .map_err(|error| Error::InvalidHeaderValue("authorization".to_string(), error))?,
+        );
+    }
+    for (name, value) in model_headers {
+        let name = name
+            .parse::<HeaderName>()
+            .map_err(|error| Error::Provider(format!("invalid header name: {error}")))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|error| Error::InvalidHeaderValue(name.as_str().to_string(), error))?;
+        headers.insert(name, value);
+    }
+    crate::utils::headers::apply_provider_headers(&mut headers, option_headers)?;
+    Ok(headers)
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiTranscriptionResponse {
+    text: String,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    duration: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use crate::providers::openai;
+    use crate::types::SpeechFormat;
+
+    use super::*;
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn transcription_uploads_a_named_file_with_form_fields() {
+        let captured = Arc::new(Mutex::new(String::new()));
+        let url = spawn_response_server(
+            Arc::clone(&captured),
+            "application/json",
+            br#"{"text":"Hello there.","language":"english","duration":1.5}"#,
+        )
+        .await;
+        let provider = openai::builder()
+            .api_key(Some("test-key"))
+            .base_url(url)
+            .build()
+            .expect("provider");
+        let model = provider
+            .transcription_model("whisper-1")
+            .build_transcription()
+            .expect("model");
+        let audio = AudioContent {
+            data: base64::engine::general_purpose::STANDARD.encode(b"RIFF-audio"),
+            mime_type: "audio/x-wav".to_string(),
+        };
+        let options = TranscriptionOptions {
+            language: Some("en".to_string()),
+            ..Default::default()
+        };
+
+        let transcription = crate::transcribe(model, audio, Some(options))
+            .await
+            .expect("transcription");
+
+        assert_eq!(
+            transcription,
+            Transcription {
+                text: "Hello there.".to_string(),
+                language: Some("english".to_string()),
+                duration: Some(1.5),
+                model: "whisper-1".to_string(),
+            }
+        );
+        let request = captured.lock().expect("request").clone();
+        assert!(request.starts_with("POST /v1/audio/transcriptions HTTP/1.1"));
+        assert!(
+            request
+                .to_ascii_lowercase()
+                .contains("content-type: multipart/form-data; boundary=")
+        );
+        assert!(request.contains("name=\"model\"\r\n\r\nwhisper-1\r\n"));
+        assert!(request.contains("name=\"language\"\r\n\r\nen\r\n"));
+        assert!(request.contains("name=\"file\"; filename=\"audio.wav\""));
+        assert!(request.contains("RIFF-audio"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn speech_streams_the_response_body() {
+        let captured = Arc::new(Mutex::new(String::new()));
+        let url = spawn_response_server(Arc::clone(&captured), "audio/wav", b"RIFF....WAVE").await;
+        let provider = openai::builder()
+            .api_key(Some("test-key"))
+            .base_url(url)
+            .build()
+            .expect("provider");
+        let model = provider
+            .speech_model("gpt-4o-mini-tts")
+            .build_speech()
+            .expect("model");
+        let options = SpeechOptions {
+            format: SpeechFormat::Wav,
+            speed: Some(1.25),
+            ..Default::default()
+        };
+
+        let speech = crate::generate_speech(model, "Hello there.", "alloy", Some(options))
+            .await
+            .expect("speech");
+
+        assert_eq!(speech.mime_type, "audio/wav");
+        assert_eq!(speech.bytes().await.expect("audio"), b"RIFF....WAVE");
+        let request = captured.lock().expect("request").clone();
+        assert!(request.starts_with("POST /v1/audio/speech HTTP/1.1"));
+        let payload: Value =
+            serde_json::from_str(request.split_once("\r\n\r\n").expect("body").1).expect("json");
+        assert_eq!(
+            payload,
+            json!({
+                "model": "gpt-4o-mini-tts",
+                "input": "Hello there.",
+                "voice": "alloy",
+                "response_format": "wav",
+                "speed": 1.25
+            })
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn speech_surfaces_api_errors() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.expect("accept");
+            read_http_request(&mut socket).await;
+            let body = r#"{"error":{"message":"bad voice"}}"#;
+            let response = format!(
+                "HTTP/1.1 400 Bad Request\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            socket
+                .write_all(response.as_bytes())
+                .await
+                .expect("write response");
+        });
+        let provider = openai::builder()
+            .api_key(Some("test-key"))
+            .base_url(format!("http://{addr}/v1"))
+            .build()
+            .expect("provider");
+        let model = provider
+            .speech_model("tts-1")
+            .build_speech()
+            .expect("model");
+
+        let error = crate::generate_speech(model, "Hi", "nobody", None)
+            .await
+            .err()
+            .expect("bad request should fail");
+
+        assert!(
+            matches!(error, Error::ApiStatus { status, body } if status == 400 && body.contains("bad voice"))
+        );
+    }
+
+    async fn spawn_response_server(
+        captured: Arc<Mutex<String>>,
+        content_type: &'static str,
+        body: &'static [u8],
+    ) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.expect("accept");
+            let request = read_http_request(&mut socket).await;
+            *captured.lock().expect("captured request") = request;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.expect("write head");
+            socket.write_all(body).await.expect("write body");
+        });
+        format!("http://{addr}/v1")
+    }
+
+    async fn read_http_request(socket: &mut tokio::net::TcpStream) -> String {
+        let mut buffer = Vec::new();
+        let mut temp = [0; 1024];
+        loop {
+            let read = socket.read(&mut temp).await.expect("read request");
+            buffer.extend_from_slice(&temp[..read]);
+            if let Some(header_end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                let headers = String::from_utf8_lossy(&buffer[..header_end]);
+                let content_length = headers
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())
+                            .flatten()
+                    })
+                    .unwrap_or_default();
+                while buffer.len() < header_end + 4 + content_length {
+                    let read = socket.read(&mut temp).await.expect("read body");
+                    buffer.extend_from_slice(&temp[..read]);
+                }
+                break;
+            }
+        }
+        String::from_utf8_lossy(&buffer).into_owned()
+    }
+}
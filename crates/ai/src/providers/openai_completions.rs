@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 
+use base64::Engine;
 use futures::{StreamExt, pin_mut};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
 use serde_json::{Value, json};
 
 use crate::event_stream::AssistantMessageEventStreamSender;
 use crate::models::{calculate_cost, clamp_thinking_level};
+use crate::providers::azure_openai;
 use crate::providers::constrained_sampling::{
     GrammarToolInputJsonBuffer, append_grammar_tool_input_json_delta,
     create_grammar_tool_input_properties, get_grammar_tool_input, grammar_arguments,
@@ -14,19 +16,24 @@ use crate::providers::constrained_sampling::{
 use crate::providers::github_copilot_headers::{
     build_copilot_dynamic_headers, has_copilot_vision_input,
 };
+use crate::providers::logprobs;
 use crate::providers::openai_prompt_cache::clamp_openai_prompt_cache_key;
+use crate::providers::sampling::{SamplingSupport, sampling};
 use crate::providers::simple_options::build_base_options;
 use crate::providers::transform_messages::transform_messages;
 use crate::types::{
-    AssistantContent, AssistantMessage, AssistantMessageEvent, CacheControlFormat, CacheRetention,
-    ChatTemplateKwargValue, ChatTemplateVariable, Context, DeferredToolsMode, ImageContent,
-    MaxTokensField, Model, ModelInput, ModelThinkingLevel, OpenAIThinkingFormat,
+    AssistantAudioContent, AssistantContent, AssistantMessage, AssistantMessageEvent, AudioContent,
+    CacheControlFormat, CacheRetention, ChatTemplateKwargValue, ChatTemplateVariable, Context,
+    DeferredToolsMode, DocumentContent, DocumentSource, ImageContent, MaxTokensField, Model,
+    ModelInput, ModelOutput, ModelThinkingLevel, OpenAIThinkingFormat, SeedField,
     SessionAffinityFormat, SimpleStreamOptions, StopReason, StreamOptions, TextContent,
-    ThinkingContent, Tool, ToolCall, ToolResultContent, Usage, UserContent, UserMessageContent,
+    ThinkingContent, Tool, ToolCall, ToolChoice, ToolResultContent, Usage, UserContent,
+    UserMessageContent,
 };
+use crate::utils::circuit_breaker::model_circuit;
 use crate::utils::hash::short_hash;
 use crate::utils::headers::{apply_provider_headers, has_non_empty_header};
-use crate::utils::http::{request_timeout, send_with_retries};
+use crate::utils::http::{join_url_path, request_timeout, send_with_retries};
 use crate::utils::json::parse_streaming_json;
 use crate::utils::provider_env::get_provider_env_value;
 use crate::utils::sse;
@@ -46,6 +53,7 @@ struct ResolvedOpenAICompletionsCompat {
     supports_reasoning_effort: bool,
     supports_usage_in_streaming: bool,
     max_tokens_field: MaxTokensField,
+    seed_field: SeedField,
     requires_tool_result_name: bool,
     requires_assistant_after_tool_result: bool,
     requires_thinking_as_text: bool,
@@ -200,7 +208,7 @@ async fn run_stream(
         Ok(base_url) => base_url,
         Err(error) => return Err(StreamFailure::new(output, error)),
     };
-    let request_url = format!("{}/chat/completions", trim_end_slash(&base_url));
+    let request_url = join_url_path(&base_url, "chat/completions");
     let request_headers = match headers(
         &model,
         &context,
@@ -213,7 +221,7 @@ async fn run_stream(
         Err(error) => return Err(StreamFailure::new(output, error)),
     };
     let client = options.base.http_client.clone().unwrap_or_default();
-    let response = match send_with_retries(&options.base, || {
+    let response = match send_with_retries(&options.base, &model_circuit(&model), || {
         client
             .post(request_url.as_str())
             .headers(request_headers.clone())
@@ -224,11 +232,20 @@ async fn run_stream(
     {
         Ok(response) => response,
         Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
-        Err(error) => return Err(StreamFailure::new(output, error)),
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
     };
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
+        output.error_status = Some(status.as_u16());
+        if azure_openai::is_azure_openai(&model)
+            && let Some(message) = azure_openai::content_filter_error(status, &body)
+        {
+            return Err(StreamFailure::new(output, message));
+        }
         return Err(StreamFailure::new(
             output,
             Error::ApiStatus { status, body },
@@ -250,6 +267,7 @@ async fn run_stream(
 
     let mut text_block: Option<usize> = None;
     let mut thinking_block: Option<usize> = None;
+    let mut audio_block: Option<(usize, Vec<u8>)> = None;
     let mut has_finish_reason = false;
     let mut tool_blocks_by_index: HashMap<i64, usize> = HashMap::new();
     let mut tool_blocks_by_id: HashMap<String, usize> = HashMap::new();
@@ -324,6 +342,16 @@ async fn run_stream(
             let mapped = map_stop_reason(reason);
             output.stop_reason = mapped.0;
             output.error_message = mapped.1;
+            if reason == "content_filter"
+                && azure_openai::is_azure_openai(&model)
+                && let Some(categories) = choice
+                    .get("content_filter_results")
+                    .and_then(azure_openai::filtered_categories)
+            {
+                output.error_message = Some(format!(
+                    "Provider finish_reason: content_filter ({categories})"
+                ));
+            }
             has_finish_reason = true;
         }
 
@@ -345,6 +373,56 @@ async fn run_stream(
                 delta: content.to_string(),
                 partial: output.clone(),
             });
+            let token_logprobs =
+                logprobs::parse_token_logprobs(choice.pointer("/logprobs/content"));
+            if !token_logprobs.is_empty() {
+                sender.push(AssistantMessageEvent::TextLogprobs {
+                    content_index: index,
+                    logprobs: token_logprobs,
+                    partial: output.clone(),
+                });
+            }
+        }
+
+        if let Some(audio) = delta.get("audio").and_then(Value::as_object) {
+            let index = ensure_audio_block(&mut output, &mut audio_block, sender);
+            let data = audio
+                .get("data")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let transcript = audio
+                .get("transcript")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if let Some((_, bytes)) = audio_block.as_mut() {
+                let chunk = base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .map_err(|error| {
+                        StreamFailure::new(output.clone(), format!("invalid audio chunk: {error}"))
+                    })?;
+                bytes.extend(chunk);
+            }
+            if let Some(AssistantContent::Audio(block)) = output.content.get_mut(index) {
+                if let Some(id) = audio
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .filter(|id| !id.is_empty())
+                {
+                    block.id = id.to_string();
+                }
+                if let Some(expires_at) = audio.get("expires_at").and_then(Value::as_u64) {
+                    block.expires_at = Some(expires_at);
+                }
+                block.transcript.push_str(transcript);
+            }
+            if !data.is_empty() || !transcript.is_empty() {
+                sender.push(AssistantMessageEvent::AudioDelta {
+                    content_index: index,
+                    data: data.to_string(),
+                    transcript: transcript.to_string(),
+                    partial: output.clone(),
+                });
+            }
         }
 
         let reasoning_field = ["reasoning_content", "reasoning", "reasoning_text"]
@@ -497,8 +575,14 @@ async fn run_stream(
         }
     }
 
-    finish_open_blocks(&mut output, &partial_args, &mut custom_tool_inputs, sender)
-        .map_err(|error| StreamFailure::new(output.clone(), error))?;
+    finish_open_blocks(
+        &mut output,
+        &partial_args,
+        &mut custom_tool_inputs,
+        audio_block,
+        sender,
+    )
+    .map_err(|error| StreamFailure::new(output.clone(), error))?;
 
     if options
         .base
@@ -580,6 +664,34 @@ fn ensure_thinking_block(
     index
 }
 
+/// Audio chunks are separately encoded, so the decoded bytes are collected
+/// next to the block and encoded once when it ends.
+fn ensure_audio_block(
+    output: &mut AssistantMessage,
+    audio_block: &mut Option<(usize, Vec<u8>)>,
+    sender: &mut AssistantMessageEventStreamSender,
+) -> usize {
+    if let Some((index, _)) = audio_block {
+        return *index;
+    }
+    output
+        .content
+        .push(AssistantContent::Audio(AssistantAudioContent {
+            id: String::new(),
+            data: String::new(),
+            mime_type: "audio/pcm".to_string(),
+            transcript: String::new(),
+            expires_at: None,
+        }));
+    let index = output.content.len() - 1;
+    *audio_block = Some((index, Vec::new()));
+    sender.push(AssistantMessageEvent::AudioStart {
+        content_index: index,
+        partial: output.clone(),
+    });
+    index
+}
+
 fn ensure_tool_call_block(
     output: &mut AssistantMessage,
     tool_call_delta: &Value,
@@ -678,6 +790,7 @@ fn finish_open_blocks(
     output: &mut AssistantMessage,
     partial_args: &HashMap<usize, String>,
     custom_tool_inputs: &mut HashMap<usize, (String, GrammarToolInputJsonBuffer)>,
+    audio_block: Option<(usize, Vec<u8>)>,
     sender: &mut AssistantMessageEventStreamSender,
 ) -> Result<()> {
     for index in 0..output.content.len() {
@@ -696,6 +809,17 @@ fn finish_open_blocks(
                     partial: output.clone(),
                 });
             }
+            Some(AssistantContent::Audio(block)) => {
+                if let Some((_, bytes)) = audio_block.as_ref().filter(|(at, _)| *at == index) {
+                    block.data = base64::engine::general_purpose::STANDARD.encode(bytes);
+                }
+                let content = block.clone();
+                sender.push(AssistantMessageEvent::AudioEnd {
+                    content_index: index,
+                    content,
+                    partial: output.clone(),
+                });
+            }
             Some(AssistantContent::ToolCall(block)) => {
                 let mut custom_delta = None;
                 if let Some((input_property, buffer)) = custom_tool_inputs.get_mut(&index) {
@@ -787,6 +911,33 @@ fn try_build_chat_completions_payload(
     if let Some(temperature) = options.base.temperature {
         object.insert("temperature".to_string(), json!(temperature));
     }
+    // Chat Completions has no `top_k`; compatible servers that accept it opt
+    // in through `supportsTopK`.
+    sampling(
+        model,
+        &options.base,
+        SamplingSupport {
+            top_k: false,
+            ..SamplingSupport::ALL
+        },
+    )
+    .insert_into(object, "stop");
+    if compat.seed_field == SeedField::RandomSeed
+        && let Some(seed) = object.remove("seed")
+    {
+        object.insert("random_seed".to_string(), seed);
+    }
+    logprobs::insert_chat_fields(object, &options.base);
+    if let Some(audio_output) = &options.base.audio_output
+        && model.output.contains(&ModelOutput::Audio)
+    {
+        object.insert("modalities".to_string(), json!(["text", "audio"]));
+        // Streaming only supports raw PCM.
+        object.insert(
+            "audio".to_string(),
+            json!({ "voice": audio_output.voice, "format": "pcm16" }),
+        );
+    }
     let deferred_names = if compat.deferred_tools_mode == Some(DeferredToolsMode::Kimi) {
         context
             .messages
@@ -819,6 +970,30 @@ fn try_build_chat_completions_payload(
     }
     if let Some(tool_choice) = &options.tool_choice {
         object.insert("tool_choice".to_string(), tool_choice.clone());
+    } else if let Some(tool_choice) = &options.base.tool_choice {
+        object.insert("tool_choice".to_string(), convert_tool_choice(tool_choice));
+    }
+    if let Some(parallel_tool_calls) = options.base.parallel_tool_calls
+        && !active_tools.is_empty()
+    {
+        object.insert(
+            "parallel_tool_calls".to_string(),
+            json!(parallel_tool_calls),
+        );
+    }
+    if let Some(format) = &options.base.response_format {
+        let mut json_schema = json!({
+            "name": format.name,
+            "schema": format.schema,
+            "strict": format.strict,
+        });
+        if let Some(description) = &format.description {
+            json_schema["description"] = json!(description);
+        }
+        object.insert(
+            "response_format".to_string(),
+            json!({ "type": "json_schema", "json_schema": json_schema }),
+        );
     }
 
     apply_reasoning_options(object, model, options, compat);
@@ -1100,6 +1275,8 @@ fn try_convert_messages(
                                     "type": "image_url",
                                     "image_url": { "url": format!("data:{};base64,{}", image.mime_type, image.data) }
                                 }),
+                                UserContent::Document(document) => convert_file_part(document),
+                                UserContent::Audio(audio) => convert_input_audio_part(audio),
                             })
                             .collect();
                     if !content.is_empty() {
@@ -1121,6 +1298,12 @@ fn try_convert_messages(
                         AssistantContent::Text(text) if !text.text.trim().is_empty() => {
                             Some(json!({ "type": "text", "text": &text.text }))
                         }
+                        AssistantContent::Audio(audio)
+                            if audio_reference(audio).is_none()
+                                && !audio.transcript.trim().is_empty() =>
+                        {
+                            Some(json!({ "type": "text", "text": &audio.transcript }))
+                        }
                         _ => None,
                     })
                     .collect();
@@ -1175,6 +1358,13 @@ fn try_convert_messages(
                     assistant_obj.insert("content".to_string(), json!(assistant_text));
                 }
 
+                if let Some(audio_id) = assistant.content.iter().find_map(|block| match block {
+                    AssistantContent::Audio(audio) => audio_reference(audio),
+                    _ => None,
+                }) {
+                    assistant_obj.insert("audio".to_string(), json!({ "id": audio_id }));
+                }
+
                 let mut tool_calls = Vec::new();
                 for block in &assistant.content {
                     let AssistantContent::ToolCall(tool_call) = block else {
@@ -1241,13 +1431,17 @@ fn try_convert_messages(
                         Value::Array(parts) => !parts.is_empty(),
                         _ => true,
                     });
-                if has_content || assistant_obj.contains_key("tool_calls") {
+                if has_content
+                    || assistant_obj.contains_key("tool_calls")
+                    || assistant_obj.contains_key("audio")
+                {
                     params.push(assistant_msg);
                     last_role = Some("assistant");
                 }
             }
             crate::types::Message::ToolResult(_) => {
-                let mut image_blocks = Vec::new();
+                let mut attachment_blocks = Vec::new();
+                let mut has_document_attachments = false;
                 let mut deferred_names = Vec::new();
                 let mut seen_deferred_names = std::collections::HashSet::new();
                 let mut cursor = index;
@@ -1269,9 +1463,13 @@ fn try_convert_messages(
                         .content
                         .iter()
                         .any(|block| matches!(block, ToolResultContent::Image(_)));
+                    let has_documents = tool_msg
+                        .content
+                        .iter()
+                        .any(|block| matches!(block, ToolResultContent::Document(_)));
                     let mut tool_result = json!({
                         "role": "tool",
-                        "content": if has_text { &text_result } else if has_images { "(see attached image)" } else { "(no tool output)" },
+                        "content": if has_text { &text_result } else if has_images { "(see attached image)" } else if has_documents { "(see attached document)" } else { "(no tool output)" },
                         "tool_call_id": tool_msg.tool_call_id
                     });
                     if compat.requires_tool_result_name && !tool_msg.tool_name.is_empty() {
@@ -1286,29 +1484,37 @@ fn try_convert_messages(
                         }
                     }
 
-                    if model.input.contains(&ModelInput::Image) {
-                        for block in &tool_msg.content {
-                            if let ToolResultContent::Image(ImageContent { data, mime_type }) =
-                                block
+                    for block in &tool_msg.content {
+                        match block {
+                            ToolResultContent::Image(ImageContent { data, mime_type })
+                                if model.input.contains(&ModelInput::Image) =>
                             {
-                                image_blocks.push(json!({
+                                attachment_blocks.push(json!({
                                     "type": "image_url",
                                     "image_url": { "url": format!("data:{mime_type};base64,{data}") }
                                 }));
                             }
+                            ToolResultContent::Document(document) => {
+                                has_document_attachments = true;
+                                attachment_blocks.push(convert_file_part(document));
+                            }
+                            _ => {}
                         }
                     }
                     cursor += 1;
                 }
                 index = cursor - 1;
-                if !image_blocks.is_empty() {
+                if !attachment_blocks.is_empty() {
                     if compat.requires_assistant_after_tool_result {
                         params.push(json!({ "role": "assistant", "content": "I have processed the tool results." }));
                     }
-                    let mut content = vec![
-                        json!({ "type": "text", "text": "Attached image(s) from tool result:" }),
-                    ];
-                    content.extend(image_blocks);
+                    let heading = if has_document_attachments {
+                        "Attached file(s) from tool result:"
+                    } else {
+                        "Attached image(s) from tool result:"
+                    };
+                    let mut content = vec![json!({ "type": "text", "text": heading })];
+                    content.extend(attachment_blocks);
                     params.push(json!({ "role": "user", "content": content }));
                     last_role = Some("user");
                 } else {
@@ -1340,6 +1546,59 @@ fn try_convert_messages(
     Ok(params)
 }
 
+fn convert_tool_choice(tool_choice: &ToolChoice) -> Value {
+    match tool_choice {
+        ToolChoice::Auto => json!("auto"),
+        ToolChoice::None => json!("none"),
+        ToolChoice::Required => json!("required"),
+        ToolChoice::Tool(name) => json!({ "type": "function", "function": { "name": name } }),
+    }
+}
+
+/// Chat Completions takes files inline only, so a URL document is passed
+/// as a text reference.
+pub(crate) fn convert_file_part(document: &DocumentContent) -> Value {
+    match &document.source {
+        DocumentSource::Base64 { data } => json!({
+            "type": "file",
+            "file": {
+                "filename": document.title.as_deref().unwrap_or("document.pdf"),
+                "file_data": format!("data:{};base64,{data}", document.mime_type)
+            }
+        }),
+        DocumentSource::Url { url } => json!({
+            "type": "text",
+            "text": match &document.title {
+                Some(title) => format!("(document \"{title}\": {url})"),
+                None => format!("(document: {url})"),
+            }
+        }),
+    }
+}
+
+pub(crate) fn convert_input_audio_part(audio: &AudioContent) -> Value {
+    let format = match audio.mime_type.as_str() {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        other => other.rsplit('/').next().unwrap_or(other),
+    };
+    json!({
+        "type": "input_audio",
+        "input_audio": { "data": audio.data, "format": format }
+    })
+}
+
+/// The id of an earlier spoken answer that OpenAI still remembers. Expired
+/// answers are replayed as their transcript instead.
+fn audio_reference(audio: &AssistantAudioContent) -> Option<&str> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    (!audio.id.is_empty() && audio.expires_at.is_none_or(|expires_at| expires_at > now))
+        .then_some(audio.id.as_str())
+}
+
 fn convert_tools(tools: &[Tool], compat: &ResolvedOpenAICompletionsCompat) -> Result<Vec<Value>> {
     tools
         .iter()
@@ -1419,7 +1678,7 @@ fn parse_chunk_usage(raw: &Value, model: &Model) -> Usage {
 fn map_stop_reason(reason: &str) -> (StopReason, Option<String>) {
     match reason {
         "stop" | "end" => (StopReason::Stop, None),
-        "length" => (StopReason::Length, None),
+        "length" | "model_length" => (StopReason::Length, None),
         "function_call" | "tool_calls" => (StopReason::ToolUse, None),
         "content_filter" => (
             StopReason::Error,
@@ -1443,6 +1702,7 @@ fn detect_compat(model: &Model) -> ResolvedOpenAICompletionsCompat {
         supports_reasoning_effort: true,
         supports_usage_in_streaming: true,
         max_tokens_field: MaxTokensField::MaxCompletionTokens,
+        seed_field: SeedField::Seed,
         requires_tool_result_name: false,
         requires_assistant_after_tool_result: false,
         requires_thinking_as_text: false,
@@ -1477,6 +1737,7 @@ fn get_compat(model: &Model) -> ResolvedOpenAICompletionsCompat {
             .supports_usage_in_streaming
             .unwrap_or(detected.supports_usage_in_streaming),
         max_tokens_field: compat.max_tokens_field.unwrap_or(detected.max_tokens_field),
+        seed_field: compat.seed_field.unwrap_or(detected.seed_field),
         requires_tool_result_name: compat
             .requires_tool_result_name
             .unwrap_or(detected.requires_tool_result_name),
@@ -1538,6 +1799,9 @@ fn has_tool_history(messages: &[crate::types::Message]) -> bool {
 }
 
 fn normalize_chat_tool_call_id(id: &str, model: &Model) -> String {
+    if model.provider == "mistral" || model.base_url.contains("mistral.ai") {
+        return crate::providers::mistral::normalize_tool_call_id(id);
+    }
     if let Some((call_id, item_id)) = id.split_once('|') {
         let call_id = sanitize_id(call_id);
         let item_id = sanitize_id(item_id);
@@ -1747,10 +2011,6 @@ fn response_headers(headers: &HeaderMap) -> HashMap<String, String> {
         .collect()
 }
 
-fn trim_end_slash(url: &str) -> &str {
-    url.trim_end_matches('/')
-}
-
 fn request_base_url(model: &Model) -> Result<String> {
     Ok(model.base_url.clone())
 }
@@ -1759,8 +2019,9 @@ fn request_base_url(model: &Model) -> Result<String> {
 mod tests {
     use super::*;
     use crate::types::{
-        ConstrainedSampling, ConstrainedSamplingConfig, ConstrainedSamplingStrict, GrammarVariants,
-        Message, ModelCompat, ModelCost, OpenAICompletionsCompat, PayloadHook, ResponseHook,
+        AudioOutputOptions, ConstrainedSampling, ConstrainedSamplingConfig,
+        ConstrainedSamplingStrict, GrammarVariants, JsonSchemaFormat, Message, ModelCompat,
+        ModelCost, OpenAICompletionsCompat, PayloadHook, ResponseHook, ToolChoice,
         ToolResultMessage,
     };
     use futures::StreamExt;
@@ -2405,6 +2666,267 @@ mod tests {
         assert!(payload.get("providerOptions").is_none());
     }
 
+    #[test]
+    fn chat_payload_maps_response_format_to_json_schema() {
+        let model = model();
+        let context = Context {
+            messages: vec![Message::user_text("hi")],
+            ..Default::default()
+        };
+        let options = OpenAICompletionsOptions {
+            base: StreamOptions {
+                response_format: Some(
+                    JsonSchemaFormat::new(json!({ "type": "object" })).description("A reply"),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let payload = build_chat_completions_payload(
+            &model,
+            &context,
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+
+        assert_eq!(
+            payload["response_format"],
+            json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "response",
+                    "description": "A reply",
+                    "schema": { "type": "object" },
+                    "strict": false
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn chat_payload_maps_sampling_options_and_drops_unsupported_top_k() {
+        let mut model = model();
+        let context = Context {
+            messages: vec![Message::user_text("hi")],
+            ..Default::default()
+        };
+        let options = OpenAICompletionsOptions {
+            base: StreamOptions {
+                top_p: Some(0.9),
+                top_k: Some(40),
+                stop: vec!["END".to_string()],
+                seed: Some(7),
+                frequency_penalty: Some(0.5),
+                presence_penalty: Some(0.25),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let payload = build_chat_completions_payload(
+            &model,
+            &context,
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+        assert_eq!(payload["top_p"], json!(0.9));
+        assert!(payload.get("top_k").is_none());
+        assert_eq!(payload["stop"], json!(["END"]));
+        assert_eq!(payload["seed"], json!(7));
+        assert_eq!(payload["frequency_penalty"], json!(0.5));
+        assert_eq!(payload["presence_penalty"], json!(0.25));
+
+        model.compat.sampling.supports_top_k = Some(true);
+        model.compat.sampling.supports_seed = Some(false);
+        let payload = build_chat_completions_payload(
+            &model,
+            &context,
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+        assert_eq!(payload["top_k"], json!(40));
+        assert!(payload.get("seed").is_none());
+    }
+
+    #[test]
+    fn chat_payload_requests_logprobs_only_when_asked() {
+        let model = model();
+        let context = Context {
+            messages: vec![Message::user_text("hi")],
+            ..Default::default()
+        };
+        let payload = build_chat_completions_payload(
+            &model,
+            &context,
+            &OpenAICompletionsOptions::default(),
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+        assert!(payload.get("logprobs").is_none());
+        assert!(payload.get("top_logprobs").is_none());
+
+        let payload = build_chat_completions_payload(
+            &model,
+            &context,
+            &OpenAICompletionsOptions {
+                base: StreamOptions {
+                    top_logprobs: Some(3),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+        assert_eq!(payload["logprobs"], json!(true));
+        assert_eq!(payload["top_logprobs"], json!(3));
+    }
+
+    #[test]
+    fn chat_payload_maps_audio_input_output_and_earlier_spoken_answers() {
+        let mut model = model();
+        model.input.push(ModelInput::Audio);
+        model.output = vec![ModelOutput::Text, ModelOutput::Audio];
+        let spoken = |id: &str, expires_at: u64, transcript: &str| {
+            Message::Assistant(AssistantMessage {
+                content: vec![AssistantContent::Audio(AssistantAudioContent {
+                    id: id.to_string(),
+                    data: "AAAA".to_string(),
+                    mime_type: "audio/pcm".to_string(),
+                    transcript: transcript.to_string(),
+                    expires_at: Some(expires_at),
+                })],
+                api: model.api.clone(),
+                provider: model.provider.clone(),
+                model: model.id.clone(),
+                response_model: None,
+                response_id: None,
+                diagnostics: Vec::new(),
+                usage: Usage::default(),
+                stop_reason: StopReason::Stop,
+                stop_sequence: None,
+                error_message: None,
+                error_status: None,
+                circuit_open: None,
+                timestamp: 0,
+            })
+        };
+        let context = Context {
+            messages: vec![
+                Message::User(crate::types::UserMessage {
+                    content: UserMessageContent::Parts(vec![
+                        UserContent::text("What did I say?"),
+                        UserContent::Audio(AudioContent {
+                            data: "UklGRg==".to_string(),
+                            mime_type: "audio/x-wav".to_string(),
+                        }),
+                    ]),
+                    timestamp: 0,
+                }),
+                spoken("audio_old", 1, "Hello."),
+                Message::user_text("Again"),
+                spoken("audio_new", u64::MAX, "Hello again."),
+                Message::user_text("Once more"),
+            ],
+            ..Default::default()
+        };
+        let options = OpenAICompletionsOptions {
+            base: StreamOptions {
+                audio_output: Some(AudioOutputOptions::new("alloy")),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let payload = build_chat_completions_payload(
+            &model,
+            &context,
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+
+        assert_eq!(payload["modalities"], json!(["text", "audio"]));
+        assert_eq!(
+            payload["audio"],
+            json!({ "voice": "alloy", "format": "pcm16" })
+        );
+        assert_eq!(
+            payload["messages"][0]["content"][1],
+            json!({
+                "type": "input_audio",
+                "input_audio": { "data": "UklGRg==", "format": "wav" }
+            })
+        );
+        assert_eq!(payload["messages"][1]["content"], json!("Hello."));
+        assert!(payload["messages"][1].get("audio").is_none());
+        assert_eq!(
+            payload["messages"][3]["audio"],
+            json!({ "id": "audio_new" })
+        );
+        assert!(payload["messages"][3]["content"].is_null());
+
+        model.output = vec![ModelOutput::Text];
+        let payload = build_chat_completions_payload(
+            &model,
+            &context,
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+        assert!(payload.get("modalities").is_none());
+        assert!(payload.get("audio").is_none());
+    }
+
+    #[test]
+    fn chat_payload_maps_typed_tool_choice_and_parallel_tool_calls() {
+        let model = model();
+        let context = Context {
+            messages: vec![Message::user_text("hi")],
+            tools: vec![lookup_tool()],
+            ..Default::default()
+        };
+        let mut options = OpenAICompletionsOptions {
+            base: StreamOptions {
+                tool_choice: Some(ToolChoice::tool("lookup")),
+                parallel_tool_calls: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let payload = build_chat_completions_payload(
+            &model,
+            &context,
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+        assert_eq!(
+            payload["tool_choice"],
+            json!({ "type": "function", "function": { "name": "lookup" } })
+        );
+        assert_eq!(payload["parallel_tool_calls"], json!(false));
+
+        options.tool_choice = Some(json!("none"));
+        let payload = build_chat_completions_payload(
+            &model,
+            &Context {
+                tools: Vec::new(),
+                ..context
+            },
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+        assert_eq!(payload["tool_choice"], json!("none"));
+        assert!(payload.get("parallel_tool_calls").is_none());
+    }
+
     #[test]
     fn chat_payload_maps_gateway_routing_to_provider_options() {
         let mut model = model();
@@ -2585,7 +3107,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::ToolUse,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 2,
         };
         let context = Context {
@@ -3755,6 +4280,155 @@ mod tests {
         assert_eq!(message.usage.total_tokens, 15);
     }
 
+    #[tokio::test]
+    async fn text_logprobs_follow_their_text_delta() {
+        let mut chat_model = model();
+        chat_model.reasoning = false;
+        chat_model.base_url = spawn_sse_server(chat_sse_body(&[
+            json!({
+                "id": "chatcmpl-logprobs",
+                "choices": [{
+                    "index": 0,
+                    "delta": { "content": "Yes" },
+                    "logprobs": {
+                        "content": [{
+                            "token": "Yes",
+                            "logprob": -0.01,
+                            "bytes": [89, 101, 115],
+                            "top_logprobs": [
+                                { "token": "Yes", "logprob": -0.01, "bytes": [89, 101, 115] },
+                                { "token": "No", "logprob": -4.6, "bytes": [78, 111] }
+                            ]
+                        }]
+                    },
+                    "finish_reason": null
+                }]
+            }),
+            json!({
+                "id": "chatcmpl-logprobs",
+                "choices": [{ "index": 0, "delta": {}, "finish_reason": "stop" }]
+            }),
+        ]))
+        .await;
+
+        let mut stream = stream_openai_completions(
+            chat_model,
+            Context {
+                messages: vec![Message::user_text("Is it spam?")],
+                ..Default::default()
+            },
+            OpenAICompletionsOptions {
+                base: StreamOptions {
+                    api_key: Some("test-key".to_string()),
+                    top_logprobs: Some(2),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await {
+            events.push(event.unwrap());
+        }
+        let position = events
+            .iter()
+            .position(|event| matches!(event, AssistantMessageEvent::TextLogprobs { .. }))
+            .expect("logprobs event");
+        assert!(matches!(
+            events[position - 1],
+            AssistantMessageEvent::TextDelta { .. }
+        ));
+        let AssistantMessageEvent::TextLogprobs {
+            content_index,
+            logprobs,
+            ..
+        } = &events[position]
+        else {
+            unreachable!();
+        };
+        assert_eq!(*content_index, 0);
+        assert_eq!(logprobs[0].token, "Yes");
+        assert_eq!(logprobs[0].logprob, -0.01);
+        assert_eq!(logprobs[0].top_logprobs[1].token, "No");
+    }
+
+    #[tokio::test]
+    async fn streams_audio_deltas_into_one_audio_block() {
+        let mut chat_model = model();
+        chat_model.reasoning = false;
+        chat_model.base_url = spawn_sse_server(chat_sse_body(&[
+            json!({
+                "id": "chatcmpl-audio",
+                "choices": [{
+                    "index": 0,
+                    "delta": {
+                        "audio": { "id": "audio_1", "data": "YWI=", "transcript": "Hel" }
+                    }
+                }]
+            }),
+            json!({
+                "id": "chatcmpl-audio",
+                "choices": [{
+                    "index": 0,
+                    "delta": {
+                        "audio": { "data": "Yw==", "transcript": "lo", "expires_at": 1_900_000_000 }
+                    }
+                }]
+            }),
+            json!({
+                "id": "chatcmpl-audio",
+                "choices": [{ "index": 0, "delta": {}, "finish_reason": "stop" }]
+            }),
+        ]))
+        .await;
+
+        let mut stream = stream_openai_completions(
+            chat_model,
+            Context {
+                messages: vec![Message::user_text("Say hello")],
+                ..Default::default()
+            },
+            OpenAICompletionsOptions {
+                base: StreamOptions {
+                    api_key: Some("test-key".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await {
+            events.push(event.unwrap());
+        }
+        let deltas = events
+            .iter()
+            .filter_map(|event| match event {
+                AssistantMessageEvent::AudioDelta {
+                    data, transcript, ..
+                } => Some((data.as_str(), transcript.as_str())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(deltas, vec![("YWI=", "Hel"), ("Yw==", "lo")]);
+        let expected = AssistantAudioContent {
+            id: "audio_1".to_string(),
+            data: "YWJj".to_string(),
+            mime_type: "audio/pcm".to_string(),
+            transcript: "Hello".to_string(),
+            expires_at: Some(1_900_000_000),
+        };
+        assert!(events.iter().any(|event| matches!(
+            event,
+            AssistantMessageEvent::AudioEnd { content, .. } if *content == expected
+        )));
+        let Some(AssistantMessageEvent::Done { message, .. }) = events.last() else {
+            panic!("expected done event");
+        };
+        assert_eq!(message.content, vec![AssistantContent::Audio(expected)]);
+    }
+
     #[tokio::test]
     async fn chat_usage_does_not_double_count_reasoning_tokens() {
         let mut chat_model = model();
@@ -5393,7 +6067,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::Stop,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 2,
         }
     }
@@ -5726,6 +6403,82 @@ mod tests {
         assert_eq!(image_parts, 2);
     }
 
+    #[test]
+    fn documents_become_file_parts_for_pdf_models() {
+        let mut model = model();
+        model.input.push(ModelInput::Pdf);
+        let compat = get_compat(&model);
+        let assistant = assistant_message(
+            vec![AssistantContent::ToolCall(ToolCall {
+                id: "tool-1".to_string(),
+                name: "fetch".to_string(),
+                arguments: json!({ "url": "https://example.com/report.pdf" }),
+                thought_signature: None,
+            })],
+            &model,
+        );
+        let context = Context {
+            messages: vec![
+                Message::User(crate::types::UserMessage {
+                    content: UserMessageContent::Parts(vec![
+                        UserContent::text("Summarize these."),
+                        UserContent::Document(DocumentContent::pdf("JVBERi0=").title("q3.pdf")),
+                        UserContent::Document(DocumentContent::url(
+                            "https://example.com/q4.pdf",
+                            "application/pdf",
+                        )),
+                    ]),
+                    timestamp: 1,
+                }),
+                Message::Assistant(assistant),
+                Message::ToolResult(ToolResultMessage {
+                    tool_call_id: "tool-1".to_string(),
+                    tool_name: "fetch".to_string(),
+                    content: vec![ToolResultContent::Document(DocumentContent::pdf(
+                        "JVBERi0x",
+                    ))],
+                    details: None,
+                    usage: None,
+                    added_tool_names: Vec::new(),
+                    is_error: false,
+                    timestamp: 3,
+                }),
+            ],
+            ..Default::default()
+        };
+
+        let messages = convert_messages(&model, &context, &compat);
+
+        assert_eq!(
+            messages[0]["content"][1],
+            json!({
+                "type": "file",
+                "file": {
+                    "filename": "q3.pdf",
+                    "file_data": "data:application/pdf;base64,JVBERi0="
+                }
+            })
+        );
+        assert_eq!(
+            messages[0]["content"][2],
+            json!({ "type": "text", "text": "(document: https://example.com/q4.pdf)" })
+        );
+        let tool_message = messages
+            .iter()
+            .find(|message| message["role"] == "tool")
+            .expect("tool message");
+        assert_eq!(tool_message["content"], json!("(see attached document)"));
+        let attachments = messages.last().expect("attachment message");
+        assert_eq!(
+            attachments["content"][0]["text"],
+            json!("Attached file(s) from tool result:")
+        );
+        assert_eq!(
+            attachments["content"][1]["file"]["file_data"],
+            json!("data:application/pdf;base64,JVBERi0x")
+        );
+    }
+
     #[test]
     fn empty_tool_result_without_images_uses_no_output_placeholder() {
         let model = model();
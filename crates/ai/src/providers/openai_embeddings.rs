@@ -8,6 +8,7 @@ use crate::types::{
     EmbeddingBatch, EmbeddingOptions, EmbeddingUsage, EmbeddingVector, KnownApi, Model,
     ProviderResponse,
 };
+use crate::utils::circuit_breaker::model_circuit;
 use crate::utils::headers::headers_to_record;
 use crate::utils::http::{request_timeout, send_request_with_retries};
 use crate::{Error, Result};
@@ -105,7 +106,7 @@ pub(crate) async fn embed_many_openai(
     let client = options.base.http_client.clone().unwrap_or_default();
     let url = format!("{}/embeddings", model.base_url.trim_end_matches('/'));
     let headers = build_headers(api_key, &model.headers, &options.base.headers)?;
-    let response = send_request_with_retries(&options.base, || {
+    let response = send_request_with_retries(&options.base, &model_circuit(&model), || {
         client
             .post(&url)
             .headers(headers.clone())
@@ -1,4 +1,6 @@
+use base64::Engine;
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use reqwest::multipart::{Form, Part};
 use serde::Deserialize;
 use serde_json::{Map, Value, json};
 
@@ -7,6 +9,7 @@ use crate::types::{
     AssistantImages, ImageContent, ImageGenerationOptions, ImageOutput, ImagesContext,
     ImagesStopReason, KnownApi, Model, ProviderResponse, TextContent, Usage, UserContent,
 };
+use crate::utils::circuit_breaker::model_circuit;
 use crate::utils::headers::headers_to_record;
 use crate::utils::http::{request_timeout, send_with_retries};
 use crate::{Error, Result};
@@ -55,7 +58,8 @@ async fn run_generate_images_openai(
         .filter(|api_key| !api_key.trim().is_empty())
         .ok_or_else(|| Error::MissingApiKey(model.provider.clone()))?;
 
-    let mut payload = build_payload(model, context, &options.base.provider_options)?;
+    let request = split_context(context)?;
+    let mut payload = build_payload(model, &request, options);
     if let Some(on_payload) = &options.base.on_payload
         && let Some(next_payload) = on_payload(Value::Object(payload.clone()), model).await?
     {
@@ -65,19 +69,52 @@ async fn run_generate_images_openai(
     }
 
     let client = options.base.http_client.clone().unwrap_or_default();
+    let headers = build_headers(api_key, &model.headers, &options.base.headers)?;
     let url = format!(
-        "{}/images/generations",
-        model.base_url.trim_end_matches('/')
+        "{}/images/{}",
+        model.base_url.trim_end_matches('/'),
+        request.endpoint.path()
     );
-    let headers = build_headers(api_key, &model.headers, &options.base.headers)?;
-    let response = send_with_retries(&options.base, || {
-        client
-            .post(&url)
-            .headers(headers.clone())
-            .json(&payload)
-            .timeout(request_timeout(options.base.timeout_ms))
-    })
-    .await?;
+    let response = if request.endpoint == ImagesEndpoint::Generations {
+        send_with_retries(&options.base, &model_circuit(model), || {
+            client
+                .post(&url)
+                .headers(headers.clone())
+                .json(&payload)
+                .timeout(request_timeout(options.base.timeout_ms))
+        })
+        .await?
+    } else {
+        // gpt-image models take several images to edit as `image[]`; DALL-E 2
+        // and variations accept a single `image` field.
+        let image_field = if request.images.len() == 1 {
+            "image"
+        } else {
+            "image[]"
+        };
+        send_with_retries(&options.base, &model_circuit(model), || {
+            let mut form = Form::new();
+            for (name, value) in &payload {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                form = form.text(name.clone(), value);
+            }
+            for image in &request.images {
+                form = form.part(image_field, image.part());
+            }
+            if let Some(mask) = &request.mask {
+                form = form.part("mask", mask.part());
+            }
+            client
+                .post(&url)
+                .headers(headers.clone())
+                .multipart(form)
+                .timeout(request_timeout(options.base.timeout_ms))
+        })
+        .await?
+    };
     let status = response.status();
     let response_headers = response.headers().clone();
     let body = response.text().await?;
@@ -121,37 +158,154 @@ async fn run_generate_images_openai(
     Ok(())
 }
 
+/// Builds the generations JSON body. Edits and variations send the same
+/// fields as multipart form fields, so the payload hook sees one shape for
+/// all three.
 fn build_payload(
     model: &Model,
-    context: ImagesContext,
-    provider_options: &std::collections::HashMap<String, Value>,
-) -> Result<Map<String, Value>> {
-    let prompt = prompt_from_context(context)?;
+    request: &ImagesRequest,
+    options: &ImageGenerationOptions,
+) -> Map<String, Value> {
+    let variation = request.endpoint == ImagesEndpoint::Variations;
     let mut payload = Map::new();
     payload.insert("model".to_string(), json!(model.id));
-    payload.insert("prompt".to_string(), json!(prompt));
+    if !variation {
+        payload.insert("prompt".to_string(), json!(request.prompt));
+    }
     if model.base_url != DEFAULT_OPENAI_BASE_URL {
         payload.insert("response_format".to_string(), json!("b64_json"));
     }
-    for (key, value) in image_provider_options(provider_options) {
+    if let Some(n) = options.n {
+        payload.insert("n".to_string(), json!(n));
+    }
+    if let Some(size) = options.size {
+        payload.insert("size".to_string(), json!(size.to_string()));
+    }
+    // Variations take neither quality nor background.
+    if let Some(quality) = options.quality.filter(|_| !variation) {
+        payload.insert("quality".to_string(), json!(quality.as_str()));
+    }
+    if let Some(background) = options.background.filter(|_| !variation) {
+        payload.insert("background".to_string(), json!(background.as_str()));
+    }
+    for (key, value) in image_provider_options(&options.base.provider_options) {
         payload.insert(key, value);
     }
-    Ok(payload)
+    payload
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImagesEndpoint {
+    Generations,
+    Edits,
+    Variations,
 }
 
-fn prompt_from_context(context: ImagesContext) -> Result<String> {
+impl ImagesEndpoint {
+    fn path(self) -> &'static str {
+        match self {
+            Self::Generations => "generations",
+            Self::Edits => "edits",
+            Self::Variations => "variations",
+        }
+    }
+}
+
+struct ImagesRequest {
+    endpoint: ImagesEndpoint,
+    prompt: String,
+    images: Vec<ImageFile>,
+    mask: Option<ImageFile>,
+}
+
+/// A decoded image ready to upload as a multipart file part.
+struct ImageFile {
+    bytes: Vec<u8>,
+    file_name: String,
+    headers: HeaderMap,
+}
+
+impl ImageFile {
+    fn decode(image: &ImageContent, name: &str) -> Result<Self> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&image.data)
+            .map_err(|error| Error::Validation(format!("{name} is not valid base64: {error}")))?;
+        // Without an explicit part type OpenAI rejects the upload as
+        // application/octet-stream.
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_str(&image.mime_type)
+                .map_err(|error| Error::InvalidHeaderValue(CONTENT_TYPE.to_string(), error))?,
+        );
+        let extension = match image.mime_type.as_str() {
+            "image/jpeg" | "image/jpg" => "jpg",
+            other => other.rsplit('/').next().unwrap_or(other),
+        };
+        Ok(Self {
+            bytes,
+            file_name: format!("{name}.{extension}"),
+            headers,
+        })
+    }
+
+    fn part(&self) -> Part {
+        Part::bytes(self.bytes.clone())
+            .file_name(self.file_name.clone())
+            .headers(self.headers.clone())
+    }
+}
+
+/// Joins the text inputs into the prompt. Image inputs turn the request into
+/// an edit of those images, or into variations of a single image when there
+/// is no prompt.
+fn split_context(context: ImagesContext) -> Result<ImagesRequest> {
     let mut text = Vec::new();
+    let mut images = Vec::new();
     for item in context.input {
         match item {
             UserContent::Text(content) => text.push(content.text),
-            UserContent::Image(_) => {
+            UserContent::Image(image) => {
+                images.push(ImageFile::decode(
+                    &image,
+                    &format!("image{}", images.len()),
+                )?);
+            }
+            UserContent::Document(_) | UserContent::Audio(_) => {
                 return Err(Error::Provider(
-                    "openai-images generations only support text input".to_string(),
+                    "openai-images only supports text and image input".to_string(),
                 ));
             }
         }
     }
-    Ok(text.join("\n\n"))
+    let mask = match context.mask {
+        Some(_) if images.is_empty() => {
+            return Err(Error::Validation(
+                "an image mask requires an input image to edit".to_string(),
+            ));
+        }
+        Some(mask) => Some(ImageFile::decode(&mask, "mask")?),
+        None => None,
+    };
+    let prompt = text.join("\n\n");
+    let endpoint = if images.is_empty() {
+        ImagesEndpoint::Generations
+    } else if prompt.trim().is_empty() && mask.is_none() {
+        if images.len() > 1 {
+            return Err(Error::Validation(
+                "image variations take a single input image".to_string(),
+            ));
+        }
+        ImagesEndpoint::Variations
+    } else {
+        ImagesEndpoint::Edits
+    };
+    Ok(ImagesRequest {
+        endpoint,
+        prompt,
+        images,
+        mask,
+    })
 }
 
 fn image_provider_options(
@@ -186,7 +340,6 @@ fn build_headers(
     option_headers: &crate::types::ProviderHeaders,
 ) -> Result<HeaderMap> {
     let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
     if !api_key.is_empty() {
         headers.insert(
             AUTHORIZATION,
@@ -273,8 +426,9 @@ mod tests {
 
     use crate::providers::openai;
     use crate::types::{
-        ImageContent, ImageGenerationOptions, ImageOutput, ImagesContext, ImagesStopReason,
-        ModelInput, ModelOutput, StreamOptions, UserContent,
+        ImageBackground, ImageContent, ImageGenerationOptions, ImageOutput, ImageQuality,
+        ImageSize, ImagesContext, ImagesStopReason, ModelInput, ModelOutput, StreamOptions,
+        UserContent,
     };
 
     #[tokio::test(flavor = "current_thread")]
@@ -318,6 +472,7 @@ mod tests {
                 .collect(),
                 ..Default::default()
             },
+            ..Default::default()
         };
 
         let output = crate::generate_images(
@@ -421,12 +576,202 @@ mod tests {
             .expect("model");
 
         assert_eq!(model.api, "openai-images");
-        assert_eq!(model.input, vec![ModelInput::Text]);
+        assert_eq!(model.input, vec![ModelInput::Text, ModelInput::Image]);
         assert_eq!(model.output, vec![ModelOutput::Image]);
     }
 
     #[tokio::test(flavor = "current_thread")]
-    async fn generations_endpoint_rejects_image_inputs() {
+    async fn image_inputs_with_a_mask_are_sent_to_the_edits_endpoint() {
+        let captured = Arc::new(Mutex::new(String::new()));
+        let url = spawn_response_server(
+            Arc::clone(&captured),
+            200,
+            r#"{ "data": [{ "b64_json": "ZWRpdGVk" }] }"#,
+        )
+        .await;
+        let provider = openai::builder()
+            .api_key(Some("test-key"))
+            .base_url(url)
+            .images()
+            .build()
+            .expect("provider");
+        let model = provider.model("gpt-image-2").build_image().expect("model");
+        let options = ImageGenerationOptions {
+            size: Some(ImageSize::pixels(1536, 1024)),
+            quality: Some(ImageQuality::High),
+            background: Some(ImageBackground::Transparent),
+            n: Some(2),
+            ..Default::default()
+        };
+
+        let output = crate::generate_images(
+            model,
+            ImagesContext::builder()
+                .text("Put the bottle on a marble counter")
+                .image(ImageContent {
+                    data: "cHJvZHVjdC1zaG90".to_string(),
+                    mime_type: "image/png".to_string(),
+                })
+                .mask(ImageContent {
+                    data: "bWFzay1wbmc=".to_string(),
+                    mime_type: "image/png".to_string(),
+                })
+                .build(),
+            Some(options),
+        )
+        .await
+        .expect("edit images");
+
+        assert_eq!(output.stop_reason, ImagesStopReason::Stop);
+        assert_eq!(
+            output.output,
+            vec![ImageOutput::Image(ImageContent {
+                data: "ZWRpdGVk".to_string(),
+                mime_type: "image/png".to_string(),
+            })]
+        );
+        let request = captured.lock().expect("request").clone();
+        assert!(request.starts_with("POST /v1/images/edits HTTP/1.1"));
+        assert!(request.contains("authorization: Bearer test-key"));
+        assert!(
+            request
+                .to_ascii_lowercase()
+                .contains("content-type: multipart/form-data; boundary=")
+        );
+        assert!(request.contains("name=\"prompt\"\r\n\r\nPut the bottle on a marble counter\r\n"));
+        assert!(request.contains("name=\"size\"\r\n\r\n1536x1024\r\n"));
+        assert!(request.contains("name=\"quality\"\r\n\r\nhigh\r\n"));
+        assert!(request.contains("name=\"background\"\r\n\r\ntransparent\r\n"));
+        assert!(request.contains("name=\"n\"\r\n\r\n2\r\n"));
+        assert!(request.contains(
+            "name=\"image\"; filename=\"image0.png\"\r\ncontent-type: image/png\r\n\r\nproduct-shot"
+        ));
+        assert!(request.contains("name=\"mask\"; filename=\"mask.png\""));
+        assert!(request.contains("mask-png"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn several_image_inputs_are_uploaded_as_an_image_array() {
+        let captured = Arc::new(Mutex::new(String::new()));
+        let url = spawn_response_server(
+            Arc::clone(&captured),
+            200,
+            r#"{ "data": [{ "b64_json": "Y29tYmluZWQ=" }] }"#,
+        )
+        .await;
+        let provider = openai::builder()
+            .api_key(Some("test-key"))
+            .base_url(url)
+            .images()
+            .build()
+            .expect("provider");
+        let model = provider.model("gpt-image-2").build_image().expect("model");
+
+        let output = crate::generate_images(
+            model,
+            ImagesContext::builder()
+                .text("Arrange both products on one shelf")
+                .image(ImageContent {
+                    data: "Ym90dGxl".to_string(),
+                    mime_type: "image/png".to_string(),
+                })
+                .image(ImageContent {
+                    data: "amFy".to_string(),
+                    mime_type: "image/jpeg".to_string(),
+                })
+                .build(),
+            None,
+        )
+        .await
+        .expect("edit images");
+
+        assert_eq!(output.stop_reason, ImagesStopReason::Stop);
+        let request = captured.lock().expect("request").clone();
+        assert!(request.starts_with("POST /v1/images/edits HTTP/1.1"));
+        assert!(request.contains("name=\"prompt\"\r\n\r\nArrange both products on one shelf\r\n"));
+        assert!(request.contains(
+            "name=\"image[]\"; filename=\"image0.png\"\r\ncontent-type: image/png\r\n\r\nbottle"
+        ));
+        assert!(request.contains(
+            "name=\"image[]\"; filename=\"image1.jpg\"\r\ncontent-type: image/jpeg\r\n\r\njar"
+        ));
+        assert!(!request.contains("name=\"image\";"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn image_input_without_a_prompt_requests_variations() {
+        let captured = Arc::new(Mutex::new(String::new()));
+        let url = spawn_response_server(
+            Arc::clone(&captured),
+            200,
+            r#"{ "data": [{ "b64_json": "dmFyaWF0aW9u" }] }"#,
+        )
+        .await;
+        let provider = openai::builder()
+            .api_key(Some("test-key"))
+            .base_url(url)
+            .images()
+            .build()
+            .expect("provider");
+        let model = provider.model("dall-e-2").build_image().expect("model");
+        let image = ImageContent {
+            data: "cHJvZHVjdC1zaG90".to_string(),
+            mime_type: "image/png".to_string(),
+        };
+        let options = ImageGenerationOptions {
+            size: Some(ImageSize::pixels(512, 512)),
+            quality: Some(ImageQuality::High),
+            n: Some(3),
+            ..Default::default()
+        };
+
+        let output = crate::generate_images(
+            model.clone(),
+            ImagesContext::builder().image(image.clone()).build(),
+            Some(options),
+        )
+        .await
+        .expect("image variations");
+
+        assert_eq!(output.stop_reason, ImagesStopReason::Stop);
+        assert_eq!(
+            output.output,
+            vec![ImageOutput::Image(ImageContent {
+                data: "dmFyaWF0aW9u".to_string(),
+                mime_type: "image/png".to_string(),
+            })]
+        );
+        let request = captured.lock().expect("request").clone();
+        assert!(request.starts_with("POST /v1/images/variations HTTP/1.1"));
+        assert!(request.contains("name=\"model\"\r\n\r\ndall-e-2\r\n"));
+        assert!(request.contains("name=\"size\"\r\n\r\n512x512\r\n"));
+        assert!(request.contains("name=\"n\"\r\n\r\n3\r\n"));
+        assert!(
+            request.contains("name=\"image\"; filename=\"image0.png\"\r\ncontent-type: image/png")
+        );
+        assert!(!request.contains("name=\"prompt\""));
+        assert!(!request.contains("name=\"quality\""));
+
+        let output = crate::generate_images(
+            model,
+            ImagesContext::builder()
+                .image(image.clone())
+                .image(image)
+                .build(),
+            None,
+        )
+        .await
+        .expect("image variations");
+
+        assert_eq!(output.stop_reason, ImagesStopReason::Error);
+        assert_eq!(
+            output.error_message.as_deref(),
+            Some("image variations take a single input image")
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn mask_without_an_input_image_is_rejected() {
         let provider = openai::builder()
             .api_key(Some("test-key"))
             .images()
@@ -437,13 +782,11 @@ mod tests {
         let output = crate::generate_images(
             model,
             ImagesContext::builder()
-                .input([
-                    UserContent::text("Edit this image"),
-                    UserContent::Image(ImageContent {
-                        data: "abc".to_string(),
-                        mime_type: "image/png".to_string(),
-                    }),
-                ])
+                .input([UserContent::text("Repaint the background")])
+                .mask(ImageContent {
+                    data: "bWFzaw==".to_string(),
+                    mime_type: "image/png".to_string(),
+                })
                 .build(),
             None,
         )
@@ -453,7 +796,7 @@ mod tests {
         assert_eq!(output.stop_reason, ImagesStopReason::Error);
         assert_eq!(
             output.error_message.as_deref(),
-            Some("provider error: openai-images generations only support text input")
+            Some("an image mask requires an input image to edit")
         );
     }
 
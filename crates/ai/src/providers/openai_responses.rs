@@ -1,11 +1,12 @@
 use std::collections::{HashMap, HashSet};
 
-use futures::{StreamExt, pin_mut};
+use futures::{Stream, StreamExt, pin_mut};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
 use serde_json::{Value, json};
 
 use crate::event_stream::AssistantMessageEventStreamSender;
 use crate::models::{calculate_cost, clamp_thinking_level};
+use crate::providers::azure_openai;
 use crate::providers::constrained_sampling::{
     GrammarToolInputJsonBuffer, append_grammar_tool_input_json_delta,
     create_grammar_tool_input_properties, get_grammar_tool_input, grammar_arguments,
@@ -15,21 +16,26 @@ use crate::providers::deferred_tools::split_deferred_tools;
 use crate::providers::github_copilot_headers::{
     build_copilot_dynamic_headers, has_copilot_vision_input,
 };
+use crate::providers::logprobs;
 use crate::providers::openai_prompt_cache::clamp_openai_prompt_cache_key;
+use crate::providers::openai_websocket::{CachedTurn, ResponsesWebSocket, WebSocketConnector};
+use crate::providers::sampling::{SamplingSupport, sampling};
 use crate::providers::simple_options::build_base_options;
 use crate::providers::transform_messages::transform_messages;
 use crate::types::{
     AssistantContent, AssistantMessage, AssistantMessageEvent, CacheRetention, Context,
-    ImageContent, Model, ModelInput, ModelThinkingLevel, SessionAffinityFormat,
-    SimpleStreamOptions, StopReason, StreamOptions, TextContent, TextPhase, TextSignatureV1,
-    ThinkingContent, Tool, ToolCall, ToolResultContent, Usage, UserContent, UserMessageContent,
+    DocumentContent, DocumentSource, ImageContent, Model, ModelInput, ModelThinkingLevel,
+    SessionAffinityFormat, SimpleStreamOptions, StopReason, StreamOptions, TextContent, TextPhase,
+    TextSignatureV1, ThinkingContent, Tool, ToolCall, ToolChoice, ToolResultContent, Transport,
+    Usage, UserContent, UserMessageContent,
 };
+use crate::utils::circuit_breaker::model_circuit;
 use crate::utils::hash::short_hash;
 use crate::utils::headers::{apply_provider_headers, has_non_empty_header};
-use crate::utils::http::{request_timeout, send_with_retries};
+use crate::utils::http::{join_url_path, request_timeout, send_with_retries};
 use crate::utils::json::parse_streaming_json;
 use crate::utils::provider_env::get_provider_env_value;
-use crate::utils::sse;
+use crate::utils::sse::{self, SseEvent};
 use crate::{Error, Result};
 
 const OPENAI_TOOL_CALL_PROVIDERS: &[&str] = &["openai", "openai-codex", "opencode"];
@@ -129,10 +135,16 @@ impl StreamFailure {
         }
     }
 
-    fn api_status(output: AssistantMessage, status: reqwest::StatusCode, body: String) -> Self {
+    fn api_status(
+        mut output: AssistantMessage,
+        model: &Model,
+        status: reqwest::StatusCode,
+        body: String,
+    ) -> Self {
+        output.error_status = Some(status.as_u16());
         Self {
             output,
-            message: format_openai_responses_api_error(status, &body),
+            message: format_openai_responses_api_error(model, status, &body),
             cancelled: false,
         }
     }
@@ -335,7 +347,7 @@ async fn run_stream(
     }
 
     let request_url = match request_base_url(&model) {
-        Ok(base_url) => format!("{}/responses", trim_end_slash(&base_url)),
+        Ok(base_url) => join_url_path(&base_url, "responses"),
         Err(error) => return Err(StreamFailure::new(output, error)),
     };
     let request_headers = match headers(
@@ -349,8 +361,29 @@ async fn run_stream(
         Ok(headers) => headers,
         Err(error) => return Err(StreamFailure::new(output, error)),
     };
+    let transport = options.base.transport.unwrap_or_default();
+    if matches!(transport, Transport::Websocket | Transport::WebsocketCached) {
+        let connector = WebSocketConnector {
+            url: request_url,
+            circuit: model_circuit(&model),
+            headers: request_headers,
+            options: options.base.clone(),
+        };
+        return run_websocket_stream(
+            &model,
+            &options,
+            &payload,
+            &connector,
+            transport == Transport::WebsocketCached,
+            &grammar_tool_input_properties,
+            output,
+            sender,
+        )
+        .await;
+    }
+
     let client = options.base.http_client.clone().unwrap_or_default();
-    let response = match send_with_retries(&options.base, || {
+    let response = match send_with_retries(&options.base, &model_circuit(&model), || {
         client
             .post(request_url.as_str())
             .headers(request_headers.clone())
@@ -361,12 +394,15 @@ async fn run_stream(
     {
         Ok(response) => response,
         Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
-        Err(error) => return Err(StreamFailure::new(output, error)),
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
     };
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
-        return Err(StreamFailure::api_status(output, status, body));
+        return Err(StreamFailure::api_status(output, &model, status, body));
     }
     if let Some(on_response) = &options.base.on_response {
         let provider_response = crate::types::ProviderResponse {
@@ -382,13 +418,118 @@ async fn run_stream(
         partial: output.clone(),
     });
 
+    let events = sse::events(response, options.base.cancellation_token.clone());
+    let output = process_events(
+        &model,
+        &options,
+        &grammar_tool_input_properties,
+        events,
+        output,
+        sender,
+    )
+    .await?;
+    sender.push(AssistantMessageEvent::Done {
+        reason: output.stop_reason,
+        message: output,
+    });
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_websocket_stream(
+    model: &Model,
+    options: &OpenAIResponsesOptions,
+    payload: &Value,
+    connector: &WebSocketConnector,
+    continue_previous: bool,
+    grammar_tool_input_properties: &HashMap<String, String>,
+    mut output: AssistantMessage,
+    sender: &mut AssistantMessageEventStreamSender,
+) -> std::result::Result<(), StreamFailure> {
+    let mut socket = match ResponsesWebSocket::acquire(connector).await {
+        Ok(socket) => socket,
+        Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+        Err(Error::ApiStatus { status, body }) => {
+            return Err(StreamFailure::api_status(output, model, status, body));
+        }
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
+    };
+    let first_event = match socket
+        .send_request(connector, payload, continue_previous)
+        .await
+    {
+        Ok(event) => event,
+        Err(Error::Cancelled) => return Err(StreamFailure::cancelled(output)),
+        Err(Error::ApiStatus { status, body }) => {
+            return Err(StreamFailure::api_status(output, model, status, body));
+        }
+        Err(error) => {
+            output.circuit_open = error.open_circuit();
+            return Err(StreamFailure::new(output, error));
+        }
+    };
+    if let Some(on_response) = &options.base.on_response
+        && let Some(handshake) = socket.handshake()
+        && let Err(error) = on_response(handshake.clone(), model).await
+    {
+        return Err(StreamFailure::new(output, error));
+    }
+
+    sender.push(AssistantMessageEvent::Start {
+        partial: output.clone(),
+    });
+
+    let events = match socket.events(options.base.cancellation_token.clone()) {
+        Ok(events) => futures::stream::iter([Ok(first_event)]).chain(events),
+        Err(error) => return Err(StreamFailure::new(output, error)),
+    };
+    let output = process_events(
+        model,
+        options,
+        grammar_tool_input_properties,
+        events,
+        output,
+        sender,
+    )
+    .await?;
+    let input = payload
+        .get("input")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    socket.release(
+        output
+            .response_id
+            .clone()
+            .map(|response_id| CachedTurn { input, response_id }),
+    );
+    sender.push(AssistantMessageEvent::Done {
+        reason: output.stop_reason,
+        message: output,
+    });
+    Ok(())
+}
+
+async fn process_events<S>(
+    model: &Model,
+    options: &OpenAIResponsesOptions,
+    grammar_tool_input_properties: &HashMap<String, String>,
+    events: S,
+    mut output: AssistantMessage,
+    sender: &mut AssistantMessageEventStreamSender,
+) -> std::result::Result<AssistantMessage, StreamFailure>
+where
+    S: Stream<Item = Result<SseEvent>>,
+{
     let mut saw_terminal_response_event = false;
     let mut output_slots: HashMap<u64, ResponsesOutputSlot> = HashMap::new();
     let mut reasoning_blocks_by_id: HashMap<String, usize> = HashMap::new();
     let mut partial_json: HashMap<usize, String> = HashMap::new();
     let mut custom_tool_inputs: HashMap<usize, (String, GrammarToolInputJsonBuffer)> =
         HashMap::new();
-    let events = sse::events(response, options.base.cancellation_token.clone());
     pin_mut!(events);
     while let Some(event) = events.next().await {
         if options
@@ -433,7 +574,7 @@ async fn run_stream(
                         &mut output_slots,
                         &mut output,
                         sender,
-                        &grammar_tool_input_properties,
+                        grammar_tool_input_properties,
                         &mut partial_json,
                         &mut custom_tool_inputs,
                     );
@@ -532,6 +673,14 @@ async fn run_stream(
                         delta: delta.to_string(),
                         partial: output.clone(),
                     });
+                    let token_logprobs = logprobs::parse_token_logprobs(parsed.get("logprobs"));
+                    if !token_logprobs.is_empty() {
+                        sender.push(AssistantMessageEvent::TextLogprobs {
+                            content_index: index,
+                            logprobs: token_logprobs,
+                            partial: output.clone(),
+                        });
+                    }
                 }
             }
             "response.function_call_arguments.delta" => {
@@ -704,7 +853,7 @@ async fn run_stream(
                     &mut output_slots,
                     &mut output,
                     sender,
-                    &grammar_tool_input_properties,
+                    grammar_tool_input_properties,
                     &mut partial_json,
                     &mut custom_tool_inputs,
                 );
@@ -937,13 +1086,13 @@ async fn run_stream(
                     output.response_id = Some(id.to_string());
                 }
                 if let Some(usage) = response.get("usage") {
-                    output.usage = parse_response_usage(usage, &model);
+                    output.usage = parse_response_usage(usage, model);
                     if let Some(service_tier) = response
                         .get("service_tier")
                         .and_then(Value::as_str)
                         .or(options.service_tier.as_deref())
                     {
-                        apply_service_tier_pricing(&mut output.usage, service_tier, &model);
+                        apply_service_tier_pricing(&mut output.usage, service_tier, model);
                     }
                 }
                 output.stop_reason =
@@ -962,12 +1111,15 @@ async fn run_stream(
                 break;
             }
             "error" => {
+                // WebSocket transports nest the details under `error`.
                 let code = parsed
                     .get("code")
+                    .or_else(|| parsed.pointer("/error/code"))
                     .and_then(Value::as_str)
                     .unwrap_or("unknown");
                 let message = parsed
                     .get("message")
+                    .or_else(|| parsed.pointer("/error/message"))
                     .and_then(Value::as_str)
                     .unwrap_or("Unknown error");
                 return Err(StreamFailure::new(
@@ -1028,11 +1180,7 @@ async fn run_stream(
     if matches!(output.stop_reason, StopReason::Aborted | StopReason::Error) {
         return Err(StreamFailure::new(output, "An unknown error occurred"));
     }
-    sender.push(AssistantMessageEvent::Done {
-        reason: output.stop_reason,
-        message: output,
-    });
-    Ok(())
+    Ok(output)
 }
 
 #[cfg(test)]
@@ -1098,6 +1246,16 @@ fn try_build_responses_payload(
     if let Some(temperature) = options.base.temperature {
         object.insert("temperature".to_string(), json!(temperature));
     }
+    // The Responses API accepts only `top_p` among the sampling controls.
+    sampling(
+        model,
+        &options.base,
+        SamplingSupport {
+            top_p: true,
+            ..SamplingSupport::NONE
+        },
+    )
+    .insert_into(object, "stop");
     if let Some(service_tier) = &options.service_tier {
         object.insert("service_tier".to_string(), json!(service_tier));
     }
@@ -1115,6 +1273,31 @@ fn try_build_responses_payload(
     }
     if let Some(tool_choice) = &options.tool_choice {
         object.insert("tool_choice".to_string(), tool_choice.clone());
+    } else if let Some(tool_choice) = &options.base.tool_choice {
+        object.insert(
+            "tool_choice".to_string(),
+            convert_responses_tool_choice(tool_choice),
+        );
+    }
+    if let Some(parallel_tool_calls) = options.base.parallel_tool_calls
+        && !placement.immediate.is_empty()
+    {
+        object.insert(
+            "parallel_tool_calls".to_string(),
+            json!(parallel_tool_calls),
+        );
+    }
+    if let Some(format) = &options.base.response_format {
+        let mut text_format = json!({
+            "type": "json_schema",
+            "name": format.name,
+            "schema": format.schema,
+            "strict": format.strict,
+        });
+        if let Some(description) = &format.description {
+            text_format["description"] = json!(description);
+        }
+        object.insert("text".to_string(), json!({ "format": text_format }));
     }
     if cache_retention != CacheRetention::None
         && let Some(session_id) = &options.base.session_id
@@ -1181,9 +1364,32 @@ fn try_build_responses_payload(
             );
         }
     }
+    if logprobs::requested(&options.base) {
+        if let Some(top_logprobs) = options.base.top_logprobs {
+            object.insert("top_logprobs".to_string(), json!(top_logprobs));
+        }
+        if let Some(include) = object
+            .entry("include")
+            .or_insert_with(|| json!([]))
+            .as_array_mut()
+        {
+            include.push(json!("message.output_text.logprobs"));
+        }
+    }
     Ok(payload)
 }
 
+fn convert_input_file(document: &DocumentContent) -> Value {
+    match &document.source {
+        DocumentSource::Base64 { data } => json!({
+            "type": "input_file",
+            "filename": document.title.as_deref().unwrap_or("document.pdf"),
+            "file_data": format!("data:{};base64,{data}", document.mime_type)
+        }),
+        DocumentSource::Url { url } => json!({ "type": "input_file", "file_url": url }),
+    }
+}
+
 #[cfg(test)]
 fn convert_responses_messages(
     model: &Model,
@@ -1260,15 +1466,18 @@ fn try_convert_responses_messages(
                 UserMessageContent::Parts(parts) => {
                     let content: Vec<Value> = parts
                         .iter()
-                        .map(|item| match item {
+                        .filter_map(|item| match item {
                             UserContent::Text(text) => {
-                                json!({ "type": "input_text", "text": &text.text })
+                                Some(json!({ "type": "input_text", "text": &text.text }))
                             }
-                            UserContent::Image(image) => json!({
+                            UserContent::Image(image) => Some(json!({
                                 "type": "input_image",
                                 "detail": "auto",
                                 "image_url": format!("data:{};base64,{}", image.mime_type, image.data)
-                            }),
+                            })),
+                            UserContent::Document(document) => Some(convert_input_file(document)),
+                            // The Responses API has no audio input part.
+                            UserContent::Audio(_) => None,
                         })
                         .collect();
                     if content.is_empty() {
@@ -1326,6 +1535,9 @@ fn try_convert_responses_messages(
                             }
                             output.push(item);
                         }
+                        // Responses never streams audio, and audio from other
+                        // models has already been turned into its transcript.
+                        AssistantContent::Audio(_) => {}
                         AssistantContent::ToolCall(tool_call) => {
                             let (call_id, item_id_raw) = tool_call
                                 .id
@@ -1392,34 +1604,47 @@ fn try_convert_responses_messages(
                     .content
                     .iter()
                     .any(|content| matches!(content, ToolResultContent::Image(_)));
+                let has_documents = tool_result
+                    .content
+                    .iter()
+                    .any(|content| matches!(content, ToolResultContent::Document(_)));
                 let (call_id, _) = tool_result
                     .tool_call_id
                     .split_once('|')
                     .unwrap_or((&tool_result.tool_call_id, ""));
-                let output = if has_images && model.input.contains(&ModelInput::Image) {
-                    let mut content = Vec::new();
-                    if !text_result.is_empty() {
-                        content.push(json!({ "type": "input_text", "text": &text_result }));
-                    }
-                    for block in tool_result.content {
-                        if let ToolResultContent::Image(ImageContent { data, mime_type }) = block {
-                            content.push(json!({
-                                "type": "input_image",
-                                "detail": "auto",
-                                "image_url": format!("data:{mime_type};base64,{data}")
-                            }));
+                let output =
+                    if (has_images && model.input.contains(&ModelInput::Image)) || has_documents {
+                        let mut content = Vec::new();
+                        if !text_result.is_empty() {
+                            content.push(json!({ "type": "input_text", "text": &text_result }));
                         }
-                    }
-                    Value::Array(content)
-                } else {
-                    json!(if !text_result.is_empty() {
-                        &text_result
-                    } else if has_images {
-                        "(see attached image)"
+                        for block in tool_result.content {
+                            match block {
+                                ToolResultContent::Image(ImageContent { data, mime_type })
+                                    if model.input.contains(&ModelInput::Image) =>
+                                {
+                                    content.push(json!({
+                                        "type": "input_image",
+                                        "detail": "auto",
+                                        "image_url": format!("data:{mime_type};base64,{data}")
+                                    }));
+                                }
+                                ToolResultContent::Document(document) => {
+                                    content.push(convert_input_file(&document));
+                                }
+                                _ => {}
+                            }
+                        }
+                        Value::Array(content)
                     } else {
-                        "(no tool output)"
-                    })
-                };
+                        json!(if !text_result.is_empty() {
+                            &text_result
+                        } else if has_images {
+                            "(see attached image)"
+                        } else {
+                            "(no tool output)"
+                        })
+                    };
                 messages.push(json!({
                     "type": if grammar_tool_input_properties.contains_key(&tool_result.tool_name) {
                         "custom_tool_call_output"
@@ -1477,6 +1702,15 @@ fn try_convert_responses_messages(
     Ok(messages)
 }
 
+fn convert_responses_tool_choice(tool_choice: &ToolChoice) -> Value {
+    match tool_choice {
+        ToolChoice::Auto => json!("auto"),
+        ToolChoice::None => json!("none"),
+        ToolChoice::Required => json!("required"),
+        ToolChoice::Tool(name) => json!({ "type": "function", "name": name }),
+    }
+}
+
 fn convert_responses_tools(
     tools: &[Tool],
     strict: Option<bool>,
@@ -1784,15 +2018,20 @@ fn apply_service_tier_pricing(usage: &mut Usage, service_tier: &str, model: &Mod
         usage.cost.input + usage.cost.output + usage.cost.cache_read + usage.cost.cache_write;
 }
 
-fn trim_end_slash(url: &str) -> &str {
-    url.trim_end_matches('/')
-}
-
 fn request_base_url(model: &Model) -> Result<String> {
     Ok(model.base_url.clone())
 }
 
-fn format_openai_responses_api_error(status: reqwest::StatusCode, body: &str) -> String {
+fn format_openai_responses_api_error(
+    model: &Model,
+    status: reqwest::StatusCode,
+    body: &str,
+) -> String {
+    if azure_openai::is_azure_openai(model)
+        && let Some(message) = azure_openai::content_filter_error(status, body)
+    {
+        return message;
+    }
     let message = openai_error_message(body).unwrap_or_else(|| {
         let body = body.trim();
         if body.is_empty() {
@@ -1826,11 +2065,13 @@ mod tests {
     use super::*;
     use crate::types::{
         ConstrainedSampling, ConstrainedSamplingConfig, ConstrainedSamplingStrict, GrammarVariants,
-        Message, ModelCost, PayloadHook, ResponseHook, ToolResultMessage,
+        JsonSchemaFormat, Message, ModelCost, PayloadHook, ResponseHook, ToolChoice,
+        ToolResultMessage,
     };
-    use futures::StreamExt;
+    use futures::{SinkExt, StreamExt};
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message as WebSocketMessage;
 
     const COPILOT_RAW_TOOL_CALL_ID: &str = "call_4VnzVawQXPB9MgYib7CiQFEY|I9b95oN1wD/cHXKTw3PpRkL6KkCtzTJhUxMouMWYwHeTo2j3htzfSk7YPx2vifiIM4g3A8XXyOj8q4Bt6SLUG7gqY1E3ELkrkVQNHglRfUmWj84lqxJY+Puieb3VKyX0FB+83TUzn91cDMF/4gzt990IzqVrc+nIb9RRscRD070Du16q1glydVjWR0SBJsE6TbY/esOjFpqplogQqrajm1eI++f3eLi73R6q7hVusY0QbeFySVxABCjhN0lXB04caBe1rzHjYzul6MAXj7uq+0r17VLq+yrtyYhN12wkmFqHeqTyEei6EFPbMy24Nc+IbJlkP0OCg02W+gOnyBFcbi2ctvJFSOhSjt1CqBdqCnnhwUqXjbWiT0wh3DmLScRgTHmGkaI+oAcQQjfic65nxj+TnEkReA==";
 
@@ -2748,6 +2989,78 @@ mod tests {
         assert_eq!(payload["tool_choice"], json!("required"));
     }
 
+    #[test]
+    fn response_payload_maps_typed_tool_choice_and_parallel_tool_calls() {
+        let model = model();
+        let context = Context {
+            tools: vec![Tool {
+                name: "lookup".to_string(),
+                description: "Look up a value".to_string(),
+                parameters: json!({ "type": "object" }),
+                constrained_sampling: None,
+            }],
+            ..Default::default()
+        };
+        let options = OpenAIResponsesOptions {
+            base: StreamOptions {
+                tool_choice: Some(ToolChoice::tool("lookup")),
+                parallel_tool_calls: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let payload = build_responses_payload(
+            &model,
+            &context,
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+
+        assert_eq!(
+            payload["tool_choice"],
+            json!({ "type": "function", "name": "lookup" })
+        );
+        assert_eq!(payload["parallel_tool_calls"], json!(false));
+    }
+
+    #[test]
+    fn response_payload_maps_response_format_to_text_format() {
+        let model = model();
+        let options = OpenAIResponsesOptions {
+            base: StreamOptions {
+                response_format: Some(
+                    JsonSchemaFormat::new(json!({ "type": "object" }))
+                        .name("answer")
+                        .strict(true),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let payload = build_responses_payload(
+            &model,
+            &Context::default(),
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+
+        assert_eq!(
+            payload["text"],
+            json!({
+                "format": {
+                    "type": "json_schema",
+                    "name": "answer",
+                    "schema": { "type": "object" },
+                    "strict": true
+                }
+            })
+        );
+    }
+
     #[test]
     fn response_payload_uses_system_role_when_developer_role_is_unsupported() {
         let mut model = model();
@@ -3340,6 +3653,104 @@ mod tests {
         );
     }
 
+    #[test]
+    fn response_payload_adds_logprobs_to_include() {
+        let mut model = reasoning_model_without_off_support("grok-4.5");
+        model.provider = "xai".to_string();
+        let context = Context {
+            messages: vec![Message::user_text("hello")],
+            ..Default::default()
+        };
+        let options = OpenAIResponsesOptions {
+            base: StreamOptions {
+                top_logprobs: Some(5),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let payload = build_responses_payload(
+            &model,
+            &context,
+            &options,
+            &get_compat(&model),
+            CacheRetention::Short,
+        );
+
+        assert_eq!(payload["top_logprobs"], json!(5));
+        assert_eq!(
+            payload["include"],
+            json!([
+                "reasoning.encrypted_content",
+                "message.output_text.logprobs"
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn response_text_delta_logprobs_are_streamed() {
+        let body = sse_body(&[
+            json!({
+                "type": "response.output_item.added",
+                "output_index": 0,
+                "item": {
+                    "type": "message",
+                    "id": "msg_test",
+                    "role": "assistant",
+                    "content": []
+                }
+            }),
+            json!({
+                "type": "response.output_text.delta",
+                "output_index": 0,
+                "delta": "Yes",
+                "logprobs": [{
+                    "token": "Yes",
+                    "logprob": -0.02,
+                    "top_logprobs": [{ "token": "No", "logprob": -3.9 }]
+                }]
+            }),
+            json!({
+                "type": "response.completed",
+                "response": { "id": "resp_test", "status": "completed" }
+            }),
+        ]);
+        let mut model = model();
+        model.base_url = spawn_sse_server(body).await;
+
+        let mut stream = stream_openai_responses(
+            model,
+            Context {
+                messages: vec![Message::user_text("Is it spam?")],
+                ..Default::default()
+            },
+            OpenAIResponsesOptions {
+                base: StreamOptions {
+                    api_key: Some("test-key".to_string()),
+                    logprobs: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let mut logprobs = Vec::new();
+        while let Some(event) = stream.next().await {
+            if let AssistantMessageEvent::TextLogprobs {
+                content_index,
+                logprobs: tokens,
+                ..
+            } = event.expect("stream event")
+            {
+                assert_eq!(content_index, 0);
+                logprobs.extend(tokens);
+            }
+        }
+
+        assert_eq!(logprobs.len(), 1);
+        assert_eq!(logprobs[0].token, "Yes");
+        assert_eq!(logprobs[0].logprob, -0.02);
+        assert_eq!(logprobs[0].top_logprobs[0].token, "No");
+    }
+
     async fn stream_reasoning_signature(done_item: Value, completed_item: Value) -> Value {
         let body = sse_body(&[
             json!({
@@ -5176,7 +5587,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::Stop,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 2,
         };
         let context = Context {
@@ -5359,7 +5773,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::ToolUse,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 2,
         };
         let context = Context {
@@ -5419,7 +5836,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::ToolUse,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 2,
         };
         let context = Context {
@@ -5460,7 +5880,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::ToolUse,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 2,
         };
         let tool_result = ToolResultMessage {
@@ -5527,7 +5950,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::ToolUse,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 1,
         };
         let tool_result = ToolResultMessage {
@@ -5603,6 +6029,377 @@ mod tests {
         );
     }
 
+    #[test]
+    fn documents_become_input_files() {
+        let mut model = model();
+        model.input.push(ModelInput::Pdf);
+        let assistant = AssistantMessage {
+            content: vec![AssistantContent::ToolCall(ToolCall {
+                id: "call-1".to_string(),
+                name: "fetch".to_string(),
+                arguments: json!({}),
+                thought_signature: None,
+            })],
+            ..AssistantMessage::empty_for(&model)
+        };
+        let context = Context {
+            messages: vec![
+                Message::User(crate::types::UserMessage {
+                    content: UserMessageContent::Parts(vec![
+                        UserContent::Document(DocumentContent::pdf("JVBERi0=").title("q3.pdf")),
+                        UserContent::Document(DocumentContent::url(
+                            "https://example.com/q4.pdf",
+                            "application/pdf",
+                        )),
+                    ]),
+                    timestamp: 1,
+                }),
+                Message::Assistant(assistant),
+                Message::ToolResult(ToolResultMessage {
+                    tool_call_id: "call-1".to_string(),
+                    tool_name: "fetch".to_string(),
+                    content: vec![ToolResultContent::Document(DocumentContent::pdf(
+                        "JVBERi0x",
+                    ))],
+                    details: None,
+                    usage: None,
+                    added_tool_names: Vec::new(),
+                    is_error: false,
+                    timestamp: 2,
+                }),
+            ],
+            ..Default::default()
+        };
+
+        let input =
+            convert_responses_messages(&model, &context, &["openai"].into_iter().collect(), true);
+
+        assert_eq!(
+            input[0]["content"],
+            json!([
+                {
+                    "type": "input_file",
+                    "filename": "q3.pdf",
+                    "file_data": "data:application/pdf;base64,JVBERi0="
+                },
+                { "type": "input_file", "file_url": "https://example.com/q4.pdf" }
+            ])
+        );
+        let output = input
+            .iter()
+            .find(|item| item["type"] == "function_call_output")
+            .expect("function_call_output item");
+        assert_eq!(
+            output["output"],
+            json!([{
+                "type": "input_file",
+                "filename": "document.pdf",
+                "file_data": "data:application/pdf;base64,JVBERi0x"
+            }])
+        );
+    }
+
+    fn websocket_turn_events(turn: usize) -> Vec<Value> {
+        vec![
+            json!({
+                "type": "response.output_item.added",
+                "output_index": 0,
+                "item": { "type": "message", "id": format!("msg_{turn}"), "role": "assistant", "content": [] }
+            }),
+            json!({
+                "type": "response.output_text.delta",
+                "output_index": 0,
+                "delta": format!("turn {turn}")
+            }),
+            json!({
+                "type": "response.output_item.done",
+                "output_index": 0,
+                "item": {
+                    "type": "message",
+                    "id": format!("msg_{turn}"),
+                    "role": "assistant",
+                    "content": [{ "type": "output_text", "text": format!("turn {turn}") }]
+                }
+            }),
+            json!({
+                "type": "response.completed",
+                "response": {
+                    "id": format!("resp_{turn}"),
+                    "status": "completed",
+                    "usage": {
+                        "input_tokens": 1,
+                        "output_tokens": 1,
+                        "total_tokens": 2,
+                        "input_tokens_details": { "cached_tokens": 0 }
+                    }
+                }
+            }),
+        ]
+    }
+
+    async fn websocket_turn(
+        model: &Model,
+        messages: &mut Vec<Message>,
+        text: &str,
+        transport: Transport,
+    ) -> AssistantMessage {
+        websocket_turn_with_key(model, messages, text, transport, "test-key").await
+    }
+
+    async fn websocket_turn_with_key(
+        model: &Model,
+        messages: &mut Vec<Message>,
+        text: &str,
+        transport: Transport,
+        api_key: &str,
+    ) -> AssistantMessage {
+        messages.push(Message::user_text(text));
+        let stream = stream_openai_responses(
+            model.clone(),
+            Context {
+                messages: messages.clone(),
+                ..Default::default()
+            },
+            OpenAIResponsesOptions {
+                base: StreamOptions {
+                    api_key: Some(api_key.to_string()),
+                    transport: Some(transport),
+                    session_id: Some(format!("websocket-{transport:?}")),
+                    cache_retention: Some(CacheRetention::None),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let message = crate::stream::final_message_from_stream(stream)
+            .await
+            .expect("final message");
+        messages.push(Message::Assistant(message.clone()));
+        message
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn websocket_transport_reuses_session_connection_across_turns() {
+        let _guard = crate::session_resources::TEST_LOCK.lock().await;
+        let server = spawn_websocket_server().await;
+        let mut model = model();
+        model.base_url = server.base_url.clone();
+        let mut messages = Vec::new();
+
+        let first = websocket_turn(&model, &mut messages, "hello", Transport::Websocket).await;
+        let second = websocket_turn(&model, &mut messages, "again", Transport::Websocket).await;
+
+        assert_eq!(first.stop_reason, StopReason::Stop, "{first:?}");
+        assert_eq!(second.response_id.as_deref(), Some("resp_2"));
+        assert_eq!(server.connections.load(Ordering::SeqCst), 1);
+        let requests = server.requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["type"], json!("response.create"));
+        assert_eq!(requests[0]["model"], json!("gpt-5.5"));
+        assert!(requests[0].get("stream").is_none());
+        assert_eq!(requests[1]["input"].as_array().map(Vec::len), Some(3));
+        assert!(requests[1].get("previous_response_id").is_none());
+
+        crate::cleanup_session_resources(Some("websocket-Websocket")).expect("cleanup");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn websocket_cached_transport_continues_previous_response_until_cleanup() {
+        let _guard = crate::session_resources::TEST_LOCK.lock().await;
+        let server = spawn_websocket_server().await;
+        let mut model = model();
+        model.base_url = server.base_url.clone();
+        let mut messages = Vec::new();
+
+        websocket_turn(&model, &mut messages, "hello", Transport::WebsocketCached).await;
+        websocket_turn(&model, &mut messages, "again", Transport::WebsocketCached).await;
+        crate::cleanup_session_resources(Some("websocket-WebsocketCached")).expect("cleanup");
+        let third = websocket_turn(
+            &model,
+            &mut messages,
+            "once more",
+            Transport::WebsocketCached,
+        )
+        .await;
+
+        assert_eq!(third.response_id.as_deref(), Some("resp_3"));
+        assert_eq!(server.connections.load(Ordering::SeqCst), 2);
+        let requests = server.requests.lock().unwrap().clone();
+        assert_eq!(requests[1]["previous_response_id"], json!("resp_1"));
+        assert_eq!(
+            requests[1]["input"],
+            json!([{ "role": "user", "content": [{ "type": "input_text", "text": "again" }] }])
+        );
+        assert!(requests[2].get("previous_response_id").is_none());
+        assert_eq!(requests[2]["input"].as_array().map(Vec::len), Some(5));
+
+        crate::cleanup_session_resources(Some("websocket-WebsocketCached")).expect("cleanup");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn websocket_session_reconnects_when_endpoint_or_credentials_change() {
+        let _guard = crate::session_resources::TEST_LOCK.lock().await;
+        let first_server = spawn_websocket_server().await;
+        let second_server = spawn_websocket_server().await;
+        let mut model = model();
+        model.base_url = first_server.base_url.clone();
+        let mut messages = Vec::new();
+
+        websocket_turn(&model, &mut messages, "hello", Transport::WebsocketCached).await;
+        websocket_turn_with_key(
+            &model,
+            &mut messages,
+            "again",
+            Transport::WebsocketCached,
+            "other-key",
+        )
+        .await;
+        model.base_url = second_server.base_url.clone();
+        let third = websocket_turn_with_key(
+            &model,
+            &mut messages,
+            "once more",
+            Transport::WebsocketCached,
+            "other-key",
+        )
+        .await;
+
+        assert_eq!(third.stop_reason, StopReason::Stop, "{third:?}");
+        assert_eq!(first_server.connections.load(Ordering::SeqCst), 2);
+        assert_eq!(second_server.connections.load(Ordering::SeqCst), 1);
+        let first_requests = first_server.requests.lock().unwrap().clone();
+        assert!(first_requests[1].get("previous_response_id").is_none());
+        let second_requests = second_server.requests.lock().unwrap().clone();
+        assert!(second_requests[0].get("previous_response_id").is_none());
+        assert_eq!(
+            second_requests[0]["input"].as_array().map(Vec::len),
+            Some(5)
+        );
+
+        crate::cleanup_session_resources(Some("websocket-WebsocketCached")).expect("cleanup");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn websocket_transport_reports_nested_error_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = accept_websocket(socket).await;
+            let _ = socket.next().await;
+            let error = json!({
+                "type": "error",
+                "status": 400,
+                "error": { "code": "invalid_request", "message": "bad input" }
+            });
+            socket
+                .send(WebSocketMessage::text(error.to_string()))
+                .await
+                .unwrap();
+        });
+        let mut model = model();
+        model.base_url = format!("http://{addr}");
+
+        let message = crate::stream::final_message_from_stream(stream_openai_responses(
+            model,
+            Context {
+                messages: vec![Message::user_text("hello")],
+                ..Default::default()
+            },
+            OpenAIResponsesOptions {
+                base: StreamOptions {
+                    api_key: Some("test-key".to_string()),
+                    transport: Some(Transport::Websocket),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .await
+        .expect("final message");
+
+        assert_eq!(message.stop_reason, StopReason::Error);
+        assert_eq!(
+            message.error_message.as_deref(),
+            Some("Error Code invalid_request: bad input")
+        );
+    }
+
+    struct WebSocketServer {
+        base_url: String,
+        connections: Arc<AtomicUsize>,
+        requests: Arc<Mutex<Vec<Value>>>,
+    }
+
+    async fn spawn_websocket_server() -> WebSocketServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let server = WebSocketServer {
+            base_url: format!("http://{addr}/v1"),
+            connections: Arc::clone(&connections),
+            requests: Arc::clone(&requests),
+        };
+        tokio::spawn(async move {
+            loop {
+                let Ok((socket, _)) = listener.accept().await else {
+                    break;
+                };
+                connections.fetch_add(1, Ordering::SeqCst);
+                let requests = Arc::clone(&requests);
+                tokio::spawn(async move {
+                    let mut socket = accept_websocket(socket).await;
+                    while let Some(Ok(WebSocketMessage::Text(text))) = socket.next().await {
+                        let turn = {
+                            let mut requests = requests.lock().unwrap();
+                            requests.push(serde_json::from_str::<Value>(&text).unwrap());
+                            requests.len()
+                        };
+                        for event in websocket_turn_events(turn) {
+                            socket
+                                .send(WebSocketMessage::text(event.to_string()))
+                                .await
+                                .unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        server
+    }
+
+    async fn accept_websocket(
+        mut socket: tokio::net::TcpStream,
+    ) -> tokio_tungstenite::WebSocketStream<tokio::net::TcpStream> {
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 1024];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            let read = socket.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+        let request = String::from_utf8(request).unwrap();
+        let key = request
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("sec-websocket-key")
+                    .then(|| value.trim().to_string())
+            })
+            .unwrap();
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nupgrade: websocket\r\nconnection: Upgrade\r\nsec-websocket-accept: {}\r\n\r\n",
+            crate::providers::openai_websocket::websocket_accept(&key)
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+        tokio_tungstenite::WebSocketStream::from_raw_socket(
+            socket,
+            tokio_tungstenite::tungstenite::protocol::Role::Server,
+            None,
+        )
+        .await
+    }
+
     fn sse_body(events: &[Value]) -> String {
         events
             .iter()
@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use async_stream::try_stream;
+use base64::Engine;
+use futures::{SinkExt, Stream, StreamExt};
+use parking_lot::Mutex;
+use reqwest::header::{CONNECTION, HeaderMap, HeaderValue, UPGRADE};
+use reqwest::{StatusCode, Upgraded};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::{Value, json};
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_util::sync::CancellationToken;
+
+use crate::session_resources::{
+    SessionResourceCleanupRegistration, register_session_resource_cleanup,
+};
+use crate::types::{ProviderResponse, StreamOptions};
+use crate::utils::headers::headers_to_record;
+use crate::utils::http::send_with_retries;
+use crate::utils::sse::SseEvent;
+use crate::{Error, Result};
+
+const DEFAULT_WEBSOCKET_CONNECT_TIMEOUT_MS: u64 = 15_000;
+const WEBSOCKET_ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+type Socket = WebSocketStream<Upgraded>;
+
+/// The last request served on a cached connection. `WebsocketCached` uses it
+/// to continue from `previous_response_id` instead of resending history.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CachedTurn {
+    pub input: Vec<Value>,
+    pub response_id: String,
+}
+
+struct CachedConnection {
+    socket: Socket,
+    /// Digest of the URL and handshake headers the socket was opened with.
+    /// A session id reused against another endpoint or with other
+    /// credentials must not pick up this socket.
+    endpoint: String,
+    last_turn: Option<CachedTurn>,
+    registration: Option<SessionResourceCleanupRegistration>,
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, CachedConnection>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, CachedConnection>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Clone)]
+pub(crate) struct WebSocketConnector {
+    pub url: String,
+    pub circuit: String,
+    pub headers: HeaderMap,
+    pub options: StreamOptions,
+}
+
+/// A Responses WebSocket checked out for one request. Connections keyed by a
+/// session id go back to the session cache on `release`; any other exit path
+/// closes them.
+pub(crate) struct ResponsesWebSocket {
+    session_id: Option<String>,
+    connection: Option<CachedConnection>,
+    reused: bool,
+    handshake: Option<ProviderResponse>,
+}
+
+impl ResponsesWebSocket {
+    pub(crate) async fn acquire(connector: &WebSocketConnector) -> Result<Self> {
+        let session_id = connector.options.session_id.clone();
+        let endpoint = endpoint_fingerprint(connector);
+        if let Some(session_id) = &session_id {
+            let cached = sessions().lock().remove(session_id);
+            match cached {
+                Some(connection) if connection.endpoint == endpoint => {
+                    return Ok(Self {
+                        session_id: Some(session_id.clone()),
+                        connection: Some(connection),
+                        reused: true,
+                        handshake: None,
+                    });
+                }
+                Some(connection) => {
+                    unregister(connection.registration);
+                    close_socket(connection.socket);
+                }
+                None => {}
+            }
+        }
+        let (socket, handshake) = connect(connector).await?;
+        Ok(Self {
+            session_id,
+            connection: Some(CachedConnection {
+                socket,
+                endpoint,
+                last_turn: None,
+                registration: None,
+            }),
+            reused: false,
+            handshake: Some(handshake),
+        })
+    }
+
+    fn last_turn(&self) -> Option<&CachedTurn> {
+        self.connection
+            .as_ref()
+            .and_then(|connection| connection.last_turn.as_ref())
+    }
+
+    /// The upgrade response when this request opened a new connection.
+    pub(crate) fn handshake(&self) -> Option<&ProviderResponse> {
+        self.handshake.as_ref()
+    }
+
+    /// Sends a `response.create` message and waits for the first server event.
+    ///
+    /// With `continue_previous`, the request continues from the last response
+    /// on this connection when possible. A cached connection may have been
+    /// closed by the server while idle, so a reused connection that fails
+    /// before producing an event is replaced by a fresh one and the full
+    /// request is sent again.
+    pub(crate) async fn send_request(
+        &mut self,
+        connector: &WebSocketConnector,
+        payload: &Value,
+        continue_previous: bool,
+    ) -> Result<SseEvent> {
+        let mut request = payload.clone();
+        if continue_previous {
+            continue_from_previous_response(&mut request, self.last_turn());
+        }
+        let result = self
+            .send_and_read_first(&response_create_message(&request)?, connector)
+            .await;
+        match result {
+            Err(Error::Cancelled) => Err(Error::Cancelled),
+            Err(_) if self.reused => {
+                let (socket, handshake) = connect(connector).await?;
+                if let Some(previous) = self.connection.take() {
+                    unregister(previous.registration);
+                }
+                self.connection = Some(CachedConnection {
+                    socket,
+                    endpoint: endpoint_fingerprint(connector),
+                    last_turn: None,
+                    registration: None,
+                });
+                self.reused = false;
+                self.handshake = Some(handshake);
+                self.send_and_read_first(&response_create_message(payload)?, connector)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    async fn send_and_read_first(
+        &mut self,
+        message: &str,
+        connector: &WebSocketConnector,
+    ) -> Result<SseEvent> {
+        let cancellation_token = connector.options.cancellation_token.clone();
+        let socket = self.socket_mut()?;
+        with_cancellation(
+            cancellation_token.as_ref(),
+            socket.send(Message::text(message)),
+        )
+        .await?
+        .map_err(websocket_error)?;
+        let events = events(socket, cancellation_token);
+        futures::pin_mut!(events);
+        events.next().await.unwrap_or_else(|| {
+            Err(Error::Provider(
+                "WebSocket closed before the first response event".to_string(),
+            ))
+        })
+    }
+
+    pub(crate) fn events(
+        &mut self,
+        cancellation_token: Option<CancellationToken>,
+    ) -> Result<impl Stream<Item = Result<SseEvent>> + Send + '_> {
+        Ok(events(self.socket_mut()?, cancellation_token))
+    }
+
+    /// Returns the connection to the session cache, recording the completed
+    /// turn for `WebsocketCached`. Connections without a session id close.
+    pub(crate) fn release(mut self, turn: Option<CachedTurn>) {
+        let Some(mut connection) = self.connection.take() else {
+            return;
+        };
+        let Some(session_id) = self.session_id.take() else {
+            close_socket(connection.socket);
+            return;
+        };
+        connection.last_turn = turn;
+        if connection.registration.is_none() {
+            connection.registration = Some(register_session_cleanup(session_id.clone()));
+        }
+        if let Some(previous) = sessions().lock().insert(session_id, connection) {
+            unregister(previous.registration);
+            close_socket(previous.socket);
+        }
+    }
+
+    fn socket_mut(&mut self) -> Result<&mut Socket> {
+        self.connection
+            .as_mut()
+            .map(|connection| &mut connection.socket)
+            .ok_or_else(|| Error::Provider("WebSocket connection was released".to_string()))
+    }
+}
+
+impl Drop for ResponsesWebSocket {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            unregister(connection.registration);
+            close_socket(connection.socket);
+        }
+    }
+}
+
+/// Rewrites `payload` to continue from the previous response on the same
+/// connection when its input extends the previous input plus the model's
+/// output items. Returns `false` and leaves the payload untouched otherwise.
+fn continue_from_previous_response(payload: &mut Value, previous: Option<&CachedTurn>) -> bool {
+    let Some(previous) = previous else {
+        return false;
+    };
+    let Some(input) = payload.get("input").and_then(Value::as_array) else {
+        return false;
+    };
+    if input.len() <= previous.input.len() || !input.starts_with(&previous.input) {
+        return false;
+    }
+    let continuation = input[previous.input.len()..]
+        .iter()
+        .position(|item| !is_response_output_item(item))
+        .map(|offset| previous.input.len() + offset);
+    let Some(continuation) = continuation.filter(|index| *index > previous.input.len()) else {
+        return false;
+    };
+    let remaining = input[continuation..].to_vec();
+    payload["input"] = Value::Array(remaining);
+    payload["previous_response_id"] = json!(previous.response_id);
+    true
+}
+
+fn is_response_output_item(item: &Value) -> bool {
+    match item.get("type").and_then(Value::as_str) {
+        Some("reasoning" | "function_call" | "custom_tool_call") => true,
+        Some("message") => item.get("role").and_then(Value::as_str) == Some("assistant"),
+        _ => false,
+    }
+}
+
+fn response_create_message(payload: &Value) -> Result<String> {
+    let mut message = payload.clone();
+    let object = message.as_object_mut().ok_or_else(|| {
+        Error::Validation("OpenAI Responses payload must be a JSON object".to_string())
+    })?;
+    object.remove("stream");
+    object.remove("background");
+    object.insert("type".to_string(), json!("response.create"));
+    Ok(serde_json::to_string(&message)?)
+}
+
+fn endpoint_fingerprint(connector: &WebSocketConnector) -> String {
+    let mut headers = connector
+        .headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_bytes()))
+        .collect::<Vec<_>>();
+    headers.sort_unstable();
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    context.update(connector.url.as_bytes());
+    for (name, value) in headers {
+        context.update(b"\n");
+        context.update(name.as_bytes());
+        context.update(b":");
+        context.update(value);
+    }
+    base64::engine::general_purpose::STANDARD.encode(context.finish().as_ref())
+}
+
+async fn connect(connector: &WebSocketConnector) -> Result<(Socket, ProviderResponse)> {
+    let key = websocket_key()?;
+    let client = connector.options.http_client.clone().unwrap_or_default();
+    let url = http_url(&connector.url);
+    let connect_timeout = Duration::from_millis(
+        connector
+            .options
+            .websocket_connect_timeout_ms
+            .unwrap_or(DEFAULT_WEBSOCKET_CONNECT_TIMEOUT_MS),
+    );
+    let request = send_with_retries(&connector.options, &connector.circuit, || {
+        client
+            .get(url.as_str())
+            .headers(connector.headers.clone())
+            .header(CONNECTION, HeaderValue::from_static("Upgrade"))
+            .header(UPGRADE, HeaderValue::from_static("websocket"))
+            .header("sec-websocket-version", HeaderValue::from_static("13"))
+            .header("sec-websocket-key", key.as_str())
+    });
+    let response = tokio::time::timeout(connect_timeout, request)
+        .await
+        .map_err(|_| {
+            Error::Provider(format!(
+                "WebSocket connection timed out after {}ms",
+                connect_timeout.as_millis()
+            ))
+        })??;
+    let status = response.status();
+    if status != StatusCode::SWITCHING_PROTOCOLS {
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::ApiStatus { status, body });
+    }
+    let expected_accept = websocket_accept(&key);
+    let accept = response
+        .headers()
+        .get("sec-websocket-accept")
+        .and_then(|value| value.to_str().ok());
+    if accept != Some(expected_accept.as_str()) {
+        return Err(Error::InvalidProviderResponse(
+            "WebSocket handshake returned an invalid sec-websocket-accept header".to_string(),
+        ));
+    }
+    let handshake = ProviderResponse {
+        status: status.as_u16(),
+        headers: headers_to_record(response.headers()),
+    };
+    let upgraded = response.upgrade().await?;
+    let socket = WebSocketStream::from_raw_socket(upgraded, Role::Client, None).await;
+    Ok((socket, handshake))
+}
+
+fn events(
+    socket: &mut Socket,
+    cancellation_token: Option<CancellationToken>,
+) -> impl Stream<Item = Result<SseEvent>> + Send + '_ {
+    try_stream! {
+        loop {
+            let message = with_cancellation(cancellation_token.as_ref(), socket.next()).await?;
+            let data = match message {
+                None | Some(Ok(Message::Close(_))) => break,
+                Some(Err(error)) => Err(websocket_error(error))?,
+                Some(Ok(Message::Text(text))) => text.to_string(),
+                Some(Ok(Message::Binary(bytes))) => String::from_utf8_lossy(&bytes).into_owned(),
+                Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => continue,
+            };
+            yield SseEvent {
+                event: None,
+                data,
+                raw: Vec::new(),
+            };
+        }
+    }
+}
+
+async fn with_cancellation<F: Future>(
+    cancellation_token: Option<&CancellationToken>,
+    future: F,
+) -> Result<F::Output> {
+    match cancellation_token {
+        Some(cancellation_token) => tokio::select! {
+            _ = cancellation_token.cancelled() => Err(Error::Cancelled),
+            output = future => Ok(output),
+        },
+        None => Ok(future.await),
+    }
+}
+
+fn register_session_cleanup(session_id: String) -> SessionResourceCleanupRegistration {
+    register_session_resource_cleanup(move |target| {
+        if target.is_none_or(|target| target == session_id) {
+            let connection = sessions().lock().remove(&session_id);
+            if let Some(connection) = connection {
+                unregister(connection.registration);
+                close_socket(connection.socket);
+            }
+        }
+        Ok(())
+    })
+}
+
+fn unregister(registration: Option<SessionResourceCleanupRegistration>) {
+    if let Some(registration) = registration {
+        registration.unregister();
+    }
+}
+
+fn close_socket(mut socket: Socket) {
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        runtime.spawn(async move {
+            let _ = socket.close(None).await;
+        });
+    }
+}
+
+fn websocket_error(error: tokio_tungstenite::tungstenite::Error) -> Error {
+    Error::Provider(format!("WebSocket error: {error}"))
+}
+
+fn websocket_key() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| Error::Provider("failed to generate WebSocket key".to_string()))?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+pub(crate) fn websocket_accept(key: &str) -> String {
+    let digest = ring::digest::digest(
+        &ring::digest::SHA1_FOR_LEGACY_USE_ONLY,
+        format!("{key}{WEBSOCKET_ACCEPT_GUID}").as_bytes(),
+    );
+    base64::engine::general_purpose::STANDARD.encode(digest.as_ref())
+}
+
+fn http_url(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("wss://") {
+        format!("https://{rest}")
+    } else if let Some(rest) = url.strip_prefix("ws://") {
+        format!("http://{rest}")
+    } else {
+        url.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(input: Vec<Value>) -> CachedTurn {
+        CachedTurn {
+            input,
+            response_id: "resp_1".to_string(),
+        }
+    }
+
+    #[test]
+    fn accept_key_matches_rfc_6455_example() {
+        assert_eq!(
+            websocket_accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn response_create_message_drops_http_only_fields() {
+        let message = response_create_message(&json!({
+            "model": "gpt-5.5",
+            "input": [],
+            "stream": true
+        }))
+        .unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&message).unwrap(),
+            json!({ "type": "response.create", "model": "gpt-5.5", "input": [] })
+        );
+    }
+
+    #[test]
+    fn continues_from_previous_response_after_model_output_items() {
+        let user = json!({ "role": "user", "content": "hi" });
+        let call = json!({ "type": "function_call", "call_id": "call_1", "name": "read" });
+        let result = json!({ "type": "function_call_output", "call_id": "call_1", "output": "ok" });
+        let mut payload = json!({ "input": [user.clone(), call, result.clone()] });
+
+        assert!(continue_from_previous_response(
+            &mut payload,
+            Some(&turn(vec![user]))
+        ));
+        assert_eq!(
+            payload,
+            json!({ "input": [result], "previous_response_id": "resp_1" })
+        );
+    }
+
+    #[test]
+    fn sends_full_input_when_history_diverges_or_has_no_model_output() {
+        let user = json!({ "role": "user", "content": "hi" });
+        let other = json!({ "role": "user", "content": "edited" });
+        let follow_up = json!({ "role": "user", "content": "again" });
+
+        let mut diverged = json!({ "input": [other, follow_up.clone()] });
+        assert!(!continue_from_previous_response(
+            &mut diverged,
+            Some(&turn(vec![user.clone()]))
+        ));
+        let mut no_output = json!({ "input": [user.clone(), follow_up] });
+        let unchanged = no_output.clone();
+        assert!(!continue_from_previous_response(
+            &mut no_output,
+            Some(&turn(vec![user]))
+        ));
+        assert_eq!(no_output, unchanged);
+    }
+
+    #[test]
+    fn maps_websocket_urls_to_http_for_the_upgrade_request() {
+        assert_eq!(
+            http_url("wss://api.openai.com/v1/responses"),
+            "https://api.openai.com/v1/responses"
+        );
+        assert_eq!(http_url("ws://127.0.0.1:1/v1"), "http://127.0.0.1:1/v1");
+        assert_eq!(http_url("http://127.0.0.1:1/v1"), "http://127.0.0.1:1/v1");
+    }
+}
@@ -8,13 +8,15 @@ use serde_json::{Value, json};
 use crate::env_api_keys::{KnownProvider, get_env_api_key};
 use crate::models::calculate_cost;
 use crate::provider::{ImageModelApi, ModelBuilder, Provider, ProviderCapabilities};
+use crate::providers::{openai, openai_completions};
 use crate::types::{
     AssistantImages, ImageContent, ImageGenerationOptions, ImageOutput, ImagesContext,
-    ImagesStopReason, KnownApi, Model, ModelInput, ModelOutput, ProviderResponse, TextContent,
-    Usage, UserContent,
+    ImagesStopReason, KnownApi, Model, ModelCost, ModelInput, ModelOutput, ProviderResponse,
+    TextContent, Usage, UserContent,
 };
+use crate::utils::circuit_breaker::model_circuit;
 use crate::utils::headers::headers_to_record;
-use crate::utils::http::{request_timeout, send_with_retries};
+use crate::utils::http::{get_json, request_timeout, send_with_retries};
 use crate::{Error, Result};
 
 const DEFAULT_PROVIDER_ID: KnownProvider = KnownProvider::OpenRouter;
@@ -44,6 +46,7 @@ impl OpenRouter {
     }
 }
 
+#[async_trait]
 impl Provider for OpenRouter {
     fn id(&self) -> &str {
         &self.provider_id
@@ -54,6 +57,10 @@ impl Provider for OpenRouter {
             language_models: false,
             image_models: true,
             embedding_models: false,
+            fim_models: false,
+            rerank_models: false,
+            transcription_models: false,
+            speech_models: false,
         }
     }
 
@@ -67,6 +74,109 @@ impl Provider for OpenRouter {
             .input(vec![ModelInput::Text])
             .output(vec![ModelOutput::Image])
     }
+
+    /// Lists `/models` with OpenRouter's pricing, context lengths, and
+    /// modalities. Models that output images are bound to the images API;
+    /// the rest are bound to Chat Completions on the same endpoint.
+    async fn list_models(&self) -> Result<Vec<Model>> {
+        let mut headers = HeaderMap::new();
+        if let Some(api_key) = &self.api_key {
+            headers.insert(
+                AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {api_key}")).map_err(|error| {
+                    Error::InvalidHeaderValue("authorization".to_string(), error)
+                })?,
+            );
+        }
+        let client = self.http_client.clone().unwrap_or_default();
+        let url = format!("{}/models", self.base_url.trim_end_matches('/'));
+        let body = get_json(&client, &url, headers).await?;
+
+        let mut chat = openai::builder()
+            .provider_id(&self.provider_id)
+            .api_key(self.api_key.as_deref())
+            .base_url(&self.base_url)
+            .chat_completions();
+        if let Some(http_client) = &self.http_client {
+            chat = chat.http_client(http_client.clone());
+        }
+        let chat = chat.build()?;
+
+        let mut models = Vec::new();
+        for entry in body
+            .get("data")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some(id) = entry.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            let modalities = |field: &str| -> Vec<&str> {
+                entry
+                    .pointer(&format!("/architecture/{field}"))
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .collect()
+            };
+            let mut builder = if modalities("output_modalities").contains(&"image") {
+                self.model(id)
+            } else {
+                chat.model(id)
+            };
+            if let Some(name) = entry.get("name").and_then(Value::as_str) {
+                builder = builder.name(name);
+            }
+            let mut input = vec![ModelInput::Text];
+            if modalities("input_modalities").contains(&"image") {
+                input.push(ModelInput::Image);
+            }
+            builder = builder.input(input).cost(openrouter_cost(entry));
+            if let Some(context_length) = entry.get("context_length").and_then(Value::as_u64) {
+                builder = builder.context_window(context_length as u32);
+            }
+            if let Some(max_tokens) = entry
+                .pointer("/top_provider/max_completion_tokens")
+                .and_then(Value::as_u64)
+            {
+                builder = builder.max_tokens(max_tokens as u32);
+            }
+            let reasoning = entry
+                .get("supported_parameters")
+                .and_then(Value::as_array)
+                .is_some_and(|parameters| {
+                    parameters
+                        .iter()
+                        .any(|parameter| parameter.as_str() == Some("reasoning"))
+                });
+            models.push(builder.reasoning(reasoning).build()?);
+        }
+        Ok(models)
+    }
+}
+
+/// OpenRouter reports prices as per-token decimal strings; `ModelCost` is per
+/// million tokens.
+fn openrouter_cost(entry: &Value) -> ModelCost {
+    let price = |field: &str| {
+        entry
+            .pointer(&format!("/pricing/{field}"))
+            .and_then(|price| match price {
+                Value::String(price) => price.parse::<f64>().ok(),
+                price => price.as_f64(),
+            })
+            .filter(|price| *price > 0.0)
+            .map_or(0.0, |price| price * 1_000_000.0)
+    };
+    ModelCost {
+        input: price("prompt"),
+        output: price("completion"),
+        cache_read: price("input_cache_read"),
+        cache_write: price("input_cache_write"),
+        ..Default::default()
+    }
 }
 
 #[derive(Default)]
@@ -200,7 +310,7 @@ async fn run_generate_images_openrouter(
     let client = options.base.http_client.clone().unwrap_or_default();
     let url = format!("{}/chat/completions", model.base_url.trim_end_matches('/'));
     let headers = build_headers(api_key, &model.headers, &options.base.headers)?;
-    let response = send_with_retries(&options.base, || {
+    let response = send_with_retries(&options.base, &model_circuit(model), || {
         client
             .post(&url)
             .headers(headers.clone())
@@ -294,6 +404,8 @@ fn build_params(model: &Model, context: ImagesContext) -> Value {
                     "url": format!("data:{};base64,{}", image.mime_type, image.data),
                 },
             }),
+            UserContent::Document(document) => openai_completions::convert_file_part(&document),
+            UserContent::Audio(audio) => openai_completions::convert_input_audio_part(&audio),
         })
         .collect::<Vec<_>>();
     let mut modalities = vec!["image"];
@@ -586,6 +698,7 @@ mod tests {
                 cancellation_token: Some(cancellation_token),
                 ..Default::default()
             },
+            ..Default::default()
         };
 
         let output = crate::generate_images(
@@ -626,6 +739,80 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn list_models_maps_pricing_limits_and_modalities() {
+        let captured = Arc::new(Mutex::new(String::new()));
+        let url = spawn_response_server(
+            Arc::clone(&captured),
+            200,
+            r#"{
+                "data": [
+                    {
+                        "id": "anthropic/claude-sonnet-4",
+                        "name": "Anthropic: Claude Sonnet 4",
+                        "context_length": 1000000,
+                        "architecture": {
+                            "input_modalities": ["text", "image", "file"],
+                            "output_modalities": ["text"]
+                        },
+                        "pricing": {
+                            "prompt": "0.000003",
+                            "completion": "0.000015",
+                            "input_cache_read": "0.0000003",
+                            "input_cache_write": "0.00000375"
+                        },
+                        "top_provider": { "max_completion_tokens": 64000 },
+                        "supported_parameters": ["tools", "reasoning"]
+                    },
+                    {
+                        "id": "google/gemini-2.5-flash-image",
+                        "name": "Google: Nano Banana",
+                        "context_length": 32768,
+                        "architecture": {
+                            "input_modalities": ["image", "text"],
+                            "output_modalities": ["image", "text"]
+                        },
+                        "pricing": { "prompt": "0.0000003", "completion": "0.0000025" },
+                        "top_provider": { "max_completion_tokens": 8192 },
+                        "supported_parameters": ["temperature"]
+                    }
+                ]
+            }"#,
+        )
+        .await;
+        let provider = builder()
+            .api_key(Some("test-key"))
+            .base_url(url)
+            .build()
+            .expect("provider");
+
+        let models = provider.list_models().await.expect("list models");
+
+        let chat = &models[0];
+        assert_eq!(chat.api, "openai-completions");
+        assert_eq!(chat.provider, "openrouter");
+        assert_eq!(chat.name, "Anthropic: Claude Sonnet 4");
+        assert!(chat.reasoning);
+        assert_eq!(chat.input, vec![ModelInput::Text, ModelInput::Image]);
+        assert_eq!(chat.context_window, 1_000_000);
+        assert_eq!(chat.max_tokens, 64_000);
+        assert!((chat.cost.input - 3.0).abs() < 1e-9);
+        assert!((chat.cost.output - 15.0).abs() < 1e-9);
+        assert!((chat.cost.cache_read - 0.3).abs() < 1e-9);
+        assert!((chat.cost.cache_write - 3.75).abs() < 1e-9);
+        assert!(chat.language_api().is_some());
+
+        let image = &models[1];
+        assert_eq!(image.api, KnownApi::OpenrouterImages.as_str());
+        assert!(!image.reasoning);
+        assert!(image.image_api().is_some());
+        assert_eq!(image.context_window, 32_768);
+
+        let request = captured.lock().expect("captured request").clone();
+        assert!(request.starts_with("GET /models "));
+        assert!(request.contains("authorization: Bearer test-key"));
+    }
+
     async fn spawn_response_server(
         captured: Arc<Mutex<String>>,
         status: u16,
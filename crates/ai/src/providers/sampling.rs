@@ -0,0 +1,138 @@
+//! Sampling controls from `StreamOptions`, filtered by what the model accepts.
+
+use serde_json::{Map, Value, json};
+
+use crate::types::{Model, SamplingCompat, StreamOptions};
+
+/// Sampling controls an API accepts by default. A model's `SamplingCompat`
+/// overrides each one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SamplingSupport {
+    pub top_p: bool,
+    pub top_k: bool,
+    pub stop: bool,
+    pub seed: bool,
+    pub penalties: bool,
+}
+
+impl SamplingSupport {
+    pub(crate) const ALL: Self = Self {
+        top_p: true,
+        top_k: true,
+        stop: true,
+        seed: true,
+        penalties: true,
+    };
+
+    pub(crate) const NONE: Self = Self {
+        top_p: false,
+        top_k: false,
+        stop: false,
+        seed: false,
+        penalties: false,
+    };
+
+    fn resolve(self, compat: &SamplingCompat) -> Self {
+        Self {
+            top_p: compat.supports_top_p.unwrap_or(self.top_p),
+            top_k: compat.supports_top_k.unwrap_or(self.top_k),
+            stop: compat.supports_stop.unwrap_or(self.stop),
+            seed: compat.supports_seed.unwrap_or(self.seed),
+            penalties: compat.supports_penalties.unwrap_or(self.penalties),
+        }
+    }
+}
+
+/// The sampling controls to send. Unsupported ones are already dropped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Sampling<'a> {
+    pub top_p: Option<f64>,
+    pub top_k: Option<u32>,
+    pub stop: &'a [String],
+    pub seed: Option<u64>,
+    pub frequency_penalty: Option<f64>,
+    pub presence_penalty: Option<f64>,
+}
+
+impl Sampling<'_> {
+    /// Writes the controls under their snake_case names, as OpenAI-style
+    /// APIs expect. APIs differ in what they call the stop list.
+    pub(crate) fn insert_into(&self, object: &mut Map<String, Value>, stop_field: &str) {
+        if let Some(top_p) = self.top_p {
+            object.insert("top_p".to_string(), json!(top_p));
+        }
+        if let Some(top_k) = self.top_k {
+            object.insert("top_k".to_string(), json!(top_k));
+        }
+        if !self.stop.is_empty() {
+            object.insert(stop_field.to_string(), json!(self.stop));
+        }
+        if let Some(seed) = self.seed {
+            object.insert("seed".to_string(), json!(seed));
+        }
+        if let Some(frequency_penalty) = self.frequency_penalty {
+            object.insert("frequency_penalty".to_string(), json!(frequency_penalty));
+        }
+        if let Some(presence_penalty) = self.presence_penalty {
+            object.insert("presence_penalty".to_string(), json!(presence_penalty));
+        }
+    }
+}
+
+pub(crate) fn sampling<'a>(
+    model: &Model,
+    options: &'a StreamOptions,
+    defaults: SamplingSupport,
+) -> Sampling<'a> {
+    let support = defaults.resolve(&model.compat.sampling);
+    Sampling {
+        top_p: options.top_p.filter(|_| support.top_p),
+        top_k: options.top_k.filter(|_| support.top_k),
+        stop: if support.stop { &options.stop } else { &[] },
+        seed: options.seed.filter(|_| support.seed),
+        frequency_penalty: options.frequency_penalty.filter(|_| support.penalties),
+        presence_penalty: options.presence_penalty.filter(|_| support.penalties),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_controls_the_api_or_model_does_not_accept() {
+        let mut model = Model::default();
+        let options = StreamOptions {
+            top_p: Some(0.9),
+            top_k: Some(40),
+            stop: vec!["END".to_string()],
+            seed: Some(7),
+            frequency_penalty: Some(0.5),
+            presence_penalty: Some(0.25),
+            ..Default::default()
+        };
+        let defaults = SamplingSupport {
+            top_k: false,
+            ..SamplingSupport::ALL
+        };
+
+        let sampled = sampling(&model, &options, defaults);
+        assert_eq!(sampled.top_p, Some(0.9));
+        assert_eq!(sampled.top_k, None);
+        assert_eq!(sampled.stop, ["END".to_string()]);
+        assert_eq!(sampled.seed, Some(7));
+        assert_eq!(sampled.presence_penalty, Some(0.25));
+
+        model.compat.sampling = SamplingCompat {
+            supports_top_k: Some(true),
+            supports_stop: Some(false),
+            supports_penalties: Some(false),
+            ..Default::default()
+        };
+        let sampled = sampling(&model, &options, defaults);
+        assert_eq!(sampled.top_k, Some(40));
+        assert!(sampled.stop.is_empty());
+        assert_eq!(sampled.frequency_penalty, None);
+        assert_eq!(sampled.presence_penalty, None);
+    }
+}
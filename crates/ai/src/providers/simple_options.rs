@@ -1,5 +1,7 @@
 use crate::models::clamp_thinking_level;
-use crate::providers::{anthropic, openai_completions, openai_responses};
+use crate::providers::{
+    anthropic, bedrock, cohere, google, ollama, openai_completions, openai_responses,
+};
 use crate::types::{
     Context, Model, ModelThinkingLevel, SimpleStreamOptions, StreamOptions, ThinkingBudgets,
 };
@@ -135,6 +137,68 @@ pub(crate) fn anthropic_options_from_stream_options(
     }
 }
 
+pub(crate) fn google_options_from_stream_options(options: StreamOptions) -> google::GoogleOptions {
+    let thinking_enabled = provider_bool(&options, &["thinkingEnabled"]);
+    let thinking_budget_tokens = provider_u32(&options, &["thinkingBudgetTokens"]);
+    let thinking_level = provider_string(&options, &["thinkingLevel"])
+        .and_then(|value| google::GoogleThinkingLevel::parse(&value));
+    let tool_choice = provider_option(&options, &["toolChoice"]).cloned();
+    google::GoogleOptions {
+        base: options,
+        thinking_enabled,
+        thinking_budget_tokens,
+        thinking_level,
+        tool_choice,
+    }
+}
+
+pub(crate) fn bedrock_options_from_stream_options(
+    options: StreamOptions,
+) -> bedrock::BedrockOptions {
+    let region = provider_string(&options, &["region"]);
+    let thinking_enabled = provider_bool(&options, &["thinkingEnabled"]);
+    let thinking_budget_tokens = provider_u32(&options, &["thinkingBudgetTokens"]);
+    let effort = provider_anthropic_effort(&options, &["effort"]);
+    let interleaved_thinking = provider_bool(&options, &["interleavedThinking"]).unwrap_or(true);
+    let tool_choice = provider_option(&options, &["toolChoice"]).cloned();
+    bedrock::BedrockOptions {
+        base: options,
+        region,
+        credentials: None,
+        thinking_enabled,
+        thinking_budget_tokens,
+        effort,
+        interleaved_thinking,
+        tool_choice,
+    }
+}
+
+pub(crate) fn ollama_options_from_stream_options(options: StreamOptions) -> ollama::OllamaOptions {
+    let num_ctx = provider_u32(&options, &["numCtx"]);
+    let keep_alive = provider_option(&options, &["keepAlive"]).and_then(|value| match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    });
+    let think = provider_option(&options, &["think"]).and_then(ollama::OllamaThink::parse);
+    ollama::OllamaOptions {
+        base: options,
+        num_ctx,
+        keep_alive,
+        think,
+    }
+}
+
+pub(crate) fn cohere_options_from_stream_options(options: StreamOptions) -> cohere::CohereOptions {
+    let thinking_enabled = provider_bool(&options, &["thinkingEnabled"]);
+    let thinking_budget_tokens = provider_u32(&options, &["thinkingBudgetTokens"]);
+    cohere::CohereOptions {
+        base: options,
+        thinking_enabled,
+        thinking_budget_tokens,
+    }
+}
+
 fn provider_option<'a>(options: &'a StreamOptions, names: &[&str]) -> Option<&'a Value> {
     names
         .iter()
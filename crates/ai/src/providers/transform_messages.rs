@@ -8,6 +8,11 @@ use crate::types::{
 const NON_VISION_USER_IMAGE_PLACEHOLDER: &str = "(image omitted: model does not support images)";
 const NON_VISION_TOOL_IMAGE_PLACEHOLDER: &str =
     "(tool image omitted: model does not support images)";
+const NON_PDF_USER_DOCUMENT_PLACEHOLDER: &str =
+    "(document omitted: model does not support documents)";
+const NON_PDF_TOOL_DOCUMENT_PLACEHOLDER: &str =
+    "(tool document omitted: model does not support documents)";
+const NON_AUDIO_USER_AUDIO_PLACEHOLDER: &str = "(audio omitted: model does not support audio)";
 
 pub fn transform_messages<F>(
     messages: &[Message],
@@ -18,10 +23,10 @@ where
     F: Fn(&str, &Model, &AssistantMessage) -> String,
 {
     let mut tool_call_id_map: HashMap<String, String> = HashMap::new();
-    let image_aware_messages = downgrade_unsupported_images(messages, model);
-    let mut transformed = Vec::with_capacity(image_aware_messages.len());
+    let input_aware_messages = downgrade_unsupported_inputs(messages, model);
+    let mut transformed = Vec::with_capacity(input_aware_messages.len());
 
-    for message in image_aware_messages {
+    for message in input_aware_messages {
         match message {
             Message::User(_) => transformed.push(message),
             Message::ToolResult(mut tool_result) => {
@@ -84,6 +89,16 @@ where
                             }
                             content.push(AssistantContent::ToolCall(normalized));
                         }
+                        AssistantContent::Audio(audio) => {
+                            if is_same_model {
+                                content.push(block.clone());
+                            } else if !audio.transcript.trim().is_empty() {
+                                content.push(AssistantContent::Text(TextContent {
+                                    text: audio.transcript.clone(),
+                                    text_signature: None,
+                                }));
+                            }
+                        }
                     }
                 }
 
@@ -99,8 +114,13 @@ where
     insert_synthetic_tool_results(transformed)
 }
 
-fn downgrade_unsupported_images(messages: &[Message], model: &Model) -> Vec<Message> {
-    if model.input.contains(&ModelInput::Image) {
+fn downgrade_unsupported_inputs(messages: &[Message], model: &Model) -> Vec<Message> {
+    let supported = SupportedInputs {
+        images: model.input.contains(&ModelInput::Image),
+        documents: model.input.contains(&ModelInput::Pdf),
+        audio: model.input.contains(&ModelInput::Audio),
+    };
+    if supported.images && supported.documents && supported.audio {
         return messages.to_vec();
     }
 
@@ -110,16 +130,13 @@ fn downgrade_unsupported_images(messages: &[Message], model: &Model) -> Vec<Mess
         .map(|message| match message {
             Message::User(mut user) => {
                 if let UserMessageContent::Parts(parts) = user.content {
-                    user.content = UserMessageContent::Parts(replace_user_images(
-                        &parts,
-                        NON_VISION_USER_IMAGE_PLACEHOLDER,
-                    ));
+                    user.content =
+                        UserMessageContent::Parts(replace_user_blocks(&parts, supported));
                 }
                 Message::User(user)
             }
             Message::ToolResult(mut tool_result) => {
-                tool_result.content =
-                    replace_tool_images(&tool_result.content, NON_VISION_TOOL_IMAGE_PLACEHOLDER);
+                tool_result.content = replace_tool_blocks(&tool_result.content, supported);
                 Message::ToolResult(tool_result)
             }
             other => other,
@@ -127,48 +144,84 @@ fn downgrade_unsupported_images(messages: &[Message], model: &Model) -> Vec<Mess
         .collect()
 }
 
-fn replace_user_images(content: &[UserContent], placeholder: &str) -> Vec<UserContent> {
+#[derive(Clone, Copy)]
+struct SupportedInputs {
+    images: bool,
+    documents: bool,
+    audio: bool,
+}
+
+/// Replaces unsupported blocks with placeholder text, collapsing runs that
+/// would repeat the same placeholder.
+fn replace_user_blocks(content: &[UserContent], supported: SupportedInputs) -> Vec<UserContent> {
     let mut result = Vec::new();
-    let mut previous_was_placeholder = false;
+    let mut previous_placeholder = None;
     for block in content {
-        match block {
-            UserContent::Image(ImageContent { .. }) => {
-                if !previous_was_placeholder {
-                    result.push(UserContent::Text(TextContent {
-                        text: placeholder.to_string(),
-                        text_signature: None,
-                    }));
-                }
-                previous_was_placeholder = true;
+        let placeholder = match block {
+            UserContent::Image(ImageContent { .. }) if !supported.images => {
+                Some(NON_VISION_USER_IMAGE_PLACEHOLDER)
+            }
+            UserContent::Document(_) if !supported.documents => {
+                Some(NON_PDF_USER_DOCUMENT_PLACEHOLDER)
             }
-            UserContent::Text(text) => {
-                previous_was_placeholder = text.text == placeholder;
-                result.push(block.clone());
+            UserContent::Audio(_) if !supported.audio => Some(NON_AUDIO_USER_AUDIO_PLACEHOLDER),
+            _ => None,
+        };
+        if let Some(placeholder) = placeholder {
+            if previous_placeholder != Some(placeholder) {
+                result.push(UserContent::text(placeholder));
             }
+            previous_placeholder = Some(placeholder);
+            continue;
         }
+        previous_placeholder = match block {
+            UserContent::Text(text) => [
+                NON_VISION_USER_IMAGE_PLACEHOLDER,
+                NON_PDF_USER_DOCUMENT_PLACEHOLDER,
+                NON_AUDIO_USER_AUDIO_PLACEHOLDER,
+            ]
+            .into_iter()
+            .find(|placeholder| text.text == *placeholder),
+            _ => None,
+        };
+        result.push(block.clone());
     }
     result
 }
 
-fn replace_tool_images(content: &[ToolResultContent], placeholder: &str) -> Vec<ToolResultContent> {
+fn replace_tool_blocks(
+    content: &[ToolResultContent],
+    supported: SupportedInputs,
+) -> Vec<ToolResultContent> {
     let mut result = Vec::new();
-    let mut previous_was_placeholder = false;
+    let mut previous_placeholder = None;
     for block in content {
-        match block {
-            ToolResultContent::Image(ImageContent { .. }) => {
-                if !previous_was_placeholder {
-                    result.push(ToolResultContent::Text(TextContent {
-                        text: placeholder.to_string(),
-                        text_signature: None,
-                    }));
-                }
-                previous_was_placeholder = true;
+        let placeholder = match block {
+            ToolResultContent::Image(ImageContent { .. }) if !supported.images => {
+                Some(NON_VISION_TOOL_IMAGE_PLACEHOLDER)
+            }
+            ToolResultContent::Document(_) if !supported.documents => {
+                Some(NON_PDF_TOOL_DOCUMENT_PLACEHOLDER)
             }
-            ToolResultContent::Text(text) => {
-                previous_was_placeholder = text.text == placeholder;
-                result.push(block.clone());
+            _ => None,
+        };
+        if let Some(placeholder) = placeholder {
+            if previous_placeholder != Some(placeholder) {
+                result.push(ToolResultContent::text(placeholder));
             }
+            previous_placeholder = Some(placeholder);
+            continue;
         }
+        previous_placeholder = match block {
+            ToolResultContent::Text(text) => [
+                NON_VISION_TOOL_IMAGE_PLACEHOLDER,
+                NON_PDF_TOOL_DOCUMENT_PLACEHOLDER,
+            ]
+            .into_iter()
+            .find(|placeholder| text.text == *placeholder),
+            _ => None,
+        };
+        result.push(block.clone());
     }
     result
 }
@@ -252,7 +305,10 @@ fn insert_synthetic_tool_results(messages: Vec<Message>) -> Vec<Message> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{ModelCost, StopReason, ThinkingContent, Usage};
+    use crate::types::{
+        AssistantAudioContent, AudioContent, DocumentContent, ModelCost, StopReason,
+        ThinkingContent, Usage,
+    };
     use serde_json::json;
 
     fn copilot_claude_model() -> Model {
@@ -282,7 +338,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::ToolUse,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 0,
         }
     }
@@ -376,6 +435,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn downgrades_documents_for_models_without_pdf_input() {
+        let model = copilot_claude_model();
+        let user = Message::User(UserMessage {
+            content: UserMessageContent::Parts(vec![
+                UserContent::Document(DocumentContent::pdf("one")),
+                UserContent::Document(DocumentContent::url(
+                    "https://example.com/two.pdf",
+                    "application/pdf",
+                )),
+                UserContent::Image(ImageContent {
+                    data: "three".to_string(),
+                    mime_type: "image/png".to_string(),
+                }),
+            ]),
+            timestamp: 1,
+        });
+        let tool_result = Message::ToolResult(ToolResultMessage {
+            tool_call_id: "call_1".to_string(),
+            tool_name: "fetch".to_string(),
+            content: vec![ToolResultContent::Document(DocumentContent::pdf("four"))],
+            details: None,
+            usage: None,
+            added_tool_names: Vec::new(),
+            is_error: false,
+            timestamp: 1,
+        });
+
+        let transformed = transform_messages(&[user, tool_result], &model, normalize_for_anthropic);
+
+        let Message::User(user) = &transformed[0] else {
+            panic!("expected user message");
+        };
+        assert_eq!(
+            user.content,
+            UserMessageContent::Parts(vec![
+                UserContent::text("(document omitted: model does not support documents)"),
+                UserContent::Image(ImageContent {
+                    data: "three".to_string(),
+                    mime_type: "image/png".to_string(),
+                }),
+            ])
+        );
+        let Message::ToolResult(result) = &transformed[1] else {
+            panic!("expected tool result");
+        };
+        assert_eq!(
+            result.content,
+            vec![ToolResultContent::text(
+                "(tool document omitted: model does not support documents)"
+            )]
+        );
+    }
+
+    #[test]
+    fn downgrades_user_audio_and_replays_other_models_audio_as_transcript() {
+        let model = copilot_claude_model();
+        let user = Message::User(UserMessage {
+            content: UserMessageContent::Parts(vec![
+                UserContent::text("listen"),
+                UserContent::Audio(AudioContent {
+                    data: "UklGRg==".to_string(),
+                    mime_type: "audio/wav".to_string(),
+                }),
+            ]),
+            timestamp: 1,
+        });
+        let mut spoken = assistant_message(vec![AssistantContent::Audio(AssistantAudioContent {
+            id: "audio_1".to_string(),
+            data: "AAAA".to_string(),
+            mime_type: "audio/pcm".to_string(),
+            transcript: "Hello there.".to_string(),
+            expires_at: None,
+        })]);
+        spoken.stop_reason = StopReason::Stop;
+
+        let transformed = transform_messages(
+            &[user, Message::Assistant(spoken)],
+            &model,
+            normalize_for_anthropic,
+        );
+
+        let Message::User(user) = &transformed[0] else {
+            panic!("expected user message");
+        };
+        assert_eq!(
+            user.content,
+            UserMessageContent::Parts(vec![
+                UserContent::text("listen"),
+                UserContent::text("(audio omitted: model does not support audio)"),
+            ])
+        );
+        let Message::Assistant(assistant) = &transformed[1] else {
+            panic!("expected assistant message");
+        };
+        assert_eq!(
+            assistant.content,
+            vec![AssistantContent::Text(TextContent {
+                text: "Hello there.".to_string(),
+                text_signature: None,
+            })]
+        );
+    }
+
     #[test]
     fn preserves_images_for_vision_models() {
         let model = copilot_claude_model();
@@ -419,7 +582,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::Stop,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 0,
         };
 
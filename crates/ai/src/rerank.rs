@@ -0,0 +1,62 @@
+use crate::types::{Model, RerankOptions, Reranking};
+use crate::{Error, Result};
+
+/// Orders `documents` by relevance to `query`, keeping the best `top_n` when
+/// set.
+pub async fn rerank<I, S>(
+    model: Model,
+    query: impl Into<String>,
+    documents: I,
+    top_n: Option<u32>,
+    options: Option<RerankOptions>,
+) -> Result<Reranking>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let documents = documents.into_iter().map(Into::into).collect::<Vec<_>>();
+    if documents.is_empty() {
+        return Err(Error::Validation(
+            "rerank input must contain at least one document".to_string(),
+        ));
+    }
+    if top_n == Some(0) {
+        return Err(Error::Validation(
+            "rerank top_n must be at least 1".to_string(),
+        ));
+    }
+    let api = model
+        .rerank_api()
+        .ok_or_else(|| Error::unsupported_capability(model.provider.clone(), "rerank models"))?;
+    api.rerank(
+        model,
+        query.into(),
+        documents,
+        top_n,
+        options.unwrap_or_default(),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rerank_rejects_empty_documents_before_provider_dispatch() {
+        let error = rerank(Model::default(), "query", Vec::<String>::new(), None, None)
+            .await
+            .expect_err("empty documents should fail");
+
+        assert!(matches!(error, Error::Validation(_)));
+    }
+
+    #[tokio::test]
+    async fn rerank_requires_a_rerank_model() {
+        let error = rerank(Model::default(), "query", ["doc"], Some(1), None)
+            .await
+            .expect_err("language-less default model should fail");
+
+        assert!(matches!(error, Error::UnsupportedCapability { .. }));
+    }
+}
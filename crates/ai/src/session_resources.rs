@@ -3,6 +3,9 @@ use std::sync::{Arc, OnceLock, RwLock};
 
 use crate::{Error, Result};
 
+#[cfg(test)]
+pub(crate) static TEST_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
 pub type SessionResourceCleanup = Arc<dyn Fn(Option<&str>) -> Result<()> + Send + Sync>;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -74,12 +77,8 @@ fn unregister_session_resource_cleanup(id: u64) {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Mutex;
-
     use super::*;
 
-    static TEST_LOCK: Mutex<()> = Mutex::new(());
-
     fn clear_registry_for_test() {
         registry()
             .write()
@@ -89,7 +88,7 @@ mod tests {
 
     #[test]
     fn invokes_registered_cleanups_with_session_id() {
-        let _guard = TEST_LOCK.lock().expect("test lock poisoned");
+        let _guard = TEST_LOCK.blocking_lock();
         clear_registry_for_test();
         let seen = Arc::new(RwLock::new(Vec::<Option<String>>::new()));
         let seen_clone = seen.clone();
@@ -112,7 +111,7 @@ mod tests {
 
     #[test]
     fn unregister_removes_cleanup() {
-        let _guard = TEST_LOCK.lock().expect("test lock poisoned");
+        let _guard = TEST_LOCK.blocking_lock();
         clear_registry_for_test();
         let call_count = Arc::new(AtomicU64::new(0));
         let call_count_clone = call_count.clone();
@@ -129,7 +128,7 @@ mod tests {
 
     #[test]
     fn aggregates_cleanup_errors_after_running_all_callbacks() {
-        let _guard = TEST_LOCK.lock().expect("test lock poisoned");
+        let _guard = TEST_LOCK.blocking_lock();
         clear_registry_for_test();
         let call_count = Arc::new(AtomicU64::new(0));
         let call_count_clone = call_count.clone();
@@ -2,8 +2,10 @@ use crate::AssistantEventStream;
 use crate::env_api_keys::{
     KnownProvider, get_anthropic_auth_token_with_env, get_env_api_key_with_env,
 };
+use crate::key_pool::ApiKeyPool;
 use crate::types::{
-    AssistantMessage, AssistantMessageEvent, Context, Model, SimpleStreamOptions, StreamOptions,
+    AssistantMessage, AssistantMessageEvent, Context, FimContext, KnownApi, Model,
+    SimpleStreamOptions, StreamOptions,
 };
 use crate::{Error, Result};
 
@@ -20,10 +22,14 @@ fn has_authorization_override(options: &StreamOptions) -> bool {
         .any(|(name, _)| name.eq_ignore_ascii_case("authorization"))
 }
 
-fn with_env_api_key(model: &Model, mut options: StreamOptions) -> StreamOptions {
+pub(crate) fn with_env_api_key(model: &Model, mut options: StreamOptions) -> StreamOptions {
     if has_explicit_api_key(&options.api_key) {
         return options;
     }
+    if let Some(api_key) = options.api_key_pool.as_ref().and_then(ApiKeyPool::next_key) {
+        options.api_key = Some(api_key);
+        return options;
+    }
     if model.provider == KnownProvider::Anthropic.as_str() {
         if !has_authorization_override(&options)
             && let Some(auth_token) = get_anthropic_auth_token_with_env(&options.env)
@@ -36,13 +42,43 @@ fn with_env_api_key(model: &Model, mut options: StreamOptions) -> StreamOptions
             return options;
         }
     }
+    // Bedrock reads `AWS_BEARER_TOKEN_BEDROCK` itself, after any signing
+    // credentials configured on the provider.
+    if model.api == KnownApi::BedrockConverseStream.as_str() {
+        return options;
+    }
     if let Some(api_key) = get_env_api_key_with_env(&model.provider, &options.env) {
         options.api_key = Some(api_key);
     }
     options
 }
 
-fn with_env_api_key_simple(model: &Model, mut options: SimpleStreamOptions) -> SimpleStreamOptions {
+/// Routes the outcome of a call back to `options.api_key_pool` when the call
+/// uses one of its keys.
+pub(crate) fn track_pooled_api_key(
+    options: &StreamOptions,
+) -> impl FnOnce(AssistantEventStream) -> AssistantEventStream + use<> {
+    let pooled = options
+        .api_key_pool
+        .clone()
+        .zip(options.api_key.clone())
+        .filter(|(pool, api_key)| pool.contains(api_key));
+    move |stream| match pooled {
+        Some((pool, api_key)) => pool.track(api_key, stream),
+        None => stream,
+    }
+}
+
+/// Fallback chains resolve keys for each model they try, so the outer call
+/// must not pin one key, or count one pooled call, for the whole chain.
+fn resolves_api_keys_per_model(model: &Model) -> bool {
+    model.api == crate::fallback::FALLBACK_API
+}
+
+pub(crate) fn with_env_api_key_simple(
+    model: &Model,
+    mut options: SimpleStreamOptions,
+) -> SimpleStreamOptions {
     options.stream = with_env_api_key(model, options.stream);
     options
 }
@@ -55,8 +91,12 @@ pub fn stream(
     let api = model
         .language_api()
         .ok_or_else(|| Error::unsupported_capability(model.provider.clone(), "language models"))?;
+    if resolves_api_keys_per_model(&model) {
+        return api.stream(model, context, options.unwrap_or_default());
+    }
     let options = with_env_api_key(&model, options.unwrap_or_default());
-    api.stream(model, context, options)
+    let track = track_pooled_api_key(&options);
+    api.stream(model, context, options).map(track)
 }
 
 pub async fn complete(
@@ -75,8 +115,12 @@ pub fn stream_simple(
     let api = model
         .language_api()
         .ok_or_else(|| Error::unsupported_capability(model.provider.clone(), "language models"))?;
+    if resolves_api_keys_per_model(&model) {
+        return api.stream_simple(model, context, options.unwrap_or_default());
+    }
     let options = with_env_api_key_simple(&model, options.unwrap_or_default());
-    api.stream_simple(model, context, options)
+    let track = track_pooled_api_key(&options.stream);
+    api.stream_simple(model, context, options).map(track)
 }
 
 pub async fn complete_simple(
@@ -87,6 +131,27 @@ pub async fn complete_simple(
     final_message_from_stream(stream_simple(model, context, options)?).await
 }
 
+pub fn stream_fim(
+    model: Model,
+    context: FimContext,
+    options: Option<StreamOptions>,
+) -> Result<AssistantEventStream> {
+    let api = model
+        .fim_api()
+        .ok_or_else(|| Error::unsupported_capability(model.provider.clone(), "FIM models"))?;
+    let options = with_env_api_key(&model, options.unwrap_or_default());
+    let track = track_pooled_api_key(&options);
+    api.stream_fim(model, context, options).map(track)
+}
+
+pub async fn complete_fim(
+    model: Model,
+    context: FimContext,
+    options: Option<StreamOptions>,
+) -> Result<AssistantMessage> {
+    final_message_from_stream(stream_fim(model, context, options)?).await
+}
+
 pub async fn final_message_from_stream(
     mut stream: AssistantEventStream,
 ) -> Result<AssistantMessage> {
@@ -203,7 +268,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::Stop,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: crate::utils::time::now_millis(),
         };
         let reason = message.stop_reason;
@@ -0,0 +1,224 @@
+//! JSON Schemas for tool parameters derived from Rust types.
+//!
+//! `#[derive(ToolSchema)]` builds the schema passed to
+//! [`ToolBuilder::parameters`](crate::ToolBuilder::parameters) from the
+//! struct that deserializes the arguments, so the two cannot drift apart.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+pub use serde_json::Value;
+use serde_json::{Map, json};
+
+/// A type whose accepted JSON shape can be described as a JSON Schema.
+///
+/// Derive it with `#[derive(ai::ToolSchema)]` for structs with named fields
+/// and for enums with unit variants.
+pub trait ToolSchema {
+    fn tool_schema() -> Value;
+}
+
+macro_rules! impl_tool_schema {
+    ($schema:tt => $($ty:ty),+) => {
+        $(impl ToolSchema for $ty {
+            fn tool_schema() -> Value {
+                json!($schema)
+            }
+        })+
+    };
+}
+
+impl_tool_schema!({ "type": "boolean" } => bool);
+impl_tool_schema!({ "type": "string" } => String, str, char);
+impl_tool_schema!({ "type": "integer" } => i8, i16, i32, i64, i128, isize);
+impl_tool_schema!({ "type": "integer", "minimum": 0 } => u8, u16, u32, u64, u128, usize);
+impl_tool_schema!({ "type": "number" } => f32, f64);
+impl_tool_schema!({} => Value);
+
+impl<T: ToolSchema + ?Sized> ToolSchema for &T {
+    fn tool_schema() -> Value {
+        T::tool_schema()
+    }
+}
+
+impl<T: ToolSchema + ?Sized> ToolSchema for Box<T> {
+    fn tool_schema() -> Value {
+        T::tool_schema()
+    }
+}
+
+/// `None` is accepted as a missing field, so the schema is the inner type's.
+impl<T: ToolSchema> ToolSchema for Option<T> {
+    fn tool_schema() -> Value {
+        T::tool_schema()
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for Vec<T> {
+    fn tool_schema() -> Value {
+        json!({ "type": "array", "items": T::tool_schema() })
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for [T] {
+    fn tool_schema() -> Value {
+        Vec::<T>::tool_schema()
+    }
+}
+
+impl<T: ToolSchema, S> ToolSchema for HashSet<T, S> {
+    fn tool_schema() -> Value {
+        json!({ "type": "array", "items": T::tool_schema(), "uniqueItems": true })
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for BTreeSet<T> {
+    fn tool_schema() -> Value {
+        HashSet::<T>::tool_schema()
+    }
+}
+
+impl<T: ToolSchema, S> ToolSchema for HashMap<String, T, S> {
+    fn tool_schema() -> Value {
+        json!({ "type": "object", "additionalProperties": T::tool_schema() })
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for BTreeMap<String, T> {
+    fn tool_schema() -> Value {
+        HashMap::<String, T>::tool_schema()
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+
+    pub struct Property {
+        name: &'static str,
+        schema: Value,
+        required: bool,
+    }
+
+    pub fn property(
+        name: &'static str,
+        mut schema: Value,
+        description: Option<&'static str>,
+        required: bool,
+    ) -> Property {
+        if let (Some(description), Some(schema)) = (description, schema.as_object_mut()) {
+            schema.insert("description".to_string(), json!(description));
+        }
+        Property {
+            name,
+            schema,
+            required,
+        }
+    }
+
+    pub fn object_schema(description: Option<&'static str>, properties: Vec<Property>) -> Value {
+        let required = properties
+            .iter()
+            .filter(|property| property.required)
+            .map(|property| json!(property.name))
+            .collect::<Vec<_>>();
+        let properties = properties
+            .into_iter()
+            .map(|property| (property.name.to_string(), property.schema))
+            .collect::<Map<_, _>>();
+        let mut schema = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+            schema["required"] = json!(required);
+        }
+        with_description(schema, description)
+    }
+
+    pub fn string_enum_schema(description: Option<&'static str>, variants: &[&str]) -> Value {
+        with_description(json!({ "type": "string", "enum": variants }), description)
+    }
+
+    fn with_description(mut schema: Value, description: Option<&'static str>) -> Value {
+        if let Some(description) = description {
+            schema["description"] = json!(description);
+        }
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::ToolSchema;
+
+    /// Look up the weather.
+    #[derive(Deserialize, ToolSchema)]
+    #[serde(rename_all = "camelCase")]
+    #[allow(dead_code)]
+    struct WeatherArgs {
+        /// City to look up.
+        city_name: String,
+        units: Option<Units>,
+        #[serde(default)]
+        days: u8,
+        #[serde(rename = "tags")]
+        labels: Vec<String>,
+        #[serde(skip)]
+        cache_key: String,
+    }
+
+    #[derive(Deserialize, ToolSchema)]
+    #[serde(rename_all = "snake_case")]
+    #[allow(dead_code)]
+    enum Units {
+        Celsius,
+        Fahrenheit,
+        #[serde(rename = "K")]
+        Kelvin,
+    }
+
+    #[derive(Deserialize, ToolSchema)]
+    #[allow(dead_code)]
+    struct Page<T> {
+        items: Vec<T>,
+        extra: HashMap<String, f64>,
+    }
+
+    #[test]
+    fn derives_object_schemas_that_follow_serde_attributes() {
+        assert_eq!(
+            WeatherArgs::tool_schema(),
+            json!({
+                "type": "object",
+                "description": "Look up the weather.",
+                "properties": {
+                    "cityName": { "type": "string", "description": "City to look up." },
+                    "units": { "type": "string", "enum": ["celsius", "fahrenheit", "K"] },
+                    "days": { "type": "integer", "minimum": 0 },
+                    "tags": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["cityName", "tags"]
+            })
+        );
+    }
+
+    #[test]
+    fn derives_schemas_for_generic_structs() {
+        assert_eq!(
+            Page::<bool>::tool_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "items": { "type": "array", "items": { "type": "boolean" } },
+                    "extra": {
+                        "type": "object",
+                        "additionalProperties": { "type": "number" }
+                    }
+                },
+                "required": ["items", "extra"]
+            })
+        );
+    }
+}
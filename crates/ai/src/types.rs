@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -9,6 +10,7 @@ use serde_json::Value;
 use tokio_util::sync::CancellationToken;
 
 use crate::Result;
+use crate::key_pool::ApiKeyPool;
 use crate::provider::{EmbeddingModelApi, LanguageModelApi};
 
 pub type Api = String;
@@ -83,6 +85,17 @@ pub enum KnownApi {
     OpenaiImages,
     AnthropicMessages,
     OpenrouterImages,
+    GoogleGenerativeAi,
+    BedrockConverseStream,
+    OllamaChat,
+    OllamaEmbed,
+    MistralFim,
+    MistralEmbeddings,
+    CohereChat,
+    CohereEmbed,
+    CohereRerank,
+    OpenaiTranscriptions,
+    OpenaiSpeech,
 }
 
 impl KnownApi {
@@ -94,6 +107,17 @@ impl KnownApi {
             Self::OpenaiImages => "openai-images",
             Self::AnthropicMessages => "anthropic-messages",
             Self::OpenrouterImages => "openrouter-images",
+            Self::GoogleGenerativeAi => "google-generative-ai",
+            Self::BedrockConverseStream => "bedrock-converse-stream",
+            Self::OllamaChat => "ollama-chat",
+            Self::OllamaEmbed => "ollama-embed",
+            Self::MistralFim => "mistral-fim",
+            Self::MistralEmbeddings => "mistral-embeddings",
+            Self::CohereChat => "cohere-chat",
+            Self::CohereEmbed => "cohere-embed",
+            Self::CohereRerank => "cohere-rerank",
+            Self::OpenaiTranscriptions => "openai-transcriptions",
+            Self::OpenaiSpeech => "openai-speech",
         }
     }
 }
@@ -229,8 +253,30 @@ pub type ResponseHook = Arc<
 pub struct StreamOptions {
     pub temperature: Option<f64>,
     pub max_tokens: Option<u32>,
+    /// Nucleus sampling: only tokens within this cumulative probability are
+    /// considered.
+    pub top_p: Option<f64>,
+    /// Only the `top_k` most likely tokens are considered.
+    pub top_k: Option<u32>,
+    /// Sequences that end generation when produced. The matched sequence is
+    /// reported in `AssistantMessage::stop_sequence` when the provider says
+    /// which one fired.
+    pub stop: Vec<String>,
+    /// Seed for best-effort deterministic sampling.
+    pub seed: Option<u64>,
+    pub frequency_penalty: Option<f64>,
+    pub presence_penalty: Option<f64>,
+    /// Streams per-token log-probabilities as `TextLogprobs` events. Only
+    /// OpenAI Chat Completions and Responses report them.
+    pub logprobs: bool,
+    /// How many of the most likely alternatives to report for each token.
+    /// Implies `logprobs`.
+    pub top_logprobs: Option<u32>,
     pub cancellation_token: Option<CancellationToken>,
     pub api_key: Option<String>,
+    /// Keys to rotate through when `api_key` is unset. Takes precedence over
+    /// environment keys.
+    pub api_key_pool: Option<ApiKeyPool>,
     pub transport: Option<Transport>,
     pub cache_retention: Option<CacheRetention>,
     pub session_id: Option<String>,
@@ -248,12 +294,102 @@ pub struct StreamOptions {
     pub max_retry_delay_ms: Option<u64>,
     pub http_client: Option<reqwest::Client>,
     pub metadata: Option<Value>,
+    /// Asks for a JSON answer matching a schema. OpenAI APIs send it as a
+    /// `json_schema` response format and Anthropic as a forced tool call;
+    /// other providers ignore it. `generate_object` handles all of them.
+    pub response_format: Option<JsonSchemaFormat>,
+    /// Asks for a spoken answer as well as text. Only OpenAI Chat Completions
+    /// models whose `output` includes `ModelOutput::Audio` use it.
+    pub audio_output: Option<AudioOutputOptions>,
+    /// How the model may use the context's tools. A provider-specific tool
+    /// choice option takes precedence.
+    pub tool_choice: Option<ToolChoice>,
+    /// Whether the model may call several tools in one turn. `None` keeps the
+    /// provider default.
+    pub parallel_tool_calls: Option<bool>,
     /// Provider-scoped environment values. These take precedence over process
     /// environment variables for provider configuration.
     pub env: ProviderEnv,
     pub provider_options: HashMap<String, Value>,
 }
 
+/// The voice for spoken answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioOutputOptions {
+    pub voice: String,
+}
+
+impl AudioOutputOptions {
+    pub fn new(voice: impl Into<String>) -> Self {
+        Self {
+            voice: voice.into(),
+        }
+    }
+}
+
+/// A named JSON Schema for structured output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub description: Option<String>,
+    pub schema: Value,
+    /// Requests strict schema adherence from OpenAI models. Strict schemas
+    /// must mark every property as required and set
+    /// `additionalProperties: false`.
+    pub strict: bool,
+}
+
+impl JsonSchemaFormat {
+    pub fn new(schema: Value) -> Self {
+        Self {
+            name: "response".to_string(),
+            description: None,
+            schema,
+            strict: false,
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
+impl From<Value> for JsonSchemaFormat {
+    fn from(schema: Value) -> Self {
+        Self::new(schema)
+    }
+}
+
+/// How the model may use tools.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model decides whether to call tools.
+    Auto,
+    /// The model must not call tools.
+    None,
+    /// The model must call at least one tool.
+    Required,
+    /// The model must call the named tool.
+    Tool(String),
+}
+
+impl ToolChoice {
+    pub fn tool(name: impl Into<String>) -> Self {
+        Self::Tool(name.into())
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct RequestOptions {
     pub cancellation_token: Option<CancellationToken>,
@@ -275,6 +411,82 @@ pub struct RequestOptions {
 #[derive(Clone, Default)]
 pub struct ImageGenerationOptions {
     pub base: StreamOptions,
+    pub size: Option<ImageSize>,
+    pub quality: Option<ImageQuality>,
+    pub background: Option<ImageBackground>,
+    /// Number of images to generate.
+    pub n: Option<u32>,
+}
+
+/// Output dimensions for generated images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageSize {
+    /// Let the model pick the dimensions.
+    Auto,
+    Pixels {
+        width: u32,
+        height: u32,
+    },
+}
+
+impl ImageSize {
+    pub const fn pixels(width: u32, height: u32) -> Self {
+        Self::Pixels { width, height }
+    }
+}
+
+impl fmt::Display for ImageSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => f.write_str("auto"),
+            Self::Pixels { width, height } => write!(f, "{width}x{height}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageQuality {
+    Auto,
+    Low,
+    Medium,
+    High,
+    /// DALL-E 3 only.
+    Standard,
+    /// DALL-E 3 only.
+    Hd,
+}
+
+impl ImageQuality {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Standard => "standard",
+            Self::Hd => "hd",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageBackground {
+    Auto,
+    /// Requires an output format with an alpha channel, such as PNG or WebP.
+    Transparent,
+    Opaque,
+}
+
+impl ImageBackground {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Transparent => "transparent",
+            Self::Opaque => "opaque",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -294,11 +506,36 @@ impl EmbeddingEncodingFormat {
     }
 }
 
+/// Which side of a retrieval pair an input is, for embedding models that embed
+/// queries and documents differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingInputType {
+    SearchDocument,
+    SearchQuery,
+    Classification,
+    Clustering,
+}
+
+impl EmbeddingInputType {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::SearchDocument => "search_document",
+            Self::SearchQuery => "search_query",
+            Self::Classification => "classification",
+            Self::Clustering => "clustering",
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct EmbeddingOptions {
     pub base: RequestOptions,
     pub dimensions: Option<u32>,
     pub encoding_format: Option<EmbeddingEncodingFormat>,
+    /// Required by Cohere embedding models; ignored by providers without the
+    /// distinction.
+    pub input_type: Option<EmbeddingInputType>,
     pub user: Option<String>,
 }
 
@@ -329,6 +566,123 @@ pub struct EmbeddingBatch {
     pub usage: EmbeddingUsage,
 }
 
+#[derive(Clone, Default)]
+pub struct RerankOptions {
+    pub base: RequestOptions,
+    /// Documents longer than this many tokens are truncated before scoring.
+    pub max_tokens_per_doc: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankedDocument {
+    /// Position of the document in the request's `documents`.
+    pub index: usize,
+    pub document: String,
+    pub relevance_score: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RerankUsage {
+    pub search_units: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reranking {
+    pub results: Vec<RankedDocument>,
+    pub model: String,
+    pub usage: RerankUsage,
+}
+
+#[derive(Clone, Default)]
+pub struct TranscriptionOptions {
+    pub base: RequestOptions,
+    /// ISO-639-1 code of the spoken language. Setting it improves accuracy
+    /// and latency.
+    pub language: Option<String>,
+    /// Text that sets the style or continues an earlier segment.
+    pub prompt: Option<String>,
+    pub temperature: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcription {
+    pub text: String,
+    /// Detected language, when the provider reports it.
+    pub language: Option<String>,
+    /// Length of the audio in seconds, when the provider reports it.
+    pub duration: Option<f64>,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeechFormat {
+    #[default]
+    Mp3,
+    Opus,
+    Aac,
+    Flac,
+    Wav,
+    /// Raw 16-bit, 24 kHz mono PCM.
+    Pcm,
+}
+
+impl SpeechFormat {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::Opus => "opus",
+            Self::Aac => "aac",
+            Self::Flac => "flac",
+            Self::Wav => "wav",
+            Self::Pcm => "pcm",
+        }
+    }
+
+    pub const fn mime_type(self) -> &'static str {
+        match self {
+            Self::Mp3 => "audio/mpeg",
+            Self::Opus => "audio/ogg",
+            Self::Aac => "audio/aac",
+            Self::Flac => "audio/flac",
+            Self::Wav => "audio/wav",
+            Self::Pcm => "audio/pcm",
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SpeechOptions {
+    pub base: RequestOptions,
+    pub format: SpeechFormat,
+    /// Playback speed from 0.25 to 4.0; 1.0 is normal.
+    pub speed: Option<f32>,
+    /// How to speak, such as tone or accent. Ignored by older models.
+    pub instructions: Option<String>,
+}
+
+pub type SpeechByteStream = Pin<Box<dyn futures::Stream<Item = Result<Vec<u8>>> + Send>>;
+
+/// Spoken audio, delivered in chunks as the provider produces it.
+pub struct GeneratedSpeech {
+    pub mime_type: String,
+    pub audio: SpeechByteStream,
+}
+
+impl GeneratedSpeech {
+    /// Waits for the whole clip.
+    pub async fn bytes(self) -> Result<Vec<u8>> {
+        use futures::TryStreamExt;
+
+        self.audio
+            .try_fold(Vec::new(), |mut bytes, chunk| async move {
+                bytes.extend(chunk);
+                Ok(bytes)
+            })
+            .await
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct SimpleStreamOptions {
     pub stream: StreamOptions,
@@ -376,6 +730,75 @@ pub struct ImageContent {
     pub mime_type: String,
 }
 
+/// A document such as a PDF, sent inline or by URL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentContent {
+    pub source: DocumentSource,
+    pub mime_type: String,
+    /// Shown to the model as the document's name, where the API allows one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl DocumentContent {
+    pub fn base64(data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            source: DocumentSource::Base64 { data: data.into() },
+            mime_type: mime_type.into(),
+            title: None,
+        }
+    }
+
+    pub fn url(url: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            source: DocumentSource::Url { url: url.into() },
+            mime_type: mime_type.into(),
+            title: None,
+        }
+    }
+
+    /// A base64-encoded PDF.
+    pub fn pdf(data: impl Into<String>) -> Self {
+        Self::base64(data, "application/pdf")
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DocumentSource {
+    Base64 { data: String },
+    Url { url: String },
+}
+
+/// Base64-encoded audio, such as `audio/wav` or `audio/mpeg`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioContent {
+    pub data: String,
+    pub mime_type: String,
+}
+
+/// Spoken output from the model, with its transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssistantAudioContent {
+    /// Provider id that later turns can use to refer back to this audio.
+    pub id: String,
+    /// Base64-encoded audio. OpenAI streams 16-bit, 24 kHz mono PCM.
+    pub data: String,
+    pub mime_type: String,
+    pub transcript: String,
+    /// Unix time in seconds after which the provider forgets `id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolCall {
@@ -393,6 +816,10 @@ pub enum UserContent {
     Text(TextContent),
     #[serde(rename = "image")]
     Image(ImageContent),
+    #[serde(rename = "document")]
+    Document(DocumentContent),
+    #[serde(rename = "audio")]
+    Audio(AudioContent),
 }
 
 impl UserContent {
@@ -411,6 +838,8 @@ pub enum ToolResultContent {
     Text(TextContent),
     #[serde(rename = "image")]
     Image(ImageContent),
+    #[serde(rename = "document")]
+    Document(DocumentContent),
 }
 
 impl ToolResultContent {
@@ -431,6 +860,8 @@ pub enum AssistantContent {
     Thinking(ThinkingContent),
     #[serde(rename = "toolCall")]
     ToolCall(ToolCall),
+    #[serde(rename = "audio")]
+    Audio(AssistantAudioContent),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -532,10 +963,27 @@ pub struct AssistantMessage {
     pub diagnostics: Vec<Value>,
     pub usage: Usage,
     pub stop_reason: StopReason,
+    /// The stop sequence that ended generation, when the provider reports it.
+    pub stop_sequence: Option<String>,
     pub error_message: Option<String>,
+    /// HTTP status of the provider response that failed the call, when the
+    /// failure came from one.
+    pub error_status: Option<u16>,
+    /// Set when the call failed fast because the model's circuit breaker was
+    /// open.
+    pub circuit_open: Option<OpenCircuit>,
     pub timestamp: u64,
 }
 
+/// A circuit breaker that rejected a call, as reported by
+/// `Error::CircuitOpen`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenCircuit {
+    pub endpoint: String,
+    pub retry_after_ms: u64,
+}
+
 impl AssistantMessage {
     pub fn empty_for(model: &Model) -> Self {
         Self {
@@ -548,7 +996,10 @@ impl AssistantMessage {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::Stop,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: crate::utils::time::now_millis(),
         }
     }
@@ -627,9 +1078,18 @@ impl Serialize for AssistantMessage {
         if !self.diagnostics.is_empty() {
             field_count += 1;
         }
+        if self.stop_sequence.is_some() {
+            field_count += 1;
+        }
         if self.error_message.is_some() {
             field_count += 1;
         }
+        if self.error_status.is_some() {
+            field_count += 1;
+        }
+        if self.circuit_open.is_some() {
+            field_count += 1;
+        }
 
         let mut state = serializer.serialize_struct("AssistantMessage", field_count)?;
         state.serialize_field("role", "assistant")?;
@@ -648,9 +1108,18 @@ impl Serialize for AssistantMessage {
         }
         state.serialize_field("usage", &self.usage)?;
         state.serialize_field("stopReason", &self.stop_reason)?;
+        if let Some(stop_sequence) = &self.stop_sequence {
+            state.serialize_field("stopSequence", stop_sequence)?;
+        }
         if let Some(error_message) = &self.error_message {
             state.serialize_field("errorMessage", error_message)?;
         }
+        if let Some(error_status) = &self.error_status {
+            state.serialize_field("errorStatus", error_status)?;
+        }
+        if let Some(circuit_open) = &self.circuit_open {
+            state.serialize_field("circuitOpen", circuit_open)?;
+        }
         state.serialize_field("timestamp", &self.timestamp)?;
         state.end()
     }
@@ -676,7 +1145,10 @@ impl<'de> Deserialize<'de> for AssistantMessage {
             diagnostics: Vec<Value>,
             usage: Usage,
             stop_reason: StopReason,
+            stop_sequence: Option<String>,
             error_message: Option<String>,
+            error_status: Option<u16>,
+            circuit_open: Option<OpenCircuit>,
             timestamp: u64,
         }
 
@@ -692,7 +1164,10 @@ impl<'de> Deserialize<'de> for AssistantMessage {
             diagnostics: helper.diagnostics,
             usage: helper.usage,
             stop_reason: helper.stop_reason,
+            stop_sequence: helper.stop_sequence,
             error_message: helper.error_message,
+            error_status: helper.error_status,
+            circuit_open: helper.circuit_open,
             timestamp: helper.timestamp,
         })
     }
@@ -1026,11 +1501,34 @@ pub struct Context {
     pub tools: Vec<Tool>,
 }
 
+/// Input for fill-in-the-middle completion. The model generates the text that
+/// belongs between `prompt` and `suffix`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FimContext {
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+}
+
+impl FimContext {
+    pub fn new(prompt: impl Into<String>, suffix: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            suffix: Some(suffix.into()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ImagesContext {
     #[serde(default)]
     pub input: Vec<UserContent>,
+    /// Marks the region of the first input image to repaint. Transparent
+    /// pixels are edited; the rest of the image is kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask: Option<ImageContent>,
 }
 
 impl ImagesContext {
@@ -1060,6 +1558,11 @@ impl ImagesContextBuilder {
         self
     }
 
+    pub fn mask(mut self, mask: ImageContent) -> Self {
+        self.context.mask = Some(mask);
+        self
+    }
+
     pub fn build(self) -> ImagesContext {
         self.context
     }
@@ -1163,6 +1666,8 @@ impl ContextBuilder {
 pub enum ModelInput {
     Text,
     Image,
+    Pdf,
+    Audio,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -1170,6 +1675,7 @@ pub enum ModelInput {
 pub enum ModelOutput {
     Text,
     Image,
+    Audio,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
@@ -1234,6 +1740,14 @@ pub struct Model {
     pub(crate) image_api: Option<Arc<dyn crate::provider::ImageModelApi>>,
     #[serde(skip)]
     pub(crate) embedding_api: Option<Arc<dyn EmbeddingModelApi>>,
+    #[serde(skip)]
+    pub(crate) fim_api: Option<Arc<dyn crate::provider::FimModelApi>>,
+    #[serde(skip)]
+    pub(crate) rerank_api: Option<Arc<dyn crate::provider::RerankModelApi>>,
+    #[serde(skip)]
+    pub(crate) transcription_api: Option<Arc<dyn crate::provider::TranscriptionModelApi>>,
+    #[serde(skip)]
+    pub(crate) speech_api: Option<Arc<dyn crate::provider::SpeechModelApi>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -1315,6 +1829,22 @@ impl Model {
     pub fn embedding_api(&self) -> Option<Arc<dyn EmbeddingModelApi>> {
         self.embedding_api.clone()
     }
+
+    pub fn fim_api(&self) -> Option<Arc<dyn crate::provider::FimModelApi>> {
+        self.fim_api.clone()
+    }
+
+    pub fn rerank_api(&self) -> Option<Arc<dyn crate::provider::RerankModelApi>> {
+        self.rerank_api.clone()
+    }
+
+    pub fn transcription_api(&self) -> Option<Arc<dyn crate::provider::TranscriptionModelApi>> {
+        self.transcription_api.clone()
+    }
+
+    pub fn speech_api(&self) -> Option<Arc<dyn crate::provider::SpeechModelApi>> {
+        self.speech_api.clone()
+    }
 }
 
 impl From<&Model> for ModelRef {
@@ -1335,6 +1865,26 @@ pub struct ModelCompat {
     pub openai_responses: OpenAIResponsesCompat,
     #[serde(flatten)]
     pub anthropic_messages: AnthropicMessagesCompat,
+    #[serde(flatten)]
+    pub sampling: SamplingCompat,
+}
+
+/// Which sampling controls a model accepts, for every API. Unset fields use
+/// the API's default; unsupported controls are left out of the request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingCompat {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_top_p: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_top_k: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_stop: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_seed: Option<bool>,
+    /// Covers both frequency and presence penalties.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_penalties: Option<bool>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
@@ -1351,6 +1901,8 @@ pub struct OpenAICompletionsCompat {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens_field: Option<MaxTokensField>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed_field: Option<SeedField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub requires_tool_result_name: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub requires_assistant_after_tool_result: Option<bool>,
@@ -1397,6 +1949,13 @@ pub enum MaxTokensField {
     MaxTokens,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedField {
+    Seed,
+    RandomSeed,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum OpenAIThinkingFormat {
@@ -1543,6 +2102,30 @@ pub struct AnthropicMessagesCompat {
     pub supports_tool_references: Option<bool>,
 }
 
+/// The log-probability of one generated token.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f64,
+    /// UTF-8 bytes of the token, for tokens that split a character.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<Vec<u8>>,
+    /// The most likely tokens at this position, when `top_logprobs` was
+    /// requested.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// An alternative token and its log-probability.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopLogprob {
+    pub token: String,
+    pub logprob: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<Vec<u8>>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum AssistantMessageEvent {
@@ -1568,6 +2151,14 @@ pub enum AssistantMessageEvent {
         content: String,
         partial: AssistantMessage,
     },
+    /// Log-probabilities of the tokens in the preceding `TextDelta`.
+    #[serde(rename = "text_logprobs")]
+    TextLogprobs {
+        #[serde(rename = "contentIndex")]
+        content_index: usize,
+        logprobs: Vec<TokenLogprob>,
+        partial: AssistantMessage,
+    },
     #[serde(rename = "thinking_start")]
     ThinkingStart {
         #[serde(rename = "contentIndex")]
@@ -1588,6 +2179,29 @@ pub enum AssistantMessageEvent {
         content: String,
         partial: AssistantMessage,
     },
+    #[serde(rename = "audio_start")]
+    AudioStart {
+        #[serde(rename = "contentIndex")]
+        content_index: usize,
+        partial: AssistantMessage,
+    },
+    /// A separately encoded chunk of base64 audio and/or transcript. Either
+    /// may be empty. The block's `data` is complete at `AudioEnd`.
+    #[serde(rename = "audio_delta")]
+    AudioDelta {
+        #[serde(rename = "contentIndex")]
+        content_index: usize,
+        data: String,
+        transcript: String,
+        partial: AssistantMessage,
+    },
+    #[serde(rename = "audio_end")]
+    AudioEnd {
+        #[serde(rename = "contentIndex")]
+        content_index: usize,
+        content: AssistantAudioContent,
+        partial: AssistantMessage,
+    },
     #[serde(rename = "toolcall_start")]
     ToolCallStart {
         #[serde(rename = "contentIndex")]
@@ -1641,7 +2255,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::Stop,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: 123,
         }
     }
@@ -1890,7 +2507,10 @@ mod tests {
                     diagnostics: Vec::new(),
                     usage: Usage::default(),
                     stop_reason: StopReason::Stop,
+                    stop_sequence: None,
                     error_message: None,
+                    error_status: None,
+                    circuit_open: None,
                     timestamp: 2,
                 }),
                 Message::ToolResult(ToolResultMessage {
@@ -2119,11 +2739,16 @@ mod tests {
                 supports_tool_references: Some(true),
                 ..Default::default()
             },
+            sampling: SamplingCompat {
+                supports_top_k: Some(false),
+                ..Default::default()
+            },
         };
         let value = serde_json::to_value(&compat).unwrap();
         assert_eq!(value["deferredToolsMode"], json!("kimi"));
         assert_eq!(value["supportsToolSearch"], json!(true));
         assert_eq!(value["supportsToolReferences"], json!(true));
+        assert_eq!(value["supportsTopK"], json!(false));
         assert_eq!(
             serde_json::from_value::<ModelCompat>(value).unwrap(),
             compat
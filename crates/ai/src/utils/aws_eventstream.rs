@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use async_stream::try_stream;
+use futures::{Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
+
+use crate::{Error, Result};
+
+const PRELUDE_BYTES: usize = 12;
+const MESSAGE_CRC_BYTES: usize = 4;
+const MIN_MESSAGE_BYTES: usize = PRELUDE_BYTES + MESSAGE_CRC_BYTES;
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// A decoded `application/vnd.amazon.eventstream` frame.
+///
+/// Only string-typed headers are kept; Bedrock uses them for the `:event-type`,
+/// `:message-type` and `:exception-type` routing headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventStreamMessage {
+    pub headers: HashMap<String, String>,
+    pub payload: Vec<u8>,
+}
+
+impl EventStreamMessage {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+pub fn messages(
+    response: reqwest::Response,
+    cancellation_token: Option<CancellationToken>,
+) -> impl Stream<Item = Result<EventStreamMessage>> + Send + 'static {
+    try_stream! {
+        let mut byte_stream = response.bytes_stream();
+        let mut buffer = Vec::new();
+
+        loop {
+            let chunk = if let Some(cancellation_token) = cancellation_token.as_ref() {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => Err(Error::Cancelled),
+                    chunk = byte_stream.next() => Ok(chunk),
+                }?
+            } else {
+                byte_stream.next().await
+            };
+
+            let Some(chunk) = chunk else {
+                break;
+            };
+            buffer.extend_from_slice(&chunk?);
+            while let Some((message, consumed)) = decode_message(&buffer)? {
+                buffer.drain(..consumed);
+                yield message;
+            }
+        }
+
+        if !buffer.is_empty() {
+            Err(Error::Provider(format!(
+                "event stream ended with {} trailing bytes",
+                buffer.len()
+            )))?;
+        }
+    }
+}
+
+/// Decodes the first complete frame in `buffer`, returning it together with
+/// the number of bytes it occupied. Returns `Ok(None)` when more bytes are
+/// needed.
+pub fn decode_message(buffer: &[u8]) -> Result<Option<(EventStreamMessage, usize)>> {
+    if buffer.len() < PRELUDE_BYTES {
+        return Ok(None);
+    }
+    let total_len = read_u32(&buffer[0..4]) as usize;
+    let headers_len = read_u32(&buffer[4..8]) as usize;
+    let prelude_crc = read_u32(&buffer[8..12]);
+    if crc32(&buffer[..8]) != prelude_crc {
+        return Err(invalid("prelude checksum mismatch"));
+    }
+    if !(MIN_MESSAGE_BYTES..=MAX_MESSAGE_BYTES).contains(&total_len) {
+        return Err(invalid(format!("invalid message length {total_len}")));
+    }
+    if headers_len > total_len - MIN_MESSAGE_BYTES {
+        return Err(invalid(format!("invalid headers length {headers_len}")));
+    }
+    if buffer.len() < total_len {
+        return Ok(None);
+    }
+    let message_crc = read_u32(&buffer[total_len - MESSAGE_CRC_BYTES..total_len]);
+    if crc32(&buffer[..total_len - MESSAGE_CRC_BYTES]) != message_crc {
+        return Err(invalid("message checksum mismatch"));
+    }
+
+    let headers_end = PRELUDE_BYTES + headers_len;
+    let headers = decode_headers(&buffer[PRELUDE_BYTES..headers_end])?;
+    let payload = buffer[headers_end..total_len - MESSAGE_CRC_BYTES].to_vec();
+    Ok(Some((EventStreamMessage { headers, payload }, total_len)))
+}
+
+fn decode_headers(mut bytes: &[u8]) -> Result<HashMap<String, String>> {
+    let mut headers = HashMap::new();
+    while !bytes.is_empty() {
+        let name_len = bytes[0] as usize;
+        let name = take(&mut bytes, 1, name_len)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        let value_type = *take(&mut bytes, 0, 1)?.first().expect("one byte");
+        match value_type {
+            // bool true / bool false carry no value bytes.
+            0 | 1 => {}
+            2 => {
+                take(&mut bytes, 0, 1)?;
+            }
+            3 => {
+                take(&mut bytes, 0, 2)?;
+            }
+            4 => {
+                take(&mut bytes, 0, 4)?;
+            }
+            5 | 8 => {
+                take(&mut bytes, 0, 8)?;
+            }
+            6 | 7 => {
+                let len = take(&mut bytes, 0, 2)?;
+                let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+                let value = take(&mut bytes, 0, len)?;
+                if value_type == 7 {
+                    headers.insert(name, String::from_utf8_lossy(value).into_owned());
+                }
+            }
+            9 => {
+                take(&mut bytes, 0, 16)?;
+            }
+            other => return Err(invalid(format!("unknown header value type {other}"))),
+        }
+    }
+    Ok(headers)
+}
+
+fn take<'a>(bytes: &mut &'a [u8], skip: usize, len: usize) -> Result<&'a [u8]> {
+    if bytes.len() < skip + len {
+        return Err(invalid("truncated header"));
+    }
+    let value = &bytes[skip..skip + len];
+    *bytes = &bytes[skip + len..];
+    Ok(value)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn invalid(message: impl std::fmt::Display) -> Error {
+    Error::InvalidProviderResponse(format!("Invalid event stream frame: {message}"))
+}
+
+/// CRC-32 (IEEE 802.3), as used by the event stream prelude and trailer.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Encodes a frame with string headers. Used by tests to stand in for
+/// Bedrock's event stream responses.
+#[cfg(test)]
+pub fn encode_message(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+    let mut header_bytes = Vec::new();
+    for (name, value) in headers {
+        header_bytes.push(name.len() as u8);
+        header_bytes.extend_from_slice(name.as_bytes());
+        header_bytes.push(7);
+        header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        header_bytes.extend_from_slice(value.as_bytes());
+    }
+    let total_len = (MIN_MESSAGE_BYTES + header_bytes.len() + payload.len()) as u32;
+    let mut message = Vec::with_capacity(total_len as usize);
+    message.extend_from_slice(&total_len.to_be_bytes());
+    message.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+    let prelude_crc = crc32(&message);
+    message.extend_from_slice(&prelude_crc.to_be_bytes());
+    message.extend_from_slice(&header_bytes);
+    message.extend_from_slice(payload);
+    let message_crc = crc32(&message);
+    message.extend_from_slice(&message_crc.to_be_bytes());
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Recorded `contentBlockDelta` frame from a Bedrock ConverseStream response.
+    const RECORDED_FRAME: &str = concat!(
+        "000000b300000057c74a69fa0b3a6576656e742d74797065070011636f6e74656e74426c6f636b44",
+        "656c74610d3a636f6e74656e742d747970650700106170706c69636174696f6e2f6a736f6e0d3a6d",
+        "6573736167652d747970650700056576656e747b22636f6e74656e74426c6f636b496e646578223a",
+        "302c2264656c7461223a7b2274657874223a2248656c6c6f227d2c2270223a226162636465666768",
+        "696a6b6c6d6e6f707172737475227d1662f48e",
+    );
+
+    fn decode_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn crc32_matches_ieee_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn decodes_recorded_bedrock_frame() {
+        let bytes = decode_hex(RECORDED_FRAME);
+
+        let (message, consumed) = decode_message(&bytes).unwrap().unwrap();
+
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(message.header(":event-type"), Some("contentBlockDelta"));
+        assert_eq!(message.header(":message-type"), Some("event"));
+        assert_eq!(message.header(":content-type"), Some("application/json"));
+        let payload: serde_json::Value = serde_json::from_slice(&message.payload).unwrap();
+        assert_eq!(payload["delta"]["text"], "Hello");
+    }
+
+    #[test]
+    fn waits_for_complete_frame_and_rejects_corruption() {
+        let bytes = encode_message(&[(":event-type", "messageStop")], b"{}");
+
+        assert!(decode_message(&bytes[..bytes.len() - 1]).unwrap().is_none());
+        let (message, consumed) = decode_message(&bytes).unwrap().unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(message.payload, b"{}");
+
+        let mut corrupted = bytes.clone();
+        let last = corrupted.len() - 5;
+        corrupted[last] ^= 0xff;
+        assert!(decode_message(&corrupted).is_err());
+    }
+}
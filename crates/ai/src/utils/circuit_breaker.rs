@@ -0,0 +1,177 @@
+//! Per-model circuit breaker for provider HTTP calls.
+//!
+//! Consecutive server errors (HTTP 5xx), timeouts, and connection failures
+//! against one model endpoint (its base URL and model id) open its circuit
+//! for a cooldown. Several providers serve every model from one host, so a
+//! failing model does not block its siblings. While open, requests fail
+//! immediately with [`Error::CircuitOpen`](crate::Error::CircuitOpen) instead
+//! of waiting on a provider that is down. After the cooldown the next request
+//! goes through; success closes the circuit and another failure reopens it.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use reqwest::Response;
+
+use crate::types::Model;
+use crate::{Error, Result};
+
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit. Zero disables the breaker.
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects requests.
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+        }
+    }
+}
+
+/// Replaces the breaker settings for every endpoint. Circuits that are
+/// already open keep their current cooldown.
+pub fn set_circuit_breaker_config(config: CircuitBreakerConfig) {
+    breaker().lock().config = config;
+}
+
+/// Closes every circuit and forgets failure counts.
+pub fn reset_circuit_breakers() {
+    breaker().lock().circuits.clear();
+}
+
+fn breaker() -> &'static Mutex<CircuitBreaker> {
+    static BREAKER: OnceLock<Mutex<CircuitBreaker>> = OnceLock::new();
+    BREAKER.get_or_init(|| Mutex::new(CircuitBreaker::new(CircuitBreakerConfig::default())))
+}
+
+/// The circuit `model`'s requests count against.
+pub(crate) fn model_circuit(model: &Model) -> String {
+    format!("{} ({})", model.base_url.trim_end_matches('/'), model.id)
+}
+
+/// Fails fast with `Error::CircuitOpen` when `circuit` is open.
+pub(crate) fn check(circuit: &str) -> Result<()> {
+    breaker().lock().check(circuit, Instant::now())
+}
+
+/// Records how an attempt against `circuit` ended. Cancelled attempts and
+/// responses below 500 other than timeouts say nothing about endpoint health
+/// beyond proving it is reachable.
+pub(crate) fn record(circuit: &str, result: &Result<Response>) {
+    let failed = match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(Error::Http(error)) => error.is_timeout() || error.is_connect(),
+        Err(_) => return,
+    };
+    breaker().lock().record(circuit, failed, Instant::now());
+}
+
+struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    circuits: HashMap<String, Circuit>,
+}
+
+#[derive(Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            circuits: HashMap::new(),
+        }
+    }
+
+    fn check(&self, endpoint: &str, now: Instant) -> Result<()> {
+        match self
+            .circuits
+            .get(endpoint)
+            .and_then(|circuit| circuit.open_until)
+        {
+            Some(open_until) if open_until > now => Err(Error::CircuitOpen {
+                endpoint: endpoint.to_string(),
+                retry_after_ms: open_until.duration_since(now).as_millis() as u64,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn record(&mut self, endpoint: &str, failed: bool, now: Instant) {
+        if !failed {
+            self.circuits.remove(endpoint);
+            return;
+        }
+        if self.config.failure_threshold == 0 {
+            return;
+        }
+        let circuit = self.circuits.entry(endpoint.to_string()).or_default();
+        circuit.consecutive_failures += 1;
+        if circuit.consecutive_failures >= self.config.failure_threshold {
+            circuit.open_until = Some(now + self.config.cooldown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_consecutive_failures_and_recovers_after_cooldown() {
+        let mut breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(10),
+        });
+        let start = Instant::now();
+        let endpoint = "https://api.example.com";
+
+        breaker.record(endpoint, true, start);
+        breaker.record(endpoint, false, start);
+        breaker.record(endpoint, true, start);
+        assert!(breaker.check(endpoint, start).is_ok());
+
+        breaker.record(endpoint, true, start);
+        let error = breaker
+            .check(endpoint, start + Duration::from_secs(4))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::CircuitOpen { ref endpoint, retry_after_ms: 6_000 }
+                if endpoint == "https://api.example.com"
+        ));
+        assert!(breaker.check("https://other.example.com", start).is_ok());
+
+        let after_cooldown = start + Duration::from_secs(10);
+        assert!(breaker.check(endpoint, after_cooldown).is_ok());
+        breaker.record(endpoint, true, after_cooldown);
+        assert!(breaker.check(endpoint, after_cooldown).is_err());
+        breaker.record(endpoint, false, after_cooldown);
+        assert!(breaker.check(endpoint, after_cooldown).is_ok());
+    }
+
+    #[test]
+    fn zero_threshold_disables_the_breaker() {
+        let mut breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 0,
+            cooldown: Duration::from_secs(10),
+        });
+        let now = Instant::now();
+        for _ in 0..10 {
+            breaker.record("https://api.example.com", true, now);
+        }
+        assert!(breaker.check("https://api.example.com", now).is_ok());
+    }
+}
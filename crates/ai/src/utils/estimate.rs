@@ -13,6 +13,10 @@ pub struct ContextUsageEstimate {
 
 const CHARS_PER_TOKEN: usize = 4;
 const ESTIMATED_IMAGE_CHARS: usize = 4_800;
+/// About three pages of a PDF.
+const ESTIMATED_DOCUMENT_CHARS: usize = 24_000;
+/// A minute of speech at Gemini's 32 tokens per second.
+const ESTIMATED_AUDIO_CHARS: usize = 7_680;
 
 fn string_length(value: &str) -> usize {
     value.encode_utf16().count()
@@ -30,9 +34,9 @@ pub fn calculate_context_tokens(usage: &Usage) -> u32 {
     }
 }
 
-fn estimate_text_and_image_content_chars<'a>(
+fn estimate_content_chars<'a>(
     text: Option<&str>,
-    parts: impl IntoIterator<Item = TextOrImage<'a>>,
+    parts: impl IntoIterator<Item = ContentPart<'a>>,
 ) -> usize {
     if let Some(text) = text {
         return string_length(text);
@@ -40,15 +44,19 @@ fn estimate_text_and_image_content_chars<'a>(
     parts
         .into_iter()
         .map(|part| match part {
-            TextOrImage::Text(text) => string_length(text),
-            TextOrImage::Image => ESTIMATED_IMAGE_CHARS,
+            ContentPart::Text(text) => string_length(text),
+            ContentPart::Image => ESTIMATED_IMAGE_CHARS,
+            ContentPart::Document => ESTIMATED_DOCUMENT_CHARS,
+            ContentPart::Audio => ESTIMATED_AUDIO_CHARS,
         })
         .sum()
 }
 
-enum TextOrImage<'a> {
+enum ContentPart<'a> {
     Text(&'a str),
     Image,
+    Document,
+    Audio,
 }
 
 pub fn estimate_text_tokens(text: &str) -> u32 {
@@ -59,21 +67,24 @@ pub fn estimate_message_tokens(message: &Message) -> u32 {
     let chars = match message {
         Message::User(user) => match &user.content {
             UserMessageContent::Text(text) => {
-                estimate_text_and_image_content_chars(Some(text), std::iter::empty())
+                estimate_content_chars(Some(text), std::iter::empty())
             }
-            UserMessageContent::Parts(parts) => estimate_text_and_image_content_chars(
+            UserMessageContent::Parts(parts) => estimate_content_chars(
                 None,
                 parts.iter().map(|part| match part {
-                    UserContent::Text(text) => TextOrImage::Text(&text.text),
-                    UserContent::Image(_) => TextOrImage::Image,
+                    UserContent::Text(text) => ContentPart::Text(&text.text),
+                    UserContent::Image(_) => ContentPart::Image,
+                    UserContent::Document(_) => ContentPart::Document,
+                    UserContent::Audio(_) => ContentPart::Audio,
                 }),
             ),
         },
-        Message::ToolResult(tool_result) => estimate_text_and_image_content_chars(
+        Message::ToolResult(tool_result) => estimate_content_chars(
             None,
             tool_result.content.iter().map(|part| match part {
-                ToolResultContent::Text(text) => TextOrImage::Text(&text.text),
-                ToolResultContent::Image(_) => TextOrImage::Image,
+                ToolResultContent::Text(text) => ContentPart::Text(&text.text),
+                ToolResultContent::Image(_) => ContentPart::Image,
+                ToolResultContent::Document(_) => ContentPart::Document,
             }),
         ),
         Message::Assistant(assistant) => assistant
@@ -82,6 +93,7 @@ pub fn estimate_message_tokens(message: &Message) -> u32 {
             .map(|block| match block {
                 AssistantContent::Text(text) => string_length(&text.text),
                 AssistantContent::Thinking(thinking) => string_length(&thinking.thinking),
+                AssistantContent::Audio(audio) => string_length(&audio.transcript),
                 AssistantContent::ToolCall(tool_call) => {
                     string_length(&tool_call.name)
                         + string_length(
@@ -234,7 +246,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: create_usage(total_tokens),
             stop_reason: StopReason::Stop,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp,
         }
     }
@@ -4,6 +4,7 @@ use reqwest::{RequestBuilder, Response, StatusCode, header::HeaderMap};
 use ring::rand::{SecureRandom, SystemRandom};
 
 use crate::types::{RequestOptions, StreamOptions};
+use crate::utils::{circuit_breaker, rate_limit};
 use crate::{Error, Result};
 
 pub const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 600_000;
@@ -13,11 +14,37 @@ pub fn request_timeout(timeout_ms: Option<u64>) -> Duration {
     Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_REQUEST_TIMEOUT_MS))
 }
 
-pub async fn send_with_retries<F>(options: &StreamOptions, mut build: F) -> Result<Response>
+/// Appends `path` to `base_url`, keeping any query string (such as Azure's
+/// `api-version`) after the path.
+pub fn join_url_path(base_url: &str, path: &str) -> String {
+    let (base, query) = match base_url.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (base_url, None),
+    };
+    let url = format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    );
+    match query {
+        Some(query) => format!("{url}?{query}"),
+        None => url,
+    }
+}
+
+/// Sends the request built by `build`, retrying as `options` allow. Server
+/// errors and connection failures count against the `circuit` breaker;
+/// providers pass one per model.
+pub async fn send_with_retries<F>(
+    options: &StreamOptions,
+    circuit: &str,
+    mut build: F,
+) -> Result<Response>
 where
     F: FnMut() -> RequestBuilder,
 {
     send_with_retry_config(
+        circuit,
         options.cancellation_token.as_ref(),
         options.max_retries,
         options.max_retry_delay_ms,
@@ -28,12 +55,14 @@ where
 
 pub async fn send_request_with_retries<F>(
     options: &RequestOptions,
+    circuit: &str,
     mut build: F,
 ) -> Result<Response>
 where
     F: FnMut() -> RequestBuilder,
 {
     send_with_retry_config(
+        circuit,
         options.cancellation_token.as_ref(),
         options.max_retries,
         options.max_retry_delay_ms,
@@ -42,7 +71,35 @@ where
     .await
 }
 
+/// GETs `url` and decodes the JSON body. Used for provider metadata routes
+/// such as model listings.
+pub async fn get_json(
+    client: &reqwest::Client,
+    url: &str,
+    headers: HeaderMap,
+) -> Result<serde_json::Value> {
+    let circuit = reqwest::Url::parse(url)
+        .map(|url| url.origin().ascii_serialization())
+        .unwrap_or_else(|_| url.to_string());
+    let response = send_request_with_retries(&RequestOptions::default(), &circuit, || {
+        client
+            .get(url)
+            .headers(headers.clone())
+            .timeout(request_timeout(None))
+    })
+    .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(Error::ApiStatus { status, body });
+    }
+    serde_json::from_str(&body).map_err(|error| {
+        Error::InvalidProviderResponse(format!("could not decode {url} response: {error}"))
+    })
+}
+
 async fn send_with_retry_config<F>(
+    circuit: &str,
     cancellation_token: Option<&tokio_util::sync::CancellationToken>,
     max_retries: Option<u32>,
     max_retry_delay_ms: Option<u64>,
@@ -59,7 +116,18 @@ where
             return Err(Error::Cancelled);
         }
 
-        let send = build().send();
+        let (client, request) = build().build_split();
+        let request = request?;
+        circuit_breaker::check(circuit)?;
+        let limiter = rate_limit::limiter_for(&request);
+        limiter
+            .acquire(
+                rate_limit::estimate_request_tokens(&request),
+                cancellation_token,
+            )
+            .await?;
+
+        let send = client.execute(request);
         let result = if let Some(cancellation_token) = cancellation_token {
             tokio::select! {
                 _ = cancellation_token.cancelled() => Err(Error::Cancelled),
@@ -68,6 +136,10 @@ where
         } else {
             send.await.map_err(Error::from)
         };
+        circuit_breaker::record(circuit, &result);
+        if let Ok(response) = &result {
+            limiter.observe(response.headers());
+        }
 
         match result {
             Ok(response) if attempt < max_retries && is_retryable_response(&response) => {
@@ -292,6 +364,21 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn join_url_path_keeps_query_string_last() {
+        assert_eq!(
+            join_url_path("https://api.openai.com/v1/", "chat/completions"),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            join_url_path(
+                "https://res.openai.azure.com/openai/deployments/gpt-4o?api-version=2024-10-21",
+                "chat/completions"
+            ),
+            "https://res.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2024-10-21"
+        );
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn retries_retryable_status_when_enabled() {
         let attempts = Arc::new(AtomicUsize::new(0));
@@ -303,7 +390,7 @@ mod tests {
             ..Default::default()
         };
 
-        let response = send_with_retries(&options, || client.get(&url))
+        let response = send_with_retries(&options, &url, || client.get(&url))
             .await
             .unwrap();
 
@@ -317,7 +404,7 @@ mod tests {
         let url = spawn_retry_server(Arc::clone(&attempts)).await;
         let client = reqwest::Client::new();
 
-        let response = send_with_retries(&StreamOptions::default(), || client.get(&url))
+        let response = send_with_retries(&StreamOptions::default(), &url, || client.get(&url))
             .await
             .unwrap();
 
@@ -325,6 +412,58 @@ mod tests {
         assert_eq!(attempts.load(Ordering::SeqCst), 1);
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn fails_fast_once_the_endpoint_circuit_opens() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let url =
+            spawn_status_server(Arc::clone(&attempts), "503 Service Unavailable", &[], "").await;
+        let client = reqwest::Client::new();
+
+        for _ in 0..5 {
+            let response = send_with_retries(&StreamOptions::default(), &url, || client.get(&url))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        }
+        let error = send_with_retries(&StreamOptions::default(), &url, || client.get(&url))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::CircuitOpen { .. }));
+        assert_eq!(attempts.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn an_open_circuit_does_not_block_other_models_on_the_same_host() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let url =
+            spawn_status_server(Arc::clone(&attempts), "503 Service Unavailable", &[], "").await;
+        let client = reqwest::Client::new();
+        let model = |id: &str| crate::types::Model {
+            id: id.to_string(),
+            base_url: url.clone(),
+            ..Default::default()
+        };
+        let failing = circuit_breaker::model_circuit(&model("gemini-2.5-pro"));
+        let backup = circuit_breaker::model_circuit(&model("gemini-2.5-flash"));
+
+        for _ in 0..5 {
+            send_with_retries(&StreamOptions::default(), &failing, || client.get(&url))
+                .await
+                .unwrap();
+        }
+        let error = send_with_retries(&StreamOptions::default(), &failing, || client.get(&url))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::CircuitOpen { ref endpoint, .. } if *endpoint == failing));
+
+        let response = send_with_retries(&StreamOptions::default(), &backup, || client.get(&url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(attempts.load(Ordering::SeqCst), 6);
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn rejects_server_retry_delay_above_configured_cap_without_retrying() {
         let attempts = Arc::new(AtomicUsize::new(0));
@@ -345,7 +484,7 @@ mod tests {
             ..Default::default()
         };
 
-        let error = send_with_retries(&options, || client.get(&url))
+        let error = send_with_retries(&options, &url, || client.get(&url))
             .await
             .unwrap_err();
 
@@ -374,7 +513,7 @@ mod tests {
             ..Default::default()
         };
 
-        let error = send_with_retries(&options, || client.get(&url))
+        let error = send_with_retries(&options, &url, || client.get(&url))
             .await
             .unwrap_err();
 
@@ -399,7 +538,7 @@ mod tests {
             ..Default::default()
         };
 
-        let response = send_with_retries(&options, || client.get(&url))
+        let response = send_with_retries(&options, &url, || client.get(&url))
             .await
             .unwrap();
 
@@ -425,7 +564,7 @@ mod tests {
             cancellation_token: Some(cancellation_token.clone()),
             ..Default::default()
         };
-        let request = send_with_retries(&options, || client.get(&url));
+        let request = send_with_retries(&options, &url, || client.get(&url));
         tokio::pin!(request);
 
         tokio::select! {
@@ -455,7 +594,7 @@ mod tests {
             ..Default::default()
         };
 
-        let response = send_with_retries(&options, || client.get(&url))
+        let response = send_with_retries(&options, &url, || client.get(&url))
             .await
             .unwrap();
 
@@ -478,7 +617,7 @@ mod tests {
             ..Default::default()
         };
 
-        let response = send_with_retries(&options, || client.get(&url))
+        let response = send_with_retries(&options, &url, || client.get(&url))
             .await
             .unwrap();
 
@@ -508,7 +647,7 @@ mod tests {
                 ..Default::default()
             };
 
-            let response = send_with_retries(&options, || client.get(&url))
+            let response = send_with_retries(&options, &url, || client.get(&url))
                 .await
                 .unwrap();
 
@@ -1,3 +1,5 @@
+pub(crate) mod aws_eventstream;
+pub mod circuit_breaker;
 pub mod diagnostics;
 pub mod estimate;
 pub mod event_stream;
@@ -5,8 +7,11 @@ pub mod hash;
 pub mod headers;
 pub(crate) mod http;
 pub mod json;
+pub(crate) mod ndjson;
 pub mod overflow;
 pub(crate) mod provider_env;
+pub mod rate_limit;
+pub(crate) mod sigv4;
 pub(crate) mod sse;
 pub(crate) mod time;
 pub mod validation;
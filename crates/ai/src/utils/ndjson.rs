@@ -0,0 +1,79 @@
+use async_stream::try_stream;
+use futures::{Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
+
+use crate::{Error, Result};
+
+const MAX_NDJSON_LINE_BYTES: usize = 8 * 1024 * 1024;
+
+/// Splits a newline-delimited JSON body into its non-empty lines.
+pub fn lines(
+    response: reqwest::Response,
+    cancellation_token: Option<CancellationToken>,
+) -> impl Stream<Item = Result<String>> + Send + 'static {
+    try_stream! {
+        let mut byte_stream = response.bytes_stream();
+        let mut buffer = Vec::new();
+
+        loop {
+            let chunk = if let Some(cancellation_token) = cancellation_token.as_ref() {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => Err(Error::Cancelled),
+                    chunk = byte_stream.next() => Ok(chunk),
+                }?
+            } else {
+                byte_stream.next().await
+            };
+
+            let Some(chunk) = chunk else {
+                break;
+            };
+            buffer.extend_from_slice(&chunk?);
+            for line in drain_lines(&mut buffer) {
+                yield line;
+            }
+            if buffer.len() > MAX_NDJSON_LINE_BYTES {
+                Err(Error::Provider(format!(
+                    "NDJSON line exceeded {MAX_NDJSON_LINE_BYTES} bytes"
+                )))?;
+            }
+        }
+
+        let line = String::from_utf8_lossy(&buffer);
+        if !line.trim().is_empty() {
+            yield line.trim().to_string();
+        }
+    }
+}
+
+/// Removes every complete line from `buffer`, leaving a trailing partial line
+/// in place.
+fn drain_lines(buffer: &mut Vec<u8>) -> Vec<String> {
+    let Some(last_newline) = buffer.iter().rposition(|byte| *byte == b'\n') else {
+        return Vec::new();
+    };
+    let complete = buffer.drain(..=last_newline).collect::<Vec<_>>();
+    String::from_utf8_lossy(&complete)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_lines_keeps_partial_line_buffered() {
+        let mut buffer = b"{\"a\":1}\r\n\n{\"b\":".to_vec();
+
+        assert_eq!(drain_lines(&mut buffer), vec!["{\"a\":1}".to_string()]);
+        assert_eq!(buffer, b"{\"b\":");
+
+        buffer.extend_from_slice(b"2}\n");
+        assert_eq!(drain_lines(&mut buffer), vec!["{\"b\":2}".to_string()]);
+        assert!(buffer.is_empty());
+    }
+}
@@ -111,7 +111,10 @@ mod tests {
             diagnostics: Vec::new(),
             usage: Usage::default(),
             stop_reason: StopReason::Error,
+            stop_sequence: None,
             error_message: Some(error_message.to_string()),
+            error_status: None,
+            circuit_open: None,
             timestamp: crate::utils::time::now_millis(),
         }
     }
@@ -136,7 +139,10 @@ mod tests {
                 cost: UsageCost::default(),
             },
             stop_reason: StopReason::Length,
+            stop_sequence: None,
             error_message: None,
+            error_status: None,
+            circuit_open: None,
             timestamp: crate::utils::time::now_millis(),
         }
     }
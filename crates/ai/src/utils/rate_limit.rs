@@ -0,0 +1,571 @@
+//! Client-side rate limiting shared by every request the crate sends.
+//!
+//! Each host and credential pair gets a limiter with a request bucket and a
+//! token bucket. Buckets start unlimited unless [`set_rate_limits`] configured
+//! the host, and are re-seeded from the `x-ratelimit-*` (OpenAI and
+//! compatible) and `anthropic-ratelimit-*` headers of every response. Requests
+//! wait in FIFO order until both buckets have room, so parallel callers queue
+//! instead of collecting 429s. Limiters nobody has used for a while are
+//! dropped; they are rebuilt from the configured limits on the next request.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use reqwest::header::{AUTHORIZATION, HeaderMap};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::utils::hash::short_hash;
+use crate::{Error, Result};
+
+const CREDENTIAL_HEADERS: &[&str] = &["x-api-key", "api-key", "x-goog-api-key"];
+const CHARS_PER_TOKEN: usize = 4;
+const MINUTE_MS: f64 = 60_000.0;
+const IDLE_LIMITER_TTL: Duration = Duration::from_secs(10 * 60);
+const SIGV4_PREFIX: &str = "AWS4-HMAC-SHA256 ";
+
+/// Limits applied to a host before its responses report any.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub requests_per_minute: Option<u32>,
+    /// Estimated from request body size, at roughly four bytes per token.
+    pub tokens_per_minute: Option<u32>,
+}
+
+/// Configures the starting limits for every credential used against `host`
+/// (for example `api.openai.com`). Limiters that already exist keep their
+/// state; response headers override these limits once they arrive.
+pub fn set_rate_limits(host: impl Into<String>, limits: RateLimits) {
+    configured_limits().lock().insert(host.into(), limits);
+}
+
+fn configured_limits() -> &'static Mutex<HashMap<String, RateLimits>> {
+    static LIMITS: OnceLock<Mutex<HashMap<String, RateLimits>>> = OnceLock::new();
+    LIMITS.get_or_init(Default::default)
+}
+
+fn limiters() -> &'static Mutex<HashMap<String, Arc<RateLimiter>>> {
+    static LIMITERS: OnceLock<Mutex<HashMap<String, Arc<RateLimiter>>>> = OnceLock::new();
+    LIMITERS.get_or_init(Default::default)
+}
+
+/// Returns the shared limiter for `request`'s host and credential.
+pub(crate) fn limiter_for(request: &reqwest::Request) -> Arc<RateLimiter> {
+    let host = request
+        .url()
+        .host_str()
+        .map(|host| match request.url().port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_string(),
+        })
+        .unwrap_or_default();
+    let credential = std::iter::once(AUTHORIZATION.as_str())
+        .chain(CREDENTIAL_HEADERS.iter().copied())
+        .find_map(|name| request.headers().get(name))
+        .map(|value| {
+            short_hash(credential_identity(&String::from_utf8_lossy(
+                value.as_bytes(),
+            )))
+        })
+        .unwrap_or_default();
+    let key = format!("{host}#{credential}");
+    let now = Instant::now();
+    let mut limiters = limiters().lock();
+    limiters.retain(|_, limiter| {
+        Arc::strong_count(limiter) > 1
+            || now.duration_since(*limiter.last_used.lock()) < IDLE_LIMITER_TTL
+    });
+    let limiter = limiters.entry(key).or_insert_with(|| {
+        let limits = configured_limits()
+            .lock()
+            .get(&host)
+            .copied()
+            .unwrap_or_default();
+        Arc::new(RateLimiter::new(limits))
+    });
+    *limiter.last_used.lock() = now;
+    Arc::clone(limiter)
+}
+
+/// The part of a credential header that identifies the caller. SigV4
+/// `Authorization` headers carry a fresh signature on every request, so they
+/// are keyed by the access key ID in `Credential=` alone.
+fn credential_identity(value: &str) -> &str {
+    value
+        .strip_prefix(SIGV4_PREFIX)
+        .and_then(|fields| {
+            fields
+                .split(',')
+                .find_map(|field| field.trim().strip_prefix("Credential="))
+        })
+        .and_then(|scope| scope.split('/').next())
+        .unwrap_or(value)
+}
+
+/// Estimates the tokens a request consumes from its body size.
+pub(crate) fn estimate_request_tokens(request: &reqwest::Request) -> u32 {
+    request
+        .body()
+        .and_then(reqwest::Body::as_bytes)
+        .map_or(0, |body| body.len().div_ceil(CHARS_PER_TOKEN) as u32)
+}
+
+pub(crate) struct RateLimiter {
+    queue: tokio::sync::Mutex<()>,
+    buckets: Mutex<Buckets>,
+    last_used: Mutex<Instant>,
+}
+
+struct Buckets {
+    requests: Bucket,
+    tokens: Bucket,
+}
+
+impl RateLimiter {
+    fn new(limits: RateLimits) -> Self {
+        let now = Instant::now();
+        Self {
+            queue: tokio::sync::Mutex::new(()),
+            buckets: Mutex::new(Buckets {
+                requests: Bucket::per_minute(limits.requests_per_minute, now),
+                tokens: Bucket::per_minute(limits.tokens_per_minute, now),
+            }),
+            last_used: Mutex::new(now),
+        }
+    }
+
+    /// Waits until one request of `tokens` estimated tokens fits, then takes
+    /// it from both buckets. Waiters are served in arrival order.
+    pub(crate) async fn acquire(
+        &self,
+        tokens: u32,
+        cancellation_token: Option<&CancellationToken>,
+    ) -> Result<()> {
+        let _turn = self.queue.lock().await;
+        loop {
+            let wait_ms = {
+                let mut buckets = self.buckets.lock();
+                let now = Instant::now();
+                let wait_ms = buckets
+                    .requests
+                    .wait_ms(1.0, now)
+                    .max(buckets.tokens.wait_ms(f64::from(tokens), now));
+                if wait_ms <= 0.0 {
+                    buckets.requests.take(1.0);
+                    buckets.tokens.take(f64::from(tokens));
+                    return Ok(());
+                }
+                wait_ms
+            };
+            let delay = Duration::from_secs_f64(wait_ms / 1000.0);
+            match cancellation_token {
+                Some(cancellation_token) => tokio::select! {
+                    _ = cancellation_token.cancelled() => return Err(Error::Cancelled),
+                    _ = tokio::time::sleep(delay) => {}
+                },
+                None => tokio::time::sleep(delay).await,
+            }
+        }
+    }
+
+    /// Re-seeds the buckets from rate limit response headers.
+    pub(crate) fn observe(&self, headers: &HeaderMap) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock();
+        if let Some(limit) = header_limit(headers, &["requests"]) {
+            buckets.requests.seed(limit, now);
+        }
+        if let Some(limit) = header_limit(headers, &["input-tokens", "tokens"]) {
+            buckets.tokens.seed(limit, now);
+        }
+    }
+}
+
+struct Bucket {
+    capacity: Option<f64>,
+    available: f64,
+    refill_per_ms: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn per_minute(limit: Option<u32>, now: Instant) -> Self {
+        let capacity = limit.map(f64::from);
+        Self {
+            capacity,
+            available: capacity.unwrap_or_default(),
+            refill_per_ms: capacity.unwrap_or_default() / MINUTE_MS,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if let Some(capacity) = self.capacity {
+            let elapsed_ms = now.duration_since(self.updated).as_secs_f64() * 1000.0;
+            self.available = (self.available + elapsed_ms * self.refill_per_ms).min(capacity);
+        }
+        self.updated = now;
+    }
+
+    /// Milliseconds until `cost` fits. Requests larger than the whole bucket
+    /// only wait for a full bucket, and a bucket with no known refill rate
+    /// never blocks.
+    fn wait_ms(&mut self, cost: f64, now: Instant) -> f64 {
+        self.refill(now);
+        let Some(capacity) = self.capacity else {
+            return 0.0;
+        };
+        let cost = cost.min(capacity);
+        if self.available >= cost || self.refill_per_ms <= 0.0 {
+            return 0.0;
+        }
+        (cost - self.available) / self.refill_per_ms
+    }
+
+    fn take(&mut self, cost: f64) {
+        if let Some(capacity) = self.capacity {
+            self.available -= cost.min(capacity);
+        }
+    }
+
+    fn seed(&mut self, limit: HeaderLimit, now: Instant) {
+        self.capacity = Some(limit.limit);
+        self.available = limit.remaining.min(limit.limit);
+        self.refill_per_ms = match limit.reset_ms {
+            Some(reset_ms) if reset_ms > 0.0 => (limit.limit - self.available) / reset_ms,
+            _ => limit.limit / MINUTE_MS,
+        };
+        self.updated = now;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HeaderLimit {
+    limit: f64,
+    remaining: f64,
+    reset_ms: Option<f64>,
+}
+
+/// Reads `x-ratelimit-{limit,remaining,reset}-<kind>` or
+/// `anthropic-ratelimit-<kind>-{limit,remaining,reset}` for the first `kind`
+/// the response reports.
+fn header_limit(headers: &HeaderMap, kinds: &[&str]) -> Option<HeaderLimit> {
+    let header = |name: String| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+    let number = |name: String| header(name).and_then(|value| value.parse::<f64>().ok());
+    kinds.iter().find_map(|kind| {
+        let (limit, remaining, reset) = match (
+            number(format!("x-ratelimit-limit-{kind}")),
+            number(format!("x-ratelimit-remaining-{kind}")),
+        ) {
+            (Some(limit), Some(remaining)) => (
+                limit,
+                remaining,
+                header(format!("x-ratelimit-reset-{kind}")),
+            ),
+            _ => (
+                number(format!("anthropic-ratelimit-{kind}-limit"))?,
+                number(format!("anthropic-ratelimit-{kind}-remaining"))?,
+                header(format!("anthropic-ratelimit-{kind}-reset")),
+            ),
+        };
+        Some(HeaderLimit {
+            limit,
+            remaining,
+            reset_ms: reset.and_then(parse_reset_ms),
+        })
+    })
+}
+
+/// Parses a reset header into milliseconds from now. OpenAI sends durations
+/// such as `1s`, `6m0s`, or `20ms`; Anthropic sends RFC 3339 timestamps.
+fn parse_reset_ms(value: &str) -> Option<f64> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Some(seconds * 1000.0);
+    }
+    parse_duration_ms(value).or_else(|| {
+        let target_ms = parse_rfc3339_ms(value)?;
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_millis() as i128;
+        Some((target_ms - now_ms).max(0) as f64)
+    })
+}
+
+fn parse_duration_ms(value: &str) -> Option<f64> {
+    let mut total_ms = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+            .filter(|end| *end > 0)?;
+        let number = rest[..number_end].parse::<f64>().ok()?;
+        rest = &rest[number_end..];
+        let (unit_ms, unit_len) = if rest.starts_with("ms") {
+            (1.0, 2)
+        } else if rest.starts_with('s') {
+            (1000.0, 1)
+        } else if rest.starts_with('m') {
+            (60_000.0, 1)
+        } else if rest.starts_with('h') {
+            (3_600_000.0, 1)
+        } else {
+            return None;
+        };
+        total_ms += number * unit_ms;
+        rest = &rest[unit_len..];
+    }
+    Some(total_ms)
+}
+
+/// Parses `YYYY-MM-DDTHH:MM:SS[.fff](Z|±HH:MM)` into Unix milliseconds.
+fn parse_rfc3339_ms(value: &str) -> Option<i128> {
+    let field = |range: std::ops::Range<usize>| value.get(range)?.parse::<i64>().ok();
+    let bytes = value.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute, second) = (field(11..13)?, field(14..16)?, field(17..19)?);
+    let mut rest = &value[19..];
+    let mut millis = 0.0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        millis = format!("0.{}", &fraction[..digits]).parse::<f64>().ok()? * 1000.0;
+        rest = &fraction[digits..];
+    }
+    let offset_minutes = match rest {
+        "Z" | "z" => 0,
+        offset if offset.len() == 6 && offset.as_bytes()[3] == b':' => {
+            let sign = match offset.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            sign * (offset[1..3].parse::<i64>().ok()? * 60 + offset[4..6].parse::<i64>().ok()?)
+        }
+        _ => return None,
+    };
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second
+        - offset_minutes * 60;
+    Some(i128::from(seconds) * 1000 + millis as i128)
+}
+
+/// Converts a proleptic Gregorian date to days since the Unix epoch.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    reqwest::header::HeaderName::from_static(name),
+                    HeaderValue::from_str(value).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_openai_and_anthropic_rate_limit_headers() {
+        let openai = headers(&[
+            ("x-ratelimit-limit-requests", "500"),
+            ("x-ratelimit-remaining-requests", "499"),
+            ("x-ratelimit-reset-requests", "120ms"),
+            ("x-ratelimit-limit-tokens", "30000"),
+            ("x-ratelimit-remaining-tokens", "29000"),
+            ("x-ratelimit-reset-tokens", "1m30s"),
+        ]);
+        assert_eq!(
+            header_limit(&openai, &["requests"]),
+            Some(HeaderLimit {
+                limit: 500.0,
+                remaining: 499.0,
+                reset_ms: Some(120.0),
+            })
+        );
+        assert_eq!(
+            header_limit(&openai, &["input-tokens", "tokens"])
+                .unwrap()
+                .reset_ms,
+            Some(90_000.0)
+        );
+
+        let anthropic = headers(&[
+            ("anthropic-ratelimit-input-tokens-limit", "40000"),
+            ("anthropic-ratelimit-input-tokens-remaining", "0"),
+            (
+                "anthropic-ratelimit-input-tokens-reset",
+                "2000-01-01T00:00:00Z",
+            ),
+        ]);
+        assert_eq!(
+            header_limit(&anthropic, &["input-tokens", "tokens"]),
+            Some(HeaderLimit {
+                limit: 40_000.0,
+                remaining: 0.0,
+                reset_ms: Some(0.0),
+            })
+        );
+        assert_eq!(header_limit(&anthropic, &["requests"]), None);
+    }
+
+    #[test]
+    fn parses_rfc3339_timestamps() {
+        assert_eq!(parse_rfc3339_ms("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_rfc3339_ms("2024-09-13T19:21:59.5Z"),
+            Some(1_726_255_319_500)
+        );
+        assert_eq!(
+            parse_rfc3339_ms("2024-09-13T21:21:59+02:00"),
+            Some(1_726_255_319_000)
+        );
+        assert_eq!(parse_rfc3339_ms("yesterday"), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queues_requests_until_the_reported_reset() {
+        let limiter = RateLimiter::new(RateLimits::default());
+        limiter.acquire(100, None).await.unwrap();
+        limiter.observe(&headers(&[
+            ("x-ratelimit-limit-requests", "10"),
+            ("x-ratelimit-remaining-requests", "0"),
+            ("x-ratelimit-reset-requests", "6s"),
+        ]));
+
+        let started = Instant::now();
+        limiter.acquire(100, None).await.unwrap();
+
+        // Ten requests refill over six seconds, so one slot takes 600ms.
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_millis(600), "waited {waited:?}");
+        assert!(waited < Duration::from_millis(700), "waited {waited:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn configured_token_budget_spaces_out_large_requests() {
+        let limiter = RateLimiter::new(RateLimits {
+            requests_per_minute: None,
+            tokens_per_minute: Some(600),
+        });
+        limiter.acquire(600, None).await.unwrap();
+
+        let started = Instant::now();
+        limiter.acquire(60, None).await.unwrap();
+
+        let waited = started.elapsed();
+        assert!(waited >= Duration::from_secs(6), "waited {waited:?}");
+        assert!(waited < Duration::from_millis(6_100), "waited {waited:?}");
+    }
+
+    fn signed_bedrock_request(body: &str, time: SystemTime) -> reqwest::Request {
+        let url = reqwest::Url::parse(
+            "https://bedrock-runtime.rate-limit-test.amazonaws.com/model/claude/converse-stream",
+        )
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        crate::utils::sigv4::sign_request(
+            "POST",
+            &url,
+            &mut headers,
+            body.as_bytes(),
+            &crate::utils::sigv4::SigningParams {
+                access_key_id: "AKIDRATELIMIT",
+                secret_access_key: "secret",
+                session_token: None,
+                region: "us-east-1",
+                service: "bedrock",
+                time,
+            },
+        )
+        .unwrap();
+        reqwest::Client::new()
+            .post(url)
+            .headers(headers)
+            .body(body.to_string())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn signed_bedrock_requests_share_one_limiter_per_access_key() {
+        let first = signed_bedrock_request("{\"a\":1}", UNIX_EPOCH + Duration::from_secs(1));
+        let second = signed_bedrock_request("{\"b\":2}", UNIX_EPOCH + Duration::from_secs(2));
+        assert_ne!(
+            first.headers().get(AUTHORIZATION),
+            second.headers().get(AUTHORIZATION)
+        );
+
+        assert!(Arc::ptr_eq(&limiter_for(&first), &limiter_for(&second)));
+        assert_eq!(
+            limiters()
+                .lock()
+                .keys()
+                .filter(|key| key.starts_with("bedrock-runtime.rate-limit-test.amazonaws.com#"))
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_limiters_are_dropped() {
+        let request = |key: &str| {
+            reqwest::Client::new()
+                .post("https://idle-limiter-test.example.com/v1/chat")
+                .bearer_auth(key)
+                .build()
+                .unwrap()
+        };
+        let count = || {
+            limiters()
+                .lock()
+                .keys()
+                .filter(|key| key.starts_with("idle-limiter-test.example.com#"))
+                .count()
+        };
+
+        let in_use = limiter_for(&request("first"));
+        drop(limiter_for(&request("second")));
+        assert_eq!(count(), 2);
+
+        tokio::time::advance(IDLE_LIMITER_TTL).await;
+        assert!(Arc::ptr_eq(&in_use, &limiter_for(&request("first"))));
+        assert_eq!(count(), 1);
+    }
+
+    #[test]
+    fn unlimited_buckets_never_wait() {
+        let mut bucket = Bucket::per_minute(None, Instant::now());
+        assert_eq!(bucket.wait_ms(1_000_000.0, Instant::now()), 0.0);
+    }
+}
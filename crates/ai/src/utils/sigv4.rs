@@ -0,0 +1,289 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use reqwest::Url;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+use ring::{digest, hmac};
+
+use crate::{Error, Result};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Inputs for AWS Signature Version 4 request signing.
+pub struct SigningParams<'a> {
+    pub access_key_id: &'a str,
+    pub secret_access_key: &'a str,
+    pub session_token: Option<&'a str>,
+    pub region: &'a str,
+    pub service: &'a str,
+    pub time: SystemTime,
+}
+
+/// Signs a request in place by adding `x-amz-date`, `x-amz-security-token`
+/// (for temporary credentials) and `authorization` headers.
+///
+/// The `host` header is derived from `url`, and every `content-type` and
+/// `x-amz-*` header already present is included in the signature. Path
+/// segments are URI-encoded a second time, as every service except S3 expects.
+pub fn sign_request(
+    method: &str,
+    url: &Url,
+    headers: &mut HeaderMap,
+    payload: &[u8],
+    params: &SigningParams<'_>,
+) -> Result<()> {
+    let amz_date = format_amz_date(params.time);
+    let date = &amz_date[..8];
+    headers.insert(
+        HeaderName::from_static("x-amz-date"),
+        HeaderValue::from_str(&amz_date)
+            .map_err(|error| Error::InvalidHeaderValue("x-amz-date".to_string(), error))?,
+    );
+    if let Some(session_token) = params.session_token {
+        headers.insert(
+            HeaderName::from_static("x-amz-security-token"),
+            HeaderValue::from_str(session_token).map_err(|error| {
+                Error::InvalidHeaderValue("x-amz-security-token".to_string(), error)
+            })?,
+        );
+    }
+
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let mut canonical_headers = vec![("host".to_string(), host)];
+    for (name, value) in headers.iter() {
+        let name = name.as_str();
+        if name == "content-type" || name.starts_with("x-amz-") {
+            let value = value
+                .to_str()
+                .map_err(|_| Error::Validation(format!("Header {name} is not valid ASCII")))?;
+            canonical_headers.push((name.to_string(), normalize_header_value(value)));
+        }
+    }
+    canonical_headers.sort();
+    let signed_headers = canonical_headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = [
+        method.to_string(),
+        canonical_uri(url),
+        canonical_query(url),
+        canonical_headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}\n"))
+            .collect::<String>(),
+        signed_headers.clone(),
+        hex(digest::digest(&digest::SHA256, payload).as_ref()),
+    ]
+    .join("\n");
+
+    let scope = format!("{date}/{}/{}/aws4_request", params.region, params.service);
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{amz_date}\n{scope}\n{}",
+        hex(digest::digest(&digest::SHA256, canonical_request.as_bytes()).as_ref())
+    );
+    let signing_key = [date, params.region, params.service, "aws4_request"]
+        .into_iter()
+        .fold(
+            format!("AWS4{}", params.secret_access_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+    let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+    let authorization = format!(
+        "{ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        params.access_key_id
+    );
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&authorization)
+            .map_err(|error| Error::InvalidHeaderValue("authorization".to_string(), error))?,
+    );
+    Ok(())
+}
+
+/// URI-encodes `value` with the unreserved set SigV4 requires.
+pub fn uri_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn canonical_uri(url: &Url) -> String {
+    let path = url.path();
+    if path.is_empty() {
+        return "/".to_string();
+    }
+    path.split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(url: &Url) -> String {
+    let mut pairs = url
+        .query_pairs()
+        .map(|(key, value)| (uri_encode(&key), uri_encode(&value)))
+        .collect::<Vec<_>>();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn normalize_header_value(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Formats `time` as the ISO 8601 basic form SigV4 uses, e.g.
+/// `20150830T123600Z`.
+fn format_amz_date(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let days = (seconds / 86_400) as i64;
+    let seconds_of_day = seconds % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        seconds_of_day / 3_600,
+        seconds_of_day % 3_600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Converts days since the Unix epoch to a proleptic Gregorian date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::CONTENT_TYPE;
+    use std::time::Duration;
+
+    fn example_time() -> SystemTime {
+        // 2015-08-30T12:36:00Z
+        UNIX_EPOCH + Duration::from_secs(1_440_938_160)
+    }
+
+    #[test]
+    fn formats_amz_date() {
+        assert_eq!(format_amz_date(example_time()), "20150830T123600Z");
+        assert_eq!(format_amz_date(UNIX_EPOCH), "19700101T000000Z");
+        // 2024-02-29T23:59:59Z
+        assert_eq!(
+            format_amz_date(UNIX_EPOCH + Duration::from_secs(1_709_251_199)),
+            "20240229T235959Z"
+        );
+    }
+
+    #[test]
+    fn signs_aws_documentation_example() {
+        let url =
+            Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08").unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded; charset=utf-8"),
+        );
+
+        sign_request(
+            "GET",
+            &url,
+            &mut headers,
+            b"",
+            &SigningParams {
+                access_key_id: "AKIDEXAMPLE",
+                secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+                session_token: None,
+                region: "us-east-1",
+                service: "iam",
+                time: example_time(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(headers["x-amz-date"], "20150830T123600Z");
+        assert_eq!(
+            headers[AUTHORIZATION],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn double_encodes_path_and_signs_session_token() {
+        let url = Url::parse(&format!(
+            "https://bedrock-runtime.us-west-2.amazonaws.com/model/{}/converse-stream",
+            uri_encode("anthropic.claude-3-haiku-20240307-v1:0")
+        ))
+        .unwrap();
+        assert_eq!(
+            canonical_uri(&url),
+            "/model/anthropic.claude-3-haiku-20240307-v1%253A0/converse-stream"
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        sign_request(
+            "POST",
+            &url,
+            &mut headers,
+            b"{}",
+            &SigningParams {
+                access_key_id: "AKIDEXAMPLE",
+                secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+                session_token: Some("session-token"),
+                region: "us-west-2",
+                service: "bedrock",
+                time: example_time(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(headers["x-amz-security-token"], "session-token");
+        assert_eq!(
+            headers[AUTHORIZATION],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-west-2/bedrock/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, \
+             Signature=137403c2633ca67680cbd207d89deed6ea1629195655ff557bc9d98d8afc1f4a"
+        );
+    }
+}
@@ -12,23 +12,46 @@ pub fn validate_tool_call(tools: &[Tool], tool_call: &ToolCall) -> Result<Value>
 }
 
 pub fn validate_tool_arguments(tool: &Tool, tool_call: &ToolCall) -> Result<Value> {
-    let args = coerce_with_json_schema(tool_call.arguments.clone(), &tool.parameters);
+    coerce_and_validate(tool_call.arguments.clone(), &tool.parameters).map_err(|errors| {
+        Error::Validation(format!(
+            "Validation failed for tool \"{}\":\n{}\n\nReceived arguments:\n{}",
+            tool_call.name,
+            format_errors(&errors),
+            serde_json::to_string_pretty(&tool_call.arguments)
+                .unwrap_or_else(|_| "null".to_string())
+        ))
+    })
+}
+
+/// Validates `value` against a JSON Schema, applying the same coercions as
+/// tool arguments (for example `"3"` to `3` for integer fields).
+pub fn validate_json_value(value: &Value, schema: &Value) -> Result<Value> {
+    coerce_and_validate(value.clone(), schema).map_err(|errors| {
+        Error::Validation(format!(
+            "Validation failed:\n{}\n\nReceived value:\n{}",
+            format_errors(&errors),
+            serde_json::to_string_pretty(value).unwrap_or_else(|_| "null".to_string())
+        ))
+    })
+}
+
+fn coerce_and_validate(value: Value, schema: &Value) -> std::result::Result<Value, Vec<String>> {
+    let value = coerce_with_json_schema(value, schema);
     let mut errors = Vec::new();
-    validate_value(&args, &tool.parameters, "root", &mut errors);
+    validate_value(&value, schema, "root", &mut errors);
     if errors.is_empty() {
-        return Ok(args);
+        Ok(value)
+    } else {
+        Err(errors)
     }
+}
 
-    Err(Error::Validation(format!(
-        "Validation failed for tool \"{}\":\n{}\n\nReceived arguments:\n{}",
-        tool_call.name,
-        errors
-            .iter()
-            .map(|error| format!("  - {error}"))
-            .collect::<Vec<_>>()
-            .join("\n"),
-        serde_json::to_string_pretty(&tool_call.arguments).unwrap_or_else(|_| "null".to_string())
-    )))
+fn format_errors(errors: &[String]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("  - {error}"))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn schema_types(schema: &Value) -> Vec<&str> {
@@ -6,7 +6,7 @@ use ai::{
     Agent, AgentError, AgentEvent, AgentOptions, AgentToolBuilder, AgentToolResult,
     AssistantContent, AssistantMessage, AssistantMessageEvent, DynAgentTool, Message, Model,
     OAuthLoginCallbacks, Result,
-    providers::{github_copilot, openai},
+    providers::{github_copilot, ollama, openai},
 };
 use serde_json::{Value, json};
 use tokio::io::{AsyncBufReadExt, BufReader};
@@ -145,15 +145,22 @@ async fn main() -> Result<()> {
 
 fn build_agent() -> Result<(Agent, ActiveProvider, Option<String>)> {
     let cwd = env::current_dir()?;
-    let base_url = env::var("OPENAI_BASE_URL").ok();
-    let api_key = openai_api_key();
-    let provider_setup_error = openai_setup_error(base_url.as_deref(), api_key.as_deref());
-    let model_id = env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-5.5".to_string());
-    let provider = ActiveProvider::OpenAi(build_openai_provider(
-        base_url.as_deref(),
-        api_key.as_deref(),
-    )?);
+    let (provider, model_id, provider_setup_error) = match env::var("OLLAMA_MODEL") {
+        Ok(model_id) => (ActiveProvider::Ollama(ollama::from_env()?), model_id, None),
+        Err(_) => {
+            let base_url = env::var("OPENAI_BASE_URL").ok();
+            let api_key = openai_api_key();
+            let provider_setup_error = openai_setup_error(base_url.as_deref(), api_key.as_deref());
+            let model_id = env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-5.5".to_string());
+            let provider = ActiveProvider::OpenAi(build_openai_provider(
+                base_url.as_deref(),
+                api_key.as_deref(),
+            )?);
+            (provider, model_id, provider_setup_error)
+        }
+    };
     let model = provider.model(&model_id)?;
+    let base_url = model.base_url.clone();
 
     let system_prompt = format!(
         r#"You are an expert coding assistant operating inside simple-coding-agent, a minimal coding agent harness.
@@ -180,10 +187,7 @@ Current working directory: {}"#,
 
     println!("simple coding agent example powered by ai.rs");
     println!("model: {model_id}");
-    println!(
-        "base URL: {}",
-        base_url.as_deref().unwrap_or("OpenAI default")
-    );
+    println!("base URL: {base_url}");
     if let Some(error) = &provider_setup_error {
         println!("{error}");
     }
@@ -265,6 +269,7 @@ fn append_limited_utf8(text: &mut String, bytes: &[u8], limit: usize) {
 #[derive(Clone)]
 enum ActiveProvider {
     OpenAi(openai::OpenAi),
+    Ollama(ollama::Ollama),
     GitHubCopilot(github_copilot::GitHubCopilot),
 }
 
@@ -272,6 +277,7 @@ impl ActiveProvider {
     fn id(&self) -> &'static str {
         match self {
             Self::OpenAi(_) => "openai",
+            Self::Ollama(_) => "ollama",
             Self::GitHubCopilot(_) => "github-copilot",
         }
     }
@@ -279,6 +285,7 @@ impl ActiveProvider {
     fn model(&self, model_id: &str) -> Result<Model> {
         match self {
             Self::OpenAi(provider) => provider.model(model_id).build(),
+            Self::Ollama(provider) => provider.model(model_id).build(),
             Self::GitHubCopilot(provider) => provider.model(model_id).build(),
         }
     }
@@ -309,12 +316,12 @@ fn openai_setup_error(base_url: Option<&str>, api_key: Option<&str>) -> Option<S
 
     match api_key {
         Some(api_key) if looks_like_github_token(api_key) => Some(
-            "OPENAI_API_KEY looks like a GitHub token. Set OPENAI_API_KEY to an OpenAI key, unset it and run /login for Copilot, or set OPENAI_BASE_URL for a local OpenAI-compatible server."
+            "OPENAI_API_KEY looks like a GitHub token. Set OPENAI_API_KEY to an OpenAI key, unset it and run /login for Copilot, set OLLAMA_MODEL for a local Ollama server, or set OPENAI_BASE_URL for a local OpenAI-compatible server."
                 .to_string(),
         ),
         Some(_) => None,
         None => Some(
-            "no OPENAI_API_KEY found; set it before prompting, run /login for Copilot, or set OPENAI_BASE_URL for a local OpenAI-compatible server"
+            "no OPENAI_API_KEY found; set it before prompting, run /login for Copilot, set OLLAMA_MODEL for a local Ollama server, or set OPENAI_BASE_URL for a local OpenAI-compatible server"
                 .to_string(),
         ),
     }
@@ -378,6 +385,7 @@ fn assistant_visible_content(message: &AssistantMessage) -> String {
         .map(|content| match content {
             AssistantContent::Text(text) => text.text.as_str(),
             AssistantContent::Thinking(thinking) => thinking.thinking.as_str(),
+            AssistantContent::Audio(audio) => audio.transcript.as_str(),
             AssistantContent::ToolCall(_) => "<tool_call>",
         })
         .collect()